@@ -4,7 +4,9 @@ use codex_common::CliConfigOverrides;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::exec_env::create_env;
+use codex_core::exec_env::set_network_proxy_env;
 use codex_core::landlock::spawn_command_under_linux_sandbox;
+use codex_core::network_proxy::NetworkProxy;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::StdioPolicy;
 use codex_protocol::config_types::SandboxMode;
@@ -76,7 +78,19 @@ async fn run_command_under_sandbox(
         },
     )?;
     let stdio_policy = StdioPolicy::Inherit;
-    let env = create_env(&config.shell_environment_policy);
+    let mut env = create_env(&config.shell_environment_policy);
+
+    // Allowlisted hosts are reached through a proxy that must outlive the
+    // command.
+    let network_proxy = match (&sandbox_type, config.sandbox_policy.network_allowlist()) {
+        (SandboxType::Landlock, allowlist) if !allowlist.is_empty() => {
+            Some(NetworkProxy::start(allowlist).await?)
+        }
+        _ => None,
+    };
+    if let Some(network_proxy) = &network_proxy {
+        set_network_proxy_env(&mut env, network_proxy.port());
+    }
 
     let mut child = match sandbox_type {
         SandboxType::Seatbelt => {
@@ -95,6 +109,7 @@ async fn run_command_under_sandbox(
                 cwd,
                 stdio_policy,
                 env,
+                network_proxy.as_ref(),
             )
             .await?
         }
@@ -45,6 +45,7 @@ struct MultitoolCli {
 }
 
 #[derive(Debug, clap::Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Subcommand {
     /// Run Codex non-interactively.
     #[clap(visible_alias = "e")]
@@ -7,6 +7,9 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
         SandboxPolicy::WorkspaceWrite {
             writable_roots,
             network_access,
+            network_allowlist,
+            read_only_paths,
+            deny_read_paths,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
         } => {
@@ -29,6 +32,14 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
             if *network_access {
                 summary.push_str(" (network access enabled)");
+            } else if !network_allowlist.is_empty() {
+                summary.push_str(&format!(" (network: {})", network_allowlist.join(", ")));
+            }
+            if !read_only_paths.is_empty() {
+                summary.push_str(&format!(" (read-only: {})", read_only_paths.join(", ")));
+            }
+            if !deny_read_paths.is_empty() {
+                summary.push_str(&format!(" (no access: {})", deny_read_paths.join(", ")));
             }
             summary
         }
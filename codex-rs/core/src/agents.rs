@@ -0,0 +1,193 @@
+//! Discovery of named sub-agent definitions stored as markdown files.
+//!
+//! Each `<name>.md` file under `$CODEX_HOME/agents` defines the agent
+//! `<name>`. An optional front-matter block delimited by `---` lines sets
+//! `description`, `model`, `sandbox_mode` and `tools`; the remainder of the
+//! file is the agent's system prompt:
+//!
+//! ```markdown
+//! ---
+//! description: Reviews the current diff for bugs
+//! model: gpt-5-mini
+//! sandbox_mode: read-only
+//! tools: [shell, view_image]
+//! ---
+//! You are a meticulous code reviewer...
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use codex_protocol::config_types::SandboxMode;
+
+use crate::config_types::AgentConfig;
+
+/// Name of the directory under `$CODEX_HOME` that holds agent definitions.
+pub(crate) const AGENTS_SUBDIR: &str = "agents";
+
+/// Discover agent definitions in `dir`, keyed by file stem. Files that are
+/// not markdown are ignored; files with invalid front matter are skipped with
+/// a warning. A missing or unreadable directory yields no agents.
+pub(crate) fn discover_agents_in(dir: &Path) -> BTreeMap<String, AgentConfig> {
+    let mut agents = BTreeMap::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return agents;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_file = entry.file_type().map(|ft| ft.is_file()).unwrap_or(false);
+        let is_md = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("md"))
+            .unwrap_or(false);
+        if !is_file || !is_md {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        match parse_agent_markdown(&contents) {
+            Ok(agent) => {
+                agents.insert(name.to_string(), agent);
+            }
+            Err(e) => {
+                tracing::warn!("ignoring agent definition {}: {e}", path.display());
+            }
+        }
+    }
+    agents
+}
+
+fn parse_agent_markdown(contents: &str) -> Result<AgentConfig, String> {
+    let mut agent = AgentConfig::default();
+    let (front_matter, body) = split_front_matter(contents)?;
+
+    for line in front_matter.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(format!(
+                "expected `key: value` in front matter, got `{line}`"
+            ));
+        };
+        let value = unquote(value.trim());
+        match key.trim() {
+            "description" => agent.description = Some(value.to_string()),
+            "model" => agent.model = Some(value.to_string()),
+            "sandbox_mode" => {
+                agent.sandbox_mode = Some(
+                    serde_json::from_value::<SandboxMode>(serde_json::Value::String(
+                        value.to_string(),
+                    ))
+                    .map_err(|e| format!("invalid sandbox_mode: {e}"))?,
+                );
+            }
+            "tools" => {
+                let list = value
+                    .strip_prefix('[')
+                    .and_then(|v| v.strip_suffix(']'))
+                    .unwrap_or(value);
+                agent.tools = Some(
+                    list.split(',')
+                        .map(|tool| unquote(tool.trim()).to_string())
+                        .filter(|tool| !tool.is_empty())
+                        .collect(),
+                );
+            }
+            other => return Err(format!("unknown front matter key `{other}`")),
+        }
+    }
+
+    let body = body.trim();
+    if !body.is_empty() {
+        agent.instructions = Some(body.to_string());
+    }
+    Ok(agent)
+}
+
+/// Splits `contents` into its front matter (empty if absent) and body.
+fn split_front_matter(contents: &str) -> Result<(&str, &str), String> {
+    let Some(rest) = contents
+        .strip_prefix("---\n")
+        .or_else(|| contents.strip_prefix("---\r\n"))
+    else {
+        return Ok(("", contents));
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Ok((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    Err("front matter is not terminated by `---`".to_string())
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn empty_when_dir_missing() {
+        let tmp = tempdir().expect("create TempDir");
+        assert!(discover_agents_in(&tmp.path().join("nope")).is_empty());
+    }
+
+    #[test]
+    fn parses_front_matter_and_body() {
+        let tmp = tempdir().expect("create TempDir");
+        let dir = tmp.path();
+        fs::write(
+            dir.join("reviewer.md"),
+            "---\ndescription: \"Reviews diffs\"\nmodel: gpt-5-mini\nsandbox_mode: read-only\ntools: [shell, 'view_image']\n---\n\nYou review code.\n",
+        )
+        .unwrap();
+        fs::write(dir.join("plain.md"), "Just a prompt.").unwrap();
+        fs::write(dir.join("notes.txt"), "not an agent").unwrap();
+        fs::write(dir.join("broken.md"), "---\nmodle: typo\n---\n").unwrap();
+
+        let agents = discover_agents_in(dir);
+        assert_eq!(agents.keys().collect::<Vec<_>>(), vec!["plain", "reviewer"]);
+        assert_eq!(
+            agents["reviewer"],
+            AgentConfig {
+                description: Some("Reviews diffs".to_string()),
+                instructions: Some("You review code.".to_string()),
+                model: Some("gpt-5-mini".to_string()),
+                sandbox_mode: Some(SandboxMode::ReadOnly),
+                tools: Some(vec!["shell".to_string(), "view_image".to_string()]),
+            }
+        );
+        assert_eq!(
+            agents["plain"],
+            AgentConfig {
+                instructions: Some("Just a prompt.".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_front_matter() {
+        assert!(parse_agent_markdown("---\nmodel: o3\nbody").is_err());
+    }
+}
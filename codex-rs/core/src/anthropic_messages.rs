@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::chat_completions::send_streaming_request;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::client_common::instructions_with_output_schema;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_model_info::get_model_info;
+use crate::openai_tools::create_tools_json_for_anthropic_api;
+use crate::protocol::TokenUsage;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+
+/// `max_tokens` is mandatory in the Messages API; used for models that are not
+/// listed in [`get_model_info`].
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8_192;
+
+/// The Messages API rejects thinking budgets below this value.
+const MIN_THINKING_BUDGET_TOKENS: u64 = 1_024;
+
+/// Implementation for the Anthropic Messages API.
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: ReasoningEffortConfig,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    let input = prompt.get_formatted_input();
+
+    // Thinking blocks must be sent back (with their signatures) only for the
+    // turn in progress, i.e. after the last user message.
+    let last_user_index = input
+        .iter()
+        .rposition(|item| matches!(item, ResponseItem::Message { role, .. } if role == "user"));
+
+    let mut messages = Vec::<Value>::new();
+    for (idx, item) in input.iter().enumerate() {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    let block = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"type": "text", "text": text})
+                        }
+                        ContentItem::InputImage { image_url } => image_block(image_url),
+                    };
+                    push_block(&mut messages, role, block);
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments).unwrap_or_else(|_| json!({}));
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": input,
+                    }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false)
+                    && let Some(obj) = block.as_object_mut()
+                {
+                    obj.insert("is_error".to_string(), json!(true));
+                }
+                push_block(&mut messages, "user", block);
+            }
+            ResponseItem::Reasoning {
+                content,
+                encrypted_content: Some(signature),
+                ..
+            } if last_user_index.is_none_or(|u_idx| idx > u_idx) => {
+                let block = match content {
+                    Some(items) => {
+                        let mut text = String::new();
+                        for c in items {
+                            match c {
+                                ReasoningItemContent::ReasoningText { text: t }
+                                | ReasoningItemContent::Text { text: t } => text.push_str(t),
+                            }
+                        }
+                        json!({"type": "thinking", "thinking": text, "signature": signature})
+                    }
+                    None => json!({"type": "redacted_thinking", "data": signature}),
+                };
+                push_block(&mut messages, "assistant", block);
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+            | ResponseItem::CustomToolCallOutput { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {
+                // These items cannot be expressed in the Messages API; the
+                // corresponding tools are never offered to the model.
+                continue;
+            }
+        }
+    }
+
+    let max_tokens = get_model_info(model_family)
+        .map(|info| info.max_output_tokens)
+        .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+    let mut payload = json!({
+        "model": model_family.slug,
+        "max_tokens": max_tokens,
+        "system": instructions_with_output_schema(prompt, model_family),
+        "messages": messages,
+        "stream": true,
+    });
+    let tools_json = create_tools_json_for_anthropic_api(&prompt.tools)?;
+    if !tools_json.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert("tools".to_string(), json!(tools_json));
+    }
+    if model_family.supports_reasoning_summaries
+        && let Some(budget_tokens) = thinking_budget_tokens(effort, max_tokens)
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "thinking".to_string(),
+            json!({"type": "enabled", "budget_tokens": budget_tokens}),
+        );
+    }
+
+    let resp = send_streaming_request(client, provider, &model_family.slug, &payload).await?;
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_anthropic_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
+
+/// Appends a content block to the conversation, merging it into the previous
+/// message when the role matches: the Messages API requires user and
+/// assistant turns to alternate.
+fn push_block(messages: &mut Vec<Value>, role: &str, block: Value) {
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(content) = last.get_mut("content").and_then(Value::as_array_mut)
+    {
+        content.push(block);
+        return;
+    }
+    messages.push(json!({"role": role, "content": [block]}));
+}
+
+/// Images arrive as `data:` URLs for local files and as plain URLs otherwise.
+fn image_block(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({"type": "image", "source": {"type": "url", "url": image_url}})
+}
+
+/// Maps the configured reasoning effort onto a thinking budget. The budget
+/// must stay below `max_tokens`, so it is capped at half of it.
+fn thinking_budget_tokens(effort: ReasoningEffortConfig, max_tokens: u64) -> Option<u64> {
+    let budget = match effort {
+        ReasoningEffortConfig::Minimal => return None,
+        ReasoningEffortConfig::Low => 4_096,
+        ReasoningEffortConfig::Medium => 16_384,
+        ReasoningEffortConfig::High => 32_000,
+    };
+    let budget = budget.min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET_TOKENS).then_some(budget)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: MessageStart,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: ContentBlockDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        #[serde(default)]
+        usage: Option<Usage>,
+    },
+    MessageStop,
+    Error {
+        error: ApiError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageStart {
+    id: String,
+    #[serde(default)]
+    usage: Usage,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        #[serde(default)]
+        text: String,
+    },
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlockDelta {
+    TextDelta {
+        text: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    SignatureDelta {
+        signature: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// A content block that is still streaming.
+enum BlockState {
+    Text(String),
+    Thinking {
+        text: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+}
+
+impl BlockState {
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            BlockState::Text(text) if !text.is_empty() => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            BlockState::Text(_) => None,
+            BlockState::Thinking { text, signature } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText { text }]),
+                encrypted_content: (!signature.is_empty()).then_some(signature),
+            }),
+            BlockState::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+            BlockState::ToolUse {
+                id,
+                name,
+                input_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input_json.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    input_json
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+/// SSE processor for the Messages streaming format. Text and thinking deltas
+/// are forwarded as they arrive and every content block becomes a
+/// [`ResponseItem`] once `content_block_stop` is received, so the output
+/// mirrors what the Responses API produces.
+async fn process_anthropic_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut blocks: HashMap<usize, BlockState> = HashMap::new();
+    let mut response_id = String::new();
+    let mut usage = Usage::default();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: StreamEvent = match serde_json::from_str(&sse.data) {
+            Ok(event) => event,
+            Err(e) => {
+                trace!(
+                    "failed to parse Anthropic SSE event: {e}, data: {}",
+                    &sse.data
+                );
+                continue;
+            }
+        };
+        trace!("anthropic_messages received SSE event: {event:?}");
+
+        match event {
+            StreamEvent::MessageStart { message } => {
+                response_id = message.id;
+                usage = message.usage;
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                let state = match content_block {
+                    ContentBlock::Text { text } => {
+                        if !text.is_empty() {
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::OutputTextDelta(text.clone())))
+                                .await;
+                        }
+                        BlockState::Text(text)
+                    }
+                    ContentBlock::Thinking { thinking } => BlockState::Thinking {
+                        text: thinking,
+                        signature: String::new(),
+                    },
+                    ContentBlock::RedactedThinking { data } => BlockState::RedactedThinking(data),
+                    ContentBlock::ToolUse { id, name } => BlockState::ToolUse {
+                        id,
+                        name,
+                        input_json: String::new(),
+                    },
+                    ContentBlock::Other => continue,
+                };
+                blocks.insert(index, state);
+            }
+            StreamEvent::ContentBlockDelta { index, delta } => {
+                match (blocks.get_mut(&index), delta) {
+                    (
+                        Some(BlockState::Text(text)),
+                        ContentBlockDelta::TextDelta { text: delta },
+                    ) => {
+                        text.push_str(&delta);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::OutputTextDelta(delta)))
+                            .await;
+                    }
+                    (
+                        Some(BlockState::Thinking { text, .. }),
+                        ContentBlockDelta::ThinkingDelta { thinking },
+                    ) => {
+                        text.push_str(&thinking);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::ReasoningContentDelta(thinking)))
+                            .await;
+                    }
+                    (
+                        Some(BlockState::Thinking { signature, .. }),
+                        ContentBlockDelta::SignatureDelta { signature: delta },
+                    ) => signature.push_str(&delta),
+                    (
+                        Some(BlockState::ToolUse { input_json, .. }),
+                        ContentBlockDelta::InputJsonDelta { partial_json },
+                    ) => input_json.push_str(&partial_json),
+                    _ => {}
+                }
+            }
+            StreamEvent::ContentBlockStop { index } => {
+                if let Some(item) = blocks
+                    .remove(&index)
+                    .and_then(BlockState::into_response_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            StreamEvent::MessageDelta {
+                usage: Some(delta_usage),
+            } => {
+                // `output_tokens` is cumulative for the message.
+                usage.output_tokens = delta_usage.output_tokens;
+            }
+            StreamEvent::MessageStop => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(usage.into()),
+                    }))
+                    .await;
+                return;
+            }
+            StreamEvent::Error { error } => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(error.message, None)))
+                    .await;
+                return;
+            }
+            StreamEvent::MessageDelta { usage: None } | StreamEvent::Other => {}
+        }
+    }
+}
+
+impl From<Usage> for TokenUsage {
+    fn from(usage: Usage) -> Self {
+        // Unlike OpenAI, Anthropic reports cache reads and writes separately
+        // from `input_tokens`.
+        let input_tokens =
+            usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + usage.output_tokens,
+        }
+    }
+}
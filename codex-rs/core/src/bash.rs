@@ -13,6 +13,20 @@ pub fn try_parse_bash(bash_lc_arg: &str) -> Option<Tree> {
     parser.parse(bash_lc_arg, old_tree)
 }
 
+/// Returns the individual commands of a `bash -lc "<script>"` invocation whose
+/// script is a word-only command sequence (see
+/// [`try_parse_word_only_commands_sequence`]).
+pub fn parse_bash_lc_plain_commands(command: &[String]) -> Option<Vec<Vec<String>>> {
+    let [bash, flag, script] = command else {
+        return None;
+    };
+    if bash != "bash" || flag != "-lc" {
+        return None;
+    }
+    let tree = try_parse_bash(script)?;
+    try_parse_word_only_commands_sequence(&tree, script)
+}
+
 /// Parse a script which may contain multiple simple commands joined only by
 /// the safe logical/pipe/sequencing operators: `&&`, `||`, `;`, `|`.
 ///
@@ -0,0 +1,199 @@
+//! Enforcement of [`Budget`] limits for sessions and sub-agents.
+
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::config_types::Budget;
+use crate::protocol::TokenUsage;
+
+/// Tracks consumption against a [`Budget`], starting from its creation.
+#[derive(Debug)]
+pub(crate) struct BudgetTracker {
+    budget: Budget,
+    started: Instant,
+    turns: u32,
+    tokens: u64,
+}
+
+/// The limit that stopped an agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BudgetExceeded {
+    Turns { limit: u32 },
+    Tokens { used: u64, limit: u64 },
+    Duration { limit: Duration },
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::Turns { limit } => write!(f, "turn limit of {limit} reached"),
+            BudgetExceeded::Tokens { used, limit } => {
+                write!(f, "token limit of {limit} reached ({used} tokens used)")
+            }
+            BudgetExceeded::Duration { limit } => {
+                write!(f, "time limit of {}s reached", limit.as_secs())
+            }
+        }
+    }
+}
+
+impl BudgetTracker {
+    pub(crate) fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            turns: 0,
+            tokens: 0,
+        }
+    }
+
+    /// Wall-clock limit of the underlying budget, if any.
+    pub(crate) fn time_limit(&self) -> Option<Duration> {
+        self.budget.max_duration_secs.map(Duration::from_secs)
+    }
+
+    /// Time left before the wall-clock limit, paired with that limit.
+    pub(crate) fn time_left(&self) -> Option<(Duration, Duration)> {
+        self.time_limit()
+            .map(|limit| (limit.saturating_sub(self.started.elapsed()), limit))
+    }
+
+    /// Counts one more model request, or reports the limit that forbids it.
+    pub(crate) fn start_turn(&mut self) -> Result<(), BudgetExceeded> {
+        if let Some(limit) = self.budget.max_turns
+            && self.turns >= limit
+        {
+            return Err(BudgetExceeded::Turns { limit });
+        }
+        if let Some(limit) = self.budget.max_tokens
+            && self.tokens >= limit
+        {
+            return Err(BudgetExceeded::Tokens {
+                used: self.tokens,
+                limit,
+            });
+        }
+        if let Some(limit) = self.time_limit()
+            && self.started.elapsed() >= limit
+        {
+            return Err(BudgetExceeded::Duration { limit });
+        }
+        self.turns += 1;
+        Ok(())
+    }
+
+    pub(crate) fn record_tokens(&mut self, usage: &TokenUsage) {
+        self.tokens = self.tokens.saturating_add(usage.total_tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn usage(total_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            total_tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unlimited_budget_never_trips() {
+        let mut tracker = BudgetTracker::new(Budget::default());
+        for _ in 0..100 {
+            tracker.record_tokens(&usage(1_000_000));
+            assert_eq!(tracker.start_turn(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn stops_at_turn_and_token_limits() {
+        let mut tracker = BudgetTracker::new(Budget {
+            max_turns: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(tracker.start_turn(), Ok(()));
+        assert_eq!(tracker.start_turn(), Ok(()));
+        assert_eq!(
+            tracker.start_turn(),
+            Err(BudgetExceeded::Turns { limit: 2 })
+        );
+
+        let mut tracker = BudgetTracker::new(Budget {
+            max_tokens: Some(100),
+            ..Default::default()
+        });
+        assert_eq!(tracker.start_turn(), Ok(()));
+        tracker.record_tokens(&usage(60));
+        assert_eq!(tracker.start_turn(), Ok(()));
+        tracker.record_tokens(&usage(60));
+        assert_eq!(
+            tracker.start_turn(),
+            Err(BudgetExceeded::Tokens {
+                used: 120,
+                limit: 100
+            })
+        );
+    }
+
+    #[test]
+    fn stops_once_time_limit_has_elapsed() {
+        let mut tracker = BudgetTracker::new(Budget {
+            max_duration_secs: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(
+            tracker.start_turn(),
+            Err(BudgetExceeded::Duration {
+                limit: Duration::ZERO
+            })
+        );
+    }
+
+    #[test]
+    fn time_left_counts_down_from_creation() {
+        let tracker = BudgetTracker::new(Budget::default());
+        assert_eq!(tracker.time_left(), None);
+
+        let tracker = BudgetTracker::new(Budget {
+            max_duration_secs: Some(60),
+            ..Default::default()
+        });
+        let Some((left, limit)) = tracker.time_left() else {
+            panic!("time limit should be set");
+        };
+        assert_eq!(limit, Duration::from_secs(60));
+        assert!(left <= limit);
+
+        let tracker = BudgetTracker::new(Budget {
+            max_duration_secs: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(tracker.time_left(), Some((Duration::ZERO, Duration::ZERO)));
+    }
+
+    #[test]
+    fn tightened_by_keeps_stricter_limits() {
+        let config = Budget {
+            max_turns: Some(10),
+            max_tokens: None,
+            max_duration_secs: Some(60),
+        };
+        let launch = Budget {
+            max_turns: Some(20),
+            max_tokens: Some(5_000),
+            max_duration_secs: Some(30),
+        };
+        assert_eq!(
+            config.tightened_by(launch),
+            Budget {
+                max_turns: Some(10),
+                max_tokens: Some(5_000),
+                max_duration_secs: Some(30),
+            }
+        );
+    }
+}
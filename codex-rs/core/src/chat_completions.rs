@@ -23,6 +23,8 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::output_schema::OUTPUT_SCHEMA_NAME;
+use crate::output_schema::is_strict_compatible;
 use crate::util::backoff;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ReasoningItemContent;
@@ -268,17 +270,53 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model_family.slug,
         "messages": messages,
         "stream": true,
         "tools": tools_json,
     });
+    if let Some(schema) = &prompt.output_schema
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "response_format".to_string(),
+            json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": OUTPUT_SCHEMA_NAME,
+                    "schema": schema,
+                    "strict": is_strict_compatible(schema),
+                },
+            }),
+        );
+    }
+
+    let resp = send_streaming_request(client, provider, &model_family.slug, &payload).await?;
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_chat_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
 
+/// POST `payload` to the provider's streaming endpoint and return the
+/// successful response, retrying rate limits, server errors, and transport
+/// failures up to the provider's `request_max_retries`. Shared by the wire
+/// APIs that do not need the auth refresh handling of the Responses API.
+pub(crate) async fn send_streaming_request(
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    model: &str,
+    payload: &serde_json::Value,
+) -> Result<reqwest::Response> {
     debug!(
         "POST to {}: {}",
-        provider.get_full_url(&None),
-        serde_json::to_string_pretty(&payload).unwrap_or_default()
+        provider.get_full_url(&None, model),
+        serde_json::to_string_pretty(payload).unwrap_or_default()
     );
 
     let mut attempt = 0;
@@ -286,25 +324,18 @@ pub(crate) async fn stream_chat_completions(
     loop {
         attempt += 1;
 
-        let req_builder = provider.create_request_builder(client, &None).await?;
+        let req_builder = provider
+            .create_request_builder(client, &None, model)
+            .await?;
 
         let res = req_builder
             .header(reqwest::header::ACCEPT, "text/event-stream")
-            .json(&payload)
+            .json(payload)
             .send()
             .await;
 
         match res {
-            Ok(resp) if resp.status().is_success() => {
-                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
-                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
-                tokio::spawn(process_chat_sse(
-                    stream,
-                    tx_event,
-                    provider.stream_idle_timeout(),
-                ));
-                return Ok(ResponseStream { rx_event });
-            }
+            Ok(resp) if resp.status().is_success() => return Ok(resp),
             Ok(res) => {
                 let status = res.status();
                 if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
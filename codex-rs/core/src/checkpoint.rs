@@ -0,0 +1,487 @@
+//! Worktree checkpoints recorded in the rollout before every user turn.
+//!
+//! Each checkpoint is a commit on the hidden ref
+//! `refs/codex/checkpoints/<conversation_id>` whose tree holds every
+//! non-ignored file in the worktree, so it survives `git gc` without touching
+//! branches, the index or the stash. Backtracking to a message can then bring
+//! the files back to how they were when that message was sent.
+//!
+//! Checkpoint refs whose latest checkpoint is older than
+//! [`CHECKPOINT_RETENTION`] are deleted whenever a conversation records its
+//! first checkpoint in a repository, so `git gc` can reclaim what only they
+//! kept alive.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use codex_protocol::mcp_protocol::ConversationId;
+use tracing::warn;
+
+use crate::conversation_manager::user_message_positions;
+use crate::protocol::CheckpointItem;
+use crate::protocol::EventMsg;
+use crate::protocol::InputMessageKind;
+use crate::protocol::RolloutItem;
+use crate::protocol::RolloutLine;
+use crate::turn_undo::WorktreeSnapshot;
+use crate::turn_undo::restore_tree;
+use crate::turn_undo::run_git;
+use crate::turn_undo::run_git_with_input;
+use crate::turn_undo::write_tree;
+
+const CHECKPOINT_REF_PREFIX: &str = "refs/codex/checkpoints";
+
+/// How long the checkpoints of a conversation are kept after its latest one.
+pub const CHECKPOINT_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// A checkpoint along with the user message it was taken for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnCheckpoint {
+    pub timestamp: String,
+    pub message: String,
+    pub checkpoint: CheckpointItem,
+}
+
+fn checkpoint_ref(conversation_id: ConversationId) -> String {
+    format!("{CHECKPOINT_REF_PREFIX}/{conversation_id}")
+}
+
+/// Stores `snapshot` as a commit on the conversation's checkpoint ref. The
+/// previous checkpoint is reused when the worktree has not changed since.
+pub(crate) async fn create_checkpoint(
+    snapshot: &WorktreeSnapshot,
+    conversation_id: ConversationId,
+) -> Result<CheckpointItem> {
+    let root = &snapshot.root;
+    let ref_name = checkpoint_ref(conversation_id);
+    let parent = run_git(
+        root,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{ref_name}^{{commit}}"),
+        ],
+        None,
+    )
+    .await
+    .ok()
+    .map(|commit| commit.trim().to_string());
+
+    if let Some(parent) = &parent {
+        let parent_tree =
+            run_git(root, &["rev-parse", &format!("{parent}^{{tree}}")], None).await?;
+        if parent_tree.trim() == snapshot.tree {
+            return Ok(CheckpointItem {
+                root: root.clone(),
+                commit: parent.clone(),
+            });
+        }
+    }
+
+    let mut args = vec![
+        "-c",
+        "user.name=Codex",
+        "-c",
+        "user.email=codex@localhost",
+        "commit-tree",
+        snapshot.tree.as_str(),
+        "-m",
+        "codex checkpoint",
+    ];
+    match &parent {
+        Some(parent) => args.extend(["-p", parent.as_str()]),
+        None => {
+            let root = root.clone();
+            tokio::spawn(async move {
+                if let Err(e) = prune_checkpoints(&root, CHECKPOINT_RETENTION).await {
+                    warn!("failed to prune checkpoints in {}: {e:#}", root.display());
+                }
+            });
+        }
+    }
+    let commit = run_git(root, &args, None).await?.trim().to_string();
+    run_git(root, &["update-ref", &ref_name, &commit], None).await?;
+    Ok(CheckpointItem {
+        root: root.clone(),
+        commit,
+    })
+}
+
+/// Restores the worktree to `checkpoint` and returns the paths that changed.
+/// The current state is checkpointed first under `conversation_id` so the
+/// restore itself can be reverted by hand.
+pub async fn restore_checkpoint(
+    checkpoint: &CheckpointItem,
+    conversation_id: ConversationId,
+) -> Result<Vec<PathBuf>> {
+    let root = &checkpoint.root;
+    let target = run_git(
+        root,
+        &["rev-parse", &format!("{}^{{tree}}", checkpoint.commit)],
+        None,
+    )
+    .await?;
+    let target = target.trim();
+    let current = write_tree(root).await?;
+    if current == target {
+        return Ok(Vec::new());
+    }
+    let snapshot = WorktreeSnapshot {
+        root: root.clone(),
+        tree: current,
+    };
+    create_checkpoint(&snapshot, conversation_id).await?;
+    restore_tree(root, &snapshot.tree, target).await
+}
+
+/// Deletes the checkpoint refs in the repository at `root` whose latest
+/// checkpoint is older than `retention`, returning how many were deleted.
+pub async fn prune_checkpoints(root: &Path, retention: Duration) -> Result<usize> {
+    let refs = run_git(
+        root,
+        &[
+            "for-each-ref",
+            "--format=%(committerdate:unix) %(refname)",
+            CHECKPOINT_REF_PREFIX,
+        ],
+        None,
+    )
+    .await?;
+    let cutoff = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .saturating_sub(retention)
+        .as_secs();
+    let stale: Vec<&str> = refs
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(committed, _)| committed.parse::<u64>().is_ok_and(|time| time < cutoff))
+        .map(|(_, ref_name)| ref_name)
+        .collect();
+    if !stale.is_empty() {
+        let commands: String = stale
+            .iter()
+            .map(|ref_name| format!("delete {ref_name}\n"))
+            .collect();
+        run_git_with_input(
+            root,
+            &["update-ref", "--stdin"],
+            None,
+            Some(commands.as_bytes()),
+        )
+        .await?;
+    }
+    Ok(stale.len())
+}
+
+/// Lists the checkpoints recorded in the rollout at `rollout_path`, oldest
+/// first, each paired with the user message that followed it.
+pub async fn list_checkpoints(rollout_path: &Path) -> std::io::Result<Vec<TurnCheckpoint>> {
+    let mut checkpoints = Vec::new();
+    let mut pending: Option<(String, CheckpointItem)> = None;
+    for line in read_rollout_lines(rollout_path).await? {
+        match line.item {
+            RolloutItem::Checkpoint(checkpoint) => {
+                if let Some((timestamp, checkpoint)) = pending.take() {
+                    checkpoints.push(TurnCheckpoint {
+                        timestamp,
+                        message: String::new(),
+                        checkpoint,
+                    });
+                }
+                pending = Some((line.timestamp, checkpoint));
+            }
+            RolloutItem::EventMsg(EventMsg::UserMessage(ev))
+                if !matches!(
+                    ev.kind,
+                    Some(InputMessageKind::UserInstructions | InputMessageKind::EnvironmentContext)
+                ) =>
+            {
+                if let Some((timestamp, checkpoint)) = pending.take() {
+                    checkpoints.push(TurnCheckpoint {
+                        timestamp,
+                        message: ev.message,
+                        checkpoint,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((timestamp, checkpoint)) = pending {
+        checkpoints.push(TurnCheckpoint {
+            timestamp,
+            message: String::new(),
+            checkpoint,
+        });
+    }
+    Ok(checkpoints)
+}
+
+/// Finds the checkpoint taken right before the `n`th-from-last user message,
+/// i.e. the worktree state matching a fork that drops the last `n` messages.
+pub async fn checkpoint_before_nth_last_user_message(
+    rollout_path: &Path,
+    n: usize,
+) -> std::io::Result<Option<CheckpointItem>> {
+    if n == 0 {
+        return Ok(None);
+    }
+    let items: Vec<RolloutItem> = read_rollout_lines(rollout_path)
+        .await?
+        .into_iter()
+        .map(|line| line.item)
+        .collect();
+    let user_positions = user_message_positions(&items);
+    let Some(&cut_idx) = user_positions
+        .len()
+        .checked_sub(n)
+        .map(|i| &user_positions[i])
+    else {
+        return Ok(None);
+    };
+    let previous_user = user_positions
+        .iter()
+        .rev()
+        .find(|&&idx| idx < cut_idx)
+        .map_or(0, |idx| idx + 1);
+    Ok(items[previous_user..cut_idx]
+        .iter()
+        .rev()
+        .find_map(|item| match item {
+            RolloutItem::Checkpoint(checkpoint) => Some(checkpoint.clone()),
+            _ => None,
+        }))
+}
+
+/// Parses every rollout line in `rollout_path`, skipping ones that do not
+/// deserialize.
+async fn read_rollout_lines(rollout_path: &Path) -> std::io::Result<Vec<RolloutLine>> {
+    let text = tokio::fs::read_to_string(rollout_path).await?;
+    Ok(text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::UserMessageEvent;
+    use codex_protocol::models::ContentItem;
+    use codex_protocol::models::ResponseItem;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git(root: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["config", "user.email", "codex@example.com"]);
+        git(root, &["config", "user.name", "Codex"]);
+        fs::write(root.join("tracked.txt"), "tracked\n").expect("write");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        dir
+    }
+
+    fn rollout_line(timestamp: &str, item: RolloutItem) -> String {
+        serde_json::to_string(&RolloutLine {
+            timestamp: timestamp.to_string(),
+            item,
+        })
+        .expect("serialize")
+    }
+
+    fn user_message(text: &str) -> [RolloutItem; 2] {
+        [
+            RolloutItem::ResponseItem(ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: text.to_string(),
+                }],
+            }),
+            RolloutItem::EventMsg(EventMsg::UserMessage(UserMessageEvent {
+                message: text.to_string(),
+                kind: Some(InputMessageKind::Plain),
+                images: None,
+            })),
+        ]
+    }
+
+    #[tokio::test]
+    async fn checkpoints_are_reused_and_restored() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        let conversation_id = ConversationId::new();
+
+        let snapshot = WorktreeSnapshot::capture(&root).await.expect("snapshot");
+        let first = create_checkpoint(&snapshot, conversation_id)
+            .await
+            .expect("checkpoint");
+        let again = create_checkpoint(&snapshot, conversation_id)
+            .await
+            .expect("checkpoint");
+        assert_eq!(first, again);
+
+        fs::write(root.join("tracked.txt"), "edited\n").expect("write");
+        fs::write(root.join("new.txt"), "new\n").expect("write");
+        let edited = WorktreeSnapshot::capture(&root).await.expect("snapshot");
+        let second = create_checkpoint(&edited, conversation_id)
+            .await
+            .expect("checkpoint");
+        assert_ne!(first, second);
+
+        let mut restored = restore_checkpoint(&first, conversation_id)
+            .await
+            .expect("restore");
+        restored.sort();
+        assert_eq!(
+            restored,
+            vec![root.join("new.txt"), root.join("tracked.txt")]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("tracked.txt")).expect("read"),
+            "tracked\n"
+        );
+        assert!(!root.join("new.txt").exists());
+        // The real index and HEAD are untouched.
+        git(&root, &["diff", "--quiet", "HEAD"]);
+
+        let restored = restore_checkpoint(&first, conversation_id)
+            .await
+            .expect("restore");
+        assert_eq!(restored, Vec::<PathBuf>::new());
+    }
+
+    #[tokio::test]
+    async fn restores_file_names_literally() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        let conversation_id = ConversationId::new();
+        // Read as pathspec magic, these would name `x.txt` and every `.txt`
+        // file respectively.
+        for name in [":x.txt", "*.txt"] {
+            fs::write(root.join(name), "original\n").expect("write");
+        }
+        let snapshot = WorktreeSnapshot::capture(&root).await.expect("snapshot");
+        let checkpoint = create_checkpoint(&snapshot, conversation_id)
+            .await
+            .expect("checkpoint");
+
+        for name in [":x.txt", "*.txt"] {
+            fs::write(root.join(name), "edited\n").expect("write");
+        }
+        let mut restored = restore_checkpoint(&checkpoint, conversation_id)
+            .await
+            .expect("restore");
+        restored.sort();
+        assert_eq!(restored, vec![root.join("*.txt"), root.join(":x.txt")]);
+        for name in [":x.txt", "*.txt"] {
+            assert_eq!(
+                fs::read_to_string(root.join(name)).expect("read"),
+                "original\n"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn prunes_stale_checkpoint_refs() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        let snapshot = WorktreeSnapshot::capture(&root).await.expect("snapshot");
+        let fresh = ConversationId::new();
+        create_checkpoint(&snapshot, fresh)
+            .await
+            .expect("checkpoint");
+
+        let stale = checkpoint_ref(ConversationId::new());
+        let output = std::process::Command::new("git")
+            .args(["commit-tree", &snapshot.tree, "-m", "old checkpoint"])
+            .env("GIT_COMMITTER_DATE", "2000-01-01T00:00:00Z")
+            .current_dir(&root)
+            .output()
+            .expect("commit-tree");
+        let commit = String::from_utf8(output.stdout).expect("utf8");
+        git(&root, &["update-ref", &stale, commit.trim()]);
+
+        assert_eq!(
+            prune_checkpoints(&root, CHECKPOINT_RETENTION)
+                .await
+                .expect("prune"),
+            1
+        );
+        let refs = run_git(&root, &["for-each-ref", "--format=%(refname)"], None)
+            .await
+            .expect("for-each-ref");
+        assert!(refs.contains(&checkpoint_ref(fresh)), "{refs}");
+        assert!(!refs.contains(&stale), "{refs}");
+    }
+
+    #[tokio::test]
+    async fn finds_checkpoints_in_rollout() {
+        let dir = TempDir::new().expect("tempdir");
+        let first = CheckpointItem {
+            root: PathBuf::from("/repo"),
+            commit: "aaaa".to_string(),
+        };
+        let second = CheckpointItem {
+            root: PathBuf::from("/repo"),
+            commit: "bbbb".to_string(),
+        };
+        let mut lines = vec![rollout_line("t0", RolloutItem::Checkpoint(first.clone()))];
+        lines.extend(user_message("first").map(|item| rollout_line("t1", item)));
+        lines.push(rollout_line("t2", RolloutItem::Checkpoint(second.clone())));
+        lines.extend(user_message("second").map(|item| rollout_line("t3", item)));
+        let path = dir.path().join("rollout.jsonl");
+        fs::write(&path, lines.join("\n")).expect("write");
+
+        assert_eq!(
+            list_checkpoints(&path).await.expect("list"),
+            vec![
+                TurnCheckpoint {
+                    timestamp: "t0".to_string(),
+                    message: "first".to_string(),
+                    checkpoint: first.clone(),
+                },
+                TurnCheckpoint {
+                    timestamp: "t2".to_string(),
+                    message: "second".to_string(),
+                    checkpoint: second.clone(),
+                },
+            ]
+        );
+        assert_eq!(
+            checkpoint_before_nth_last_user_message(&path, 1)
+                .await
+                .expect("lookup"),
+            Some(second)
+        );
+        assert_eq!(
+            checkpoint_before_nth_last_user_message(&path, 2)
+                .await
+                .expect("lookup"),
+            Some(first)
+        );
+        assert_eq!(
+            checkpoint_before_nth_last_user_message(&path, 3)
+                .await
+                .expect("lookup"),
+            None
+        );
+    }
+}
@@ -21,6 +21,7 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::anthropic_messages::stream_anthropic_messages;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -35,6 +36,7 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini_generate_content;
 use crate::model_family::ModelFamily;
 use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
@@ -105,9 +107,10 @@ impl ModelClient {
             .or_else(|| get_model_info(&self.config.model_family).map(|info| info.context_window))
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
-    /// specialised helpers are private to avoid accidental misuse.
+    /// Dispatches to the Responses, Chat, Anthropic, or Gemini implementation
+    /// depending on the provider config.  Public callers always invoke
+    /// `stream()` – the specialised helpers are private to avoid accidental
+    /// misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
@@ -146,6 +149,25 @@ impl ModelClient {
 
                 Ok(ResponseStream { rx_event: rx })
             }
+            WireApi::Anthropic => {
+                stream_anthropic_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.effort,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                stream_gemini_generate_content(
+                    prompt,
+                    &self.config.model_family,
+                    &self.client,
+                    &self.provider,
+                )
+                .await
+            }
         }
     }
 
@@ -176,8 +198,8 @@ impl ModelClient {
         let input_with_instructions = prompt.get_formatted_input();
 
         // Only include `text.verbosity` for GPT-5 family models
-        let text = if self.config.model_family.family == "gpt-5" {
-            create_text_param_for_request(self.config.model_verbosity)
+        let verbosity = if self.config.model_family.family == "gpt-5" {
+            self.config.model_verbosity
         } else {
             if self.config.model_verbosity.is_some() {
                 warn!(
@@ -187,6 +209,7 @@ impl ModelClient {
             }
             None
         };
+        let text = create_text_param_for_request(verbosity, prompt.output_schema.as_ref());
 
         let payload = ResponsesApiRequest {
             model: &self.config.model,
@@ -214,13 +237,13 @@ impl ModelClient {
 
             trace!(
                 "POST to {}: {}",
-                self.provider.get_full_url(&auth),
+                self.provider.get_full_url(&auth, &self.config.model),
                 serde_json::to_string(&payload)?
             );
 
             let mut req_builder = self
                 .provider
-                .create_request_builder(&self.client, &auth)
+                .create_request_builder(&self.client, &auth, &self.config.model)
                 .await?;
 
             req_builder = req_builder
@@ -351,6 +374,11 @@ impl ModelClient {
         self.config.model.clone()
     }
 
+    /// Returns the JSON Schema the final assistant message must match, if any.
+    pub fn get_output_schema(&self) -> Option<serde_json::Value> {
+        self.config.output_schema.clone()
+    }
+
     /// Returns the currently configured model family.
     pub fn get_model_family(&self) -> ModelFamily {
         self.config.model_family.clone()
@@ -405,6 +433,22 @@ impl ModelClient {
             summary.unwrap_or(self.get_reasoning_summary()),
             self.conversation_id,
         )
+    }
+
+    /// Returns a client for entry `index` of `fallback_providers`, or `None`
+    /// once the list is exhausted. The fallback keeps the current reasoning
+    /// settings and only overrides the model when the entry names one.
+    pub fn fallback_client(&self, index: usize) -> Option<Self> {
+        let fallback = self.config.fallback_providers.get(index)?;
+        let mut client = self.clone_with_overrides(fallback.model.clone(), None, None);
+        client.provider = fallback.provider.clone();
+        Some(client)
+    }
+
+    /// Returns how long a fallback provider stays active before the primary
+    /// provider is tried again.
+    pub fn fallback_cooldown(&self) -> Duration {
+        self.config.fallback_cooldown
     }
 }
 
@@ -1,6 +1,9 @@
 use crate::error::Result;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::OpenAiTool;
+use crate::output_schema::OUTPUT_SCHEMA_NAME;
+use crate::output_schema::is_strict_compatible;
+use crate::output_schema::output_schema_instructions;
 use crate::protocol::TokenUsage;
 use codex_apply_patch::APPLY_PATCH_TOOL_INSTRUCTIONS;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -9,6 +12,7 @@ use codex_protocol::config_types::Verbosity as VerbosityConfig;
 use codex_protocol::models::ResponseItem;
 use futures::Stream;
 use serde::Serialize;
+use serde_json::Value;
 use std::borrow::Cow;
 use std::ops::Deref;
 use std::pin::Pin;
@@ -28,6 +32,9 @@ pub struct Prompt {
 
     /// Optional override for the model-specific base instructions.
     pub base_instructions_override: Option<String>,
+
+    /// JSON Schema the final assistant message must match.
+    pub output_schema: Option<Value>,
 }
 
 impl Prompt {
@@ -84,11 +91,22 @@ pub(crate) struct Reasoning {
     pub(crate) summary: ReasoningSummaryConfig,
 }
 
-/// Controls under the `text` field in the Responses API for GPT-5.
-#[derive(Debug, Serialize, Default, Clone, Copy)]
+/// Controls under the `text` field in the Responses API.
+#[derive(Debug, Serialize, Default, Clone)]
 pub(crate) struct TextControls {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) verbosity: Option<OpenAiVerbosity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) format: Option<TextFormat>,
+}
+
+/// Structured output format under `text.format` in the Responses API.
+#[derive(Debug, Serialize, Clone)]
+pub(crate) struct TextFormat {
+    pub(crate) r#type: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) schema: Value,
+    pub(crate) strict: bool,
 }
 
 #[derive(Debug, Serialize, Default, Clone, Copy)]
@@ -133,6 +151,16 @@ pub(crate) struct ResponsesApiRequest<'a> {
     pub(crate) text: Option<TextControls>,
 }
 
+/// Full instructions for providers without native structured output, with
+/// the output schema (if any) spelled out at the end.
+pub(crate) fn instructions_with_output_schema(prompt: &Prompt, model: &ModelFamily) -> String {
+    let instructions = prompt.get_full_instructions(model);
+    match &prompt.output_schema {
+        Some(schema) => format!("{instructions}\n\n{}", output_schema_instructions(schema)),
+        None => instructions.into_owned(),
+    }
+}
+
 pub(crate) fn create_reasoning_param_for_request(
     model_family: &ModelFamily,
     effort: ReasoningEffortConfig,
@@ -147,9 +175,19 @@ pub(crate) fn create_reasoning_param_for_request(
 
 pub(crate) fn create_text_param_for_request(
     verbosity: Option<VerbosityConfig>,
+    output_schema: Option<&Value>,
 ) -> Option<TextControls> {
-    verbosity.map(|v| TextControls {
-        verbosity: Some(v.into()),
+    if verbosity.is_none() && output_schema.is_none() {
+        return None;
+    }
+    Some(TextControls {
+        verbosity: verbosity.map(Into::into),
+        format: output_schema.map(|schema| TextFormat {
+            r#type: "json_schema",
+            name: OUTPUT_SCHEMA_NAME,
+            schema: schema.clone(),
+            strict: is_strict_compatible(schema),
+        }),
     })
 }
 
@@ -245,6 +283,7 @@ mod tests {
             prompt_cache_key: None,
             text: Some(TextControls {
                 verbosity: Some(OpenAiVerbosity::Low),
+                format: None,
             }),
         };
 
@@ -257,6 +296,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn serializes_text_format_for_output_schema() {
+        let schema = serde_json::json!({"type": "object", "additionalProperties": false});
+        let text = create_text_param_for_request(None, Some(&schema));
+        let v = serde_json::to_value(&text).expect("json");
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "format": {
+                    "type": "json_schema",
+                    "name": OUTPUT_SCHEMA_NAME,
+                    "schema": {"type": "object", "additionalProperties": false},
+                    "strict": true,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn sends_non_strict_schemas_without_strict() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"note": {"type": "string"}},
+            "additionalProperties": false
+        });
+        let text = create_text_param_for_request(None, Some(&schema));
+        let v = serde_json::to_value(&text).expect("json");
+        assert_eq!(v["format"]["strict"], false);
+    }
+
     #[test]
     fn omits_text_when_not_set() {
         let input: Vec<ResponseItem> = vec![];
@@ -1,12 +1,14 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::event_mapping::map_response_item_to_event_messages;
@@ -29,6 +31,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde_json;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::task::AbortHandle;
 use tracing::debug;
 use tracing::error;
@@ -42,10 +45,15 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::budget::BudgetExceeded;
+use crate::budget::BudgetTracker;
+use crate::checkpoint::create_checkpoint;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::Budget;
+use crate::config_types::McpToolApproval;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
 use crate::environment_context::EnvironmentContext;
@@ -65,14 +73,26 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::exec_policy::ExecPolicy;
+use crate::hooks::HookEvent;
+use crate::hooks::Hooks;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_resources::LIST_MCP_RESOURCES_TOOL_NAME;
+use crate::mcp_resources::READ_MCP_RESOURCE_TOOL_NAME;
+use crate::mcp_resources::handle_list_mcp_resources;
+use crate::mcp_resources::handle_read_mcp_resource;
+use crate::mcp_resources::resolve_resource_mentions;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
+use crate::model_provider_info::WireApi;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::output_schema::MAX_OUTPUT_SCHEMA_RETRIES;
+use crate::output_schema::output_schema_retry_message;
+use crate::output_schema::validate_output;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
@@ -93,6 +113,8 @@ use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpInvocation;
+use crate::protocol::McpToolApprovalRequestEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
@@ -102,12 +124,15 @@ use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::SubAgentCompletedEvent;
 use crate::protocol::SubAgentFailedEvent;
+use crate::protocol::SubAgentSource;
 use crate::protocol::SubAgentStartedEvent;
 use crate::protocol::SubAgentStatusEvent;
 use crate::protocol::Submission;
 use crate::protocol::TaskCompleteEvent;
+use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::TurnUndoneEvent;
 use crate::protocol::WebSearchBeginEvent;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
@@ -115,7 +140,11 @@ use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
 use crate::shell;
+use crate::sub_agent;
+use crate::sub_agent::SubAgentLaunchArgs;
 use crate::turn_diff_tracker::TurnDiffTracker;
+use crate::turn_undo::TurnUndo;
+use crate::turn_undo::WorktreeSnapshot;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
 use crate::user_notification::UserNotification;
@@ -172,6 +201,20 @@ pub(crate) const MODEL_FORMAT_HEAD_LINES: usize = MODEL_FORMAT_MAX_LINES / 2;
 pub(crate) const MODEL_FORMAT_TAIL_LINES: usize = MODEL_FORMAT_MAX_LINES - MODEL_FORMAT_HEAD_LINES; // 128
 pub(crate) const MODEL_FORMAT_HEAD_BYTES: usize = MODEL_FORMAT_MAX_BYTES / 2;
 
+/// Instructions and user input for the request that summarizes the conversation.
+const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
+const SUMMARIZATION_TRIGGER: &str = "Start Summarization";
+
+/// Number of completed turns `Op::UndoTurn` can step back through.
+const MAX_UNDO_TURNS: usize = 20;
+
+/// How long to wait for an aborted task to stop before giving up on making
+/// its turn undoable.
+const ABORTED_TASK_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Appended after an automatic compaction in the middle of a task so the model
+/// picks the task back up from the summary.
+const CONTINUE_AFTER_COMPACTION: &str = "The conversation history was compacted into the summary above. Continue working on the current task.";
+
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
     pub async fn spawn(
@@ -263,11 +306,68 @@ impl Codex {
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// (server, tool) pairs the user approved for the rest of the session.
+    approved_mcp_tools: HashSet<(String, String)>,
     current_task: Option<AgentTask>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
+    /// Sub-agents launched with `background: true`, keyed by the call_id of
+    /// their `sub_agent_launch`. Entries are removed once their outcome has
+    /// been collected by `sub_agent_await`.
+    sub_agents: HashMap<String, BackgroundSubAgent>,
+    /// Fallback provider in use after the primary provider became
+    /// unavailable; cleared once `fallback_cooldown` has elapsed.
+    active_fallback: Option<ActiveFallback>,
+    /// Wire API that produced the reasoning items in `history`.
+    reasoning_wire_api: Option<WireApi>,
+    /// Undo information for completed turns that changed the workspace, most
+    /// recent last.
+    undo_stack: Vec<TurnUndo>,
+    /// Task aborted last. It hands over `aborted_turn` once it has stopped.
+    aborted_task: Option<AbortHandle>,
+    /// Start-of-turn state of an aborted turn, not yet on `undo_stack`.
+    aborted_turn: Option<AbortedTurn>,
+}
+
+/// What an aborted turn needs to become undoable once its task has stopped.
+struct AbortedTurn {
+    tracker: TurnDiffTracker,
+    snapshot: Option<WorktreeSnapshot>,
+}
+
+/// Entry of `fallback_providers` the session switched to, and when.
+#[derive(Clone, Copy)]
+struct ActiveFallback {
+    index: usize,
+    since: Instant,
+}
+
+/// A sub-agent running concurrently with the turn that launched it.
+struct BackgroundSubAgent {
+    /// Submission id of the turn that launched the sub-agent; used as the
+    /// event id for any events emitted on its behalf after an interrupt.
+    parent_sub_id: String,
+    label: String,
+    outcome: SubAgentOutcomeReceiver,
+    handle: AbortHandle,
+}
+
+/// Resolves to `Some` once the sub-agent has finished.
+type SubAgentOutcomeReceiver = watch::Receiver<Option<SubAgentOutcome>>;
+
+/// Final result of a sub-agent run.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum SubAgentOutcome {
+    Completed {
+        summary: String,
+        commands: Vec<String>,
+    },
+    Failed {
+        error: String,
+    },
 }
 
 /// Context for an initialized model agent
@@ -285,14 +385,28 @@ pub(crate) struct Session {
     /// External notifier command (will be passed as args to exec()). When
     /// `None` this feature is disabled.
     notify: Option<Vec<String>>,
+    /// Lifecycle hooks from the `[hooks]` config table.
+    hooks: Hooks,
 
     /// Optional rollout recorder for persisting the conversation transcript so
     /// sessions can be replayed or inspected later.
     rollout: Mutex<Option<RolloutRecorder>>,
+    /// Codex home directory, where sub-agent rollouts are written.
+    codex_home: PathBuf,
     state: Mutex<State>,
+    /// Consumption against `session_budget`, shared with sub-agents.
+    budget: Mutex<BudgetTracker>,
+    /// Default limits for each sub-agent.
+    sub_agent_budget: Budget,
+    /// Declarative allow/deny rules consulted before running commands.
+    exec_policy: ExecPolicy,
+    /// Context window usage, in percent, that triggers automatic compaction.
+    auto_compact_threshold_percent: Option<u8>,
     codex_linux_sandbox_exe: Option<PathBuf>,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
+    /// Whether a worktree checkpoint is recorded before every user message.
+    checkpoints: bool,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -408,7 +522,7 @@ impl Session {
         // - load history metadata
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
-        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
+        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone(), tx_event.clone());
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
 
@@ -453,6 +567,15 @@ impl Session {
             }
         }
 
+        let (exec_policy, exec_policy_errors) = ExecPolicy::load(&config.exec_policy_dirs);
+        for message in exec_policy_errors {
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
         // Now that the conversation id is final (may have been updated by resume),
         // construct the model client.
         let client = ModelClient::new(
@@ -463,7 +586,7 @@ impl Session {
             model_reasoning_summary,
             conversation_id,
         );
-        let turn_context = TurnContext {
+        let mut turn_context = TurnContext {
             client,
             tools_config: ToolsConfig::new(&ToolsConfigParams {
                 model_family: &config.model_family,
@@ -475,6 +598,7 @@ impl Session {
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                agents: &config.agents,
             }),
             user_instructions,
             base_instructions,
@@ -484,6 +608,7 @@ impl Session {
             cwd,
             default_exec_timeout_ms: config.default_exec_timeout_ms,
         };
+        turn_context.tools_config.mcp_resource_tools = mcp_connection_manager.has_resources();
         let sess = Arc::new(Session {
             conversation_id,
             tx_event: tx_event.clone(),
@@ -491,11 +616,18 @@ impl Session {
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notify,
+            hooks: Hooks::new(config.hooks.clone()),
             state: Mutex::new(state),
             rollout: Mutex::new(Some(rollout_recorder)),
+            codex_home: config.codex_home.clone(),
+            budget: Mutex::new(BudgetTracker::new(config.session_budget)),
+            sub_agent_budget: config.sub_agent_budget,
+            exec_policy,
+            auto_compact_threshold_percent: config.auto_compact_threshold_percent,
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            checkpoints: config.checkpoints,
         });
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
@@ -520,6 +652,13 @@ impl Session {
         for event in events {
             sess.send_event(event).await;
         }
+        sess.hooks.spawn(
+            conversation_id,
+            turn_context.cwd.clone(),
+            HookEvent::SessionStart {
+                model: turn_context.client.get_model(),
+            },
+        );
 
         Ok((sess, turn_context))
     }
@@ -527,6 +666,7 @@ impl Session {
     pub fn set_task(&self, task: AgentTask) {
         let mut state = self.state.lock_unchecked();
         if let Some(current_task) = state.current_task.take() {
+            state.aborted_task = Some(current_task.handle.clone());
             current_task.abort(TurnAbortReason::Replaced);
         }
         state.current_task = Some(task);
@@ -644,6 +784,36 @@ impl Session {
         rx_approve
     }
 
+    pub async fn request_mcp_tool_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        invocation: McpInvocation,
+        reason: Option<String>,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        // Add the tx_approve callback to the map before sending the request.
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut state = self.state.lock_unchecked();
+            state.pending_approvals.insert(sub_id, tx_approve)
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending approval for sub_id: {event_id}");
+        }
+
+        let event = Event {
+            id: event_id,
+            msg: EventMsg::McpToolApprovalRequest(McpToolApprovalRequestEvent {
+                call_id,
+                invocation,
+                reason,
+            }),
+        };
+        self.send_event(event).await;
+        rx_approve
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut state = self.state.lock_unchecked();
@@ -664,6 +834,18 @@ impl Session {
         state.approved_commands.insert(cmd);
     }
 
+    pub(crate) fn add_approved_mcp_tool(&self, server: String, tool: String) {
+        let mut state = self.state.lock_unchecked();
+        state.approved_mcp_tools.insert((server, tool));
+    }
+
+    pub(crate) fn is_mcp_tool_approved(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock_unchecked();
+        state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
     /// Records input items: always append to conversation history and
     /// persist these response items to rollout.
     async fn record_conversation_items(&self, items: &[ResponseItem]) {
@@ -714,6 +896,60 @@ impl Session {
         }
     }
 
+    /// Count a model request against the session budget.
+    fn start_budgeted_turn(&self) -> Result<(), BudgetExceeded> {
+        self.budget.lock_unchecked().start_turn()
+    }
+
+    /// Time left in the session budget, measured from session start, and its
+    /// limit.
+    fn budget_time_left(&self) -> Option<(Duration, Duration)> {
+        self.budget.lock_unchecked().time_left()
+    }
+
+    async fn notify_budget_exceeded(&self, sub_id: &str, exceeded: BudgetExceeded) {
+        info!("Session budget exceeded: {exceeded}");
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::Error(ErrorEvent {
+                message: format!("session budget exceeded: {exceeded}"),
+            }),
+        };
+        self.send_event(event).await;
+    }
+
+    fn record_budget_tokens(&self, token_usage: Option<&TokenUsage>) {
+        if let Some(usage) = token_usage {
+            self.budget.lock_unchecked().record_tokens(usage);
+        }
+    }
+
+    /// Open a child rollout for the transcript of sub-agent `agent_id`. On
+    /// failure the sub-agent still runs, just without a persisted transcript.
+    async fn create_sub_agent_rollout(
+        &self,
+        agent_id: &str,
+        label: &str,
+        sa_context: &TurnContext,
+    ) -> Option<RolloutRecorder> {
+        let params = RolloutRecorderParams::new_sub_agent(
+            ConversationId::default(),
+            sa_context.user_instructions.clone(),
+            SubAgentSource {
+                parent_id: self.conversation_id,
+                sub_id: agent_id.to_string(),
+                label: label.to_string(),
+            },
+        );
+        match RolloutRecorder::new_in(&self.codex_home, sa_context.cwd.clone(), params).await {
+            Ok(recorder) => Some(recorder),
+            Err(e) => {
+                error!("failed to initialize sub-agent rollout recorder: {e:#}");
+                None
+            }
+        }
+    }
+
     /// Record a user input item to conversation history and also persist a
     /// corresponding UserMessage EventMsg to rollout.
     async fn record_input_and_rollout_usermsg(&self, response_input: &ResponseInputItem) {
@@ -904,6 +1140,161 @@ impl Session {
         self.send_event(event).await;
     }
 
+    /// Returns how full the context window was after the last model request,
+    /// in percent, when that crosses the auto-compaction threshold.
+    fn context_window_over_threshold(&self, turn_context: &TurnContext) -> Option<u64> {
+        let threshold = u64::from(self.auto_compact_threshold_percent?);
+        let context_window = turn_context.client.get_model_context_window()?;
+        if context_window == 0 {
+            return None;
+        }
+        let used = self
+            .state
+            .lock_unchecked()
+            .token_info
+            .as_ref()?
+            .last_token_usage
+            .tokens_in_context_window();
+        let percent = used.saturating_mul(100) / context_window;
+        (percent >= threshold).then_some(percent)
+    }
+
+    /// Reverts the workspace changes of the most recent turn that made any.
+    /// The turn stays undoable if the attempt fails.
+    async fn undo_last_turn(&self) -> Result<Vec<PathBuf>, String> {
+        if self.state.lock_unchecked().current_task.is_some() {
+            return Err("cannot undo while a turn is running".to_string());
+        }
+        self.finish_aborted_turn().await;
+        let undo = {
+            let mut state = self.state.lock_unchecked();
+            if state.current_task.is_some() {
+                return Err("cannot undo while a turn is running".to_string());
+            }
+            state
+                .undo_stack
+                .pop()
+                .ok_or_else(|| "no turn to undo".to_string())?
+        };
+        match undo.restore().await {
+            Ok(paths) => Ok(paths),
+            Err(e) => {
+                self.state.lock_unchecked().undo_stack.push(undo);
+                Err(format!("cannot undo the last turn: {e:#}"))
+            }
+        }
+    }
+
+    fn push_undo(&self, undo: TurnUndo) {
+        let mut state = self.state.lock_unchecked();
+        if state.undo_stack.len() == MAX_UNDO_TURNS {
+            state.undo_stack.remove(0);
+        }
+        state.undo_stack.push(undo);
+    }
+
+    /// Puts the last aborted turn on the undo stack. Its task hands over the
+    /// turn's start-of-turn state when it is dropped, which happens some time
+    /// after the abort, so wait for the task to stop first.
+    async fn finish_aborted_turn(&self) {
+        let task = self.state.lock_unchecked().aborted_task.take();
+        if let Some(task) = task {
+            let stopped = async {
+                while !task.is_finished() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            };
+            if tokio::time::timeout(ABORTED_TASK_STOP_TIMEOUT, stopped)
+                .await
+                .is_err()
+            {
+                warn!("aborted task did not stop in time; its turn cannot be undone");
+            }
+        }
+        let aborted_turn = self.state.lock_unchecked().aborted_turn.take();
+        if let Some(AbortedTurn { tracker, snapshot }) = aborted_turn
+            && let Some(undo) = TurnUndo::finish(&tracker, snapshot.as_ref()).await
+        {
+            self.push_undo(undo);
+        }
+    }
+
+    /// Returns the client to send the next request with: the active fallback
+    /// provider while its cooldown runs, otherwise the primary provider.
+    async fn active_model_client(
+        &self,
+        turn_context: &TurnContext,
+        sub_id: &str,
+    ) -> (ModelClient, Option<usize>) {
+        let (active, cooled_down) = {
+            let mut state = self.state.lock_unchecked();
+            match state.active_fallback {
+                Some(fallback)
+                    if fallback.since.elapsed() >= turn_context.client.fallback_cooldown() =>
+                {
+                    state.active_fallback = None;
+                    (None, true)
+                }
+                active => (active.map(|fallback| fallback.index), false),
+            }
+        };
+        if cooled_down {
+            let primary = turn_context.client.get_provider().name;
+            self.notify_background_event(
+                sub_id,
+                format!("returning to primary model provider `{primary}` after cooldown"),
+            )
+            .await;
+        }
+        match active.and_then(|index| turn_context.client.fallback_client(index)) {
+            Some(client) => (client, active),
+            None => (turn_context.client.clone(), None),
+        }
+    }
+
+    /// Drops reasoning items from `input` and the history when `wire_api`
+    /// differs from the one that produced them: their encrypted content, or
+    /// signature, is only understood by the API that produced it.
+    fn drop_foreign_reasoning(&self, wire_api: WireApi, input: &mut Vec<ResponseItem>) {
+        let mut state = self.state.lock_unchecked();
+        if state
+            .reasoning_wire_api
+            .is_some_and(|produced_by| produced_by != wire_api)
+        {
+            state.history.remove_reasoning();
+            input.retain(|item| !matches!(item, ResponseItem::Reasoning { .. }));
+        }
+        state.reasoning_wire_api = Some(wire_api);
+    }
+
+    /// Switches to the fallback provider after `current`, or the first one
+    /// when the primary failed. Returns `None` once the list is exhausted.
+    async fn switch_to_next_fallback(
+        &self,
+        turn_context: &TurnContext,
+        sub_id: &str,
+        failed: &ModelClient,
+        current: Option<usize>,
+        error: &CodexErr,
+    ) -> Option<(ModelClient, usize)> {
+        let index = current.map_or(0, |index| index + 1);
+        let client = turn_context.client.fallback_client(index)?;
+        self.state.lock_unchecked().active_fallback = Some(ActiveFallback {
+            index,
+            since: Instant::now(),
+        });
+        let from = failed.get_provider().name;
+        let to = client.get_provider().name;
+        let model = client.get_model();
+        warn!("model provider `{from}` unavailable ({error}); switching to `{to}`");
+        self.notify_background_event(
+            sub_id,
+            format!("model provider `{from}` unavailable ({error}); switching to `{to}` ({model})"),
+        )
+        .await;
+        Some((client, index))
+    }
+
     async fn notify_stream_error(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
@@ -954,14 +1345,91 @@ impl Session {
             .await
     }
 
+    pub(crate) fn mcp_tool_approval(&self, server: &str, tool: &str) -> Option<McpToolApproval> {
+        self.mcp_connection_manager.tool_approval(server, tool)
+    }
+
+    pub(crate) fn is_read_only_mcp_tool(&self, server: &str, tool: &str) -> bool {
+        self.mcp_connection_manager.is_read_only_tool(server, tool)
+    }
+
     fn interrupt_task(&self) {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock_unchecked();
         state.pending_approvals.clear();
         state.pending_input.clear();
         if let Some(task) = state.current_task.take() {
+            state.aborted_task = Some(task.handle.clone());
             task.abort(TurnAbortReason::Interrupted);
         }
+        for (agent_id, agent) in state.sub_agents.drain() {
+            if agent.handle.is_finished() {
+                continue;
+            }
+            agent.handle.abort();
+            // Let UIs clear the sub-agent's working tree. The event channel is
+            // unbounded so this never blocks.
+            let _ = self.tx_event.try_send(Event {
+                id: agent.parent_sub_id,
+                msg: EventMsg::SubAgentFailed(SubAgentFailedEvent {
+                    sub_id: agent_id,
+                    label: agent.label,
+                    error: "interrupted".to_string(),
+                }),
+            });
+        }
+    }
+
+    fn register_sub_agent(&self, agent_id: String, agent: BackgroundSubAgent) {
+        let mut state = self.state.lock_unchecked();
+        if let Some(prev) = state.sub_agents.insert(agent_id.clone(), agent) {
+            warn!("Replacing background sub-agent with duplicate id: {agent_id}");
+            prev.handle.abort();
+        }
+    }
+
+    /// Returns outcome receivers for the requested background sub-agents, or
+    /// for every outstanding one when `agent_ids` is `None`.
+    fn sub_agent_receivers(
+        &self,
+        agent_ids: Option<&[String]>,
+    ) -> Result<Vec<(String, SubAgentOutcomeReceiver)>, String> {
+        let state = self.state.lock_unchecked();
+        match agent_ids {
+            Some(ids) => ids
+                .iter()
+                .map(|id| match state.sub_agents.get(id) {
+                    Some(agent) => Ok((id.clone(), agent.outcome.clone())),
+                    None => Err(format!("unknown sub-agent id: {id}")),
+                })
+                .collect(),
+            None => {
+                let mut receivers: Vec<_> = state
+                    .sub_agents
+                    .iter()
+                    .map(|(id, agent)| (id.clone(), agent.outcome.clone()))
+                    .collect();
+                receivers.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(receivers)
+            }
+        }
+    }
+
+    /// Removes a finished background sub-agent, returning its label and
+    /// outcome. Sub-agents that are still running are left in place.
+    fn take_finished_sub_agent(&self, agent_id: &str) -> Option<(String, SubAgentOutcome)> {
+        let mut state = self.state.lock_unchecked();
+        let agent = state.sub_agents.get(agent_id)?;
+        let outcome = match agent.outcome.borrow().clone() {
+            Some(outcome) => outcome,
+            // The task was aborted before it could report an outcome.
+            None if agent.handle.is_finished() => SubAgentOutcome::Failed {
+                error: "sub-agent was aborted before completing".to_string(),
+            },
+            None => return None,
+        };
+        let agent = state.sub_agents.remove(agent_id)?;
+        Some((agent.label, outcome))
     }
 
     /// Spawn the configured notifier (if any) with the given JSON payload as
@@ -1148,7 +1616,7 @@ async fn submission_loop(
                     .unwrap_or(prev.sandbox_policy.clone());
                 let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
 
-                let tools_config = ToolsConfig::new(&ToolsConfigParams {
+                let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
                     model_family: &effective_family,
                     approval_policy: new_approval_policy,
                     sandbox_policy: new_sandbox_policy.clone(),
@@ -1158,7 +1626,9 @@ async fn submission_loop(
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    agents: &config.agents,
                 });
+                tools_config.mcp_resource_tools = prev.tools_config.mcp_resource_tools;
 
                 let new_turn_context = TurnContext {
                     client,
@@ -1235,7 +1705,7 @@ async fn submission_loop(
                         sess.conversation_id,
                     );
 
-                    let fresh_turn_context = TurnContext {
+                    let mut fresh_turn_context = TurnContext {
                         client,
                         tools_config: ToolsConfig::new(&ToolsConfigParams {
                             model_family: &model_family,
@@ -1249,6 +1719,7 @@ async fn submission_loop(
                             include_view_image_tool: config.include_view_image_tool,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
+                            agents: &config.agents,
                         }),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1258,6 +1729,8 @@ async fn submission_loop(
                         cwd,
                         default_exec_timeout_ms: turn_context.default_exec_timeout_ms,
                     };
+                    fresh_turn_context.tools_config.mcp_resource_tools =
+                        turn_context.tools_config.mcp_resource_tools;
                     // TODO: record the new environment context in the conversation history
                     // no current task, spawn a new one with the per‑turn context
                     let task =
@@ -1277,6 +1750,12 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other),
             },
+            Op::McpToolApproval { id, decision } => match decision {
+                ReviewDecision::Abort => {
+                    sess.interrupt_task();
+                }
+                other => sess.notify_approval(&id, other),
+            },
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1326,14 +1805,52 @@ async fn submission_loop(
 
                 // This is a cheap lookup from the connection manager's cache.
                 let tools = sess.mcp_connection_manager.list_all_tools();
+                let servers = sess.mcp_connection_manager.server_statuses();
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::McpListToolsResponse(
-                        crate::protocol::McpListToolsResponseEvent { tools },
+                        crate::protocol::McpListToolsResponseEvent { tools, servers },
+                    ),
+                };
+                sess.send_event(event).await;
+            }
+            Op::ListMcpResources => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::McpListResourcesResponse(
+                        crate::protocol::McpListResourcesResponseEvent {
+                            resources: sess.mcp_connection_manager.list_all_resources(),
+                        },
                     ),
                 };
                 sess.send_event(event).await;
             }
+            Op::GetMcpPrompt {
+                server,
+                name,
+                arguments,
+            } => {
+                let sess_clone = sess.clone();
+                let sub_id = sub.id.clone();
+                tokio::spawn(async move {
+                    let result = sess_clone
+                        .mcp_connection_manager
+                        .get_prompt(&server, &name, arguments)
+                        .await
+                        .map_err(|e| format!("{e:#}"));
+                    let event = Event {
+                        id: sub_id,
+                        msg: EventMsg::GetMcpPromptResponse(
+                            crate::protocol::GetMcpPromptResponseEvent {
+                                server,
+                                name,
+                                result,
+                            },
+                        ),
+                    };
+                    sess_clone.send_event(event).await;
+                });
+            }
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
 
@@ -1348,17 +1865,15 @@ async fn submission_loop(
                     id: sub_id,
                     msg: EventMsg::ListCustomPromptsResponse(ListCustomPromptsResponseEvent {
                         custom_prompts,
+                        mcp_prompts: sess.mcp_connection_manager.list_all_prompts(),
                     }),
                 };
                 sess.send_event(event).await;
             }
             Op::Compact => {
-                // Create a summarization request as user input
-                const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
-
                 // Attempt to inject input into current task
                 if let Err(items) = sess.inject_input(vec![InputItem::Text {
-                    text: "Start Summarization".to_string(),
+                    text: SUMMARIZATION_TRIGGER.to_string(),
                 }]) {
                     let task = AgentTask::compact(
                         sess.clone(),
@@ -1370,6 +1885,17 @@ async fn submission_loop(
                     sess.set_task(task);
                 }
             }
+            Op::UndoTurn => {
+                let msg = match sess.undo_last_turn().await {
+                    Ok(paths) => EventMsg::TurnUndone(TurnUndoneEvent { paths }),
+                    Err(message) => EventMsg::Error(ErrorEvent { message }),
+                };
+                sess.send_event(Event {
+                    id: sub.id.clone(),
+                    msg,
+                })
+                .await;
+            }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
 
@@ -1461,16 +1987,69 @@ async fn run_task(
     };
     sess.send_event(event).await;
 
+    // An interrupted previous turn must be put on the undo stack before this
+    // one changes the worktree.
+    sess.finish_aborted_turn().await;
+
+    // Taken before the model runs anything so `Op::UndoTurn` can also revert
+    // changes made by shell commands. The checkpoint reuses the snapshot;
+    // both run while resource mentions are resolved.
+    let snapshot_and_checkpoint = async {
+        let snapshot = WorktreeSnapshot::capture(&turn_context.cwd).await?;
+        let checkpoint = if sess.checkpoints {
+            create_checkpoint(&snapshot, sess.conversation_id)
+                .await
+                .inspect_err(|e| warn!("failed to record worktree checkpoint: {e:#}"))
+                .ok()
+        } else {
+            None
+        };
+        Some((snapshot, checkpoint))
+    };
+    let (snapshot_and_checkpoint, (attachments, mention_errors)) = tokio::join!(
+        snapshot_and_checkpoint,
+        resolve_resource_mentions(&sess.mcp_connection_manager, &input)
+    );
+    for message in mention_errors {
+        sess.notify_background_event(&sub_id, message).await;
+    }
+    let (worktree_snapshot, checkpoint) = snapshot_and_checkpoint.unzip();
+    // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
+    // many turns, from the perspective of the user, it is a single turn.
+    let mut undo = TurnUndoGuard {
+        sess: Arc::clone(&sess),
+        tracker: TurnDiffTracker::new(),
+        snapshot: worktree_snapshot,
+        recorded: false,
+    };
+
+    // Recorded right before the user message so backtracking to it can offer
+    // to bring the files back.
+    if let Some(checkpoint) = checkpoint.flatten() {
+        sess.persist_rollout_items(&[RolloutItem::Checkpoint(checkpoint)])
+            .await;
+    }
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     sess.record_input_and_rollout_usermsg(&initial_input_for_turn)
         .await;
+    // Resources mentioned in the message follow it as a separate message so
+    // the user's own text is recorded as typed.
+    if !attachments.is_empty() {
+        let attachments: ResponseInputItem = ResponseInputItem::from(attachments);
+        sess.record_conversation_items(&[attachments.into()]).await;
+    }
 
     let mut last_agent_message: Option<String> = None;
-    // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
-    // many turns, from the perspective of the user, it is a single turn.
-    let mut turn_diff_tracker = TurnDiffTracker::new();
+    let output_schema = turn_context.client.get_output_schema();
+    let mut output_schema_retries = 0;
 
     loop {
+        if let Err(exceeded) = sess.start_budgeted_turn() {
+            sess.notify_budget_exceeded(&sub_id, exceeded).await;
+            break;
+        }
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1501,15 +2080,27 @@ async fn run_task(
                 })
             })
             .collect();
-        match run_turn(
+        // A single stream or tool call may run past the session's time limit,
+        // so the turn is cut off at the deadline rather than checked after.
+        let turn = run_turn(
             &sess,
             turn_context,
-            &mut turn_diff_tracker,
+            &mut undo.tracker,
             sub_id.clone(),
             turn_input,
-        )
-        .await
-        {
+        );
+        let turn_result = match sess.budget_time_left() {
+            Some((left, limit)) => match tokio::time::timeout(left, turn).await {
+                Ok(result) => result,
+                Err(_) => {
+                    sess.notify_budget_exceeded(&sub_id, BudgetExceeded::Duration { limit })
+                        .await;
+                    break;
+                }
+            },
+            None => turn.await,
+        };
+        match turn_result {
             Ok(turn_output) => {
                 let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
                 let mut responses = Vec::<ResponseInputItem>::new();
@@ -1610,18 +2201,61 @@ async fn run_task(
                         .await;
                 }
 
+                let context_window_used = sess.context_window_over_threshold(turn_context);
+
                 if responses.is_empty() {
                     debug!("Turn completed");
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
+                    if let Some(schema) = &output_schema
+                        && output_schema_retries < MAX_OUTPUT_SCHEMA_RETRIES
+                        && let Err(e) = validate_output(
+                            schema,
+                            last_agent_message.as_deref().unwrap_or_default(),
+                        )
+                    {
+                        output_schema_retries += 1;
+                        sess.notify_background_event(
+                            &sub_id,
+                            format!(
+                                "final message does not match the output schema ({e}); asking the model to fix it"
+                            ),
+                        )
+                        .await;
+                        sess.record_conversation_items(&[ResponseItem::Message {
+                            id: None,
+                            role: "user".to_string(),
+                            content: vec![ContentItem::InputText {
+                                text: output_schema_retry_message(&e),
+                            }],
+                        }])
+                        .await;
+                        continue;
+                    }
+                    sess.hooks.spawn(
+                        sess.conversation_id,
+                        turn_context.cwd.clone(),
+                        HookEvent::TurnComplete {
+                            turn_id: sub_id.clone(),
+                            input_messages: turn_input_messages.clone(),
+                            last_assistant_message: last_agent_message.clone(),
+                        },
+                    );
                     sess.maybe_notify(UserNotification::AgentTurnComplete {
                         turn_id: sub_id.clone(),
                         input_messages: turn_input_messages,
                         last_assistant_message: last_agent_message.clone(),
                     });
+                    if let Some(percent_used) = context_window_used {
+                        auto_compact(&sess, turn_context, &sub_id, percent_used, false).await;
+                    }
                     break;
                 }
+
+                if let Some(percent_used) = context_window_used {
+                    auto_compact(&sess, turn_context, &sub_id, percent_used, true).await;
+                }
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
@@ -1637,6 +2271,7 @@ async fn run_task(
             }
         }
     }
+    undo.record().await;
     sess.remove_task(&sub_id);
     let event = Event {
         id: sub_id,
@@ -1645,8 +2280,38 @@ async fn run_task(
     sess.send_event(event).await;
 }
 
+/// Owns what `run_task` needs to make its turn undoable. A task that is
+/// aborted never reaches [`TurnUndoGuard::record`], so dropping the guard
+/// hands the state over to the session instead.
+struct TurnUndoGuard {
+    sess: Arc<Session>,
+    tracker: TurnDiffTracker,
+    snapshot: Option<WorktreeSnapshot>,
+    recorded: bool,
+}
+
+impl TurnUndoGuard {
+    async fn record(mut self) {
+        if let Some(undo) = TurnUndo::finish(&self.tracker, self.snapshot.as_ref()).await {
+            self.sess.push_undo(undo);
+        }
+        self.recorded = true;
+    }
+}
+
+impl Drop for TurnUndoGuard {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.sess.state.lock_unchecked().aborted_turn = Some(AbortedTurn {
+                tracker: std::mem::take(&mut self.tracker),
+                snapshot: self.snapshot.take(),
+            });
+        }
+    }
+}
+
 async fn run_turn(
-    sess: &Session,
+    sess: &Arc<Session>,
     turn_context: &TurnContext,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
@@ -1657,25 +2322,36 @@ async fn run_turn(
         Some(sess.mcp_connection_manager.list_all_tools()),
     );
 
-    let prompt = Prompt {
+    let mut prompt = Prompt {
         input,
         tools,
         base_instructions_override: turn_context.base_instructions.clone(),
+        output_schema: turn_context.client.get_output_schema(),
     };
 
+    let (mut client, mut fallback_index) = sess.active_model_client(turn_context, &sub_id).await;
     let mut retries = 0;
     loop {
-        match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt).await {
+        sess.drop_foreign_reasoning(client.get_provider().wire_api, &mut prompt.input);
+        match try_run_turn(
+            sess,
+            turn_context,
+            &client,
+            turn_diff_tracker,
+            &sub_id,
+            &prompt,
+        )
+        .await
+        {
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
-            Err(e @ (CodexErr::UsageLimitReached(_) | CodexErr::UsageNotIncluded)) => {
-                return Err(e);
-            }
+            Err(e @ CodexErr::UsageNotIncluded) => return Err(e),
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
-                let max_retries = turn_context.client.get_provider().stream_max_retries();
-                if retries < max_retries {
+                let max_retries = client.get_provider().stream_max_retries();
+                let retryable = !matches!(e, CodexErr::UsageLimitReached(_));
+                if retryable && retries < max_retries {
                     retries += 1;
                     let delay = match e {
                         CodexErr::Stream(_, Some(delay)) => delay,
@@ -1697,6 +2373,14 @@ async fn run_turn(
                     .await;
 
                     tokio::time::sleep(delay).await;
+                } else if e.is_provider_unavailable()
+                    && let Some((next, index)) = sess
+                        .switch_to_next_fallback(turn_context, &sub_id, &client, fallback_index, &e)
+                        .await
+                {
+                    client = next;
+                    fallback_index = Some(index);
+                    retries = 0;
                 } else {
                     return Err(e);
                 }
@@ -1716,8 +2400,9 @@ struct ProcessedResponseItem {
 }
 
 async fn try_run_turn(
-    sess: &Session,
+    sess: &Arc<Session>,
     turn_context: &TurnContext,
+    client: &ModelClient,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
     prompt: &Prompt,
@@ -1781,12 +2466,12 @@ async fn try_run_turn(
         cwd: turn_context.cwd.clone(),
         approval_policy: turn_context.approval_policy,
         sandbox_policy: turn_context.sandbox_policy.clone(),
-        model: turn_context.client.get_model(),
-        effort: turn_context.client.get_reasoning_effort(),
-        summary: turn_context.client.get_reasoning_summary(),
+        model: client.get_model(),
+        effort: client.get_reasoning_effort(),
+        summary: client.get_reasoning_summary(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
-    let mut stream = turn_context.client.clone().stream(&prompt).await?;
+    let mut stream = client.clone().stream(&prompt).await?;
 
     let mut output = Vec::new();
 
@@ -1844,11 +2529,12 @@ async fn try_run_turn(
                     let info = TokenUsageInfo::new_or_append(
                         &st.token_info,
                         &token_usage,
-                        turn_context.client.get_model_context_window(),
+                        client.get_model_context_window(),
                     );
                     st.token_info = info.clone();
                     info
                 };
+                sess.record_budget_tokens(token_usage.as_ref());
                 let _ = sess
                     .send_event(Event {
                         id: sub_id.to_string(),
@@ -1920,31 +2606,77 @@ async fn run_compact_task(
     };
     sess.send_event(start_event).await;
 
-    let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    let turn_input: Vec<ResponseItem> =
-        sess.turn_input_with_history(vec![initial_input_for_turn.clone().into()]);
-
-    let prompt = Prompt {
-        input: turn_input,
-        tools: Vec::new(),
-        base_instructions_override: Some(compact_instructions.clone()),
-    };
+    match compact_history(&sess, turn_context, &sub_id, input, &compact_instructions).await {
+        Ok(()) => {}
+        Err(CodexErr::Interrupted) => return,
+        Err(e) => {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: e.to_string(),
+                }),
+            };
+            sess.send_event(event).await;
+            return;
+        }
+    }
+
+    sess.remove_task(&sub_id);
+
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::AgentMessage(AgentMessageEvent {
+            message: "Compact task completed".to_string(),
+        }),
+    };
+    sess.send_event(event).await;
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+        }),
+    };
+    sess.send_event(event).await;
+}
+
+/// Summarizes the conversation with `compact_instructions`, replaces the
+/// history with the summary and records a `Compacted` rollout item.
+async fn compact_history(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    input: Vec<InputItem>,
+    compact_instructions: &str,
+) -> CodexResult<()> {
+    let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
+    let turn_input: Vec<ResponseItem> =
+        sess.turn_input_with_history(vec![initial_input_for_turn.into()]);
+
+    let mut prompt = Prompt {
+        input: turn_input,
+        tools: Vec::new(),
+        base_instructions_override: Some(compact_instructions.to_string()),
+        output_schema: None,
+    };
 
-    let max_retries = turn_context.client.get_provider().stream_max_retries();
+    // Summarize with whichever provider normal turns are currently using.
+    let (client, _) = sess.active_model_client(turn_context, sub_id).await;
+    sess.drop_foreign_reasoning(client.get_provider().wire_api, &mut prompt.input);
+    let max_retries = client.get_provider().stream_max_retries();
     let mut retries = 0;
 
     loop {
-        let attempt_result = drain_to_completed(&sess, turn_context, &sub_id, &prompt).await;
+        let attempt_result = drain_to_completed(sess, turn_context, &client, sub_id, &prompt).await;
 
         match attempt_result {
             Ok(()) => break,
-            Err(CodexErr::Interrupted) => return,
+            Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(e) => {
                 if retries < max_retries {
                     retries += 1;
                     let delay = backoff(retries);
                     sess.notify_stream_error(
-                        &sub_id,
+                        sub_id,
                         format!(
                             "stream error: {e}; retrying {retries}/{max_retries} in {delay:?}…"
                         ),
@@ -1953,21 +2685,12 @@ async fn run_compact_task(
                     tokio::time::sleep(delay).await;
                     continue;
                 } else {
-                    let event = Event {
-                        id: sub_id.clone(),
-                        msg: EventMsg::Error(ErrorEvent {
-                            message: e.to_string(),
-                        }),
-                    };
-                    sess.send_event(event).await;
-                    return;
+                    return Err(e);
                 }
             }
         }
     }
 
-    sess.remove_task(&sub_id);
-
     let rollout_item = {
         let mut state = sess.state.lock_unchecked();
         state.history.keep_last_messages(1);
@@ -1976,25 +2699,52 @@ async fn run_compact_task(
         })
     };
     sess.persist_rollout_items(&[rollout_item]).await;
+    Ok(())
+}
 
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::AgentMessage(AgentMessageEvent {
-            message: "Compact task completed".to_string(),
-        }),
-    };
-    sess.send_event(event).await;
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::TaskComplete(TaskCompleteEvent {
-            last_agent_message: None,
-        }),
-    };
-    sess.send_event(event).await;
+/// Compacts the conversation history within a running task once the context
+/// window has filled past the configured threshold. When `continue_task` is
+/// set, the model is asked to carry on from the summary.
+async fn auto_compact(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    percent_used: u64,
+    continue_task: bool,
+) {
+    sess.notify_background_event(
+        sub_id,
+        format!("Context window is {percent_used}% full; compacting conversation history…"),
+    )
+    .await;
+    let input = vec![InputItem::Text {
+        text: SUMMARIZATION_TRIGGER.to_string(),
+    }];
+    match compact_history(sess, turn_context, sub_id, input, SUMMARIZATION_PROMPT).await {
+        Ok(()) => {
+            if continue_task {
+                sess.record_conversation_items(&[ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: CONTINUE_AFTER_COMPACTION.to_string(),
+                    }],
+                }])
+                .await;
+            }
+            sess.notify_background_event(sub_id, "Conversation history compacted.")
+                .await;
+        }
+        Err(e) => {
+            warn!("automatic compaction failed: {e}");
+            sess.notify_background_event(sub_id, format!("Automatic compaction failed: {e}"))
+                .await;
+        }
+    }
 }
 
 async fn handle_response_item(
-    sess: &Session,
+    sess: &Arc<Session>,
     turn_context: &TurnContext,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
@@ -2176,7 +2926,7 @@ async fn handle_unified_exec_tool_call(
 }
 
 async fn handle_function_call(
-    sess: &Session,
+    sess: &Arc<Session>,
     turn_context: &TurnContext,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
@@ -2184,16 +2934,14 @@ async fn handle_function_call(
     arguments: String,
     call_id: String,
 ) -> ResponseInputItem {
-    if name == "sub_agent_launch" {
-        return handle_sub_agent_launch(
-            sess,
-            turn_context,
-            turn_diff_tracker,
-            sub_id,
-            arguments,
-            call_id,
-        )
-        .await;
+    match name.as_str() {
+        "sub_agent_launch" => {
+            return handle_sub_agent_launch(sess, turn_context, sub_id, arguments, call_id).await;
+        }
+        "sub_agent_await" => {
+            return handle_sub_agent_await(sess, arguments, call_id).await;
+        }
+        _ => {}
     }
     handle_function_call_no_subagent(
         sess,
@@ -2207,7 +2955,7 @@ async fn handle_function_call(
     .await
 }
 
-// Handles all function calls except sub_agent_launch (to avoid async-recursion cycles).
+// Handles all function calls except the sub-agent tools (to avoid async-recursion cycles).
 async fn handle_function_call_no_subagent(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2258,14 +3006,29 @@ async fn handle_function_call_no_subagent(
                 }
             };
 
-            handle_unified_exec_tool_call(
+            // Only a call without a session id starts a command; the others
+            // write to one that is already running.
+            let pre_hook = match args.session_id {
+                Some(_) => None,
+                None => Some(HookEvent::PreExec {
+                    call_id: call_id.clone(),
+                    command: args.input.clone(),
+                }),
+            };
+            let action = handle_unified_exec_tool_call(
                 sess,
-                call_id,
+                call_id.clone(),
                 args.session_id,
                 args.input,
                 args.timeout_ms,
-            )
-            .await
+            );
+            match pre_hook {
+                Some(pre_hook) => {
+                    let action = async { (action.await, None) };
+                    run_with_hooks(sess, &turn_context.cwd, call_id, pre_hook, action).await
+                }
+                None => action.await,
+            }
         }
         "view_image" => {
             #[derive(serde::Deserialize)]
@@ -2297,7 +3060,14 @@ async fn handle_function_call_no_subagent(
             };
             ResponseInputItem::FunctionCallOutput { call_id, output }
         }
-        "sub_agent_launch" => unreachable!("handled in handle_function_call"),
+        // Only reachable from within a sub-agent: nested sub-agents are not supported.
+        "sub_agent_launch" | "sub_agent_await" => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: format!("{name} is not available to sub-agents"),
+                success: Some(false),
+            },
+        },
         "apply_patch" => {
             let args = match serde_json::from_str::<ApplyPatchToolArgs>(&arguments) {
                 Ok(a) => a,
@@ -2330,6 +3100,12 @@ async fn handle_function_call_no_subagent(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        LIST_MCP_RESOURCES_TOOL_NAME => {
+            handle_list_mcp_resources(&sess.mcp_connection_manager, arguments, call_id)
+        }
+        READ_MCP_RESOURCE_TOOL_NAME => {
+            handle_read_mcp_resource(&sess.mcp_connection_manager, arguments, call_id).await
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
@@ -2344,15 +3120,23 @@ async fn handle_function_call_no_subagent(
                     };
                 }
             };
-            let result = sess
-                .session_manager
-                .handle_exec_command_request(exec_params)
-                .await;
-            let function_call_output = crate::exec_command::result_into_payload(result);
-            ResponseInputItem::FunctionCallOutput {
-                call_id,
-                output: function_call_output,
-            }
+            let pre_hook = HookEvent::PreExec {
+                call_id: call_id.clone(),
+                command: exec_params.command(),
+            };
+            let action = async {
+                let result = sess
+                    .session_manager
+                    .handle_exec_command_request(exec_params)
+                    .await;
+                let exit_code = result.as_ref().ok().and_then(|output| output.exit_code());
+                let output = ResponseInputItem::FunctionCallOutput {
+                    call_id: call_id.clone(),
+                    output: crate::exec_command::result_into_payload(result),
+                };
+                (output, exit_code)
+            };
+            run_with_hooks(sess, &turn_context.cwd, call_id.clone(), pre_hook, action).await
         }
         WRITE_STDIN_TOOL_NAME => {
             let write_stdin_params = match serde_json::from_str::<WriteStdinParams>(&arguments) {
@@ -2384,7 +3168,14 @@ async fn handle_function_call_no_subagent(
                     // TODO(mbolin): Determine appropriate timeout for tool call.
                     let timeout = None;
                     handle_mcp_tool_call(
-                        sess, &sub_id, call_id, server, tool_name, arguments, timeout,
+                        sess,
+                        turn_context,
+                        &sub_id,
+                        call_id,
+                        server,
+                        tool_name,
+                        arguments,
+                        timeout,
                     )
                     .await
                 }
@@ -2404,25 +3195,12 @@ async fn handle_function_call_no_subagent(
 }
 
 async fn handle_sub_agent_launch(
-    sess: &Session,
+    sess: &Arc<Session>,
     turn_context: &TurnContext,
-    _turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
     arguments: String,
     call_id: String,
 ) -> ResponseInputItem {
-    #[derive(serde::Deserialize, Clone)]
-    #[serde(untagged)]
-    enum SandboxPolicyArg {
-        String(String),
-        Object(SandboxPolicy),
-    }
-
-    #[derive(serde::Deserialize)]
-    struct SubAgentLaunchArgs {
-        prompt: String,
-    }
-
     let args = match serde_json::from_str::<SubAgentLaunchArgs>(&arguments) {
         Ok(a) => a,
         Err(e) => {
@@ -2435,67 +3213,240 @@ async fn handle_sub_agent_launch(
             };
         }
     };
+    // Derive the sub-turn context. Unless overridden, the sub-agent uses the
+    // same model, reasoning settings, approval and sandbox policies as the
+    // main agent, but may not launch or await sub-agents of its own.
+    let sa_context = match sub_agent::derive_turn_context(
+        turn_context,
+        args.agent.as_deref(),
+        args.overrides,
+        Some(sess.mcp_connection_manager.list_all_tools()),
+    ) {
+        Ok(sa_context) => sa_context,
+        Err(e) => {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("invalid sub_agent_launch arguments: {e}"),
+                    success: Some(false),
+                },
+            };
+        }
+    };
+    let label = args.agent.unwrap_or_else(|| "sub-agent".to_string());
+    let budget = sess.sub_agent_budget.tightened_by(args.budget);
+    let transcript = sess
+        .create_sub_agent_rollout(&call_id, &label, &sa_context)
+        .await;
 
-    // Announce sub-agent start so the UI can display status.
-    sess.tx_event
-        .send(Event {
-            id: sub_id.clone(),
-            msg: EventMsg::SubAgentStarted(SubAgentStartedEvent {
-                sub_id: call_id.clone(),
-                label: "sub-agent".to_string(),
-            }),
-        })
-        .await
-        .ok();
+    // Announce sub-agent start so the UI can display status. The event is
+    // persisted so the parent rollout links to the sub-agent's transcript.
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::SubAgentStarted(SubAgentStartedEvent {
+            sub_id: call_id.clone(),
+            label: label.clone(),
+            rollout_path: transcript.as_ref().map(RolloutRecorder::get_rollout_path),
+        }),
+    })
+    .await;
 
-    // Build an isolated prompt (do not include main history).
-    let mut prompt = Prompt::default();
-    prompt.input.push(ResponseItem::Message {
-        id: None,
-        role: "user".to_string(),
-        content: vec![ContentItem::InputText {
-            text: args.prompt.clone(),
-        }],
-    });
-    // Inherit declared tools (including MCP) so sub-agent can use them if needed.
-    prompt.tools = get_openai_tools(
-        &turn_context.tools_config,
-        Some(sess.mcp_connection_manager.list_all_tools()),
+    if !args.background {
+        let outcome = run_sub_agent(
+            sess,
+            &sa_context,
+            budget,
+            transcript,
+            &sub_id,
+            &call_id,
+            &label,
+            args.prompt,
+        )
+        .await;
+        let output = match outcome {
+            SubAgentOutcome::Completed { summary, .. } => FunctionCallOutputPayload {
+                content: summary,
+                success: Some(true),
+            },
+            SubAgentOutcome::Failed { error } => FunctionCallOutputPayload {
+                content: format!("sub-agent failed: {error}"),
+                success: Some(false),
+            },
+        };
+        return ResponseInputItem::FunctionCallOutput { call_id, output };
+    }
+
+    let (tx_outcome, rx_outcome) = watch::channel(None);
+    let handle = {
+        let sess = Arc::clone(sess);
+        let sub_id = sub_id.clone();
+        let agent_id = call_id.clone();
+        let label = label.clone();
+        tokio::spawn(async move {
+            let outcome = run_sub_agent(
+                &sess,
+                &sa_context,
+                budget,
+                transcript,
+                &sub_id,
+                &agent_id,
+                &label,
+                args.prompt,
+            )
+            .await;
+            tx_outcome.send_replace(Some(outcome));
+        })
+        .abort_handle()
+    };
+    sess.register_sub_agent(
+        call_id.clone(),
+        BackgroundSubAgent {
+            parent_sub_id: sub_id,
+            label,
+            outcome: rx_outcome,
+            handle,
+        },
     );
 
-    // Derive a sub-turn context by applying runtime execution settings
-    // and optional model/effort/summary overrides.
-    // Use the same model and reasoning settings as the main agent by default.
-    let sa_client = turn_context.client.clone();
-
-    // Default sub-agent to run without approval prompts and with a permissive,
-    // workspace-write sandbox unless explicitly overridden by the tool call.
-    // Inherit approval and sandbox policies from the main agent.
-    let sa_approval_policy = turn_context.approval_policy;
-    let sa_sandbox_policy = turn_context.sandbox_policy.clone();
-
-    let sa_context = TurnContext {
-        client: sa_client,
-        tools_config: turn_context.tools_config.clone(),
-        user_instructions: turn_context.user_instructions.clone(),
-        base_instructions: turn_context.base_instructions.clone(),
-        approval_policy: sa_approval_policy,
-        sandbox_policy: sa_sandbox_policy,
-        shell_environment_policy: turn_context.shell_environment_policy.clone(),
-        cwd: turn_context.cwd.clone(),
-        default_exec_timeout_ms: turn_context.default_exec_timeout_ms,
+    ResponseInputItem::FunctionCallOutput {
+        output: FunctionCallOutputPayload {
+            content: format!(
+                "sub-agent `{call_id}` launched in the background; call sub_agent_await to collect its summary"
+            ),
+            success: Some(true),
+        },
+        call_id,
+    }
+}
+
+async fn handle_sub_agent_await(
+    sess: &Session,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    #[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    enum AwaitMode {
+        #[default]
+        All,
+        Any,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct SubAgentAwaitArgs {
+        #[serde(default)]
+        agent_ids: Option<Vec<String>>,
+        #[serde(default)]
+        mode: AwaitMode,
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    }
+
+    #[derive(Serialize)]
+    struct AwaitedSubAgent {
+        agent_id: String,
+        label: String,
+        #[serde(flatten)]
+        outcome: SubAgentOutcome,
+    }
+
+    #[derive(Serialize)]
+    struct SubAgentAwaitOutput {
+        finished: Vec<AwaitedSubAgent>,
+        running: Vec<String>,
+    }
+
+    let failure = |content: String| ResponseInputItem::FunctionCallOutput {
+        call_id: call_id.clone(),
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    };
+
+    let args = match serde_json::from_str::<SubAgentAwaitArgs>(&arguments) {
+        Ok(a) => a,
+        Err(e) => return failure(format!("failed to parse function arguments: {e}")),
+    };
+    let mut receivers = match sess.sub_agent_receivers(args.agent_ids.as_deref()) {
+        Ok(receivers) => receivers,
+        Err(e) => return failure(e),
+    };
+    if receivers.is_empty() {
+        return failure("no background sub-agents to await".to_string());
+    }
+
+    // `wait_for` resolves immediately for sub-agents that already finished and
+    // errors if the sub-agent task was aborted; both count as "done" here.
+    let waits = receivers.iter_mut().map(|(_, rx)| {
+        Box::pin(async move {
+            let _ = rx.wait_for(Option::is_some).await;
+        })
+    });
+    let wait = async {
+        match args.mode {
+            AwaitMode::All => {
+                future::join_all(waits).await;
+            }
+            AwaitMode::Any => {
+                future::select_all(waits).await;
+            }
+        }
     };
+    match args.timeout_ms {
+        Some(ms) => {
+            let _ = tokio::time::timeout(Duration::from_millis(ms), wait).await;
+        }
+        None => wait.await,
+    }
+
+    let mut finished = Vec::new();
+    let mut running = Vec::new();
+    for (agent_id, _) in receivers {
+        match sess.take_finished_sub_agent(&agent_id) {
+            Some((label, outcome)) => finished.push(AwaitedSubAgent {
+                agent_id,
+                label,
+                outcome,
+            }),
+            None => running.push(agent_id),
+        }
+    }
+
+    match serde_json::to_string(&SubAgentAwaitOutput { finished, running }) {
+        Ok(content) => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(true),
+            },
+        },
+        Err(e) => failure(format!("failed to serialize sub-agent results: {e}")),
+    }
+}
 
-    // Sub-agent runs a normal multi-turn loop: execute tools and feed outputs
-    // back to the model between turns, collecting the last assistant text as
-    // the final summary and any executed commands for provenance.
+/// Runs a sub-agent to completion, streaming its lifecycle events to clients
+/// under `agent_id`, and returns its outcome. The sub-agent's conversation is
+/// recorded to `transcript` when present. The sub-agent is stopped once it
+/// exhausts `budget` or the session's budget.
+#[allow(clippy::too_many_arguments)]
+async fn run_sub_agent(
+    sess: &Session,
+    sa_context: &TurnContext,
+    budget: Budget,
+    transcript: Option<RolloutRecorder>,
+    sub_id: &str,
+    agent_id: &str,
+    label: &str,
+    prompt: String,
+) -> SubAgentOutcome {
     // Basic in-progress status update.
     sess.tx_event
         .send(Event {
-            id: sub_id.clone(),
+            id: sub_id.to_string(),
             msg: EventMsg::SubAgentStatus(SubAgentStatusEvent {
-                sub_id: call_id.clone(),
-                label: "sub-agent".to_string(),
+                sub_id: agent_id.to_string(),
+                label: label.to_string(),
                 message: "working".to_string(),
                 progress: None,
             }),
@@ -2503,6 +3454,110 @@ async fn handle_sub_agent_launch(
         .await
         .ok();
 
+    let mut tracker = BudgetTracker::new(budget);
+    // Background sub-agents outlive the turn that launched them, so they are
+    // held to whichever of their own and the session's deadlines comes first.
+    let own = tracker.time_limit().map(|limit| {
+        let exceeded = BudgetExceeded::Duration { limit };
+        (limit, format!("sub-agent budget exceeded: {exceeded}"))
+    });
+    let session = sess.budget_time_left().map(|(left, limit)| {
+        let exceeded = BudgetExceeded::Duration { limit };
+        (left, format!("session budget exceeded: {exceeded}"))
+    });
+    let deadline = match (own, session) {
+        (Some(own), Some(session)) => Some(if session.0 < own.0 { session } else { own }),
+        (own, session) => own.or(session),
+    };
+    let drive = drive_sub_agent(
+        sess,
+        sa_context,
+        &mut tracker,
+        transcript.as_ref(),
+        sub_id,
+        prompt,
+    );
+    let result = match deadline {
+        Some((timeout, message)) => tokio::time::timeout(timeout, drive)
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!(message))),
+        None => drive.await,
+    };
+    if let Some(transcript) = transcript
+        && let Err(e) = transcript.flush().await
+    {
+        error!("failed to flush sub-agent rollout: {e:#}");
+    }
+    match result {
+        Ok((summary, commands)) => {
+            // Announce completion for UI consumers.
+            sess.tx_event
+                .send(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::SubAgentCompleted(SubAgentCompletedEvent {
+                        sub_id: agent_id.to_string(),
+                        label: label.to_string(),
+                        summary: summary.clone(),
+                        commands: if commands.is_empty() {
+                            None
+                        } else {
+                            Some(commands.clone())
+                        },
+                    }),
+                })
+                .await
+                .ok();
+            sess.hooks.spawn(
+                sess.conversation_id,
+                sa_context.cwd.clone(),
+                HookEvent::SubAgentComplete {
+                    agent_id: agent_id.to_string(),
+                    label: label.to_string(),
+                    summary: Some(summary.clone()),
+                    error: None,
+                },
+            );
+            SubAgentOutcome::Completed { summary, commands }
+        }
+        Err(err) => {
+            let error = format!("{err:#}");
+            sess.tx_event
+                .send(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::SubAgentFailed(SubAgentFailedEvent {
+                        sub_id: agent_id.to_string(),
+                        label: label.to_string(),
+                        error: error.clone(),
+                    }),
+                })
+                .await
+                .ok();
+            sess.hooks.spawn(
+                sess.conversation_id,
+                sa_context.cwd.clone(),
+                HookEvent::SubAgentComplete {
+                    agent_id: agent_id.to_string(),
+                    label: label.to_string(),
+                    summary: None,
+                    error: Some(error.clone()),
+                },
+            );
+            SubAgentOutcome::Failed { error }
+        }
+    }
+}
+
+/// Sub-agent runs a normal multi-turn loop: execute tools and feed outputs
+/// back to the model between turns, collecting the last assistant text as
+/// the final summary and any executed commands for provenance.
+async fn drive_sub_agent(
+    sess: &Session,
+    sa_context: &TurnContext,
+    budget: &mut BudgetTracker,
+    transcript: Option<&RolloutRecorder>,
+    sub_id: &str,
+    prompt: String,
+) -> anyhow::Result<(String, Vec<String>)> {
     let mut last_assistant_text = String::new();
     let mut executed_commands: Vec<String> = Vec::new();
     let mut td = TurnDiffTracker::new();
@@ -2510,12 +3565,41 @@ async fn handle_sub_agent_launch(
     let mut conversation: Vec<ResponseItem> = vec![ResponseItem::Message {
         id: None,
         role: "user".to_string(),
-        content: vec![ContentItem::InputText {
-            text: args.prompt.clone(),
-        }],
+        content: vec![ContentItem::InputText { text: prompt }],
     }];
+    // Persist the prompt along with its UserMessage event so the transcript
+    // is listed and replayed like any other conversation.
+    let mut initial_items: Vec<RolloutItem> = conversation
+        .iter()
+        .cloned()
+        .map(RolloutItem::ResponseItem)
+        .collect();
+    initial_items.extend(
+        map_response_item_to_event_messages(&conversation[0], sess.show_raw_agent_reasoning)
+            .into_iter()
+            .filter(|msg| matches!(msg, EventMsg::UserMessage(_)))
+            .map(RolloutItem::EventMsg),
+    );
+    record_sub_agent_items(transcript, &initial_items).await;
 
-    let summary_result: Result<(String, Vec<String>), anyhow::Error> = loop {
+    loop {
+        budget
+            .start_turn()
+            .map_err(|e| anyhow::anyhow!("sub-agent budget exceeded: {e}"))?;
+        sess.start_budgeted_turn()
+            .map_err(|e| anyhow::anyhow!("session budget exceeded: {e}"))?;
+        record_sub_agent_items(
+            transcript,
+            &[RolloutItem::TurnContext(TurnContextItem {
+                cwd: sa_context.cwd.clone(),
+                approval_policy: sa_context.approval_policy,
+                sandbox_policy: sa_context.sandbox_policy.clone(),
+                model: sa_context.client.get_model(),
+                effort: sa_context.client.get_reasoning_effort(),
+                summary: sa_context.client.get_reasoning_summary(),
+            })],
+        )
+        .await;
         // Build prompt for this turn using the sub-agent's local conversation.
         let tools = get_openai_tools(
             &sa_context.tools_config,
@@ -2525,242 +3609,239 @@ async fn handle_sub_agent_launch(
             input: conversation.clone(),
             tools,
             base_instructions_override: sa_context.base_instructions.clone(),
+            output_schema: None,
         };
 
-        let mut stream = sa_context.client.clone().stream(&prompt).await;
+        let mut stream = sa_context
+            .client
+            .clone()
+            .stream(&prompt)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to start sub-agent stream: {e:#}"))?;
         let mut turn_responses: Vec<ResponseInputItem> = Vec::new();
-        let turn_result: Result<(String, Vec<String>), anyhow::Error> = match stream.as_mut() {
-            Ok(s) => {
-                let s = s;
-                loop {
-                    let ev = s.next().await;
-                    let Some(ev) = ev else {
-                        break Err(anyhow::anyhow!("stream closed before completion"));
-                    };
-                    match ev {
-                        Ok(ResponseEvent::OutputItemDone(item)) => {
-                            // Record the item in the sub-agent conversation.
-                            conversation.push(item.clone());
-
-                            // Capture assistant text for summary.
-                            if let ResponseItem::Message { role, content, .. } = &item
-                                && role == "assistant"
-                            {
-                                for ci in content.iter() {
-                                    if let ContentItem::OutputText { text } = ci
-                                        && !text.trim().is_empty()
-                                    {
-                                        last_assistant_text = text.clone();
-                                    }
-                                }
-                            }
-
-                            // Capture executed commands for provenance.
-                            match &item {
-                                ResponseItem::FunctionCall {
-                                    name, arguments, ..
-                                } => {
-                                    if name == "container.exec" || name == "shell" {
-                                        if let Ok(p) =
-                                            serde_json::from_str::<ShellToolCallParams>(arguments)
-                                        {
-                                            executed_commands.push(p.command.join(" "));
-                                        }
-                                    } else if name == EXEC_COMMAND_TOOL_NAME
-                                        && let Ok(p) =
-                                            serde_json::from_str::<ExecCommandParams>(arguments)
-                                    {
-                                        executed_commands.push(p.cmd.clone());
-                                    }
-                                }
-                                ResponseItem::LocalShellCall { action, .. } => {
-                                    let LocalShellAction::Exec(exec) = action;
-                                    executed_commands.push(exec.command.join(" "));
-                                }
-                                _ => {}
-                            }
-
-                            // Execute tools for sub-agent items without causing async recursion.
-                            let response_opt = match item.clone() {
-                                ResponseItem::FunctionCall {
-                                    name,
-                                    arguments,
-                                    call_id,
-                                    ..
-                                } => Some(
-                                    handle_function_call_no_subagent(
-                                        sess,
-                                        &sa_context,
-                                        &mut td,
-                                        sub_id.clone(),
-                                        name,
-                                        arguments,
-                                        call_id,
-                                    )
-                                    .await,
-                                ),
-                                ResponseItem::LocalShellCall {
-                                    id,
-                                    call_id,
-                                    status: _,
-                                    action,
-                                } => {
-                                    let LocalShellAction::Exec(action) = action;
-                                    tracing::info!("LocalShellCall (sub-agent): {action:?}");
-                                    let params = ShellToolCallParams {
-                                        command: action.command,
-                                        workdir: action.working_directory,
-                                        timeout_ms: action.timeout_ms,
-                                        with_escalated_permissions: None,
-                                        justification: None,
-                                    };
-                                    let effective_call_id = match (call_id, id) {
-                                        (Some(call_id), _) => call_id,
-                                        (None, Some(id)) => id,
-                                        (None, None) => {
-                                            error!("LocalShellCall without call_id or id");
-                                            String::new()
-                                        }
-                                    };
-                                    let exec_params = to_exec_params(params, &sa_context);
-                                    Some(
-                                        handle_container_exec_with_params(
-                                            exec_params,
-                                            sess,
-                                            &sa_context,
-                                            &mut td,
-                                            sub_id.clone(),
-                                            effective_call_id,
-                                        )
-                                        .await,
-                                    )
-                                }
-                                ResponseItem::CustomToolCall {
-                                    id: _,
-                                    call_id,
-                                    name,
-                                    input,
-                                    status: _,
-                                } => Some(
-                                    handle_custom_tool_call(
-                                        sess,
-                                        &sa_context,
-                                        &mut td,
-                                        sub_id.clone(),
-                                        name,
-                                        input,
-                                        call_id,
-                                    )
-                                    .await,
-                                ),
-                                ResponseItem::Message { .. }
-                                | ResponseItem::Reasoning { .. }
-                                | ResponseItem::WebSearchCall { .. } => {
-                                    let msgs = map_response_item_to_event_messages(
-                                        &item,
-                                        sess.show_raw_agent_reasoning,
-                                    );
-                                    for msg in msgs {
-                                        let event = Event {
-                                            id: sub_id.clone(),
-                                            msg,
-                                        };
-                                        sess.send_event(event).await;
-                                    }
-                                    None
-                                }
-                                _ => None,
-                            };
+        loop {
+            let Some(ev) = stream.next().await else {
+                return Err(anyhow::anyhow!("stream closed before completion"));
+            };
+            let item = match ev? {
+                ResponseEvent::OutputItemDone(item) => item,
+                ResponseEvent::Completed { token_usage, .. } => {
+                    if let Some(usage) = &token_usage {
+                        budget.record_tokens(usage);
+                    }
+                    sess.record_budget_tokens(token_usage.as_ref());
+                    break;
+                }
+                _ => continue,
+            };
 
-                            if let Some(response) = response_opt {
-                                // Defer appending outputs to conversation until after this turn completes.
-                                turn_responses.push(response);
-                            }
-                        }
-                        Ok(ResponseEvent::Completed { .. }) => {
-                            // If no tool outputs were produced this turn, we are done.
-                            if turn_responses.is_empty() {
-                                break Ok((last_assistant_text.clone(), executed_commands.clone()));
-                            }
-                            // Otherwise, append outputs to the conversation and start the next turn.
-                            for r in turn_responses.drain(..) {
-                                conversation.push(ResponseItem::from(r));
-                            }
-                            break Ok((String::new(), Vec::new())); // sentinel to continue outer loop
-                        }
-                        Ok(_) => {}
-                        Err(e) => break Err(e.into()),
+            // Record the item in the sub-agent conversation.
+            conversation.push(item.clone());
+            record_sub_agent_items(transcript, &[RolloutItem::ResponseItem(item.clone())]).await;
+
+            // Capture assistant text for summary.
+            if let ResponseItem::Message { role, content, .. } = &item
+                && role == "assistant"
+            {
+                for ci in content.iter() {
+                    if let ContentItem::OutputText { text } = ci
+                        && !text.trim().is_empty()
+                    {
+                        last_assistant_text = text.clone();
                     }
                 }
             }
-            Err(e) => Err(anyhow::anyhow!("failed to start sub-agent stream: {e:#}")),
-        };
 
-        match turn_result {
-            Ok((s, cmds)) => {
-                // If the turn produced no final summary (sentinel), continue; otherwise return.
-                if s.is_empty() && cmds.is_empty() {
-                    continue;
-                } else {
-                    // We already track last_assistant_text and executed_commands across turns.
-                    break Ok((last_assistant_text.clone(), executed_commands.clone()));
+            // Refuse calls to tools outside the sub-agent's allow-list.
+            if let Some(rejection) = reject_disallowed_tool_call(&item, &sa_context.tools_config) {
+                turn_responses.push(rejection);
+                continue;
+            }
+
+            // Capture executed commands for provenance.
+            match &item {
+                ResponseItem::FunctionCall {
+                    name, arguments, ..
+                } => {
+                    if name == "container.exec" || name == "shell" {
+                        if let Ok(p) = serde_json::from_str::<ShellToolCallParams>(arguments) {
+                            executed_commands.push(p.command.join(" "));
+                        }
+                    } else if name == EXEC_COMMAND_TOOL_NAME
+                        && let Ok(p) = serde_json::from_str::<ExecCommandParams>(arguments)
+                    {
+                        executed_commands.push(p.cmd.clone());
+                    }
+                }
+                ResponseItem::LocalShellCall { action, .. } => {
+                    let LocalShellAction::Exec(exec) = action;
+                    executed_commands.push(exec.command.join(" "));
                 }
+                _ => {}
             }
-            Err(e) => break Err(e),
-        }
-    };
 
-    match summary_result {
-        Ok((summary, commands)) => {
-            // Announce completion for UI consumers.
-            sess.tx_event
-                .send(Event {
-                    id: sub_id.clone(),
-                    msg: EventMsg::SubAgentCompleted(SubAgentCompletedEvent {
-                        sub_id: call_id.clone(),
-                        label: "sub-agent".to_string(),
-                        summary: summary.clone(),
-                        commands: if commands.is_empty() {
-                            None
-                        } else {
-                            Some(commands.clone())
-                        },
-                    }),
-                })
-                .await
-                .ok();
+            // Execute tools for sub-agent items without causing async recursion.
+            let response_opt = match item.clone() {
+                ResponseItem::FunctionCall {
+                    name,
+                    arguments,
+                    call_id,
+                    ..
+                } => Some(
+                    handle_function_call_no_subagent(
+                        sess,
+                        sa_context,
+                        &mut td,
+                        sub_id.to_string(),
+                        name,
+                        arguments,
+                        call_id,
+                    )
+                    .await,
+                ),
+                ResponseItem::LocalShellCall {
+                    id,
+                    call_id,
+                    status: _,
+                    action,
+                } => {
+                    let LocalShellAction::Exec(action) = action;
+                    tracing::info!("LocalShellCall (sub-agent): {action:?}");
+                    let params = ShellToolCallParams {
+                        command: action.command,
+                        workdir: action.working_directory,
+                        timeout_ms: action.timeout_ms,
+                        with_escalated_permissions: None,
+                        justification: None,
+                    };
+                    let effective_call_id = match (call_id, id) {
+                        (Some(call_id), _) => call_id,
+                        (None, Some(id)) => id,
+                        (None, None) => {
+                            error!("LocalShellCall without call_id or id");
+                            String::new()
+                        }
+                    };
+                    let exec_params = to_exec_params(params, sa_context);
+                    Some(
+                        handle_container_exec_with_params(
+                            exec_params,
+                            sess,
+                            sa_context,
+                            &mut td,
+                            sub_id.to_string(),
+                            effective_call_id,
+                        )
+                        .await,
+                    )
+                }
+                ResponseItem::CustomToolCall {
+                    id: _,
+                    call_id,
+                    name,
+                    input,
+                    status: _,
+                } => Some(
+                    handle_custom_tool_call(
+                        sess,
+                        sa_context,
+                        &mut td,
+                        sub_id.to_string(),
+                        name,
+                        input,
+                        call_id,
+                    )
+                    .await,
+                ),
+                ResponseItem::Message { .. }
+                | ResponseItem::Reasoning { .. }
+                | ResponseItem::WebSearchCall { .. } => {
+                    // These events belong to the sub-agent's transcript, not
+                    // the parent rollout.
+                    let msgs =
+                        map_response_item_to_event_messages(&item, sess.show_raw_agent_reasoning);
+                    for msg in msgs {
+                        record_sub_agent_items(transcript, &[RolloutItem::EventMsg(msg.clone())])
+                            .await;
+                        let event = Event {
+                            id: sub_id.to_string(),
+                            msg,
+                        };
+                        if let Err(e) = sess.tx_event.send(event).await {
+                            error!("failed to send sub-agent event: {e}");
+                        }
+                    }
+                    None
+                }
+                _ => None,
+            };
 
-            ResponseInputItem::FunctionCallOutput {
-                call_id,
-                output: FunctionCallOutputPayload {
-                    content: summary,
-                    success: Some(true),
-                },
+            if let Some(response) = response_opt {
+                // Defer appending outputs to conversation until after this turn completes.
+                turn_responses.push(response);
             }
         }
-        Err(err) => {
-            sess.tx_event
-                .send(Event {
-                    id: sub_id.clone(),
-                    msg: EventMsg::SubAgentFailed(SubAgentFailedEvent {
-                        sub_id: call_id.clone(),
-                        label: "sub-agent".to_string(),
-                        error: format!("{err:#}"),
-                    }),
-                })
-                .await
-                .ok();
-            ResponseInputItem::FunctionCallOutput {
-                call_id,
-                output: FunctionCallOutputPayload {
-                    content: format!("sub-agent failed: {err:#}"),
-                    success: Some(false),
-                },
-            }
+
+        // If no tool outputs were produced this turn, we are done.
+        if turn_responses.is_empty() {
+            return Ok((last_assistant_text, executed_commands));
         }
+        // Otherwise, append outputs to the conversation and start the next turn.
+        let outputs: Vec<ResponseItem> =
+            turn_responses.into_iter().map(ResponseItem::from).collect();
+        let rollout_items: Vec<RolloutItem> = outputs
+            .iter()
+            .cloned()
+            .map(RolloutItem::ResponseItem)
+            .collect();
+        record_sub_agent_items(transcript, &rollout_items).await;
+        conversation.extend(outputs);
     }
 }
 
+async fn record_sub_agent_items(transcript: Option<&RolloutRecorder>, items: &[RolloutItem]) {
+    if let Some(transcript) = transcript
+        && let Err(e) = transcript.record_items(items).await
+    {
+        error!("failed to record sub-agent rollout items: {e:#}");
+    }
+}
+
+/// Returns an error output for a tool call that `allowed_tools` does not
+/// permit, or `None` if the item may be executed.
+fn reject_disallowed_tool_call(
+    item: &ResponseItem,
+    tools_config: &ToolsConfig,
+) -> Option<ResponseInputItem> {
+    let (name, call_id) = match item {
+        ResponseItem::FunctionCall { name, call_id, .. }
+        | ResponseItem::CustomToolCall { name, call_id, .. } => (name.as_str(), call_id.clone()),
+        ResponseItem::LocalShellCall { id, call_id, .. } => (
+            "local_shell",
+            call_id.clone().or_else(|| id.clone()).unwrap_or_default(),
+        ),
+        _ => return None,
+    };
+    if tools_config.is_tool_allowed(name) {
+        return None;
+    }
+
+    let content = format!("tool `{name}` is not available to this sub-agent");
+    Some(match item {
+        ResponseItem::CustomToolCall { .. } => ResponseInputItem::CustomToolCallOutput {
+            call_id,
+            output: content,
+        },
+        _ => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        },
+    })
+}
+
 async fn handle_custom_tool_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2964,6 +4045,7 @@ async fn handle_container_exec_with_params(
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
                     &state.approved_commands,
+                    &sess.exec_policy,
                     params.with_escalated_permissions.unwrap_or(false),
                 )
             };
@@ -3032,6 +4114,73 @@ async fn handle_container_exec_with_params(
         ),
     };
 
+    let pre_hook = match &exec_command_context.apply_patch {
+        Some(ApplyPatchCommandContext { changes, .. }) => HookEvent::PreApplyPatch {
+            call_id: call_id.clone(),
+            changes: changes.clone(),
+        },
+        None => HookEvent::PreExec {
+            call_id: call_id.clone(),
+            command: command_for_display,
+        },
+    };
+    let hook_cwd = params.cwd.clone();
+    let action = run_exec_params(
+        params,
+        sess,
+        turn_context,
+        turn_diff_tracker,
+        exec_command_context,
+        sandbox_type,
+    );
+    run_with_hooks(sess, &hook_cwd, call_id, pre_hook, action).await
+}
+
+/// Runs `action` between the `pre_*` hooks for `pre_hook` and the matching
+/// `post_*` hooks. Every tool that starts a command or applies a patch goes
+/// through here so that hooks see all of them. `action` returns the tool
+/// output and, when known, the command's exit code.
+async fn run_with_hooks(
+    sess: &Session,
+    cwd: &Path,
+    call_id: String,
+    pre_hook: HookEvent,
+    action: impl Future<Output = (ResponseInputItem, Option<i32>)>,
+) -> ResponseInputItem {
+    if let Err(blocked) = sess.hooks.run(sess.conversation_id, cwd, &pre_hook).await {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: blocked.message,
+                success: Some(false),
+            },
+        };
+    }
+
+    let (response, exit_code) = action.await;
+    let success = matches!(
+        &response,
+        ResponseInputItem::FunctionCallOutput { output, .. } if output.success == Some(true)
+    );
+    if let Some(post_hook) = pre_hook.into_post(exit_code, success) {
+        // Post hooks cannot block, so the result only matters for logging.
+        let _ = sess.hooks.run(sess.conversation_id, cwd, &post_hook).await;
+    }
+    response
+}
+
+/// Runs an approved shell command or patch, returning the tool output and
+/// the command's exit code.
+async fn run_exec_params(
+    params: ExecParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    exec_command_context: ExecCommandContext,
+    sandbox_type: SandboxType,
+) -> (ResponseInputItem, Option<i32>) {
+    let sub_id = exec_command_context.sub_id.clone();
+    let call_id = exec_command_context.call_id.clone();
     let params = maybe_translate_shell_command(params, sess, turn_context);
     let output_result = sess
         .run_exec_with_events(
@@ -3055,7 +4204,8 @@ async fn handle_container_exec_with_params(
         )
         .await;
 
-    match output_result {
+    let exit_code = output_result.as_ref().ok().map(|output| output.exit_code);
+    let response = match output_result {
         Ok(output) => {
             let ExecToolCallOutput { exit_code, .. } = &output;
 
@@ -3088,7 +4238,9 @@ async fn handle_container_exec_with_params(
                 success: None,
             },
         },
-    }
+    };
+
+    (response, exit_code)
 }
 
 async fn handle_sandbox_error(
@@ -3390,6 +4542,7 @@ fn get_last_assistant_message_from_turn(responses: &[ResponseItem]) -> Option<St
 async fn drain_to_completed(
     sess: &Session,
     turn_context: &TurnContext,
+    client: &ModelClient,
     sub_id: &str,
     prompt: &Prompt,
 ) -> CodexResult<()> {
@@ -3397,12 +4550,12 @@ async fn drain_to_completed(
         cwd: turn_context.cwd.clone(),
         approval_policy: turn_context.approval_policy,
         sandbox_policy: turn_context.sandbox_policy.clone(),
-        model: turn_context.client.get_model(),
-        effort: turn_context.client.get_reasoning_effort(),
-        summary: turn_context.client.get_reasoning_summary(),
+        model: client.get_model(),
+        effort: client.get_reasoning_effort(),
+        summary: client.get_reasoning_summary(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
-    let mut stream = turn_context.client.clone().stream(prompt).await?;
+    let mut stream = client.clone().stream(prompt).await?;
     loop {
         let maybe_event = stream.next().await;
         let Some(event) = maybe_event else {
@@ -3426,11 +4579,12 @@ async fn drain_to_completed(
                     let info = TokenUsageInfo::new_or_append(
                         &st.token_info,
                         &token_usage,
-                        turn_context.client.get_model_context_window(),
+                        client.get_model_context_window(),
                     );
                     st.token_info = info.clone();
                     info
                 };
+                sess.record_budget_tokens(token_usage.as_ref());
 
                 sess.tx_event
                     .send(Event {
@@ -1,5 +1,11 @@
+use crate::agents::AGENTS_SUBDIR;
+use crate::agents::discover_agents_in;
 use crate::config_profile::ConfigProfile;
+use crate::config_types::AgentConfig;
+use crate::config_types::Budget;
+use crate::config_types::FallbackProviderToml;
 use crate::config_types::History;
+use crate::config_types::HooksConfig;
 use crate::config_types::McpServerConfig;
 use crate::config_types::ReasoningSummaryFormat;
 use crate::config_types::SandboxWorkspaceWrite;
@@ -7,10 +13,14 @@ use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::WebConfig;
+use crate::exec_policy::POLICY_SUBDIR;
+use crate::git_info::get_git_repo_root;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
 use crate::model_family::find_family_for_model;
+use crate::model_provider_info::FallbackProvider;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
 use crate::openai_model_info::get_model_info;
@@ -25,9 +35,11 @@ use codex_protocol::mcp_protocol::Tools;
 use codex_protocol::mcp_protocol::UserSavedConfig;
 use dirs::home_dir;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 use toml::Value as TomlValue;
 use toml_edit::DocumentMut;
@@ -42,6 +54,14 @@ pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
+/// Percentage of the model's context window at which the conversation history
+/// is compacted automatically.
+pub(crate) const DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT: u8 = 85;
+
+/// Seconds to stay on a fallback model provider before the primary provider
+/// is tried again.
+pub(crate) const DEFAULT_FALLBACK_COOLDOWN_SECS: u64 = 300;
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -56,12 +76,25 @@ pub struct Config {
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Percentage of `model_context_window` that, once used, triggers an
+    /// automatic compaction of the conversation history. `None` disables
+    /// automatic compaction.
+    pub auto_compact_threshold_percent: Option<u8>,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Providers to switch to, in order, once `model_provider` keeps failing
+    /// with rate limits, server errors, or stream timeouts.
+    pub fallback_providers: Vec<FallbackProvider>,
+
+    /// How long to stay on a fallback provider before returning to
+    /// `model_provider`.
+    pub fallback_cooldown: Duration,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
@@ -78,6 +111,10 @@ pub struct Config {
     /// Defaults to `false`.
     pub show_raw_agent_reasoning: bool,
 
+    /// Whether a worktree checkpoint is recorded before every user message
+    /// inside a git repository.
+    pub checkpoints: bool,
+
     /// User-provided instructions from AGENTS.md.
     pub user_instructions: Option<String>,
 
@@ -106,6 +143,9 @@ pub struct Config {
     /// If unset the feature is disabled.
     pub notify: Option<Vec<String>>,
 
+    /// Lifecycle hooks from the `[hooks]` table.
+    pub hooks: HooksConfig,
+
     /// The directory that should be treated as the current working directory
     /// for the session. All relative paths inside the business-logic layer are
     /// resolved against this path.
@@ -114,6 +154,26 @@ pub struct Config {
     /// Definition for MCP servers that Codex can reach out to for tool calls.
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Named sub-agents available to `sub_agent_launch`: markdown definitions
+    /// from `$CODEX_HOME/agents` merged with (and overridden by) the
+    /// `[agents]` tables in `config.toml`.
+    pub agents: BTreeMap<String, AgentConfig>,
+
+    /// Limits for the whole session, including any sub-agents it launches.
+    pub session_budget: Budget,
+
+    /// Default limits for each sub-agent. `sub_agent_launch` may tighten
+    /// these but never relax them.
+    pub sub_agent_budget: Budget,
+
+    /// Settings for the `codex web` server.
+    pub web: WebConfig,
+
+    /// Directories searched for `*.policy` exec policy files, on top of the
+    /// built-in default policy: `$CODEX_HOME/policy`, plus
+    /// `<project>/.codex/policy` when the project is trusted.
+    pub exec_policy_dirs: Vec<PathBuf>,
+
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
@@ -150,6 +210,10 @@ pub struct Config {
     /// Optional verbosity control for GPT-5 models (Responses API `text.verbosity`).
     pub model_verbosity: Option<Verbosity>,
 
+    /// JSON Schema the final assistant message of every turn must match. Not
+    /// read from config.toml; set by `codex exec --output-schema`.
+    pub output_schema: Option<serde_json::Value>,
+
     /// Base URL for requests to ChatGPT (as opposed to the OpenAI API).
     pub chatgpt_base_url: String,
 
@@ -579,12 +643,24 @@ pub struct ConfigToml {
     /// Provider to use from the model_providers map.
     pub model_provider: Option<String>,
 
+    /// Providers from the model_providers map to switch to, in order, when
+    /// `model_provider` is rate limited or unavailable.
+    pub fallback_providers: Option<Vec<FallbackProviderToml>>,
+
+    /// Seconds to stay on a fallback provider before trying the primary
+    /// provider again.
+    pub fallback_cooldown_secs: Option<u64>,
+
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
 
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Percentage of the context window at which the conversation history is
+    /// compacted automatically. `0` disables automatic compaction.
+    pub auto_compact_threshold_percent: Option<u8>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
@@ -601,6 +677,10 @@ pub struct ConfigToml {
     #[serde(default)]
     pub notify: Option<Vec<String>>,
 
+    /// Programs to run on session, tool-call and turn events.
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
     /// System instructions.
     pub instructions: Option<String>,
 
@@ -608,6 +688,22 @@ pub struct ConfigToml {
     #[serde(default)]
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Named sub-agents that `sub_agent_launch` can delegate to.
+    #[serde(default)]
+    pub agents: HashMap<String, AgentConfig>,
+
+    /// Turn, token and time limits for the whole session.
+    #[serde(default)]
+    pub session_budget: Budget,
+
+    /// Default turn, token and time limits for each sub-agent.
+    #[serde(default)]
+    pub sub_agent_budget: Budget,
+
+    /// Access tokens for `codex web`.
+    #[serde(default)]
+    pub web: WebConfig,
+
     /// User-defined provider entries that extend/override the built-in list.
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
@@ -641,6 +737,10 @@ pub struct ConfigToml {
     /// Defaults to `false`.
     pub show_raw_agent_reasoning: Option<bool>,
 
+    /// When set to `false`, no worktree checkpoints are recorded. Defaults to
+    /// `true`.
+    pub checkpoints: Option<bool>,
+
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
     /// Optional verbosity control for GPT-5 models (Responses API `text.verbosity`).
@@ -738,11 +838,17 @@ impl ConfigToml {
                 Some(SandboxWorkspaceWrite {
                     writable_roots,
                     network_access,
+                    network_allowlist,
+                    read_only_paths,
+                    deny_read_paths,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
                     network_access: *network_access,
+                    network_allowlist: network_allowlist.clone(),
+                    read_only_paths: read_only_paths.clone(),
+                    deny_read_paths: deny_read_paths.clone(),
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
                 },
@@ -863,8 +969,36 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
+        let resolved_cwd = {
+            use std::env;
+
+            match cwd {
+                None => {
+                    tracing::info!("cwd not set, using current dir");
+                    env::current_dir()?
+                }
+                Some(p) if p.is_absolute() => p,
+                Some(p) => {
+                    // Resolve relative path against the current working directory.
+                    tracing::info!("cwd is relative, resolving against current dir");
+                    let mut current = env::current_dir()?;
+                    current.push(p);
+                    current
+                }
+            }
+        };
+
         let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
 
+        // Project policies can auto-approve commands, so they only apply to
+        // projects the user has marked trusted.
+        let mut exec_policy_dirs = vec![codex_home.join(POLICY_SUBDIR)];
+        if cfg.is_cwd_trusted(&resolved_cwd) {
+            let project_root =
+                get_git_repo_root(&resolved_cwd).unwrap_or_else(|| resolved_cwd.clone());
+            exec_policy_dirs.push(project_root.join(".codex").join(POLICY_SUBDIR));
+        }
+
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
         for (key, provider) in cfg.model_providers.into_iter() {
@@ -885,26 +1019,40 @@ impl Config {
             })?
             .clone();
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
-
-        let resolved_cwd = {
-            use std::env;
+        let fallback_providers = config_profile
+            .fallback_providers
+            .or(cfg.fallback_providers)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                let (provider_id, model) = match entry {
+                    FallbackProviderToml::Id(provider_id) => (provider_id, None),
+                    FallbackProviderToml::WithModel { provider, model } => (provider, model),
+                };
+                let provider = model_providers
+                    .get(&provider_id)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("Fallback model provider `{provider_id}` not found"),
+                        )
+                    })?
+                    .clone();
+                Ok(FallbackProvider {
+                    provider_id,
+                    provider,
+                    model,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let fallback_cooldown = Duration::from_secs(
+            config_profile
+                .fallback_cooldown_secs
+                .or(cfg.fallback_cooldown_secs)
+                .unwrap_or(DEFAULT_FALLBACK_COOLDOWN_SECS),
+        );
 
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
-            }
-        };
+        let shell_environment_policy = cfg.shell_environment_policy.into();
 
         let history = cfg.history.unwrap_or_default();
 
@@ -921,8 +1069,8 @@ impl Config {
             .or(cfg.model)
             .unwrap_or_else(default_model);
 
-        let mut model_family = find_family_for_model(&model)
-            .unwrap_or_else(|| derive_default_model_family(&model));
+        let mut model_family =
+            find_family_for_model(&model).unwrap_or_else(|| derive_default_model_family(&model));
 
         if let Some(supports_reasoning_summaries) = cfg.model_supports_reasoning_summaries {
             model_family.supports_reasoning_summaries = supports_reasoning_summaries;
@@ -941,6 +1089,20 @@ impl Config {
                 .map(|info| info.max_output_tokens)
         });
 
+        let auto_compact_threshold_percent = match cfg
+            .auto_compact_threshold_percent
+            .unwrap_or(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT)
+        {
+            0 => None,
+            percent @ 1..=100 => Some(percent),
+            percent => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("auto_compact_threshold_percent must be at most 100, got {percent}"),
+                ));
+            }
+        };
+
         let experimental_resume = cfg.experimental_resume;
 
         // Load base instructions override from a file if specified. If the
@@ -954,13 +1116,19 @@ impl Config {
             Self::get_base_instructions(experimental_instructions_path, &resolved_cwd)?;
         let base_instructions = base_instructions.or(file_base_instructions);
 
+        let mut agents = discover_agents_in(&codex_home.join(AGENTS_SUBDIR));
+        agents.extend(cfg.agents);
+
         let config = Self {
             model,
             model_family,
             model_context_window,
             model_max_output_tokens,
+            auto_compact_threshold_percent,
             model_provider_id,
             model_provider,
+            fallback_providers,
+            fallback_cooldown,
             cwd: resolved_cwd,
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
@@ -969,9 +1137,15 @@ impl Config {
             sandbox_policy,
             shell_environment_policy,
             notify: cfg.notify,
+            hooks: cfg.hooks,
             user_instructions,
             base_instructions,
             mcp_servers: cfg.mcp_servers,
+            agents,
+            session_budget: cfg.session_budget,
+            sub_agent_budget: cfg.sub_agent_budget,
+            web: cfg.web,
+            exec_policy_dirs,
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             codex_home,
@@ -984,6 +1158,7 @@ impl Config {
                 .show_raw_agent_reasoning
                 .or(show_raw_agent_reasoning)
                 .unwrap_or(false),
+            checkpoints: cfg.checkpoints.unwrap_or(true),
             model_reasoning_effort: config_profile
                 .model_reasoning_effort
                 .or(cfg.model_reasoning_effort)
@@ -993,6 +1168,7 @@ impl Config {
                 .or(cfg.model_reasoning_summary)
                 .unwrap_or_default(),
             model_verbosity: config_profile.model_verbosity.or(cfg.model_verbosity),
+            output_schema: None,
             chatgpt_base_url: config_profile
                 .chatgpt_base_url
                 .or(cfg.chatgpt_base_url)
@@ -1118,6 +1294,7 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use crate::config_types::HistoryPersistence;
+    use crate::config_types::McpServerTransportConfig;
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -1205,6 +1382,9 @@ exclude_slash_tmp = true
             SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![PathBuf::from("/my/workspace")],
                 network_access: false,
+                network_allowlist: vec![],
+                read_only_paths: vec![],
+                deny_read_paths: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
@@ -1212,6 +1392,218 @@ exclude_slash_tmp = true
         );
     }
 
+    #[test]
+    fn auto_compact_threshold_defaults_and_can_be_disabled() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |toml: &str| {
+            Config::load_from_base_config_with_overrides(
+                toml::from_str::<ConfigToml>(toml).expect("TOML deserialization should succeed"),
+                ConfigOverrides::default(),
+                codex_home.path().to_path_buf(),
+            )
+        };
+
+        assert_eq!(
+            load("")?.auto_compact_threshold_percent,
+            Some(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT)
+        );
+        assert_eq!(
+            load("auto_compact_threshold_percent = 70")?.auto_compact_threshold_percent,
+            Some(70)
+        );
+        assert_eq!(
+            load("auto_compact_threshold_percent = 0")?.auto_compact_threshold_percent,
+            None
+        );
+        assert!(load("auto_compact_threshold_percent = 150").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fallback_providers_resolve_and_profile_overrides() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |toml: &str, profile: Option<&str>| {
+            Config::load_from_base_config_with_overrides(
+                toml::from_str::<ConfigToml>(toml).expect("TOML deserialization should succeed"),
+                ConfigOverrides {
+                    config_profile: profile.map(str::to_string),
+                    ..Default::default()
+                },
+                codex_home.path().to_path_buf(),
+            )
+        };
+        let toml = r#"
+fallback_providers = ["openai", { provider = "oss", model = "gpt-oss:20b" }]
+
+[profiles.local]
+fallback_providers = ["oss"]
+fallback_cooldown_secs = 60
+"#;
+
+        let config = load(toml, None)?;
+        let fallbacks: Vec<_> = config
+            .fallback_providers
+            .iter()
+            .map(|f| (f.provider_id.as_str(), f.model.as_deref()))
+            .collect();
+        assert_eq!(
+            fallbacks,
+            vec![("openai", None), ("oss", Some("gpt-oss:20b"))]
+        );
+        assert_eq!(
+            config.fallback_cooldown,
+            Duration::from_secs(DEFAULT_FALLBACK_COOLDOWN_SECS)
+        );
+
+        let config = load(toml, Some("local"))?;
+        assert_eq!(config.fallback_providers.len(), 1);
+        assert_eq!(config.fallback_providers[0].provider_id, "oss");
+        assert_eq!(config.fallback_cooldown, Duration::from_secs(60));
+
+        assert!(load("fallback_providers = [\"missing\"]", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mcp_servers_accept_tool_approval_and_filters() {
+        use crate::config_types::McpToolApproval;
+        use crate::config_types::McpToolConfig;
+
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+[mcp_servers.tickets]
+command = "tickets-server"
+approval = "on-request"
+disabled_tools = ["purge"]
+
+[mcp_servers.tickets.tools.delete_ticket]
+approval = "always"
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        let tickets = cfg.mcp_servers.get("tickets").expect("tickets server");
+        assert_eq!(tickets.approval, Some(McpToolApproval::OnRequest));
+        assert_eq!(tickets.enabled_tools, None);
+        assert_eq!(tickets.disabled_tools, Some(vec!["purge".to_string()]));
+        assert_eq!(
+            tickets.tools,
+            HashMap::from([(
+                "delete_ticket".to_string(),
+                McpToolConfig {
+                    approval: Some(McpToolApproval::Always),
+                },
+            )])
+        );
+        assert!(
+            toml::from_str::<ConfigToml>(
+                "[mcp_servers.x]\ncommand = \"x\"\napproval = \"sometimes\""
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn mcp_servers_accept_commands_and_urls() {
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+[mcp_servers.local]
+command = "docs-server"
+args = ["--port", "4000"]
+
+[mcp_servers.shared]
+url = "https://mcp.example.com/mcp"
+headers = { "X-Team" = "infra" }
+bearer_token_env_var = "SHARED_MCP_TOKEN"
+startup_timeout_ms = 2500
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        assert_eq!(
+            cfg.mcp_servers.get("local"),
+            Some(&McpServerConfig {
+                transport: McpServerTransportConfig::Stdio {
+                    command: "docs-server".to_string(),
+                    args: vec!["--port".to_string(), "4000".to_string()],
+                    env: None,
+                },
+                startup_timeout_ms: None,
+                approval: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                tools: HashMap::new(),
+            })
+        );
+        assert_eq!(
+            cfg.mcp_servers.get("shared"),
+            Some(&McpServerConfig {
+                transport: McpServerTransportConfig::StreamableHttp {
+                    url: "https://mcp.example.com/mcp".to_string(),
+                    headers: Some(HashMap::from([("X-Team".to_string(), "infra".to_string())])),
+                    bearer_token_env_var: Some("SHARED_MCP_TOKEN".to_string()),
+                },
+                startup_timeout_ms: Some(2500),
+                approval: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                tools: HashMap::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn agents_merge_markdown_and_config_definitions() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let agents_dir = codex_home.path().join(AGENTS_SUBDIR);
+        std::fs::create_dir(&agents_dir)?;
+        std::fs::write(
+            agents_dir.join("reviewer.md"),
+            "---\ndescription: from markdown\n---\nReview.",
+        )?;
+        std::fs::write(agents_dir.join("test-writer.md"), "Write tests.")?;
+
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[agents.reviewer]
+description = "from config"
+model = "o3"
+sandbox_mode = "read-only"
+tools = ["shell"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.agents,
+            BTreeMap::from([
+                (
+                    "reviewer".to_string(),
+                    AgentConfig {
+                        description: Some("from config".to_string()),
+                        instructions: None,
+                        model: Some("o3".to_string()),
+                        sandbox_mode: Some(SandboxMode::ReadOnly),
+                        tools: Some(vec!["shell".to_string()]),
+                    }
+                ),
+                (
+                    "test-writer".to_string(),
+                    AgentConfig {
+                        instructions: Some("Write tests.".to_string()),
+                        ..Default::default()
+                    }
+                ),
+            ])
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn persist_model_selection_updates_defaults() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1495,15 +1887,24 @@ model_verbosity = "high"
                 model_family: find_family_for_model("o3").expect("known model slug"),
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
+                auto_compact_threshold_percent: Some(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT),
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                fallback_providers: Vec::new(),
+                fallback_cooldown: Duration::from_secs(DEFAULT_FALLBACK_COOLDOWN_SECS),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
                 user_instructions: None,
                 notify: None,
+                hooks: HooksConfig::default(),
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
+                agents: BTreeMap::new(),
+                session_budget: Budget::default(),
+                sub_agent_budget: Budget::default(),
+                web: WebConfig::default(),
+                exec_policy_dirs: vec![fixture.codex_home().join(POLICY_SUBDIR)],
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 codex_home: fixture.codex_home(),
@@ -1512,9 +1913,11 @@ model_verbosity = "high"
                 codex_linux_sandbox_exe: None,
                 hide_agent_reasoning: false,
                 show_raw_agent_reasoning: false,
+                checkpoints: true,
                 model_reasoning_effort: ReasoningEffort::High,
                 model_reasoning_summary: ReasoningSummary::Detailed,
                 model_verbosity: None,
+                output_schema: None,
                 chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
                 experimental_resume: None,
                 base_instructions: None,
@@ -1552,15 +1955,24 @@ model_verbosity = "high"
             model_family: find_family_for_model("gpt-3.5-turbo").expect("known model slug"),
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
+            auto_compact_threshold_percent: Some(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT),
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            fallback_providers: Vec::new(),
+            fallback_cooldown: Duration::from_secs(DEFAULT_FALLBACK_COOLDOWN_SECS),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            agents: BTreeMap::new(),
+            session_budget: Budget::default(),
+            sub_agent_budget: Budget::default(),
+            web: WebConfig::default(),
+            exec_policy_dirs: vec![fixture.codex_home().join(POLICY_SUBDIR)],
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
@@ -1569,9 +1981,11 @@ model_verbosity = "high"
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
+            checkpoints: true,
             model_reasoning_effort: ReasoningEffort::default(),
             model_reasoning_summary: ReasoningSummary::default(),
             model_verbosity: None,
+            output_schema: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             experimental_resume: None,
             base_instructions: None,
@@ -1624,15 +2038,24 @@ model_verbosity = "high"
             model_family: find_family_for_model("o3").expect("known model slug"),
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
+            auto_compact_threshold_percent: Some(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback_providers: Vec::new(),
+            fallback_cooldown: Duration::from_secs(DEFAULT_FALLBACK_COOLDOWN_SECS),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            agents: BTreeMap::new(),
+            session_budget: Budget::default(),
+            sub_agent_budget: Budget::default(),
+            web: WebConfig::default(),
+            exec_policy_dirs: vec![fixture.codex_home().join(POLICY_SUBDIR)],
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
@@ -1641,9 +2064,11 @@ model_verbosity = "high"
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
+            checkpoints: true,
             model_reasoning_effort: ReasoningEffort::default(),
             model_reasoning_summary: ReasoningSummary::default(),
             model_verbosity: None,
+            output_schema: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             experimental_resume: None,
             base_instructions: None,
@@ -1682,15 +2107,24 @@ model_verbosity = "high"
             model_family: find_family_for_model("gpt-5").expect("known model slug"),
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
+            auto_compact_threshold_percent: Some(DEFAULT_AUTO_COMPACT_THRESHOLD_PERCENT),
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback_providers: Vec::new(),
+            fallback_cooldown: Duration::from_secs(DEFAULT_FALLBACK_COOLDOWN_SECS),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
+            hooks: HooksConfig::default(),
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            agents: BTreeMap::new(),
+            session_budget: Budget::default(),
+            sub_agent_budget: Budget::default(),
+            web: WebConfig::default(),
+            exec_policy_dirs: vec![fixture.codex_home().join(POLICY_SUBDIR)],
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
@@ -1699,9 +2133,11 @@ model_verbosity = "high"
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
+            checkpoints: true,
             model_reasoning_effort: ReasoningEffort::High,
             model_reasoning_summary: ReasoningSummary::Detailed,
             model_verbosity: Some(Verbosity::High),
+            output_schema: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             experimental_resume: None,
             base_instructions: None,
@@ -7,21 +7,220 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use wildmatch::WildMatchPattern;
 
+use codex_protocol::config_types::SandboxMode;
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
-    pub command: String,
+    #[serde(flatten)]
+    pub transport: McpServerTransportConfig,
 
+    /// Startup timeout in milliseconds for initializing MCP server & initially listing tools.
     #[serde(default)]
-    pub args: Vec<String>,
+    pub startup_timeout_ms: Option<u64>,
 
+    /// When to ask the user before calling this server's tools. Unset follows
+    /// `approval_policy`.
     #[serde(default)]
-    pub env: Option<HashMap<String, String>>,
+    pub approval: Option<McpToolApproval>,
 
-    /// Startup timeout in milliseconds for initializing MCP server & initially listing tools.
+    /// Only offer these tools to the model. Unset offers every tool.
     #[serde(default)]
-    pub startup_timeout_ms: Option<u64>,
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Never offer these tools to the model, even if listed in
+    /// `enabled_tools`.
+    #[serde(default)]
+    pub disabled_tools: Option<Vec<String>>,
+
+    /// Per-tool settings, keyed by the tool name the server reports.
+    #[serde(default)]
+    pub tools: HashMap<String, McpToolConfig>,
+}
+
+/// Settings for a single tool of an MCP server.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct McpToolConfig {
+    /// Overrides the server's `approval` for this tool.
+    #[serde(default)]
+    pub approval: Option<McpToolApproval>,
+}
+
+/// When to ask the user before Codex calls an MCP tool.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpToolApproval {
+    /// Ask before every call.
+    Always,
+
+    /// Never ask.
+    Never,
+
+    /// Ask unless the server marks the tool as read-only.
+    OnRequest,
+}
+
+/// How to reach an MCP server: a `command` to spawn, or a `url` to connect to.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum McpServerTransportConfig {
+    /// Spawn the server and talk to it over stdio.
+    Stdio {
+        command: String,
+
+        #[serde(default)]
+        args: Vec<String>,
+
+        #[serde(default)]
+        env: Option<HashMap<String, String>>,
+    },
+
+    /// Connect to a server over Streamable HTTP.
+    StreamableHttp {
+        url: String,
+
+        /// Extra HTTP headers sent with every request.
+        #[serde(default)]
+        headers: Option<HashMap<String, String>>,
+
+        /// Environment variable holding a token sent as
+        /// `Authorization: Bearer <token>`.
+        #[serde(default)]
+        bearer_token_env_var: Option<String>,
+    },
+}
+
+/// A named sub-agent that `sub_agent_launch` can delegate to. Defined in an
+/// `[agents.<name>]` table in `config.toml` or in a markdown file under
+/// `$CODEX_HOME/agents`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AgentConfig {
+    /// Short summary shown to the model so it can pick an agent.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// System prompt for the agent. Replaces the built-in base instructions.
+    #[serde(default)]
+    pub instructions: Option<String>,
+
+    /// Model to run the agent with. Defaults to the launching agent's model.
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Sandbox to run the agent under. Defaults to the launching agent's.
+    #[serde(default)]
+    pub sandbox_mode: Option<SandboxMode>,
+
+    /// Names of the tools the agent may use. Defaults to all tools.
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+}
+
+/// Limits on how much work an agent may do before it is stopped. Each limit
+/// is optional; the default budget is unlimited.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Budget {
+    /// Maximum number of model requests.
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+
+    /// Maximum number of tokens, as reported by the model provider.
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+
+    /// Maximum wall-clock time, in seconds.
+    #[serde(default)]
+    pub max_duration_secs: Option<u64>,
+}
+
+impl Budget {
+    /// Combines two budgets, keeping the stricter of each limit.
+    pub fn tightened_by(self, other: Budget) -> Budget {
+        fn stricter<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Budget {
+            max_turns: stricter(self.max_turns, other.max_turns),
+            max_tokens: stricter(self.max_tokens, other.max_tokens),
+            max_duration_secs: stricter(self.max_duration_secs, other.max_duration_secs),
+        }
+    }
+}
+
+/// Entry in `fallback_providers`: either the id of a provider in
+/// `model_providers`, or a table that also names the model to request from it.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FallbackProviderToml {
+    Id(String),
+    WithModel {
+        provider: String,
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+/// Settings for the `codex web` server.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct WebConfig {
+    /// Access tokens accepted by the server, keyed by a name that identifies
+    /// the token's sessions. When empty, a token is generated at startup.
+    #[serde(default)]
+    pub tokens: HashMap<String, WebTokenConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct WebTokenConfig {
+    /// Secret presented as `Authorization: Bearer <token>`.
+    pub token: String,
+
+    /// Directories that sessions started with this token may use as their
+    /// working directory. Unset allows any directory.
+    #[serde(default)]
+    pub cwd_roots: Option<Vec<PathBuf>>,
+}
+
+/// External programs run at fixed points in a session. Each hook receives a
+/// JSON description of the event on stdin.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub session_start: Vec<HookCommand>,
+
+    /// Run before a shell command executes. A non-zero exit blocks the
+    /// command.
+    #[serde(default)]
+    pub pre_exec: Vec<HookCommand>,
+
+    #[serde(default)]
+    pub post_exec: Vec<HookCommand>,
+
+    /// Run before a patch is applied. A non-zero exit blocks the patch.
+    #[serde(default)]
+    pub pre_apply_patch: Vec<HookCommand>,
+
+    #[serde(default)]
+    pub post_apply_patch: Vec<HookCommand>,
+
+    #[serde(default)]
+    pub turn_complete: Vec<HookCommand>,
+
+    #[serde(default)]
+    pub sub_agent_complete: Vec<HookCommand>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct HookCommand {
+    /// Program and arguments, e.g. `["./scripts/audit.sh", "--json"]`.
+    pub command: Vec<String>,
+
+    /// How long to wait for the hook before giving up on it. Defaults to 60
+    /// seconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
@@ -87,6 +286,12 @@ pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub network_access: bool,
     #[serde(default)]
+    pub network_allowlist: Vec<String>,
+    #[serde(default)]
+    pub read_only_paths: Vec<String>,
+    #[serde(default)]
+    pub deny_read_paths: Vec<String>,
+    #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
     pub exclude_slash_tmp: bool,
@@ -33,6 +33,12 @@ impl ConversationHistory {
         }
     }
 
+    /// Removes every reasoning item from the transcript.
+    pub(crate) fn remove_reasoning(&mut self) {
+        self.items
+            .retain(|item| !matches!(item, ResponseItem::Reasoning { .. }));
+    }
+
     pub(crate) fn keep_last_messages(&mut self, n: usize) {
         if n == 0 {
             self.items.clear();
@@ -179,15 +179,7 @@ fn truncate_after_dropping_last_messages(history: InitialHistory, n: usize) -> I
     // Work directly on rollout items, and cut the vector at the nth-from-last user message input.
     let items: Vec<RolloutItem> = history.get_rollout_items();
 
-    // Find indices of user message inputs in rollout order.
-    let mut user_positions: Vec<usize> = Vec::new();
-    for (idx, item) in items.iter().enumerate() {
-        if let RolloutItem::ResponseItem(ResponseItem::Message { role, .. }) = item
-            && role == "user"
-        {
-            user_positions.push(idx);
-        }
-    }
+    let user_positions = user_message_positions(&items);
 
     // If fewer than n user messages exist, treat as empty.
     if user_positions.len() < n {
@@ -205,6 +197,20 @@ fn truncate_after_dropping_last_messages(history: InitialHistory, n: usize) -> I
     }
 }
 
+/// Indices of user message inputs in rollout order.
+pub(crate) fn user_message_positions(items: &[RolloutItem]) -> Vec<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| match item {
+            RolloutItem::ResponseItem(ResponseItem::Message { role, .. }) if role == "user" => {
+                Some(idx)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,6 +147,9 @@ mod tests {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: writable_roots.into_iter().map(PathBuf::from).collect(),
             network_access,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         }
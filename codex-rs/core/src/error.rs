@@ -241,6 +241,23 @@ impl CodexErr {
     pub fn downcast_ref<T: std::any::Any>(&self) -> Option<&T> {
         (self as &dyn std::any::Any).downcast_ref::<T>()
     }
+
+    /// Whether the error means the model provider is rate limited or
+    /// unavailable, as opposed to the request itself being invalid. Once
+    /// retries are exhausted these errors move the session to the next entry
+    /// in `fallback_providers`.
+    pub fn is_provider_unavailable(&self) -> bool {
+        match self {
+            CodexErr::Stream(..)
+            | CodexErr::InternalServerError
+            | CodexErr::UsageLimitReached(_) => true,
+            CodexErr::RetryLimit(status) | CodexErr::UnexpectedStatus(status, _) => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            CodexErr::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
 }
 
 pub fn get_error_message_ui(e: &CodexErr) -> String {
@@ -375,4 +392,18 @@ mod tests {
             "You've hit your usage limit. Try again in less than a minute."
         );
     }
+
+    #[test]
+    fn provider_unavailable_covers_rate_limits_and_server_errors() {
+        assert!(CodexErr::RetryLimit(StatusCode::TOO_MANY_REQUESTS).is_provider_unavailable());
+        assert!(CodexErr::RetryLimit(StatusCode::BAD_GATEWAY).is_provider_unavailable());
+        assert!(
+            CodexErr::Stream("idle timeout waiting for SSE".into(), None).is_provider_unavailable()
+        );
+        assert!(
+            !CodexErr::UnexpectedStatus(StatusCode::BAD_REQUEST, "bad".into())
+                .is_provider_unavailable()
+        );
+        assert!(!CodexErr::Interrupted.is_provider_unavailable());
+    }
 }
@@ -17,7 +17,9 @@ use tokio::process::Child;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
+use crate::exec_env::set_network_proxy_env;
 use crate::landlock::spawn_command_under_linux_sandbox;
+use crate::network_proxy::NetworkProxy;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
@@ -107,12 +109,24 @@ pub async fn process_exec_tool_call(
         SandboxType::LinuxSeccomp => {
             let timeout = params.timeout_duration();
             let ExecParams {
-                command, cwd, env, ..
+                command,
+                cwd,
+                mut env,
+                ..
             } = params;
 
             let codex_linux_sandbox_exe = codex_linux_sandbox_exe
                 .as_ref()
                 .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+            // Allowlisted hosts are reached through a proxy that lives as long
+            // as the command.
+            let network_proxy = match sandbox_policy.network_allowlist() {
+                [] => None,
+                allowlist => Some(NetworkProxy::start(allowlist).await?),
+            };
+            if let Some(network_proxy) = &network_proxy {
+                set_network_proxy_env(&mut env, network_proxy.port());
+            }
             let child = spawn_command_under_linux_sandbox(
                 codex_linux_sandbox_exe,
                 command,
@@ -120,10 +134,13 @@ pub async fn process_exec_tool_call(
                 cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
+                network_proxy.as_ref(),
             )
             .await?;
 
-            consume_truncated_output(child, timeout, stdout_stream).await
+            let output = consume_truncated_output(child, timeout, stdout_stream).await;
+            drop(network_proxy);
+            output
         }
     };
     let duration = start.elapsed();
@@ -20,6 +20,18 @@ pub struct ExecCommandParams {
     pub(crate) login: bool,
 }
 
+impl ExecCommandParams {
+    /// The argv the command is spawned with.
+    pub(crate) fn command(&self) -> Vec<String> {
+        let shell_mode_opt = if self.login { "-lc" } else { "-c" };
+        vec![
+            self.shell.clone(),
+            shell_mode_opt.to_string(),
+            self.cmd.clone(),
+        ]
+    }
+}
+
 fn default_yield_time() -> u64 {
     10_000
 }
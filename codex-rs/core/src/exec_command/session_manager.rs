@@ -38,6 +38,14 @@ pub struct ExecCommandOutput {
 }
 
 impl ExecCommandOutput {
+    /// `None` while the process is still running.
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        match self.exit_status {
+            ExitStatus::Exited(code) => Some(code),
+            ExitStatus::Ongoing(_) => None,
+        }
+    }
+
     fn to_text_output(&self) -> String {
         let wall_time_secs = self.wall_time.as_secs_f32();
         let termination_status = match self.exit_status {
@@ -246,14 +254,6 @@ impl SessionManager {
 async fn create_exec_command_session(
     params: ExecCommandParams,
 ) -> anyhow::Result<(ExecCommandSession, oneshot::Receiver<i32>)> {
-    let ExecCommandParams {
-        cmd,
-        yield_time_ms: _,
-        max_output_tokens: _,
-        shell,
-        login,
-    } = params;
-
     // Use the native pty implementation for the system
     let pty_system = native_pty_system();
 
@@ -266,10 +266,8 @@ async fn create_exec_command_session(
     })?;
 
     // Spawn a shell into the pty
-    let mut command_builder = CommandBuilder::new(shell);
-    let shell_mode_opt = if login { "-lc" } else { "-c" };
-    command_builder.arg(shell_mode_opt);
-    command_builder.arg(cmd);
+    let command_builder =
+        CommandBuilder::from_argv(params.command().into_iter().map(Into::into).collect());
 
     let mut child = pair.slave.spawn_command(command_builder)?;
     // Obtain a killer that can signal the process independently of `.wait()`.
@@ -68,6 +68,25 @@ where
     env_map
 }
 
+/// Points HTTP clients at the sandbox network proxy listening on `port` on
+/// loopback, so that allowlisted hosts are reachable from inside the sandbox.
+/// `NO_PROXY` is dropped because direct connections are blocked anyway.
+pub fn set_network_proxy_env(env: &mut HashMap<String, String>, port: u16) {
+    let proxy_url = format!("http://127.0.0.1:{port}");
+    for key in [
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "ALL_PROXY",
+        "http_proxy",
+        "https_proxy",
+        "all_proxy",
+    ] {
+        env.insert(key.to_string(), proxy_url.clone());
+    }
+    env.remove("NO_PROXY");
+    env.remove("no_proxy");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,218 @@
+//! Command safety decisions backed by `codex-execpolicy`.
+//!
+//! The built-in default policy is always loaded. Additional `*.policy` files
+//! are read from `$CODEX_HOME/policy` and, for trusted projects, from
+//! `<project>/.codex/policy` (see `Config::exec_policy_dirs`). A command that
+//! any policy forbids is rejected; a command that some policy matches without
+//! writing files is safe to run without approval, inside the sandbox.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::get_default_policy;
+
+use crate::bash::parse_bash_lc_plain_commands;
+
+/// Name of the directory (under `$CODEX_HOME`, or a project's `.codex`) that
+/// holds exec policy files.
+pub(crate) const POLICY_SUBDIR: &str = "policy";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ExecPolicyDecision {
+    /// Every command matched a policy rule and none of them writes files.
+    Safe,
+    Forbidden {
+        reason: String,
+    },
+    /// No policy vouches for the command.
+    Unmatched,
+}
+
+#[derive(Default)]
+pub(crate) struct ExecPolicy {
+    policies: Vec<Policy>,
+}
+
+impl ExecPolicy {
+    /// Loads the default policy plus every `*.policy` file in `dirs`, in file
+    /// name order. Returns the messages for any policy that failed to load;
+    /// those policies are skipped.
+    pub(crate) fn load(dirs: &[PathBuf]) -> (Self, Vec<String>) {
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        match get_default_policy() {
+            Ok(policy) => policies.push(policy),
+            Err(e) => errors.push(format!(
+                "failed to parse default exec policy: {:#}",
+                e.into_anyhow()
+            )),
+        }
+        for path in dirs.iter().flat_map(|dir| policy_files_in(dir)) {
+            match load_policy_file(&path) {
+                Ok(policy) => policies.push(policy),
+                Err(e) => errors.push(format!(
+                    "failed to load exec policy {}: {e:#}",
+                    path.display()
+                )),
+            }
+        }
+        (Self { policies }, errors)
+    }
+
+    /// Evaluates `command` as passed to exec. `bash -lc` scripts made up of
+    /// plain commands are evaluated command by command.
+    pub(crate) fn evaluate(&self, command: &[String]) -> ExecPolicyDecision {
+        if self.policies.is_empty() {
+            return ExecPolicyDecision::Unmatched;
+        }
+        let commands = match parse_bash_lc_plain_commands(command) {
+            Some(commands) => commands,
+            None => vec![command.to_vec()],
+        };
+        let mut all_safe = !commands.is_empty();
+        for command in &commands {
+            match self.evaluate_one(command) {
+                ExecPolicyDecision::Safe => {}
+                ExecPolicyDecision::Forbidden { reason } => {
+                    return ExecPolicyDecision::Forbidden { reason };
+                }
+                ExecPolicyDecision::Unmatched => all_safe = false,
+            }
+        }
+        if all_safe {
+            ExecPolicyDecision::Safe
+        } else {
+            ExecPolicyDecision::Unmatched
+        }
+    }
+
+    fn evaluate_one(&self, command: &[String]) -> ExecPolicyDecision {
+        let Some((program, args)) = command.split_first() else {
+            return ExecPolicyDecision::Unmatched;
+        };
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+        let mut safe = false;
+        for policy in &self.policies {
+            match policy.check(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return ExecPolicyDecision::Forbidden { reason };
+                }
+                Ok(MatchedExec::Match { exec }) if !exec.might_write_files() => safe = true,
+                Ok(MatchedExec::Match { .. }) | Err(_) => {}
+            }
+        }
+        if safe {
+            ExecPolicyDecision::Safe
+        } else {
+            ExecPolicyDecision::Unmatched
+        }
+    }
+}
+
+fn policy_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "policy"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn load_policy_file(path: &Path) -> anyhow::Result<Policy> {
+    let source = std::fs::read_to_string(path)?;
+    PolicyParser::new(&path.to_string_lossy(), &source)
+        .parse()
+        .map_err(|e| e.into_anyhow())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn default_policy_marks_read_only_commands_safe() {
+        let (policy, errors) = ExecPolicy::load(&[]);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            policy.evaluate(&vec_str(&["pwd"])),
+            ExecPolicyDecision::Safe
+        );
+        assert_eq!(
+            policy.evaluate(&vec_str(&["bash", "-lc", "pwd && which rg"])),
+            ExecPolicyDecision::Safe
+        );
+        assert_eq!(
+            policy.evaluate(&vec_str(&["bash", "-lc", "pwd && rm -rf build"])),
+            ExecPolicyDecision::Unmatched
+        );
+        assert_eq!(
+            policy.evaluate(&vec_str(&["rm", "-rf", "build"])),
+            ExecPolicyDecision::Unmatched
+        );
+    }
+
+    #[test]
+    fn user_policies_can_allow_and_forbid() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(
+            dir.path().join("team.policy"),
+            r#"
+define_program(
+    program="cargo",
+    args=["check"],
+)
+
+forbid_program_regex(
+    regex="^terraform$",
+    reason="terraform must be run by a human",
+)
+"#,
+        )
+        .expect("write policy");
+        std::fs::write(dir.path().join("notes.txt"), "not a policy").expect("write notes");
+
+        let (policy, errors) = ExecPolicy::load(&[dir.path().to_path_buf()]);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            policy.evaluate(&vec_str(&["cargo", "check"])),
+            ExecPolicyDecision::Safe
+        );
+        assert_eq!(
+            policy.evaluate(&vec_str(&["bash", "-lc", "ls && terraform apply"])),
+            ExecPolicyDecision::Forbidden {
+                reason: "terraform must be run by a human".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_policy_files_are_reported_and_skipped() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(dir.path().join("broken.policy"), "define_program(").expect("write");
+
+        let (policy, errors) = ExecPolicy::load(&[dir.path().to_path_buf()]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("broken.policy"), "{}", errors[0]);
+        assert_eq!(
+            policy.evaluate(&vec_str(&["pwd"])),
+            ExecPolicyDecision::Safe
+        );
+    }
+}
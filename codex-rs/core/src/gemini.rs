@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::ModelProviderInfo;
+use crate::chat_completions::send_streaming_request;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::client_common::instructions_with_output_schema;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_gemini_api;
+use crate::protocol::TokenUsage;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+
+/// Implementation for the Gemini `streamGenerateContent` API.
+pub(crate) async fn stream_gemini_generate_content(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+) -> Result<ResponseStream> {
+    let input = prompt.get_formatted_input();
+
+    // Thought signatures are only replayed for the turn in progress, i.e.
+    // after the last user message.
+    let last_user_index = input
+        .iter()
+        .rposition(|item| matches!(item, ResponseItem::Message { role, .. } if role == "user"));
+
+    // `functionResponse` parts are matched to their call by name, which the
+    // output items do not carry.
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+    let mut pending_signature: Option<&String> = None;
+    let mut contents = Vec::<Value>::new();
+    for (idx, item) in input.iter().enumerate() {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" { "model" } else { "user" };
+                for c in content {
+                    let part = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"text": text})
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            // Only inline data is supported; remote URLs would
+                            // have to be uploaded through the Files API first.
+                            let Some((mime_type, data)) = image_url
+                                .strip_prefix("data:")
+                                .and_then(|rest| rest.split_once(";base64,"))
+                            else {
+                                continue;
+                            };
+                            json!({"inlineData": {"mimeType": mime_type, "data": data}})
+                        }
+                    };
+                    push_part(&mut contents, role, part);
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                call_names.insert(call_id, name);
+                let args = serde_json::from_str::<Value>(arguments).unwrap_or_else(|_| json!({}));
+                let mut part = json!({"functionCall": {"name": name, "args": args}});
+                if let Some(signature) = pending_signature.take()
+                    && let Some(obj) = part.as_object_mut()
+                {
+                    obj.insert("thoughtSignature".to_string(), json!(signature));
+                }
+                push_part(&mut contents, "model", part);
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let name = call_names
+                    .get(call_id.as_str())
+                    .copied()
+                    .unwrap_or_default();
+                push_part(
+                    &mut contents,
+                    "user",
+                    json!({
+                        "functionResponse": {
+                            "name": name,
+                            "response": {"content": output.content},
+                        }
+                    }),
+                );
+            }
+            ResponseItem::Reasoning {
+                encrypted_content, ..
+            } if last_user_index.is_none_or(|u_idx| idx > u_idx) => {
+                // Thought text cannot be sent back; only the signature is
+                // attached to the function call that follows it.
+                pending_signature = encrypted_content.as_ref();
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+            | ResponseItem::CustomToolCallOutput { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {
+                // These items cannot be expressed in the Gemini API; the
+                // corresponding tools are never offered to the model.
+                continue;
+            }
+        }
+    }
+
+    let mut payload = json!({
+        "systemInstruction": {
+            "parts": [{"text": instructions_with_output_schema(prompt, model_family)}],
+        },
+        "contents": contents,
+    });
+    let tools_json = create_tools_json_for_gemini_api(&prompt.tools)?;
+    if !tools_json.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "tools".to_string(),
+            json!([{"functionDeclarations": tools_json}]),
+        );
+    }
+    if model_family.supports_reasoning_summaries
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "generationConfig".to_string(),
+            json!({"thinkingConfig": {"includeThoughts": true}}),
+        );
+    }
+
+    let resp = send_streaming_request(client, provider, &model_family.slug, &payload).await?;
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_gemini_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
+
+/// Appends a part to the conversation, merging it into the previous content
+/// when the role matches.
+fn push_part(contents: &mut Vec<Value>, role: &str, part: Value) {
+    if let Some(last) = contents.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(parts) = last.get_mut("parts").and_then(Value::as_array_mut)
+    {
+        parts.push(part);
+        return;
+    }
+    contents.push(json!({"role": role, "parts": [part]}));
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    usage_metadata: Option<UsageMetadata>,
+    response_id: Option<String>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<Content>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    text: Option<String>,
+    #[serde(default)]
+    thought: bool,
+    function_call: Option<FunctionCall>,
+    thought_signature: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionCall {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    cached_content_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    #[serde(default)]
+    thoughts_token_count: u64,
+    #[serde(default)]
+    total_token_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// SSE processor for the Gemini streaming format. Each event is a complete
+/// `GenerateContentResponse` chunk and the stream simply ends after the chunk
+/// carrying `finishReason`, at which point the accumulated reasoning, message,
+/// and function calls are emitted in that order.
+async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut assistant_text = String::new();
+    let mut reasoning_text = String::new();
+    let mut thought_signature: Option<String> = None;
+    let mut function_calls = Vec::<ResponseItem>::new();
+    let mut response_id = String::new();
+    let mut usage: Option<UsageMetadata> = None;
+    let mut finished = false;
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let chunk: GenerateContentResponse = match serde_json::from_str(&sse.data) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                trace!("failed to parse Gemini SSE chunk: {e}, data: {}", &sse.data);
+                continue;
+            }
+        };
+        trace!("gemini received SSE chunk: {chunk:?}");
+
+        if let Some(error) = chunk.error {
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(error.message, None)))
+                .await;
+            return;
+        }
+        if let Some(id) = chunk.response_id {
+            response_id = id;
+        }
+        if chunk.usage_metadata.is_some() {
+            usage = chunk.usage_metadata;
+        }
+
+        let Some(candidate) = chunk.candidates.into_iter().next() else {
+            continue;
+        };
+        for part in candidate.content.map(|c| c.parts).unwrap_or_default() {
+            if let Some(signature) = part.thought_signature {
+                thought_signature.get_or_insert(signature);
+            }
+            if let Some(call) = part.function_call {
+                function_calls.push(ResponseItem::FunctionCall {
+                    id: None,
+                    name: call.name,
+                    arguments: call.args.to_string(),
+                    call_id: call
+                        .id
+                        .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple())),
+                });
+            } else if let Some(text) = part.text.filter(|t| !t.is_empty()) {
+                if part.thought {
+                    reasoning_text.push_str(&text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::ReasoningContentDelta(text)))
+                        .await;
+                } else {
+                    assistant_text.push_str(&text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::OutputTextDelta(text)))
+                        .await;
+                }
+            }
+        }
+        if candidate.finish_reason.is_some() {
+            finished = true;
+        }
+    }
+
+    if !finished {
+        let _ = tx_event
+            .send(Err(CodexErr::Stream(
+                "stream closed before finishReason".into(),
+                None,
+            )))
+            .await;
+        return;
+    }
+
+    let mut items = Vec::new();
+    if !reasoning_text.is_empty() || thought_signature.is_some() {
+        items.push(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: (!reasoning_text.is_empty()).then(|| {
+                vec![ReasoningItemContent::ReasoningText {
+                    text: reasoning_text,
+                }]
+            }),
+            encrypted_content: thought_signature,
+        });
+    }
+    if !assistant_text.is_empty() {
+        items.push(ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: assistant_text,
+            }],
+        });
+    }
+    items.extend(function_calls);
+    for item in items {
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage: usage.map(Into::into),
+        }))
+        .await;
+}
+
+impl From<UsageMetadata> for TokenUsage {
+    fn from(usage: UsageMetadata) -> Self {
+        TokenUsage {
+            input_tokens: usage.prompt_token_count,
+            cached_input_tokens: usage.cached_content_token_count,
+            output_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+            reasoning_output_tokens: usage.thoughts_token_count,
+            total_tokens: usage.total_token_count,
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! Lifecycle hooks: programs configured under `[hooks]` in `config.toml` that
+//! run at fixed points in a session. Each hook receives a JSON payload on
+//! stdin with the session id, the working directory and the event fields, e.g.
+//!
+//! ```json
+//! {"session_id":"…","cwd":"/repo","event":"pre_exec","call_id":"call_1","command":["cargo","test"]}
+//! ```
+//!
+//! A `pre_*` hook that exits non-zero (or times out) blocks the action, and
+//! its stderr is returned to the model. Failures of other hooks are logged
+//! and otherwise ignored.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::config_types::HookCommand;
+use crate::config_types::HooksConfig;
+use crate::protocol::FileChange;
+
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum HookEvent {
+    SessionStart {
+        model: String,
+    },
+    PreExec {
+        call_id: String,
+        command: Vec<String>,
+    },
+    PostExec {
+        call_id: String,
+        command: Vec<String>,
+        /// `None` when the command could not be run, or when it keeps
+        /// running in the background (`exec_command`, `unified_exec`).
+        exit_code: Option<i32>,
+    },
+    PreApplyPatch {
+        call_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+    },
+    PostApplyPatch {
+        call_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+        success: bool,
+    },
+    TurnComplete {
+        turn_id: String,
+        input_messages: Vec<String>,
+        last_assistant_message: Option<String>,
+    },
+    SubAgentComplete {
+        agent_id: String,
+        label: String,
+        /// The sub-agent's final message, when it succeeded.
+        summary: Option<String>,
+        /// Why the sub-agent failed, when it did.
+        error: Option<String>,
+    },
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            HookEvent::SessionStart { .. } => "session_start",
+            HookEvent::PreExec { .. } => "pre_exec",
+            HookEvent::PostExec { .. } => "post_exec",
+            HookEvent::PreApplyPatch { .. } => "pre_apply_patch",
+            HookEvent::PostApplyPatch { .. } => "post_apply_patch",
+            HookEvent::TurnComplete { .. } => "turn_complete",
+            HookEvent::SubAgentComplete { .. } => "sub_agent_complete",
+        }
+    }
+
+    /// The `post_*` event that follows this `pre_*` event, given the exit
+    /// code of the command and whether the action succeeded. `None` for
+    /// other events.
+    pub(crate) fn into_post(self, exit_code: Option<i32>, success: bool) -> Option<HookEvent> {
+        match self {
+            HookEvent::PreExec { call_id, command } => Some(HookEvent::PostExec {
+                call_id,
+                command,
+                exit_code,
+            }),
+            HookEvent::PreApplyPatch { call_id, changes } => Some(HookEvent::PostApplyPatch {
+                call_id,
+                changes,
+                success,
+            }),
+            _ => None,
+        }
+    }
+
+    fn can_block(&self) -> bool {
+        matches!(
+            self,
+            HookEvent::PreExec { .. } | HookEvent::PreApplyPatch { .. }
+        )
+    }
+
+    fn commands<'a>(&self, config: &'a HooksConfig) -> &'a [HookCommand] {
+        match self {
+            HookEvent::SessionStart { .. } => &config.session_start,
+            HookEvent::PreExec { .. } => &config.pre_exec,
+            HookEvent::PostExec { .. } => &config.post_exec,
+            HookEvent::PreApplyPatch { .. } => &config.pre_apply_patch,
+            HookEvent::PostApplyPatch { .. } => &config.post_apply_patch,
+            HookEvent::TurnComplete { .. } => &config.turn_complete,
+            HookEvent::SubAgentComplete { .. } => &config.sub_agent_complete,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HookPayload<'a> {
+    session_id: ConversationId,
+    cwd: &'a Path,
+    #[serde(flatten)]
+    event: &'a HookEvent,
+}
+
+/// Why a `pre_*` hook blocked an action; `message` is returned to the model.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct HookBlocked {
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Hooks {
+    config: HooksConfig,
+}
+
+impl Hooks {
+    pub(crate) fn new(config: HooksConfig) -> Self {
+        Self { config }
+    }
+
+    /// Runs the hooks for `event` in order and waits for them. For `pre_*`
+    /// events the first hook that fails blocks the action and the remaining
+    /// hooks are skipped.
+    pub(crate) async fn run(
+        &self,
+        session_id: ConversationId,
+        cwd: &Path,
+        event: &HookEvent,
+    ) -> Result<(), HookBlocked> {
+        let commands = event.commands(&self.config);
+        if commands.is_empty() {
+            return Ok(());
+        }
+        let payload = HookPayload {
+            session_id,
+            cwd,
+            event,
+        };
+        let Ok(json) = serde_json::to_vec(&payload) else {
+            warn!("failed to serialise {} hook payload", event.name());
+            return Ok(());
+        };
+        for hook in commands {
+            if let Err(message) = run_hook(hook, cwd, &json).await {
+                let program = hook.command.first().map(String::as_str).unwrap_or("");
+                warn!("{} hook `{program}` failed: {message}", event.name());
+                if event.can_block() {
+                    return Err(HookBlocked { message });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the hooks for `event` without waiting for them.
+    pub(crate) fn spawn(&self, session_id: ConversationId, cwd: PathBuf, event: HookEvent) {
+        if event.commands(&self.config).is_empty() {
+            return;
+        }
+        let hooks = self.clone();
+        tokio::spawn(async move {
+            let _ = hooks.run(session_id, &cwd, &event).await;
+        });
+    }
+}
+
+/// Runs one hook with `payload` on stdin. On failure, returns the hook's
+/// stderr or a description of what went wrong.
+async fn run_hook(hook: &HookCommand, cwd: &Path, payload: &[u8]) -> Result<(), String> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err("hook command is empty".to_string());
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start hook `{program}`: {e}"))?;
+    let stdin = child.stdin.take();
+    let write_payload = async {
+        if let Some(mut stdin) = stdin {
+            // Hooks are free to ignore their input. Dropping `stdin` once the
+            // payload is written closes it.
+            let _ = stdin.write_all(payload).await;
+        }
+    };
+    let run = async {
+        let ((), output) = tokio::join!(write_payload, child.wait_with_output());
+        output
+    };
+
+    // The timeout covers writing the payload too: a hook that never reads
+    // its stdin must not hang the session once the pipe buffer is full.
+    let timeout = hook
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_HOOK_TIMEOUT);
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("failed to wait for hook `{program}`: {e}")),
+        Err(_) => {
+            return Err(format!(
+                "hook `{program}` timed out after {}ms",
+                timeout.as_millis()
+            ));
+        }
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        Err(format!("hook `{program}` failed with {}", output.status))
+    } else {
+        Err(stderr)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn sh(script: &str) -> HookCommand {
+        HookCommand {
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_ms: None,
+        }
+    }
+
+    fn pre_exec() -> HookEvent {
+        HookEvent::PreExec {
+            call_id: "call_1".to_string(),
+            command: vec!["cargo".to_string(), "test".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_receive_the_event_as_json_on_stdin() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks::new(HooksConfig {
+            pre_exec: vec![sh("cat > payload.json")],
+            ..Default::default()
+        });
+
+        let session_id = ConversationId::new();
+        hooks
+            .run(session_id, dir.path(), &pre_exec())
+            .await
+            .expect("hook allows the command");
+
+        let payload: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("payload.json")).expect("payload written"),
+        )
+        .expect("payload is JSON");
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "session_id": session_id,
+                "cwd": dir.path(),
+                "event": "pre_exec",
+                "call_id": "call_1",
+                "command": ["cargo", "test"],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_pre_hook_blocks_with_its_stderr() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks::new(HooksConfig {
+            pre_exec: vec![
+                sh("echo 'cargo test is disabled here' >&2; exit 3"),
+                sh("touch second-hook-ran"),
+            ],
+            ..Default::default()
+        });
+
+        let blocked = hooks
+            .run(ConversationId::new(), dir.path(), &pre_exec())
+            .await;
+        assert_eq!(
+            blocked,
+            Err(HookBlocked {
+                message: "cargo test is disabled here".to_string()
+            })
+        );
+        assert!(!dir.path().join("second-hook-ran").exists());
+    }
+
+    #[tokio::test]
+    async fn failing_post_hook_does_not_block() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks::new(HooksConfig {
+            post_exec: vec![sh("exit 1"), sh("touch second-hook-ran")],
+            ..Default::default()
+        });
+
+        let event = HookEvent::PostExec {
+            call_id: "call_1".to_string(),
+            command: vec!["true".to_string()],
+            exit_code: Some(0),
+        };
+        assert_eq!(
+            hooks.run(ConversationId::new(), dir.path(), &event).await,
+            Ok(())
+        );
+        assert!(dir.path().join("second-hook-ran").exists());
+    }
+
+    #[tokio::test]
+    async fn slow_pre_hook_times_out_and_blocks() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks::new(HooksConfig {
+            pre_exec: vec![HookCommand {
+                timeout_ms: Some(100),
+                ..sh("sleep 5")
+            }],
+            ..Default::default()
+        });
+
+        let blocked = hooks
+            .run(ConversationId::new(), dir.path(), &pre_exec())
+            .await;
+        assert_eq!(
+            blocked,
+            Err(HookBlocked {
+                message: "hook `sh` timed out after 100ms".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn hook_ignoring_a_large_payload_still_times_out() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks::new(HooksConfig {
+            pre_exec: vec![HookCommand {
+                timeout_ms: Some(100),
+                ..sh("sleep 5")
+            }],
+            ..Default::default()
+        });
+        // Far more than a pipe buffer holds.
+        let event = HookEvent::PreExec {
+            call_id: "call_1".to_string(),
+            command: vec!["x".repeat(1 << 20)],
+        };
+
+        let started = std::time::Instant::now();
+        let blocked = hooks.run(ConversationId::new(), dir.path(), &event).await;
+        assert!(blocked.is_err());
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
@@ -5,10 +5,14 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod agents;
+mod anthropic_messages;
 mod apply_patch;
 pub mod auth;
 pub mod bash;
+mod budget;
 mod chat_completions;
+pub mod checkpoint;
 mod client;
 mod client_common;
 pub mod codex;
@@ -26,20 +30,26 @@ pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod exec_policy;
 mod flags;
+mod gemini;
 pub mod git_info;
+mod hooks;
 pub mod internal_storage;
 mod is_safe_command;
 pub mod landlock;
 mod mcp_connection_manager;
+mod mcp_resources;
 mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
+pub mod network_proxy;
 pub mod parse_command;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
+pub use model_provider_info::FallbackProvider;
 pub use model_provider_info::ModelProviderInfo;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
@@ -56,6 +66,7 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+pub mod output_schema;
 pub mod plan_tool;
 pub mod project_doc;
 mod rollout;
@@ -63,9 +74,11 @@ pub(crate) mod safety;
 pub mod seatbelt;
 pub mod shell;
 pub mod spawn;
+mod sub_agent;
 pub mod terminal;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
+mod turn_undo;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
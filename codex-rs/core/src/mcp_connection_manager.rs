@@ -5,19 +5,40 @@
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
 //! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//!
+//! Resources and prompts are listed from the servers that advertise them
+//! whenever a server connects and are kept per server name.
+//!
+//! A server whose connection closes mid-session, normally because its process
+//! exited, is started again with exponential backoff. Its tools, resources and
+//! prompts are listed again once it is back, and every change of state is
+//! reported to the user as a `BackgroundEvent`.
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::Weak;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use async_channel::Sender;
 use codex_mcp_client::McpClient;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
 
 use serde_json::json;
@@ -27,7 +48,15 @@ use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
 
+use crate::codex::INITIAL_SUBMIT_ID;
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerTransportConfig;
+use crate::config_types::McpToolApproval;
+use crate::protocol::BackgroundEventEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::McpServerState;
+use crate::protocol::McpServerStatus;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -40,6 +69,18 @@ const MAX_TOOL_NAME_LENGTH: usize = 64;
 /// Default timeout for initializing MCP server & initially listing tools.
 const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Number of times a server that exited is started again before it is marked
+/// as failed.
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+/// Delay before the first restart attempt. It doubles with every attempt.
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// A server that stays up this long after a restart is healthy again: if it
+/// exits later, it gets a fresh set of restart attempts.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
 /// Map that holds a startup error for every MCP server that could **not** be
 /// spawned successfully.
 pub type ClientStartErrors = HashMap<String, anyhow::Error>;
@@ -85,19 +126,117 @@ struct ToolInfo {
 struct ManagedClient {
     client: Arc<McpClient>,
     startup_timeout: Duration,
+    capabilities: ServerCapabilities,
 }
 
-/// A thin wrapper around a set of running [`McpClient`] instances.
+/// What a server offered when it last connected.
 #[derive(Default)]
-pub(crate) struct McpConnectionManager {
-    /// Server-name -> client instance.
+struct ServerListing {
+    tools: Vec<Tool>,
+    resources: Vec<Resource>,
+    prompts: Vec<Prompt>,
+}
+
+/// A server that could not be started, along with what it wrote to stderr.
+struct StartFailure {
+    error: anyhow::Error,
+    stderr_tail: Vec<String>,
+}
+
+/// State shared between the manager and the tasks that restart servers.
+#[derive(Default)]
+struct Servers {
+    /// Server-name -> client instance, for the servers that are connected.
     ///
     /// The server name originates from the keys of the `mcp_servers` map in
     /// the user configuration.
     clients: HashMap<String, ManagedClient>,
 
+    /// Server name -> configuration, for every configured server with a valid
+    /// name.
+    configs: HashMap<String, McpServerConfig>,
+
+    /// Server name -> state, for every configured server with a valid name.
+    states: HashMap<String, McpServerState>,
+
+    /// Server name -> last stderr lines of a server that is not connected.
+    stderr_tails: HashMap<String, Vec<String>>,
+
+    /// Server name -> tools it listed when it last connected.
+    server_tools: HashMap<String, Vec<Tool>>,
+
     /// Fully qualified tool name -> tool instance.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server name -> resources it listed when it last connected.
+    resources: HashMap<String, Vec<Resource>>,
+
+    /// Server name -> prompts it listed when it last connected.
+    prompts: HashMap<String, Vec<Prompt>>,
+}
+
+impl Servers {
+    fn set_connected(&mut self, server_name: &str, managed: ManagedClient, listing: ServerListing) {
+        self.clients.insert(server_name.to_string(), managed);
+        self.states
+            .insert(server_name.to_string(), McpServerState::Connected);
+        self.stderr_tails.remove(server_name);
+        self.server_tools
+            .insert(server_name.to_string(), listing.tools);
+        set_or_remove(&mut self.resources, server_name, listing.resources);
+        set_or_remove(&mut self.prompts, server_name, listing.prompts);
+        self.qualify_tools();
+    }
+
+    /// Forgets everything `server_name` offered, so the model stops seeing
+    /// tools it can no longer call.
+    fn set_failed(&mut self, server_name: &str, error: String) {
+        self.clients.remove(server_name);
+        self.states
+            .insert(server_name.to_string(), McpServerState::Failed { error });
+        self.server_tools.remove(server_name);
+        self.resources.remove(server_name);
+        self.prompts.remove(server_name);
+        self.qualify_tools();
+    }
+
+    fn qualify_tools(&mut self) {
+        // Qualify in a stable order so the same tool wins a name collision
+        // every time the list is rebuilt.
+        let mut server_names: Vec<&String> = self.server_tools.keys().collect();
+        server_names.sort();
+        let tools = server_names
+            .into_iter()
+            .flat_map(|server_name| {
+                self.server_tools[server_name]
+                    .iter()
+                    .map(move |tool| ToolInfo {
+                        server_name: server_name.clone(),
+                        tool_name: tool.name.clone(),
+                        tool: tool.clone(),
+                    })
+            })
+            .collect();
+        self.tools = qualify_tools(tools);
+    }
+}
+
+fn set_or_remove<T>(map: &mut HashMap<String, Vec<T>>, server_name: &str, items: Vec<T>) {
+    if items.is_empty() {
+        map.remove(server_name);
+    } else {
+        map.insert(server_name.to_string(), items);
+    }
+}
+
+fn lock_servers(servers: &Mutex<Servers>) -> MutexGuard<'_, Servers> {
+    servers.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A thin wrapper around a set of running [`McpClient`] instances.
+#[derive(Default)]
+pub(crate) struct McpConnectionManager {
+    servers: Arc<Mutex<Servers>>,
 }
 
 impl McpConnectionManager {
@@ -106,11 +245,14 @@ impl McpConnectionManager {
     /// * `mcp_servers` – Map loaded from the user configuration where *keys*
     ///   are human-readable server identifiers and *values* are the spawn
     ///   instructions.
+    /// * `tx_event` – Receives a `BackgroundEvent` whenever a server exits,
+    ///   reconnects or is given up on.
     ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
     /// user should be informed about these errors.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
+        tx_event: Sender<Event>,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
@@ -132,106 +274,77 @@ impl McpConnectionManager {
                 continue;
             }
 
-            let startup_timeout = cfg
-                .startup_timeout_ms
-                .map(Duration::from_millis)
-                .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
-
             join_set.spawn(async move {
-                let McpServerConfig {
-                    command, args, env, ..
-                } = cfg;
-                let client_res = McpClient::new_stdio_client(
-                    command.into(),
-                    args.into_iter().map(OsString::from).collect(),
-                    env,
-                )
-                .await;
-                match client_res {
-                    Ok(client) => {
-                        // Initialize the client.
-                        let params = mcp_types::InitializeRequestParams {
-                            capabilities: ClientCapabilities {
-                                experimental: None,
-                                roots: None,
-                                sampling: None,
-                                // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                                // indicates this should be an empty object.
-                                elicitation: Some(json!({})),
-                            },
-                            client_info: Implementation {
-                                name: "codex-mcp-client".to_owned(),
-                                version: env!("CARGO_PKG_VERSION").to_owned(),
-                                title: Some("Codex".into()),
-                                // This field is used by Codex when it is an MCP
-                                // server: it should not be used when Codex is
-                                // an MCP client.
-                                user_agent: None,
-                            },
-                            protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                        };
-                        let initialize_notification_params = None;
-                        match client
-                            .initialize(
-                                params,
-                                initialize_notification_params,
-                                Some(startup_timeout),
-                            )
-                            .await
-                        {
-                            Ok(_response) => (server_name, Ok((client, startup_timeout))),
-                            Err(e) => (server_name, Err(e)),
-                        }
+                let res = match start_server(&cfg).await {
+                    Ok(managed) => {
+                        let listing = list_server(&server_name, &cfg, &managed).await;
+                        Ok((managed, listing))
                     }
-                    Err(e) => (server_name, Err(e.into())),
-                }
+                    Err(failure) => Err(failure),
+                };
+                (server_name, cfg, res)
             });
         }
 
-        let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
-
+        let manager = Self::default();
         while let Some(res) = join_set.join_next().await {
-            let (server_name, client_res) = match res {
-                Ok((server_name, client_res)) => (server_name, client_res),
+            let (server_name, cfg, client_res) = match res {
+                Ok(result) => result,
                 Err(e) => {
                     warn!("Task panic when starting MCP server: {e:#}");
                     continue;
                 }
             };
+            manager
+                .lock()
+                .configs
+                .insert(server_name.clone(), cfg.clone());
 
             match client_res {
-                Ok((client, startup_timeout)) => {
-                    clients.insert(
+                Ok((managed, listing)) => {
+                    let closed = Box::pin(managed.client.closed());
+                    manager.lock().set_connected(&server_name, managed, listing);
+                    tokio::spawn(supervise_server(
+                        Arc::downgrade(&manager.servers),
                         server_name,
-                        ManagedClient {
-                            client: Arc::new(client),
-                            startup_timeout,
-                        },
-                    );
+                        cfg,
+                        closed,
+                        tx_event.clone(),
+                    ));
                 }
-                Err(e) => {
-                    errors.insert(server_name, e);
+                Err(StartFailure { error, stderr_tail }) => {
+                    let mut servers = manager.lock();
+                    servers.set_failed(&server_name, format!("{error:#}"));
+                    servers
+                        .stderr_tails
+                        .insert(server_name.clone(), stderr_tail);
+                    drop(servers);
+                    errors.insert(server_name, error);
                 }
             }
         }
 
-        let all_tools = match list_all_tools(&clients).await {
-            Ok(tools) => tools,
-            Err(e) => {
-                warn!("Failed to list tools from some MCP servers: {e:#}");
-                Vec::new()
-            }
-        };
+        {
+            let servers = manager.lock();
+            info!(
+                "aggregated {} tools from {} servers",
+                servers.tools.len(),
+                servers.clients.len()
+            );
+        }
 
-        let tools = qualify_tools(all_tools);
+        Ok((manager, errors))
+    }
 
-        Ok((Self { clients, tools }, errors))
+    fn lock(&self) -> MutexGuard<'_, Servers> {
+        lock_servers(&self.servers)
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
     /// fully-qualified name for the tool.
     pub fn list_all_tools(&self) -> HashMap<String, Tool> {
-        self.tools
+        self.lock()
+            .tools
             .iter()
             .map(|(name, tool)| (name.clone(), tool.tool.clone()))
             .collect()
@@ -245,12 +358,7 @@ impl McpConnectionManager {
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
     ) -> Result<mcp_types::CallToolResult> {
-        let client = self
-            .clients
-            .get(server)
-            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?
-            .client
-            .clone();
+        let (client, _) = self.client(server)?;
 
         client
             .call_tool(tool.to_string(), arguments, timeout)
@@ -258,65 +366,406 @@ impl McpConnectionManager {
             .with_context(|| format!("tool call failed for `{server}/{tool}`"))
     }
 
+    /// Returns the resources of every server that has any, keyed by server
+    /// name.
+    pub fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        self.lock().resources.clone()
+    }
+
+    /// Returns the prompts of every server that has any, keyed by server name.
+    pub fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        self.lock().prompts.clone()
+    }
+
+    pub fn has_resources(&self) -> bool {
+        !self.lock().resources.is_empty()
+    }
+
+    /// Looks up a resource of `server` by URI, falling back to its name.
+    pub fn find_resource(&self, server: &str, uri_or_name: &str) -> Option<Resource> {
+        let servers = self.lock();
+        let resources = servers.resources.get(server)?;
+        resources
+            .iter()
+            .find(|resource| resource.uri == uri_or_name)
+            .or_else(|| {
+                resources
+                    .iter()
+                    .find(|resource| resource.name == uri_or_name)
+            })
+            .cloned()
+    }
+
+    /// Read the resource at `uri` from `server`.
+    pub async fn read_resource(
+        &self,
+        server: &str,
+        uri: &str,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let (client, _) = self.client(server)?;
+        client
+            .read_resource(uri.to_string(), timeout)
+            .await
+            .with_context(|| format!("failed to read resource `{uri}` from `{server}`"))
+    }
+
+    /// Render the prompt `name` of `server` with the given arguments.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let (client, startup_timeout) = self.client(server)?;
+        let arguments = (!arguments.is_empty()).then(|| json!(arguments));
+        client
+            .get_prompt(name.to_string(), arguments, Some(startup_timeout))
+            .await
+            .with_context(|| format!("failed to get prompt `{name}` from `{server}`"))
+    }
+
+    /// Returns the state of every configured server, keyed by server name.
+    pub fn server_statuses(&self) -> HashMap<String, McpServerStatus> {
+        let servers = self.lock();
+        servers
+            .states
+            .iter()
+            .map(|(server_name, state)| {
+                let stderr_tail = match servers.clients.get(server_name) {
+                    Some(managed) => managed.client.stderr_tail(),
+                    None => servers
+                        .stderr_tails
+                        .get(server_name)
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                (
+                    server_name.clone(),
+                    McpServerStatus {
+                        state: state.clone(),
+                        stderr_tail,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn client(&self, server: &str) -> Result<(Arc<McpClient>, Duration)> {
+        let servers = self.lock();
+        if let Some(managed) = servers.clients.get(server) {
+            return Ok((managed.client.clone(), managed.startup_timeout));
+        }
+        match servers.states.get(server) {
+            Some(McpServerState::Restarting { .. }) => {
+                Err(anyhow!("MCP server `{server}` exited and is restarting"))
+            }
+            Some(McpServerState::Failed { error }) => {
+                Err(anyhow!("MCP server `{server}` is not running: {error}"))
+            }
+            Some(McpServerState::Connected) | None => Err(anyhow!("unknown MCP server '{server}'")),
+        }
+    }
+
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
-        self.tools
+        self.lock()
+            .tools
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// The configured approval for `tool` of `server`: the tool's own setting,
+    /// falling back to the server's.
+    pub fn tool_approval(&self, server: &str, tool: &str) -> Option<McpToolApproval> {
+        let servers = self.lock();
+        let cfg = servers.configs.get(server)?;
+        cfg.tools
+            .get(tool)
+            .and_then(|tool_cfg| tool_cfg.approval)
+            .or(cfg.approval)
+    }
+
+    /// Whether the server annotates `tool` as not modifying its environment.
+    pub fn is_read_only_tool(&self, server: &str, tool: &str) -> bool {
+        self.lock()
+            .server_tools
+            .get(server)
+            .and_then(|tools| tools.iter().find(|t| t.name == tool))
+            .and_then(|t| t.annotations.as_ref())
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
 }
 
-/// Query every server for its available tools and return a single map that
-/// contains **all** tools. Each key is the fully-qualified name for the tool.
-async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<ToolInfo>> {
-    let mut join_set = JoinSet::new();
-
-    // Spawn one task per server so we can query them concurrently. This
-    // keeps the overall latency roughly at the slowest server instead of
-    // the cumulative latency.
-    for (server_name, managed_client) in clients {
-        let server_name_cloned = server_name.clone();
-        let client_clone = managed_client.client.clone();
-        let startup_timeout = managed_client.startup_timeout;
-        join_set.spawn(async move {
-            let res = client_clone.list_tools(None, Some(startup_timeout)).await;
-            (server_name_cloned, res)
-        });
-    }
-
-    let mut aggregated: Vec<ToolInfo> = Vec::with_capacity(join_set.len());
-
-    while let Some(join_res) = join_set.join_next().await {
-        let (server_name, list_result) = if let Ok(result) = join_res {
-            result
-        } else {
-            warn!("Task panic when listing tools for MCP server: {join_res:#?}");
-            continue;
-        };
+/// Whether the model should be offered `tool`, according to the server's
+/// `enabled_tools` and `disabled_tools`.
+fn is_tool_enabled(cfg: &McpServerConfig, tool: &str) -> bool {
+    let enabled = cfg
+        .enabled_tools
+        .as_ref()
+        .is_none_or(|enabled| enabled.iter().any(|name| name == tool));
+    let disabled = cfg
+        .disabled_tools
+        .as_ref()
+        .is_some_and(|disabled| disabled.iter().any(|name| name == tool));
+    enabled && !disabled
+}
 
-        let list_result = if let Ok(result) = list_result {
-            result
-        } else {
-            warn!("Failed to list tools for MCP server '{server_name}': {list_result:#?}");
-            continue;
+/// Spawn the server described by `cfg` and run the `initialize` handshake.
+async fn start_server(cfg: &McpServerConfig) -> Result<ManagedClient, StartFailure> {
+    let startup_timeout = cfg
+        .startup_timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+
+    let client_res = match cfg.transport.clone() {
+        McpServerTransportConfig::Stdio { command, args, env } => McpClient::new_stdio_client(
+            command.into(),
+            args.into_iter().map(OsString::from).collect(),
+            env,
+        )
+        .await
+        .map_err(anyhow::Error::from),
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            headers,
+            bearer_token_env_var,
+        } => http_headers(headers, bearer_token_env_var)
+            .and_then(|headers| McpClient::new_streamable_http_client(url, headers)),
+    };
+    let client = client_res.map_err(|error| StartFailure {
+        error,
+        stderr_tail: Vec::new(),
+    })?;
+
+    // Initialize the client.
+    let params = mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: None,
+            sampling: None,
+            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+            // indicates this should be an empty object.
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            title: Some("Codex".into()),
+            // This field is used by Codex when it is an MCP
+            // server: it should not be used when Codex is
+            // an MCP client.
+            user_agent: None,
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    };
+    let initialize_notification_params = None;
+    match client
+        .initialize(
+            params,
+            initialize_notification_params,
+            Some(startup_timeout),
+        )
+        .await
+    {
+        Ok(response) => Ok(ManagedClient {
+            client: Arc::new(client),
+            startup_timeout,
+            capabilities: response.capabilities,
+        }),
+        Err(error) => Err(StartFailure {
+            error,
+            stderr_tail: client.stderr_tail(),
+        }),
+    }
+}
+
+/// Query a server for the tools `cfg` enables, and for its resources and
+/// prompts if it advertises them. Lists that fail are logged and left empty.
+async fn list_server(
+    server_name: &str,
+    cfg: &McpServerConfig,
+    managed: &ManagedClient,
+) -> ServerListing {
+    let timeout = Some(managed.startup_timeout);
+    let client = &managed.client;
+
+    let tools = match client.list_tools(None, timeout).await {
+        Ok(result) => result
+            .tools
+            .into_iter()
+            .filter(|tool| is_tool_enabled(cfg, &tool.name))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to list tools for MCP server '{server_name}': {e:#}");
+            Vec::new()
+        }
+    };
+    let resources = if managed.capabilities.resources.is_some() {
+        match client.list_resources(None, timeout).await {
+            Ok(result) => result.resources,
+            Err(e) => {
+                warn!("Failed to list resources for MCP server '{server_name}': {e:#}");
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    let prompts = if managed.capabilities.prompts.is_some() {
+        match client.list_prompts(None, timeout).await {
+            Ok(result) => result.prompts,
+            Err(e) => {
+                warn!("Failed to list prompts for MCP server '{server_name}': {e:#}");
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    ServerListing {
+        tools,
+        resources,
+        prompts,
+    }
+}
+
+/// Starts `server_name` again every time its connection closes, until the
+/// manager is dropped or the restart attempts run out.
+async fn supervise_server(
+    servers: Weak<Mutex<Servers>>,
+    server_name: String,
+    cfg: McpServerConfig,
+    mut closed: Pin<Box<dyn Future<Output = ()> + Send>>,
+    tx_event: Sender<Event>,
+) {
+    let mut connected_at = Instant::now();
+    let mut attempt = 0;
+    loop {
+        closed.await;
+
+        let Some(shared) = servers.upgrade() else {
+            return;
         };
+        if connected_at.elapsed() >= STABLE_UPTIME {
+            attempt = 0;
+        }
+        {
+            let mut servers = lock_servers(&shared);
+            if let Some(managed) = servers.clients.remove(&server_name) {
+                let stderr_tail = managed.client.stderr_tail();
+                servers
+                    .stderr_tails
+                    .insert(server_name.clone(), stderr_tail);
+            }
+            servers.states.insert(
+                server_name.clone(),
+                McpServerState::Restarting {
+                    attempt: attempt + 1,
+                },
+            );
+        }
+        drop(shared);
+        warn!("MCP server '{server_name}' exited; restarting it");
+        notify(
+            &tx_event,
+            format!("MCP server `{server_name}` exited; restarting it"),
+        )
+        .await;
+
+        let mut last_error = None;
+        let managed = loop {
+            attempt += 1;
+            if attempt > MAX_RESTART_ATTEMPTS {
+                let error = last_error.unwrap_or_else(|| {
+                    format!("exited {MAX_RESTART_ATTEMPTS} times shortly after starting")
+                });
+                if let Some(shared) = servers.upgrade() {
+                    lock_servers(&shared).set_failed(&server_name, error.clone());
+                }
+                notify(
+                    &tx_event,
+                    format!("MCP server `{server_name}` could not be restarted: {error}"),
+                )
+                .await;
+                return;
+            }
 
-        for tool in list_result.tools {
-            let tool_info = ToolInfo {
-                server_name: server_name.clone(),
-                tool_name: tool.name.clone(),
-                tool,
+            let Some(shared) = servers.upgrade() else {
+                return;
             };
-            aggregated.push(tool_info);
-        }
+            lock_servers(&shared)
+                .states
+                .insert(server_name.clone(), McpServerState::Restarting { attempt });
+            drop(shared);
+
+            tokio::time::sleep(restart_delay(attempt)).await;
+            match start_server(&cfg).await {
+                Ok(managed) => break managed,
+                Err(StartFailure { error, stderr_tail }) => {
+                    warn!("Failed to restart MCP server '{server_name}': {error:#}");
+                    last_error = Some(format!("{error:#}"));
+                    if !stderr_tail.is_empty()
+                        && let Some(shared) = servers.upgrade()
+                    {
+                        lock_servers(&shared)
+                            .stderr_tails
+                            .insert(server_name.clone(), stderr_tail);
+                    }
+                }
+            }
+        };
+
+        let listing = list_server(&server_name, &cfg, &managed).await;
+        let tool_count = listing.tools.len();
+        closed = Box::pin(managed.client.closed());
+        let Some(shared) = servers.upgrade() else {
+            return;
+        };
+        lock_servers(&shared).set_connected(&server_name, managed, listing);
+        drop(shared);
+        connected_at = Instant::now();
+        info!("MCP server '{server_name}' reconnected");
+        notify(
+            &tx_event,
+            format!("MCP server `{server_name}` reconnected with {tool_count} tools"),
+        )
+        .await;
     }
+}
 
-    info!(
-        "aggregated {} tools from {} servers",
-        aggregated.len(),
-        clients.len()
-    );
+/// Delay before restart `attempt` (1-based): doubles from
+/// [`INITIAL_RESTART_DELAY`] up to [`MAX_RESTART_DELAY`].
+fn restart_delay(attempt: u32) -> Duration {
+    INITIAL_RESTART_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(MAX_RESTART_DELAY)
+}
+
+async fn notify(tx_event: &Sender<Event>, message: String) {
+    let event = Event {
+        id: INITIAL_SUBMIT_ID.to_owned(),
+        msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+    };
+    if let Err(e) = tx_event.send(event).await {
+        warn!("failed to send MCP server status event: {e}");
+    }
+}
 
-    Ok(aggregated)
+/// Headers to send to a Streamable HTTP server: the configured `headers` plus
+/// an `Authorization` header carrying the token from `bearer_token_env_var`.
+fn http_headers(
+    headers: Option<HashMap<String, String>>,
+    bearer_token_env_var: Option<String>,
+) -> Result<HashMap<String, String>> {
+    let mut headers = headers.unwrap_or_default();
+    if let Some(var) = bearer_token_env_var {
+        let token = std::env::var(&var)
+            .with_context(|| format!("bearer token environment variable `{var}` is not set"))?;
+        headers.insert("Authorization".to_string(), format!("Bearer {token}"));
+    }
+    Ok(headers)
 }
 
 fn is_valid_mcp_server_name(server_name: &str) -> bool {
@@ -350,6 +799,125 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_http_headers_adds_bearer_token() {
+        let headers = HashMap::from([("X-Team".to_string(), "infra".to_string())]);
+        // PATH is set in every environment the tests run in.
+        let path = std::env::var("PATH").unwrap();
+        let headers = http_headers(Some(headers), Some("PATH".to_string())).unwrap();
+        assert_eq!(
+            headers,
+            HashMap::from([
+                ("X-Team".to_string(), "infra".to_string()),
+                ("Authorization".to_string(), format!("Bearer {path}")),
+            ])
+        );
+
+        let err = http_headers(None, Some("CODEX_TEST_UNSET_TOKEN_VAR".to_string())).unwrap_err();
+        assert!(err.to_string().contains("CODEX_TEST_UNSET_TOKEN_VAR"));
+    }
+
+    #[test]
+    fn test_find_resource_by_uri_or_name() {
+        let resource = |uri: &str, name: &str| Resource {
+            annotations: None,
+            description: None,
+            mime_type: None,
+            name: name.to_string(),
+            size: None,
+            title: None,
+            uri: uri.to_string(),
+        };
+        let manager = McpConnectionManager::default();
+        lock_servers(&manager.servers).resources = HashMap::from([(
+            "docs".to_string(),
+            vec![
+                resource("file:///README.md", "readme"),
+                resource("file:///readme", "file:///README.md"),
+            ],
+        )]);
+
+        let uri = |uri_or_name| {
+            manager
+                .find_resource("docs", uri_or_name)
+                .map(|resource| resource.uri)
+        };
+        assert_eq!(uri("readme").as_deref(), Some("file:///README.md"));
+        // URIs take precedence over names.
+        assert_eq!(
+            uri("file:///README.md").as_deref(),
+            Some("file:///README.md")
+        );
+        assert_eq!(uri("file:///readme").as_deref(), Some("file:///readme"));
+        assert_eq!(uri("changelog"), None);
+        assert!(manager.find_resource("db", "readme").is_none());
+    }
+
+    #[test]
+    fn test_enabled_and_disabled_tools_filter() {
+        let cfg = |enabled: Option<&[&str]>, disabled: Option<&[&str]>| McpServerConfig {
+            transport: McpServerTransportConfig::Stdio {
+                command: "server".to_string(),
+                args: Vec::new(),
+                env: None,
+            },
+            startup_timeout_ms: None,
+            approval: None,
+            enabled_tools: enabled.map(|names| names.iter().map(ToString::to_string).collect()),
+            disabled_tools: disabled.map(|names| names.iter().map(ToString::to_string).collect()),
+            tools: HashMap::new(),
+        };
+
+        assert!(is_tool_enabled(&cfg(None, None), "search"));
+        let only_search = cfg(Some(&["search", "read"]), Some(&["read"]));
+        assert!(is_tool_enabled(&only_search, "search"));
+        assert!(!is_tool_enabled(&only_search, "read"));
+        assert!(!is_tool_enabled(&only_search, "delete_ticket"));
+        assert!(!is_tool_enabled(
+            &cfg(None, Some(&["delete_ticket"])),
+            "delete_ticket"
+        ));
+    }
+
+    #[test]
+    fn test_restart_delay_doubles_up_to_the_cap() {
+        let delays: Vec<u64> = (1..=7).map(|n| restart_delay(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(restart_delay(u32::MAX), MAX_RESTART_DELAY);
+    }
+
+    #[test]
+    fn test_client_reports_restarting_and_failed_servers() {
+        let manager = McpConnectionManager::default();
+        {
+            let mut servers = lock_servers(&manager.servers);
+            servers.states.insert(
+                "docs".to_string(),
+                McpServerState::Restarting { attempt: 2 },
+            );
+            servers.set_failed("db", "connection refused".to_string());
+        }
+
+        let err = |server| manager.client(server).err().map(|e| e.to_string());
+        assert_eq!(
+            err("docs").as_deref(),
+            Some("MCP server `docs` exited and is restarting")
+        );
+        assert_eq!(
+            err("db").as_deref(),
+            Some("MCP server `db` is not running: connection refused")
+        );
+        assert_eq!(
+            manager
+                .server_statuses()
+                .get("db")
+                .map(|status| &status.state),
+            Some(&McpServerState::Failed {
+                error: "connection refused".to_string()
+            })
+        );
+    }
+
     #[test]
     fn test_qualify_tools_short_non_duplicated_names() {
         let tools = vec![
@@ -0,0 +1,346 @@
+//! Resources offered by MCP servers.
+//!
+//! The model reads them through the `list_mcp_resources` and
+//! `read_mcp_resource` tools. Users attach them by mentioning
+//! `@server:resource` in their message, where `resource` is the URI or name
+//! of a resource the server listed.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::mcp_connection_manager::McpConnectionManager;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::InputItem;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+
+pub(crate) const LIST_MCP_RESOURCES_TOOL_NAME: &str = "list_mcp_resources";
+pub(crate) const READ_MCP_RESOURCE_TOOL_NAME: &str = "read_mcp_resource";
+
+/// How long to wait for a server to return a resource.
+const READ_RESOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Characters that commonly trail a mention without being part of it, as in
+/// "see @docs:readme.".
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')'];
+
+pub(crate) fn create_list_mcp_resources_tool() -> ResponsesApiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Only list the resources of this MCP server. Lists every server's resources when omitted."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ResponsesApiTool {
+        name: LIST_MCP_RESOURCES_TOOL_NAME.to_string(),
+        description: "List the resources (files, documents, records, ...) that connected MCP servers make available to read.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false),
+        },
+    }
+}
+
+pub(crate) fn create_read_mcp_resource_tool() -> ResponsesApiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some("Name of the MCP server that offers the resource.".to_string()),
+        },
+    );
+    properties.insert(
+        "uri".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "URI of the resource, as returned by list_mcp_resources.".to_string(),
+            ),
+        },
+    );
+
+    ResponsesApiTool {
+        name: READ_MCP_RESOURCE_TOOL_NAME.to_string(),
+        description: "Read the contents of a resource offered by an MCP server.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["server".to_string(), "uri".to_string()]),
+            additional_properties: Some(false),
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct ListMcpResourcesArgs {
+    #[serde(default)]
+    server: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ListedResource<'a> {
+    server: &'a str,
+    uri: &'a str,
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<&'a str>,
+}
+
+pub(crate) fn handle_list_mcp_resources(
+    manager: &McpConnectionManager,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match parse_arguments::<ListMcpResourcesArgs>(&arguments) {
+        Ok(args) => args,
+        Err(output) => return ResponseInputItem::FunctionCallOutput { call_id, output },
+    };
+
+    let all_resources = manager.list_all_resources();
+    if let Some(server) = &args.server
+        && !all_resources.contains_key(server)
+    {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: format!("MCP server `{server}` has no resources"),
+                success: Some(false),
+            },
+        };
+    }
+
+    let mut servers: Vec<&String> = all_resources
+        .keys()
+        .filter(|server| args.server.as_ref().is_none_or(|wanted| wanted == *server))
+        .collect();
+    servers.sort();
+    let listed: Vec<ListedResource> = servers
+        .into_iter()
+        .flat_map(|server| {
+            all_resources[server]
+                .iter()
+                .map(move |resource| ListedResource {
+                    server,
+                    uri: &resource.uri,
+                    name: &resource.name,
+                    description: resource.description.as_deref(),
+                    mime_type: resource.mime_type.as_deref(),
+                })
+        })
+        .collect();
+
+    let output = match serde_json::to_string(&listed) {
+        Ok(content) => FunctionCallOutputPayload {
+            content,
+            success: Some(true),
+        },
+        Err(e) => FunctionCallOutputPayload {
+            content: format!("failed to serialize resources: {e}"),
+            success: Some(false),
+        },
+    };
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
+
+#[derive(Deserialize)]
+struct ReadMcpResourceArgs {
+    server: String,
+    uri: String,
+}
+
+pub(crate) async fn handle_read_mcp_resource(
+    manager: &McpConnectionManager,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match parse_arguments::<ReadMcpResourceArgs>(&arguments) {
+        Ok(args) => args,
+        Err(output) => return ResponseInputItem::FunctionCallOutput { call_id, output },
+    };
+
+    let output = match manager
+        .read_resource(&args.server, &args.uri, Some(READ_RESOURCE_TIMEOUT))
+        .await
+    {
+        Ok(result) => FunctionCallOutputPayload {
+            content: render_resource(&args.server, &result),
+            success: Some(true),
+        },
+        Err(e) => FunctionCallOutputPayload {
+            content: format!("{e:#}"),
+            success: Some(false),
+        },
+    };
+    ResponseInputItem::FunctionCallOutput { call_id, output }
+}
+
+fn parse_arguments<T: for<'de> Deserialize<'de>>(
+    arguments: &str,
+) -> Result<T, FunctionCallOutputPayload> {
+    // Tools whose parameters are all optional may be called without any.
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    serde_json::from_str(arguments).map_err(|e| FunctionCallOutputPayload {
+        content: format!("failed to parse function arguments: {e}"),
+        success: Some(false),
+    })
+}
+
+/// Reads the resources mentioned as `@server:resource` in the text of
+/// `input`. Returns the items to attach after the user's message, along with
+/// a description of every mention that could not be read.
+pub(crate) async fn resolve_resource_mentions(
+    manager: &McpConnectionManager,
+    input: &[InputItem],
+) -> (Vec<InputItem>, Vec<String>) {
+    let mut seen: Vec<(String, String)> = Vec::new();
+    for item in input {
+        let InputItem::Text { text } = item else {
+            continue;
+        };
+        for (server, resource) in resource_mentions(text) {
+            let uri = manager
+                .find_resource(server, resource)
+                .or_else(|| {
+                    manager.find_resource(server, resource.trim_end_matches(TRAILING_PUNCTUATION))
+                })
+                .map(|resource| resource.uri);
+            let Some(uri) = uri else {
+                continue;
+            };
+            if !seen.iter().any(|(s, u)| s == server && *u == uri) {
+                seen.push((server.to_string(), uri));
+            }
+        }
+    }
+
+    let mut attachments = Vec::new();
+    let mut errors = Vec::new();
+    for (server, uri) in seen {
+        match manager
+            .read_resource(&server, &uri, Some(READ_RESOURCE_TIMEOUT))
+            .await
+        {
+            Ok(result) => {
+                attachments.push(InputItem::Text {
+                    text: render_resource(&server, &result),
+                });
+                attachments.extend(image_attachments(&result));
+            }
+            Err(e) => errors.push(format!("{e:#}")),
+        }
+    }
+    (attachments, errors)
+}
+
+/// Splits every `@server:resource` token of `text` into its server and
+/// resource parts.
+fn resource_mentions(text: &str) -> Vec<(&str, &str)> {
+    text.split_whitespace()
+        .filter_map(|token| token.strip_prefix('@'))
+        .filter_map(|mention| mention.split_once(':'))
+        .filter(|(server, resource)| !server.is_empty() && !resource.is_empty())
+        .collect()
+}
+
+/// Renders the contents of a resource as text for the model. Binary contents
+/// are described rather than included.
+fn render_resource(server: &str, result: &ReadResourceResult) -> String {
+    result
+        .contents
+        .iter()
+        .map(|contents| match contents {
+            ReadResourceResultContents::TextResourceContents(text) => format!(
+                "<mcp_resource server=\"{server}\" uri=\"{}\">\n{}\n</mcp_resource>",
+                text.uri, text.text
+            ),
+            ReadResourceResultContents::BlobResourceContents(blob) => format!(
+                "<mcp_resource server=\"{server}\" uri=\"{}\">\n[binary contents of type {} omitted]\n</mcp_resource>",
+                blob.uri,
+                blob.mime_type.as_deref().unwrap_or("unknown")
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Image contents of a resource, which the model can look at directly.
+fn image_attachments(result: &ReadResourceResult) -> Vec<InputItem> {
+    result
+        .contents
+        .iter()
+        .filter_map(|contents| match contents {
+            ReadResourceResultContents::BlobResourceContents(blob) => blob
+                .mime_type
+                .as_deref()
+                .filter(|mime_type| mime_type.starts_with("image/"))
+                .map(|mime_type| InputItem::Image {
+                    image_url: format!("data:{mime_type};base64,{}", blob.blob),
+                }),
+            ReadResourceResultContents::TextResourceContents(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::BlobResourceContents;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_mentions_in_text() {
+        assert_eq!(
+            resource_mentions("compare @docs:file:///README.md with @db:users, not @me or a@b:c"),
+            vec![("docs", "file:///README.md"), ("db", "users,")]
+        );
+        assert_eq!(resource_mentions("@:x @docs: plain"), Vec::new());
+    }
+
+    #[test]
+    fn renders_text_and_describes_blobs() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: None,
+                    text: "hello".to_string(),
+                    uri: "file:///a.txt".to_string(),
+                }),
+                ReadResourceResultContents::BlobResourceContents(BlobResourceContents {
+                    blob: "aGk=".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uri: "file:///b.png".to_string(),
+                }),
+            ],
+        };
+        assert_eq!(
+            render_resource("files", &result),
+            "<mcp_resource server=\"files\" uri=\"file:///a.txt\">\nhello\n</mcp_resource>\n\
+             <mcp_resource server=\"files\" uri=\"file:///b.png\">\n[binary contents of type image/png omitted]\n</mcp_resource>"
+        );
+        assert_eq!(
+            image_attachments(&result),
+            vec![InputItem::Image {
+                image_url: "data:image/png;base64,aGk=".to_string()
+            }]
+        );
+    }
+}
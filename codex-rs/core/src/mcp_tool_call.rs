@@ -4,18 +4,26 @@ use std::time::Instant;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config_types::McpToolApproval;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
 /// Handles the specified tool call dispatches the appropriate
-/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+/// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`. Asks the
+/// user first if the tool's `approval` setting or the session's
+/// `approval_policy` calls for it.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
+    turn_context: &TurnContext,
     sub_id: &str,
     call_id: String,
     server: String,
@@ -49,6 +57,16 @@ pub(crate) async fn handle_mcp_tool_call(
         arguments: arguments_value.clone(),
     };
 
+    if let Err(content) = ensure_approved(sess, turn_context, sub_id, &call_id, &invocation).await {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        };
+    }
+
     let tool_call_begin_event = EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
         call_id: call_id.clone(),
         invocation: invocation.clone(),
@@ -73,6 +91,73 @@ pub(crate) async fn handle_mcp_tool_call(
     ResponseInputItem::McpToolCallOutput { call_id, result }
 }
 
+/// Asks the user to approve `invocation` if it needs approval. Returns the
+/// message for the model when the call must not go ahead.
+async fn ensure_approved(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
+    invocation: &McpInvocation,
+) -> Result<(), String> {
+    let McpInvocation { server, tool, .. } = invocation;
+    let configured = sess.mcp_tool_approval(server, tool);
+    let read_only = sess.is_read_only_mcp_tool(server, tool);
+    if !requires_approval(configured, read_only, turn_context.approval_policy)
+        || sess.is_mcp_tool_approved(server, tool)
+    {
+        return Ok(());
+    }
+
+    if turn_context.approval_policy == AskForApproval::Never {
+        return Err(format!(
+            "MCP tool call `{server}/{tool}` rejected: it requires approval, but approval_policy is never"
+        ));
+    }
+
+    let reason = (configured.is_none() || configured == Some(McpToolApproval::OnRequest))
+        .then(|| format!("`{tool}` is not marked read-only by `{server}`"));
+    let rx_approve = sess
+        .request_mcp_tool_approval(
+            sub_id.to_string(),
+            call_id.to_string(),
+            invocation.clone(),
+            reason,
+        )
+        .await;
+    match rx_approve.await.unwrap_or_default() {
+        ReviewDecision::Approved => Ok(()),
+        ReviewDecision::ApprovedForSession => {
+            sess.add_approved_mcp_tool(server.clone(), tool.clone());
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            Err(format!("MCP tool call `{server}/{tool}` rejected by user"))
+        }
+    }
+}
+
+/// Whether a call needs the user's approval. A tool without an `approval`
+/// setting is treated as `on-request` under the `untrusted` policy and as
+/// `never` otherwise.
+fn requires_approval(
+    configured: Option<McpToolApproval>,
+    read_only: bool,
+    approval_policy: AskForApproval,
+) -> bool {
+    let approval = configured.unwrap_or(match approval_policy {
+        AskForApproval::UnlessTrusted => McpToolApproval::OnRequest,
+        AskForApproval::OnFailure | AskForApproval::OnRequest | AskForApproval::Never => {
+            McpToolApproval::Never
+        }
+    });
+    match approval {
+        McpToolApproval::Always => true,
+        McpToolApproval::Never => false,
+        McpToolApproval::OnRequest => !read_only,
+    }
+}
+
 async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMsg) {
     sess.send_event(Event {
         id: sub_id.to_string(),
@@ -80,3 +165,43 @@ async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMs
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unconfigured_tools_only_need_approval_when_untrusted() {
+        assert!(requires_approval(
+            None,
+            false,
+            AskForApproval::UnlessTrusted
+        ));
+        assert!(!requires_approval(
+            None,
+            true,
+            AskForApproval::UnlessTrusted
+        ));
+        assert!(!requires_approval(None, false, AskForApproval::OnRequest));
+        assert!(!requires_approval(None, false, AskForApproval::Never));
+    }
+
+    #[test]
+    fn configured_approval_overrides_the_policy() {
+        let always = Some(McpToolApproval::Always);
+        let never = Some(McpToolApproval::Never);
+        let on_request = Some(McpToolApproval::OnRequest);
+        assert!(requires_approval(always, true, AskForApproval::OnFailure));
+        assert!(!requires_approval(
+            never,
+            false,
+            AskForApproval::UnlessTrusted
+        ));
+        assert!(requires_approval(on_request, false, AskForApproval::Never));
+        assert!(!requires_approval(
+            on_request,
+            true,
+            AskForApproval::OnRequest
+        ));
+    }
+}
@@ -112,6 +112,22 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
         )
+    } else if slug.starts_with("claude-") {
+        // Extended thinking is available from Claude 3.7 Sonnet onwards.
+        model_family!(
+            slug, "claude",
+            supports_reasoning_summaries:
+                !slug.starts_with("claude-3-") || slug.starts_with("claude-3-7"),
+            apply_patch_tool_type: Some(ApplyPatchToolType::Function),
+        )
+    } else if slug.starts_with("gemini-") {
+        // Thinking is available from Gemini 2.5 onwards.
+        model_family!(
+            slug, "gemini",
+            supports_reasoning_summaries:
+                !slug.starts_with("gemini-1") && !slug.starts_with("gemini-2.0"),
+            apply_patch_tool_type: Some(ApplyPatchToolType::Function),
+        )
     } else {
         None
     }
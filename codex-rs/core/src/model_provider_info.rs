@@ -25,9 +25,10 @@ const MAX_REQUEST_MAX_RETRIES: u64 = 100;
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
 /// itself (and a handful of others) additionally expose the more modern
-/// *Responses* API. The two protocols use different request/response shapes
-/// and *cannot* be auto-detected at runtime, therefore each provider entry
-/// must declare which one it expects.
+/// *Responses* API. Anthropic and Google each have their own native streaming
+/// APIs as well. The protocols use different request/response shapes and
+/// *cannot* be auto-detected at runtime, therefore each provider entry must
+/// declare which one it expects.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
@@ -37,8 +38,19 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// The Anthropic Messages API exposed at `/v1/messages`.
+    Anthropic,
+
+    /// The Google Gemini API exposed at
+    /// `/v1beta/models/{model}:streamGenerateContent`.
+    Gemini,
 }
 
+/// Value sent in the `anthropic-version` header unless the provider config
+/// overrides it through `http_headers`.
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
 /// Serializable representation of a provider definition.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct ModelProviderInfo {
@@ -88,19 +100,34 @@ pub struct ModelProviderInfo {
     pub requires_openai_auth: bool,
 }
 
+/// A provider from `fallback_providers`, resolved against `model_providers`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallbackProvider {
+    /// Key of the provider in the `model_providers` map.
+    pub provider_id: String,
+    pub provider: ModelProviderInfo,
+    /// Model to request from this provider instead of the configured one.
+    pub model: Option<String>,
+}
+
 impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available (`x-api-key` for
+    ///     Anthropic, `x-goog-api-key` for Gemini).
     ///   • Auth token for OAuth.
     ///
+    /// `model` is only used by wire APIs that address the model in the URL
+    /// path (Gemini).
+    ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
     /// one produced by [`ModelProviderInfo::api_key`].
     pub async fn create_request_builder<'a>(
         &'a self,
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
+        model: &str,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
         let effective_auth = match self.api_key() {
             Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
@@ -114,12 +141,27 @@ impl ModelProviderInfo {
             }
         };
 
-        let url = self.get_full_url(&effective_auth);
+        let url = self.get_full_url(&effective_auth, model);
 
         let mut builder = client.post(url);
 
         if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::Responses | WireApi::Chat => builder.bearer_auth(token),
+                WireApi::Anthropic => builder.header("x-api-key", token),
+                WireApi::Gemini => builder.header("x-goog-api-key", token),
+            };
+        }
+
+        if self.wire_api == WireApi::Anthropic
+            && !self.http_headers.as_ref().is_some_and(|headers| {
+                headers
+                    .keys()
+                    .any(|k| k.eq_ignore_ascii_case("anthropic-version"))
+            })
+        {
+            builder = builder.header("anthropic-version", ANTHROPIC_API_VERSION);
         }
 
         Ok(self.apply_http_headers(builder))
@@ -138,17 +180,22 @@ impl ModelProviderInfo {
             })
     }
 
-    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
-            auth,
-            Some(CodexAuth {
-                mode: AuthMode::ChatGPT,
-                ..
-            })
-        ) {
-            "https://chatgpt.com/backend-api/codex"
-        } else {
-            "https://api.openai.com/v1"
+    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>, model: &str) -> String {
+        let default_base_url = match self.wire_api {
+            WireApi::Anthropic => "https://api.anthropic.com/v1",
+            WireApi::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            WireApi::Responses | WireApi::Chat
+                if matches!(
+                    auth,
+                    Some(CodexAuth {
+                        mode: AuthMode::ChatGPT,
+                        ..
+                    })
+                ) =>
+            {
+                "https://chatgpt.com/backend-api/codex"
+            }
+            WireApi::Responses | WireApi::Chat => "https://api.openai.com/v1",
         };
         let query_string = self.get_query_string();
         let base_url = self
@@ -159,6 +206,16 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Anthropic => format!("{base_url}/messages{query_string}"),
+            WireApi::Gemini => {
+                // Gemini only streams as SSE when asked to via `alt=sse`.
+                let query_string = if query_string.is_empty() {
+                    "?alt=sse".to_string()
+                } else {
+                    format!("{query_string}&alt=sse")
+                };
+                format!("{base_url}/models/{model}:streamGenerateContent{query_string}")
+            }
         }
     }
 
@@ -419,4 +476,77 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
         assert_eq!(expected_provider, provider);
     }
+
+    #[test]
+    fn test_anthropic_and_gemini_urls() {
+        let anthropic_provider_toml = r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(anthropic_provider_toml).unwrap();
+        assert_eq!(WireApi::Anthropic, provider.wire_api);
+        assert_eq!(
+            "https://api.anthropic.com/v1/messages",
+            provider.get_full_url(&None, "claude-sonnet-4-5")
+        );
+
+        let gemini_provider_toml = r#"
+name = "Gemini"
+env_key = "GEMINI_API_KEY"
+wire_api = "gemini"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(gemini_provider_toml).unwrap();
+        assert_eq!(WireApi::Gemini, provider.wire_api);
+        assert_eq!(
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse",
+            provider.get_full_url(&None, "gemini-2.5-pro")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_and_gemini_auth_headers() {
+        let client = reqwest::Client::new();
+        let auth = Some(CodexAuth::from_api_key("test-key"));
+
+        let anthropic_provider = ModelProviderInfo {
+            name: "Anthropic".into(),
+            base_url: None,
+            env_key: None,
+            env_key_instructions: None,
+            wire_api: WireApi::Anthropic,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+        };
+        let request = anthropic_provider
+            .create_request_builder(&client, &auth, "claude-sonnet-4-5")
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let headers = request.headers();
+        assert_eq!(headers["x-api-key"], "test-key");
+        assert_eq!(headers["anthropic-version"], ANTHROPIC_API_VERSION);
+        assert!(!headers.contains_key(reqwest::header::AUTHORIZATION));
+
+        let gemini_provider = ModelProviderInfo {
+            wire_api: WireApi::Gemini,
+            ..anthropic_provider
+        };
+        let request = gemini_provider
+            .create_request_builder(&client, &auth, "gemini-2.5-pro")
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        let headers = request.headers();
+        assert_eq!(headers["x-goog-api-key"], "test-key");
+        assert!(!headers.contains_key("anthropic-version"));
+        assert!(!headers.contains_key(reqwest::header::AUTHORIZATION));
+    }
 }
@@ -0,0 +1,447 @@
+//! HTTP proxy through which sandboxed commands reach the hosts in a sandbox
+//! policy's `network_allowlist`. The Linux sandbox runs the command in a
+//! network namespace of its own where `127.0.0.1:<port>` is bridged to the
+//! proxy's Unix socket, so the proxy is the single way out.
+//!
+//! Both `CONNECT host:port` tunnels (HTTPS) and absolute-form requests such as
+//! `GET http://host/path` (plain HTTP) are supported. Everything the client
+//! sends after the request head goes to the host that was checked, so a
+//! connection cannot be reused to reach another host.
+
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tempfile::TempDir;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::net::UnixListener;
+use tokio::task::JoinHandle;
+use tokio::task::JoinSet;
+use tracing::debug;
+use tracing::warn;
+
+/// Largest request head the proxy reads before giving up on a client.
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+const UPSTREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A running proxy. It stops accepting connections, and drops the open ones,
+/// when this value is dropped.
+pub struct NetworkProxy {
+    port: u16,
+    socket_dir: TempDir,
+    task: JoinHandle<()>,
+}
+
+const SOCKET_NAME: &str = "proxy.sock";
+
+impl NetworkProxy {
+    /// Starts a proxy on `127.0.0.1` and on a Unix socket in a private
+    /// directory that forwards to hosts matching `allowlist` (`host`,
+    /// `host:port` or `*.domain`).
+    pub async fn start(allowlist: &[String]) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let port = listener.local_addr()?.port();
+        let socket_dir = tempfile::Builder::new().prefix("codex-proxy-").tempdir()?;
+        let unix_listener = UnixListener::bind(socket_dir.path().join(SOCKET_NAME))?;
+        let allowlist: Arc<[AllowedHost]> =
+            allowlist.iter().map(|e| AllowedHost::parse(e)).collect();
+        let task = tokio::spawn(async move {
+            // Owning the connections here means aborting this task closes them.
+            let mut connections = JoinSet::new();
+            loop {
+                let allowlist = Arc::clone(&allowlist);
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted.map(|(stream, _)| {
+                        connections.spawn(async move { serve(stream, &allowlist).await })
+                    }),
+                    accepted = unix_listener.accept() => accepted.map(|(stream, _)| {
+                        connections.spawn(async move { serve(stream, &allowlist).await })
+                    }),
+                };
+                if let Err(e) = accepted {
+                    warn!("network proxy stopped accepting connections: {e}");
+                    break;
+                }
+                while connections.try_join_next().is_some() {}
+            }
+        });
+        Ok(Self {
+            port,
+            socket_dir,
+            task,
+        })
+    }
+
+    /// Loopback port the proxy listens on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Unix socket the proxy listens on. Unlike the loopback port it can be
+    /// reached from another network namespace.
+    pub fn socket_path(&self) -> PathBuf {
+        self.socket_dir.path().join(SOCKET_NAME)
+    }
+}
+
+async fn serve<S>(stream: S, allowlist: &[AllowedHost])
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if let Err(e) = serve_connection(stream, allowlist).await {
+        debug!("network proxy connection failed: {e}");
+    }
+}
+
+impl Drop for NetworkProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// One `network_allowlist` entry.
+#[derive(Debug, PartialEq, Eq)]
+struct AllowedHost {
+    host: HostPattern,
+    /// `None` allows every port.
+    port: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum HostPattern {
+    Exact(String),
+    /// `*.example.com` matches any subdomain of `example.com`.
+    Subdomains(String),
+}
+
+impl AllowedHost {
+    fn parse(entry: &str) -> Self {
+        let (host, port) = split_host_port(entry.trim());
+        let host = normalize_host(host);
+        let host = match host.strip_prefix("*.") {
+            Some(domain) => HostPattern::Subdomains(domain.to_string()),
+            None => HostPattern::Exact(host),
+        };
+        Self { host, port }
+    }
+
+    fn allows(&self, host: &str, port: u16) -> bool {
+        if self.port.is_some_and(|allowed| allowed != port) {
+            return false;
+        }
+        match &self.host {
+            HostPattern::Exact(allowed) => allowed == host,
+            HostPattern::Subdomains(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.') && prefix.len() > 1),
+        }
+    }
+}
+
+fn is_allowed(allowlist: &[AllowedHost], host: &str, port: u16) -> bool {
+    let host = normalize_host(host);
+    allowlist.iter().any(|allowed| allowed.allows(&host, port))
+}
+
+/// Splits `host:port`, `[v6]:port`, `host` or `[v6]` into its parts. A bare
+/// IPv6 address without brackets is treated as a host without a port.
+fn split_host_port(authority: &str) -> (&str, Option<u16>) {
+    if let Some(rest) = authority.strip_prefix('[')
+        && let Some((host, after)) = rest.split_once(']')
+    {
+        return (host, after.strip_prefix(':').and_then(|p| p.parse().ok()));
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => (authority, None),
+        },
+        _ => (authority, None),
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
+
+/// Where a client asked to go, parsed from its request line.
+struct Target {
+    host: String,
+    port: u16,
+    tunnel: bool,
+}
+
+fn parse_request_line(line: &str) -> Option<Target> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_host_port(target);
+        return Some(Target {
+            host: host.to_string(),
+            port: port?,
+            tunnel: true,
+        });
+    }
+    let rest = target
+        .get(..7)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("http://"))
+        .map(|_| &target[7..])?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    // Drop any `user:password@` prefix.
+    let authority = authority.rsplit('@').next()?;
+    let (host, port) = split_host_port(authority);
+    Some(Target {
+        host: host.to_string(),
+        port: port.unwrap_or(80),
+        tunnel: false,
+    })
+}
+
+async fn serve_connection<S>(mut client: S, allowlist: &[AllowedHost]) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buf = Vec::with_capacity(1024);
+    let head_len = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() >= MAX_REQUEST_HEAD_BYTES {
+            return respond(&mut client, "431 Request Header Fields Too Large", "").await;
+        }
+        let mut chunk = [0u8; 4096];
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_len]);
+    let request_line = head.lines().next().unwrap_or_default();
+    let Some(target) = parse_request_line(request_line) else {
+        return respond(
+            &mut client,
+            "400 Bad Request",
+            "the sandbox network proxy only supports CONNECT and http:// requests\n",
+        )
+        .await;
+    };
+    if !is_allowed(allowlist, &target.host, target.port) {
+        debug!("network proxy refused {}:{}", target.host, target.port);
+        return respond(
+            &mut client,
+            "403 Forbidden",
+            &format!(
+                "{}:{} is not in the sandbox network allowlist\n",
+                target.host, target.port
+            ),
+        )
+        .await;
+    }
+
+    let upstream = tokio::time::timeout(
+        UPSTREAM_CONNECT_TIMEOUT,
+        TcpStream::connect((target.host.as_str(), target.port)),
+    )
+    .await;
+    let mut upstream = match upstream {
+        Ok(Ok(upstream)) => upstream,
+        Ok(Err(e)) => {
+            return respond(
+                &mut client,
+                "502 Bad Gateway",
+                &format!(
+                    "failed to connect to {}:{}: {e}\n",
+                    target.host, target.port
+                ),
+            )
+            .await;
+        }
+        Err(_) => {
+            return respond(
+                &mut client,
+                "504 Gateway Timeout",
+                &format!("timed out connecting to {}:{}\n", target.host, target.port),
+            )
+            .await;
+        }
+    };
+
+    if target.tunnel {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+        upstream.write_all(&buf[head_len..]).await?;
+    } else {
+        upstream.write_all(&buf).await?;
+    }
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+async fn respond<S>(client: &mut S, status: &str, body: &str) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn allowlist(entries: &[&str]) -> Vec<AllowedHost> {
+        entries
+            .iter()
+            .map(|entry| AllowedHost::parse(entry))
+            .collect()
+    }
+
+    #[test]
+    fn allowlist_matches_hosts_ports_and_subdomains() {
+        let allowlist = allowlist(&[
+            "crates.example.com",
+            "localhost:8080",
+            "*.npm.example.org",
+            "[::1]:3000",
+        ]);
+
+        assert!(is_allowed(&allowlist, "crates.example.com", 443));
+        assert!(is_allowed(&allowlist, "Crates.Example.com.", 80));
+        assert!(!is_allowed(&allowlist, "example.com", 443));
+        assert!(is_allowed(&allowlist, "localhost", 8080));
+        assert!(!is_allowed(&allowlist, "localhost", 8081));
+        assert!(is_allowed(&allowlist, "registry.npm.example.org", 443));
+        assert!(!is_allowed(&allowlist, "npm.example.org", 443));
+        assert!(!is_allowed(&allowlist, "evilnpm.example.org", 443));
+        assert!(is_allowed(&allowlist, "[::1]", 3000));
+        assert!(!is_allowed(&allowlist, "::1", 3001));
+    }
+
+    #[test]
+    fn parses_connect_and_absolute_form_requests() {
+        let target = parse_request_line("CONNECT crates.example.com:443 HTTP/1.1").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port, target.tunnel),
+            ("crates.example.com", 443, true)
+        );
+
+        let target =
+            parse_request_line("GET http://user@mirror.local:8080/index HTTP/1.1").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port, target.tunnel),
+            ("mirror.local", 8080, false)
+        );
+
+        let target = parse_request_line("GET http://mirror.local?x=1 HTTP/1.1").unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("mirror.local", 80));
+
+        assert!(parse_request_line("GET /index HTTP/1.1").is_none());
+        assert!(parse_request_line("CONNECT crates.example.com HTTP/1.1").is_none());
+    }
+
+    /// Starts a TCP server that echoes the first message it receives.
+    async fn echo_server() -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let _ = stream.write_all(&buf[..n]).await;
+                });
+            }
+        });
+        port
+    }
+
+    async fn send(proxy: &NetworkProxy, request: &str) -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, proxy.port()))
+            .await
+            .unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        String::from_utf8_lossy(&response).to_string()
+    }
+
+    #[tokio::test]
+    async fn tunnels_to_allowed_hosts_and_refuses_others() {
+        let port = echo_server().await;
+        let proxy = NetworkProxy::start(&[format!("127.0.0.1:{port}")])
+            .await
+            .unwrap();
+
+        let response = send(
+            &proxy,
+            &format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\nping"),
+        )
+        .await;
+        assert_eq!(response, "HTTP/1.1 200 Connection Established\r\n\r\nping");
+
+        let response = send(
+            &proxy,
+            &format!("CONNECT localhost:{port} HTTP/1.1\r\n\r\n"),
+        )
+        .await;
+        assert!(
+            response.starts_with("HTTP/1.1 403 Forbidden\r\n"),
+            "{response}"
+        );
+        assert!(response.ends_with(&format!(
+            "localhost:{port} is not in the sandbox network allowlist\n"
+        )));
+    }
+
+    #[tokio::test]
+    async fn forwards_plain_http_requests_unchanged() {
+        let port = echo_server().await;
+        let proxy = NetworkProxy::start(&["127.0.0.1".to_string()])
+            .await
+            .unwrap();
+
+        let request =
+            format!("GET http://127.0.0.1:{port}/index HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n");
+        assert_eq!(send(&proxy, &request).await, request);
+    }
+
+    #[tokio::test]
+    async fn serves_the_unix_socket_too() {
+        let port = echo_server().await;
+        let proxy = NetworkProxy::start(&[format!("127.0.0.1:{port}")])
+            .await
+            .unwrap();
+
+        let mut stream = tokio::net::UnixStream::connect(proxy.socket_path())
+            .await
+            .unwrap();
+        stream
+            .write_all(format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\n\r\nping").as_bytes())
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        assert_eq!(
+            String::from_utf8_lossy(&response),
+            "HTTP/1.1 200 Connection Established\r\n\r\nping"
+        );
+    }
+}
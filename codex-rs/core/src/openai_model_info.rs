@@ -93,6 +93,38 @@ pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
             max_output_tokens: 128_000,
         }),
 
+        // https://docs.anthropic.com/en/docs/about-claude/models/overview
+        _ if slug.starts_with("claude-opus-4") => Some(ModelInfo {
+            context_window: 200_000,
+            max_output_tokens: 32_000,
+        }),
+
+        _ if slug.starts_with("claude-sonnet-4")
+            || slug.starts_with("claude-haiku-4")
+            || slug.starts_with("claude-3-7-sonnet") =>
+        {
+            Some(ModelInfo {
+                context_window: 200_000,
+                max_output_tokens: 64_000,
+            })
+        }
+
+        _ if slug.starts_with("claude-3-5") => Some(ModelInfo {
+            context_window: 200_000,
+            max_output_tokens: 8_192,
+        }),
+
+        // https://ai.google.dev/gemini-api/docs/models
+        _ if slug.starts_with("gemini-2.5") || slug.starts_with("gemini-3") => Some(ModelInfo {
+            context_window: 1_048_576,
+            max_output_tokens: 65_536,
+        }),
+
+        _ if slug.starts_with("gemini-2.0") => Some(ModelInfo {
+            context_window: 1_048_576,
+            max_output_tokens: 8_192,
+        }),
+
         _ => None,
     }
 }
@@ -5,6 +5,7 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::config_types::AgentConfig;
 use crate::model_family::ModelFamily;
 use crate::plan_tool::PLAN_TOOL;
 use crate::protocol::AskForApproval;
@@ -55,6 +56,18 @@ pub(crate) enum OpenAiTool {
     Freeform(FreeformTool),
 }
 
+impl OpenAiTool {
+    /// The name the model uses to call this tool.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            OpenAiTool::Function(ResponsesApiTool { name, .. }) => name,
+            OpenAiTool::LocalShell {} => "local_shell",
+            OpenAiTool::WebSearch {} => "web_search",
+            OpenAiTool::Freeform(FreeformTool { name, .. }) => name,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ConfigShellToolType {
     DefaultShell,
@@ -71,6 +84,17 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    /// Whether `sub_agent_launch` and `sub_agent_await` are offered. Cleared
+    /// for sub-agents themselves, which may not spawn further sub-agents.
+    pub sub_agent_tools: bool,
+    /// Whether `list_mcp_resources` and `read_mcp_resource` are offered. Set
+    /// when a connected MCP server has resources.
+    pub mcp_resource_tools: bool,
+    /// When set, only the tools with these names are offered and may be
+    /// called. Used to restrict sub-agents to a subset of the parent's tools.
+    pub allowed_tools: Option<Vec<String>>,
+    /// Named agents that `sub_agent_launch` can delegate to.
+    pub agents: BTreeMap<String, AgentConfig>,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -83,6 +107,7 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) agents: &'a BTreeMap<String, AgentConfig>,
 }
 
 impl ToolsConfig {
@@ -97,6 +122,7 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            agents,
         } = params;
         let mut shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::StreamableShell
@@ -130,8 +156,19 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            sub_agent_tools: true,
+            mcp_resource_tools: false,
+            allowed_tools: None,
+            agents: (*agents).clone(),
         }
     }
+
+    /// Whether a call to the tool `name` is permitted by `allowed_tools`.
+    pub fn is_tool_allowed(&self, name: &str) -> bool {
+        self.allowed_tools
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allowed| allowed == name))
+    }
 }
 
 /// Generic JSON‑Schema subset needed for our tool definitions
@@ -291,12 +328,18 @@ fn create_shell_tool_for_sandbox(sandbox_policy: &SandboxPolicy) -> OpenAiTool {
     let description = match sandbox_policy {
         SandboxPolicy::WorkspaceWrite {
             network_access,
+            network_allowlist,
             ..
         } => {
-            let network_line = if !network_access {
-                "\n    - Commands that require network access"
+            let network_line = if *network_access {
+                String::new()
+            } else if network_allowlist.is_empty() {
+                "\n    - Commands that require network access".to_string()
             } else {
-                ""
+                format!(
+                    "\n    - Commands that require network access to hosts other than {} (these are reachable through the HTTP proxy set in the environment)",
+                    network_allowlist.join(", ")
+                )
             };
 
             format!(
@@ -373,7 +416,7 @@ fn create_view_image_tool() -> OpenAiTool {
     })
 }
 
-fn create_sub_agent_launch_tool() -> OpenAiTool {
+fn create_sub_agent_launch_tool(agents: &BTreeMap<String, AgentConfig>) -> OpenAiTool {
     let mut properties = BTreeMap::new();
     properties.insert(
         "prompt".to_string(),
@@ -383,12 +426,107 @@ fn create_sub_agent_launch_tool() -> OpenAiTool {
             ),
         },
     );
+    properties.insert(
+        "background".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Return immediately and run the sub-agent concurrently. Collect its summary later with sub_agent_await.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "model".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Model for the sub-agent, e.g. a cheaper model for exploration. Defaults to the current model.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "effort".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Reasoning effort for the sub-agent: \"minimal\", \"low\", \"medium\" or \"high\"."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "sandbox_policy".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Sandbox for the sub-agent: \"read-only\", \"workspace-write\" or \"danger-full-access\". May not grant more access than the current sandbox.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "approval_policy".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Approval policy for the sub-agent: \"untrusted\", \"on-failure\", \"on-request\" or \"never\". May not ask for approval less often than the current policy.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "cwd".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Working directory for the sub-agent, relative to the current one. Defaults to the current working directory.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "allowed_tools".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Names of the tools the sub-agent may use. Defaults to all of your tools except the sub-agent tools.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_turns".to_string(),
+        JsonSchema::Number {
+            description: Some("Stop the sub-agent after this many model requests.".to_string()),
+        },
+    );
+    properties.insert(
+        "max_tokens".to_string(),
+        JsonSchema::Number {
+            description: Some("Stop the sub-agent once it has used this many tokens.".to_string()),
+        },
+    );
+    properties.insert(
+        "max_duration_secs".to_string(),
+        JsonSchema::Number {
+            description: Some("Stop the sub-agent after this many seconds.".to_string()),
+        },
+    );
+
+    let mut description = "Launch a sub-agent with isolated context and await its summary before continuing. Set background to true to launch several independent sub-agents in parallel; the call returns an agent id (the call_id of this tool call).".to_string();
+    if !agents.is_empty() {
+        properties.insert(
+            "agent".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Name of a predefined agent to delegate to. Its model, tools and sandbox apply unless overridden by the other arguments.".to_string(),
+                ),
+            },
+        );
+        description.push_str("\n\nAvailable agents:");
+        for (name, agent) in agents {
+            match &agent.description {
+                Some(agent_description) => {
+                    description.push_str(&format!("\n- {name}: {agent_description}"));
+                }
+                None => description.push_str(&format!("\n- {name}")),
+            }
+        }
+    }
 
     OpenAiTool::Function(ResponsesApiTool {
         name: "sub_agent_launch".to_string(),
-        description:
-            "Launch a sub-agent with isolated context and await its summary before continuing."
-                .to_string(),
+        description,
         strict: false,
         parameters: JsonSchema::Object {
             properties,
@@ -397,6 +535,48 @@ fn create_sub_agent_launch_tool() -> OpenAiTool {
         },
     })
 }
+
+fn create_sub_agent_await_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "agent_ids".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Ids of background sub-agents to wait for. Defaults to all outstanding sub-agents."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "mode".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "\"all\" (default) waits until every sub-agent finished; \"any\" returns as soon as one has.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Optional maximum time to wait. Sub-agents still running are reported as such."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "sub_agent_await".to_string(),
+        description: "Wait for sub-agents launched with background set to true and return the summaries of those that finished. Each result is returned only once.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false),
+        },
+    })
+}
 /// TODO(dylan): deprecate once we get rid of json tool
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
@@ -449,6 +629,67 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Returns JSON values that are compatible with tool use in the Anthropic
+/// Messages API:
+/// https://docs.anthropic.com/en/docs/agents-and-tools/tool-use/overview
+pub(crate) fn create_tools_json_for_anthropic_api(
+    tools: &Vec<OpenAiTool>,
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let tools_json = function_tools_json(tools)?
+        .into_iter()
+        .map(|(name, description, parameters)| {
+            json!({
+                "name": name,
+                "description": description,
+                "input_schema": parameters,
+            })
+        })
+        .collect();
+    Ok(tools_json)
+}
+
+/// Returns the `functionDeclarations` compatible with function calling in the
+/// Gemini API. The schemas are passed through `parametersJsonSchema`, which
+/// accepts full JSON Schema rather than Gemini's OpenAPI subset:
+/// https://ai.google.dev/gemini-api/docs/function-calling
+pub(crate) fn create_tools_json_for_gemini_api(
+    tools: &Vec<OpenAiTool>,
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let tools_json = function_tools_json(tools)?
+        .into_iter()
+        .map(|(name, description, parameters)| {
+            json!({
+                "name": name,
+                "description": description,
+                "parametersJsonSchema": parameters,
+            })
+        })
+        .collect();
+    Ok(tools_json)
+}
+
+/// Extracts `(name, description, parameters)` for every function tool. Other
+/// tool types (freeform, local shell, web search) have no equivalent outside
+/// the OpenAI APIs and are dropped, as they are for Chat Completions.
+fn function_tools_json(
+    tools: &Vec<OpenAiTool>,
+) -> crate::error::Result<Vec<(serde_json::Value, serde_json::Value, serde_json::Value)>> {
+    let responses_api_tools_json = create_tools_json_for_responses_api(tools)?;
+    let functions = responses_api_tools_json
+        .into_iter()
+        .filter(|tool| tool.get("type") == Some(&serde_json::Value::String("function".to_string())))
+        .map(|mut tool| {
+            let mut take = |key: &str| {
+                tool.as_object_mut()
+                    .and_then(|map| map.remove(key))
+                    .unwrap_or(serde_json::Value::Null)
+            };
+            (take("name"), take("description"), take("parameters"))
+        })
+        .collect();
+    Ok(functions)
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -655,11 +896,21 @@ pub(crate) fn get_openai_tools(
         tools.push(create_view_image_tool());
     }
 
-    // Include the sub-agent launch tool only for non-unified-exec configs to avoid
-    // surprising existing tests that assert exact tool lists.
-    // Include the sub-agent launch tool by default so models may orchestrate
-    // focused child tasks and return a concise summary.
-    tools.push(create_sub_agent_launch_tool());
+    // Include the sub-agent tools by default so models may orchestrate
+    // focused child tasks, possibly in parallel, and collect concise summaries.
+    if config.sub_agent_tools {
+        tools.push(create_sub_agent_launch_tool(&config.agents));
+        tools.push(create_sub_agent_await_tool());
+    }
+
+    if config.mcp_resource_tools {
+        tools.push(OpenAiTool::Function(
+            crate::mcp_resources::create_list_mcp_resources_tool(),
+        ));
+        tools.push(OpenAiTool::Function(
+            crate::mcp_resources::create_read_mcp_resource_tool(),
+        ));
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -675,6 +926,10 @@ pub(crate) fn get_openai_tools(
         }
     }
 
+    if config.allowed_tools.is_some() {
+        tools.retain(|tool| config.is_tool_allowed(tool.name()));
+    }
+
     tools
 }
 
@@ -687,15 +942,7 @@ mod tests {
     use super::*;
 
     fn assert_eq_tool_names(tools: &[OpenAiTool], expected_names: &[&str]) {
-        let tool_names = tools
-            .iter()
-            .map(|tool| match tool {
-                OpenAiTool::Function(ResponsesApiTool { name, .. }) => name,
-                OpenAiTool::LocalShell {} => "local_shell",
-                OpenAiTool::WebSearch {} => "web_search",
-                OpenAiTool::Freeform(FreeformTool { name, .. }) => name,
-            })
-            .collect::<Vec<_>>();
+        let tool_names = tools.iter().map(OpenAiTool::name).collect::<Vec<_>>();
 
         assert_eq!(
             tool_names.len(),
@@ -724,6 +971,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -735,6 +983,7 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
             ],
         );
     }
@@ -752,6 +1001,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -763,10 +1013,55 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
             ],
         );
     }
 
+    #[test]
+    fn test_sub_agent_launch_lists_agents() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let agents = BTreeMap::from([
+            (
+                "reviewer".to_string(),
+                AgentConfig {
+                    description: Some("Reviews the current diff".to_string()),
+                    ..Default::default()
+                },
+            ),
+            ("test-writer".to_string(), AgentConfig::default()),
+        ]);
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::ReadOnly,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            agents: &agents,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        let Some(OpenAiTool::Function(ResponsesApiTool {
+            description,
+            parameters: JsonSchema::Object { properties, .. },
+            ..
+        })) = tools.iter().find(|tool| tool.name() == "sub_agent_launch")
+        else {
+            panic!("expected sub_agent_launch function tool");
+        };
+        assert!(
+            description.ends_with(
+                "\n\nAvailable agents:\n- reviewer: Reviews the current diff\n- test-writer"
+            ),
+            "{description}"
+        );
+        assert!(properties.contains_key("agent"));
+    }
+
     #[test]
     fn test_get_openai_tools_mcp_tools() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -780,6 +1075,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
         let tools = get_openai_tools(
             &config,
@@ -826,12 +1122,13 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "test_server/do_something_cool",
             ],
         );
 
         assert_eq!(
-            tools[4],
+            tools[5],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "test_server/do_something_cool".to_string(),
                 parameters: JsonSchema::Object {
@@ -887,6 +1184,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -946,6 +1244,7 @@ mod tests {
                 "unified_exec",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "test_server/cool",
                 "test_server/do",
                 "test_server/something",
@@ -966,6 +1265,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
 
         let tools = get_openai_tools(
@@ -998,12 +1298,13 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "dash/search",
             ],
         );
 
         assert_eq!(
-            tools[4],
+            tools[5],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/search".to_string(),
                 parameters: JsonSchema::Object {
@@ -1035,6 +1336,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
 
         let tools = get_openai_tools(
@@ -1065,11 +1367,12 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "dash/paginate",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[5],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/paginate".to_string(),
                 parameters: JsonSchema::Object {
@@ -1099,6 +1402,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
 
         let tools = get_openai_tools(
@@ -1129,11 +1433,12 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "dash/tags",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[5],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/tags".to_string(),
                 parameters: JsonSchema::Object {
@@ -1166,6 +1471,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            agents: &BTreeMap::new(),
         });
 
         let tools = get_openai_tools(
@@ -1196,11 +1502,12 @@ mod tests {
                 "web_search",
                 "view_image",
                 "sub_agent_launch",
+                "sub_agent_await",
                 "dash/value",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[5],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/value".to_string(),
                 parameters: JsonSchema::Object {
@@ -1222,6 +1529,9 @@ mod tests {
         let sandbox_policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec!["workspace".into()],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         };
@@ -0,0 +1,376 @@
+//! JSON Schema constraint on the final assistant message of a turn.
+//!
+//! Providers with native structured output (`text.format` on the Responses
+//! API, `response_format` on Chat Completions) enforce the schema themselves;
+//! for the others the schema is spelled out in the instructions. Either way
+//! the final message is checked here and the model is asked to try again when
+//! it does not match.
+//!
+//! Validation covers the commonly used subset of JSON Schema: `type`,
+//! `enum`, `const`, `anyOf`/`oneOf`, `properties`, `required`,
+//! `additionalProperties` and `items`. Schemas using any other constraint
+//! are rejected up front by [`check_supported_schema`] rather than being
+//! silently under-enforced.
+
+use serde_json::Value;
+
+/// Name under which the schema is sent to providers that require one.
+pub(crate) const OUTPUT_SCHEMA_NAME: &str = "codex_output_schema";
+
+/// Times the model is asked to fix a final message that does not match.
+pub(crate) const MAX_OUTPUT_SCHEMA_RETRIES: usize = 2;
+
+/// Instructions appended to the system prompt for providers without native
+/// structured output.
+pub(crate) fn output_schema_instructions(schema: &Value) -> String {
+    format!(
+        "Your final message must be a single JSON value, without code fences or any other text, that matches this JSON Schema:\n{schema}"
+    )
+}
+
+/// Message sent back to the model when its final message did not match.
+pub(crate) fn output_schema_retry_message(error: &str) -> String {
+    format!(
+        "Your final message does not match the required JSON Schema: {error}. Reply with only a JSON value that matches the schema."
+    )
+}
+
+/// Keywords that do not constrain the value and are therefore always allowed.
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+/// Keywords that [`validate_output`] enforces.
+const VALIDATED_KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "anyOf",
+    "oneOf",
+    "properties",
+    "required",
+    "additionalProperties",
+    "items",
+];
+
+/// Checks that `schema` only uses keywords that [`validate_output`] enforces,
+/// so that a final message is never accepted against a constraint that was
+/// not checked.
+pub fn check_supported_schema(schema: &Value) -> Result<(), String> {
+    check_supported(schema, "$")
+}
+
+fn check_supported(schema: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{path}: a schema must be an object or a boolean")),
+    };
+    for keyword in schema.keys() {
+        if !VALIDATED_KEYWORDS.contains(&keyword.as_str())
+            && !ANNOTATION_KEYWORDS.contains(&keyword.as_str())
+        {
+            return Err(format!("{path}: unsupported keyword `{keyword}`"));
+        }
+    }
+
+    if let Some(properties) = schema.get("properties") {
+        let Value::Object(properties) = properties else {
+            return Err(format!("{path}.properties: expected an object"));
+        };
+        for (name, property) in properties {
+            check_supported(property, &format!("{path}.properties.{name}"))?;
+        }
+    }
+    for keyword in ["additionalProperties", "items"] {
+        if let Some(subschema) = schema.get(keyword) {
+            check_supported(subschema, &format!("{path}.{keyword}"))?;
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(keyword) {
+            let Value::Array(options) = options else {
+                return Err(format!("{path}.{keyword}: expected an array"));
+            };
+            for (index, option) in options.iter().enumerate() {
+                check_supported(option, &format!("{path}.{keyword}[{index}]"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether providers can enforce `schema` in strict mode, which requires
+/// an object at the root, every property of every object to be `required`,
+/// `additionalProperties: false` on every object, and no `oneOf`. Other
+/// schemas are sent non-strict and enforced by [`validate_output`] alone.
+pub(crate) fn is_strict_compatible(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("object") && strict_compatible(schema)
+}
+
+fn strict_compatible(schema: &Value) -> bool {
+    let Value::Object(schema) = schema else {
+        return false;
+    };
+    if schema.contains_key("oneOf") {
+        return false;
+    }
+
+    let is_object = match schema.get("type") {
+        Some(Value::String(name)) => name == "object",
+        Some(Value::Array(names)) => names.iter().any(|name| name == "object"),
+        _ => false,
+    };
+    if is_object || schema.contains_key("properties") {
+        if schema.get("additionalProperties") != Some(&Value::Bool(false)) {
+            return false;
+        }
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object)
+            && !properties.iter().all(|(name, property)| {
+                required.contains(&name.as_str()) && strict_compatible(property)
+            })
+        {
+            return false;
+        }
+    }
+
+    if let Some(items) = schema.get("items")
+        && !strict_compatible(items)
+    {
+        return false;
+    }
+    match schema.get("anyOf") {
+        Some(Value::Array(options)) => options.iter().all(strict_compatible),
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// Parses `message` as JSON and checks it against `schema`, returning the
+/// parsed value. A surrounding Markdown code fence is tolerated.
+pub fn validate_output(schema: &Value, message: &str) -> Result<Value, String> {
+    let text = strip_code_fence(message.trim());
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| format!("final message is not JSON: {e}"))?;
+    validate(schema, &value, "$")?;
+    Ok(value)
+}
+
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return text;
+    };
+    // Drop the info string (e.g. `json`) on the opening line.
+    body.split_once('\n').map_or(body, |(_, body)| body).trim()
+}
+
+fn validate(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(format!("{path}: no value is allowed here")),
+        Value::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        return Err(format!("{path}: expected {expected}"));
+    }
+    if let Some(Value::Array(options)) = schema.get("enum")
+        && !options.contains(value)
+    {
+        return Err(format!(
+            "{path}: {value} is not one of {}",
+            Value::Array(options.clone())
+        ));
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(options)) = schema.get(keyword)
+            && !options
+                .iter()
+                .any(|option| validate(option, value, path).is_ok())
+        {
+            return Err(format!("{path}: does not match any allowed schema"));
+        }
+    }
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| has_type(value, name)) {
+            return Err(format!("{path}: expected {}", allowed.join(" or ")));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(name) {
+                        return Err(format!("{path}: missing required property `{name}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, property) in object {
+                let property_path = format!("{path}.{name}");
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => validate(property_schema, property, &property_path)?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{path}: unexpected property `{name}`"));
+                        }
+                        Some(additional) => validate(additional, property, &property_path)?,
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "status": { "type": "string", "enum": ["pass", "fail"] },
+                "failures": { "type": "array", "items": { "type": "integer" } },
+                "note": { "type": ["string", "null"] }
+            },
+            "required": ["status", "failures"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn accepts_matching_output() {
+        let value = validate_output(
+            &schema(),
+            r#"{"status": "fail", "failures": [1, 2], "note": null}"#,
+        )
+        .expect("valid");
+        assert_eq!(value["failures"], json!([1, 2]));
+    }
+
+    #[test]
+    fn accepts_fenced_output() {
+        let message = "```json\n{\"status\": \"pass\", \"failures\": []}\n```";
+        assert_eq!(
+            validate_output(&schema(), message),
+            Ok(json!({"status": "pass", "failures": []}))
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_keywords() {
+        assert_eq!(check_supported_schema(&schema()), Ok(()));
+        let cases = [
+            (
+                json!({"$defs": {}, "$ref": "#/$defs/a"}),
+                "$: unsupported keyword `$defs`",
+            ),
+            (
+                json!({"properties": {"n": {"type": "integer", "minimum": 0}}}),
+                "$.properties.n: unsupported keyword `minimum`",
+            ),
+            (
+                json!({"items": {"anyOf": [{"type": "string", "pattern": "^a"}]}}),
+                "$.items.anyOf[0]: unsupported keyword `pattern`",
+            ),
+        ];
+        for (schema, expected) in cases {
+            assert_eq!(
+                check_supported_schema(&schema),
+                Err(expected.to_string()),
+                "{schema}"
+            );
+        }
+    }
+
+    #[test]
+    fn detects_strict_compatible_schemas() {
+        assert!(!is_strict_compatible(&schema()), "`note` is optional");
+        let mut strict = schema();
+        strict["required"] = json!(["status", "failures", "note"]);
+        assert!(is_strict_compatible(&strict));
+
+        strict["properties"]["failures"]["items"] =
+            json!({"type": "object", "properties": {"id": {"type": "integer"}}});
+        assert!(
+            !is_strict_compatible(&strict),
+            "nested objects need additionalProperties: false"
+        );
+        assert!(!is_strict_compatible(&json!({"type": "string"})));
+    }
+
+    #[test]
+    fn reports_the_first_mismatch() {
+        let cases = [
+            ("all good", "final message is not JSON"),
+            (
+                r#"{"status": "pass"}"#,
+                "$: missing required property `failures`",
+            ),
+            (
+                r#"{"status": "ok", "failures": []}"#,
+                r#"$.status: "ok" is not one of"#,
+            ),
+            (
+                r#"{"status": "pass", "failures": ["x"]}"#,
+                "$.failures[0]: expected integer",
+            ),
+            (
+                r#"{"status": "pass", "failures": [], "extra": 1}"#,
+                "$: unexpected property `extra`",
+            ),
+        ];
+        for (message, expected) in cases {
+            let error = validate_output(&schema(), message).expect_err(message);
+            assert!(error.starts_with(expected), "{message}: {error}");
+        }
+    }
+}
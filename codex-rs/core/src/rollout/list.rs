@@ -13,6 +13,7 @@ use super::SESSIONS_SUBDIR;
 use crate::protocol::EventMsg;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::SubAgentSource;
 
 /// Returned page of conversation summaries.
 #[derive(Debug, Default, PartialEq)]
@@ -36,6 +37,16 @@ pub struct ConversationItem {
     pub head: Vec<serde_json::Value>,
 }
 
+impl ConversationItem {
+    /// Link to the launching conversation when this rollout records a sub-agent.
+    pub fn sub_agent(&self) -> Option<SubAgentSource> {
+        self.head
+            .first()
+            .and_then(|meta| meta.get("sub_agent"))
+            .and_then(|source| serde_json::from_value(source.clone()).ok())
+    }
+}
+
 /// Hard cap to bound worst‑case work per request.
 const MAX_SCAN_FILES: usize = 100;
 const HEAD_RECORD_LIMIT: usize = 10;
@@ -104,6 +115,40 @@ pub(crate) async fn get_conversations(
     Ok(result)
 }
 
+/// Find the rollout file recorded for conversation `id`, searching every day
+/// directory newest first. Unlike listing, this is not bounded by a scan cap.
+pub(crate) async fn find_conversation_path_by_id(
+    codex_home: &Path,
+    id: Uuid,
+) -> io::Result<Option<PathBuf>> {
+    let mut root = codex_home.to_path_buf();
+    root.push(SESSIONS_SUBDIR);
+    if !root.exists() {
+        return Ok(None);
+    }
+
+    for (_year, year_path) in collect_dirs_desc(&root, |s| s.parse::<u16>().ok()).await? {
+        for (_month, month_path) in collect_dirs_desc(&year_path, |s| s.parse::<u8>().ok()).await? {
+            for (_day, day_path) in collect_dirs_desc(&month_path, |s| s.parse::<u8>().ok()).await?
+            {
+                let found = collect_files(&day_path, |name_str, path| {
+                    match parse_timestamp_uuid_from_filename(name_str) {
+                        Some((_ts, file_id)) if file_id == id && name_str.ends_with(".jsonl") => {
+                            Some(path.to_path_buf())
+                        }
+                        _ => None,
+                    }
+                })
+                .await?;
+                if let Some(path) = found.into_iter().next() {
+                    return Ok(Some(path));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
 /// Load the full contents of a single conversation session file at `path`.
 /// Returns the entire file contents as a String.
 #[allow(dead_code)]
@@ -324,6 +369,9 @@ async fn read_head_and_flags(
             RolloutItem::Compacted(_) => {
                 // Not included in `head`; skip.
             }
+            RolloutItem::Checkpoint(_) => {
+                // Not included in `head`; skip.
+            }
             RolloutItem::EventMsg(ev) => {
                 if matches!(ev, EventMsg::UserMessage(_)) {
                     saw_user_event = true;
@@ -9,9 +9,10 @@ pub(crate) fn is_persisted_response_item(item: &RolloutItem) -> bool {
         RolloutItem::ResponseItem(item) => should_persist_response_item(item),
         RolloutItem::EventMsg(ev) => should_persist_event_msg(ev),
         // Persist Codex executive markers so we can analyze flows (e.g., compaction, API turns).
-        RolloutItem::Compacted(_) | RolloutItem::TurnContext(_) | RolloutItem::SessionMeta(_) => {
-            true
-        }
+        RolloutItem::Compacted(_)
+        | RolloutItem::TurnContext(_)
+        | RolloutItem::SessionMeta(_)
+        | RolloutItem::Checkpoint(_) => true,
     }
 }
 
@@ -38,7 +39,9 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::AgentMessage(_)
         | EventMsg::AgentReasoning(_)
         | EventMsg::AgentReasoningRawContent(_)
-        | EventMsg::TokenCount(_) => true,
+        | EventMsg::TokenCount(_)
+        // Links the parent rollout to the sub-agent's own rollout file.
+        | EventMsg::SubAgentStarted(_) => true,
         EventMsg::Error(_)
         | EventMsg::TaskStarted(_)
         | EventMsg::TaskComplete(_)
@@ -56,6 +59,7 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::ExecCommandEnd(_)
         | EventMsg::ExecApprovalRequest(_)
         | EventMsg::ApplyPatchApprovalRequest(_)
+        | EventMsg::McpToolApprovalRequest(_)
         | EventMsg::BackgroundEvent(_)
         | EventMsg::StreamError(_)
         | EventMsg::PatchApplyBegin(_)
@@ -63,13 +67,15 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::TurnDiff(_)
         | EventMsg::GetHistoryEntryResponse(_)
         | EventMsg::McpListToolsResponse(_)
+        | EventMsg::McpListResourcesResponse(_)
         | EventMsg::ListCustomPromptsResponse(_)
+        | EventMsg::GetMcpPromptResponse(_)
         | EventMsg::PlanUpdate(_)
-        | EventMsg::SubAgentStarted(_)
         | EventMsg::SubAgentStatus(_)
         | EventMsg::SubAgentCompleted(_)
         | EventMsg::SubAgentFailed(_)
         | EventMsg::TurnAborted(_)
+        | EventMsg::TurnUndone(_)
         | EventMsg::ShutdownComplete
         | EventMsg::ConversationPath(_) => false,
     }
@@ -23,6 +23,7 @@ use tracing::warn;
 use super::SESSIONS_SUBDIR;
 use super::list::ConversationsPage;
 use super::list::Cursor;
+use super::list::find_conversation_path_by_id;
 use super::list::get_conversations;
 use super::policy::is_persisted_response_item;
 use crate::config::Config;
@@ -35,6 +36,7 @@ use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::SessionMeta;
 use codex_protocol::protocol::SessionMetaLine;
+use codex_protocol::protocol::SubAgentSource;
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct SessionStateSnapshot {}
@@ -69,6 +71,7 @@ pub enum RolloutRecorderParams {
     Create {
         conversation_id: ConversationId,
         instructions: Option<String>,
+        sub_agent: Option<SubAgentSource>,
     },
     Resume {
         path: PathBuf,
@@ -91,6 +94,20 @@ impl RolloutRecorderParams {
         Self::Create {
             conversation_id,
             instructions,
+            sub_agent: None,
+        }
+    }
+
+    /// Parameters for a child rollout recording a sub-agent's transcript.
+    pub fn new_sub_agent(
+        conversation_id: ConversationId,
+        instructions: Option<String>,
+        source: SubAgentSource,
+    ) -> Self {
+        Self::Create {
+            conversation_id,
+            instructions,
+            sub_agent: Some(source),
         }
     }
 
@@ -109,21 +126,41 @@ impl RolloutRecorder {
         get_conversations(codex_home, page_size, cursor).await
     }
 
+    /// Find the rollout file of conversation `id` under the provided Codex
+    /// home directory.
+    pub async fn find_conversation_path(
+        codex_home: &Path,
+        id: ConversationId,
+    ) -> std::io::Result<Option<PathBuf>> {
+        find_conversation_path_by_id(codex_home, id.into()).await
+    }
+
     /// Attempt to create a new [`RolloutRecorder`]. If the sessions directory
     /// cannot be created or the rollout file cannot be opened we return the
     /// error so the caller can decide whether to disable persistence.
     pub async fn new(config: &Config, params: RolloutRecorderParams) -> std::io::Result<Self> {
+        Self::new_in(&config.codex_home, config.cwd.clone(), params).await
+    }
+
+    /// Like [`RolloutRecorder::new`], but without requiring a full [`Config`].
+    /// Used for sub-agent rollouts, which run in their own `cwd`.
+    pub(crate) async fn new_in(
+        codex_home: &Path,
+        cwd: PathBuf,
+        params: RolloutRecorderParams,
+    ) -> std::io::Result<Self> {
         let (file, rollout_path, meta) = match params {
             RolloutRecorderParams::Create {
                 conversation_id,
                 instructions,
+                sub_agent,
             } => {
                 let LogFileInfo {
                     file,
                     path,
                     conversation_id: session_id,
                     timestamp,
-                } = create_log_file(config, conversation_id)?;
+                } = create_log_file(codex_home, conversation_id)?;
 
                 let timestamp_format: &[FormatItem] = format_description!(
                     "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
@@ -139,10 +176,11 @@ impl RolloutRecorder {
                     Some(SessionMeta {
                         id: session_id,
                         timestamp,
-                        cwd: config.cwd.clone(),
+                        cwd: cwd.clone(),
                         originator: ORIGINATOR.value.clone(),
                         cli_version: env!("CARGO_PKG_VERSION").to_string(),
                         instructions,
+                        sub_agent,
                     }),
                 )
             }
@@ -156,9 +194,6 @@ impl RolloutRecorder {
             ),
         };
 
-        // Clone the cwd for the spawned task to collect git info asynchronously
-        let cwd = config.cwd.clone();
-
         // A reasonably-sized bounded channel. If the buffer fills up the send
         // future will yield, which is fine – we only need to ensure we do not
         // perform *blocking* I/O on the caller's thread.
@@ -202,7 +237,7 @@ impl RolloutRecorder {
             .map_err(|e| IoError::other(format!("failed waiting for rollout flush: {e}")))
     }
 
-    pub(crate) async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
+    pub async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
         let text = tokio::fs::read_to_string(path).await?;
         if text.trim().is_empty() {
@@ -250,6 +285,9 @@ impl RolloutRecorder {
                     RolloutItem::EventMsg(_ev) => {
                         items.push(RolloutItem::EventMsg(_ev));
                     }
+                    RolloutItem::Checkpoint(item) => {
+                        items.push(RolloutItem::Checkpoint(item));
+                    }
                 },
                 Err(e) => {
                     warn!("failed to parse rollout line: {v:?}, error: {e}");
@@ -312,13 +350,13 @@ struct LogFileInfo {
 }
 
 fn create_log_file(
-    config: &Config,
+    codex_home: &Path,
     conversation_id: ConversationId,
 ) -> std::io::Result<LogFileInfo> {
     // Resolve ~/.codex/sessions/YYYY/MM/DD and create it if missing.
     let timestamp = OffsetDateTime::now_local()
         .map_err(|e| IoError::other(format!("failed to get local time: {e}")))?;
-    let mut dir = config.codex_home.clone();
+    let mut dir = codex_home.to_path_buf();
     dir.push(SESSIONS_SUBDIR);
     dir.push(timestamp.year().to_string());
     dir.push(format!("{:02}", u8::from(timestamp.month())));
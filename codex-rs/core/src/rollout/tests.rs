@@ -15,6 +15,7 @@ use uuid::Uuid;
 use crate::rollout::list::ConversationItem;
 use crate::rollout::list::ConversationsPage;
 use crate::rollout::list::Cursor;
+use crate::rollout::list::find_conversation_path_by_id;
 use crate::rollout::list::get_conversation;
 use crate::rollout::list::get_conversations;
 
@@ -443,3 +444,25 @@ async fn test_stable_ordering_same_second_pagination() {
     };
     assert_eq!(page2, expected_page2);
 }
+
+#[tokio::test]
+async fn test_find_conversation_path_by_id() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+
+    let old = Uuid::from_u128(1);
+    let new = Uuid::from_u128(2);
+    write_session_file(home, "2025-01-01T12-00-00", old, 1).unwrap();
+    write_session_file(home, "2025-03-04T09-30-00", new, 1).unwrap();
+
+    let found = find_conversation_path_by_id(home, old).await.unwrap();
+    let expected = home
+        .join("sessions/2025/01/01")
+        .join(format!("rollout-2025-01-01T12-00-00-{old}.jsonl"));
+    assert_eq!(found, Some(expected));
+
+    let missing = find_conversation_path_by_id(home, Uuid::from_u128(3))
+        .await
+        .unwrap();
+    assert_eq!(missing, None);
+}
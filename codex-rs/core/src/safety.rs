@@ -7,6 +7,8 @@ use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_policy::ExecPolicyDecision;
 use crate::is_safe_command::is_known_safe_command;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
@@ -72,19 +74,40 @@ pub fn assess_patch_safety(
     }
 }
 
-/// For a command to be run _without_ a sandbox, one of the following must be
-/// true:
+/// Commands forbidden by the exec policy are rejected outright, while
+/// commands the exec policy matches without writing files run in the platform
+/// sandbox without asking. Otherwise, for a command to be run _without_ a
+/// sandbox, one of the following must be true:
 ///
 /// - the user has explicitly approved the command
-/// - the command is on the "known safe" list
+/// - the command is on the "known safe" list, or the exec policy matches it
+///   and no platform sandbox is available, and in either case the sandbox
+///   policy has no `read_only_paths` or `deny_read_paths` (which a command
+///   such as `cat .env` would otherwise get around)
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
-pub fn assess_command_safety(
+pub(crate) fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
+    exec_policy: &ExecPolicy,
     with_escalated_permissions: bool,
 ) -> SafetyCheck {
+    match exec_policy.evaluate(command) {
+        ExecPolicyDecision::Forbidden { reason } => return SafetyCheck::Reject { reason },
+        // A policy match means the command needs no approval, not that it
+        // needs no sandbox.
+        ExecPolicyDecision::Safe => match get_platform_sandbox() {
+            Some(sandbox_type) => return SafetyCheck::AutoApprove { sandbox_type },
+            None => {
+                if let Some(safety_check) = auto_approve_safe_command(sandbox_policy) {
+                    return safety_check;
+                }
+            }
+        },
+        ExecPolicyDecision::Unmatched => {}
+    }
+
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
     // - the user has explicitly approved the command for this session
@@ -98,15 +121,33 @@ pub fn assess_command_safety(
     // would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
-    if is_known_safe_command(command) || approved.contains(command) {
+    if approved.contains(command) {
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         };
     }
+    if is_known_safe_command(command)
+        && let Some(safety_check) = auto_approve_safe_command(sandbox_policy)
+    {
+        return safety_check;
+    }
 
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
 
+/// Auto-approval for a command that does not write files. It may run without
+/// a sandbox unless the sandbox policy protects paths from being read, which
+/// only a sandbox can enforce. Returns `None` when no sandbox is available to
+/// do so, in which case the command is treated like any other.
+fn auto_approve_safe_command(sandbox_policy: &SandboxPolicy) -> Option<SafetyCheck> {
+    if !sandbox_policy.has_protected_paths() {
+        return Some(SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        });
+    }
+    get_platform_sandbox().map(|sandbox_type| SafetyCheck::AutoApprove { sandbox_type })
+}
+
 pub(crate) fn assess_safety_for_untrusted_command(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
@@ -274,6 +315,9 @@ mod tests {
         let policy_workspace_only = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -295,6 +339,9 @@ mod tests {
         let policy_with_parent = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![parent],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -305,6 +352,38 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn patches_to_protected_paths_are_not_constrained() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec!["infra/prod/**".to_string()],
+            deny_read_paths: vec![".env".to_string()],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let add = |path: &str| ApplyPatchAction::new_add_for_test(&cwd.join(path), String::new());
+
+        assert!(is_write_patch_constrained_to_writable_paths(
+            &add("src/main.rs"),
+            &policy,
+            &cwd,
+        ));
+        assert!(!is_write_patch_constrained_to_writable_paths(
+            &add("infra/prod/main.tf"),
+            &policy,
+            &cwd,
+        ));
+        assert!(!is_write_patch_constrained_to_writable_paths(
+            &add(".env"),
+            &policy,
+            &cwd,
+        ));
+    }
+
     #[test]
     fn test_request_escalated_privileges() {
         // Should not be a trusted command
@@ -319,6 +398,7 @@ mod tests {
             approval_policy,
             &sandbox_policy,
             &approved,
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
@@ -338,6 +418,7 @@ mod tests {
             approval_policy,
             &sandbox_policy,
             &approved,
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
@@ -347,4 +428,76 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn exec_policy_forbids_and_approves_commands() {
+        let (exec_policy, errors) = ExecPolicy::load(&[]);
+        assert!(errors.is_empty(), "{errors:?}");
+        let approved: HashSet<Vec<String>> = HashSet::new();
+
+        // `printenv` is not on the hard-coded list but the default policy
+        // allows it. It still runs in the sandbox.
+        let safety_check = assess_command_safety(
+            &["printenv".to_string()],
+            AskForApproval::UnlessTrusted,
+            &SandboxPolicy::ReadOnly,
+            &approved,
+            &exec_policy,
+            false,
+        );
+        assert_eq!(
+            safety_check,
+            SafetyCheck::AutoApprove {
+                sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None)
+            }
+        );
+
+        let safety_check = assess_command_safety(
+            &["git".to_string(), "commit".to_string()],
+            AskForApproval::UnlessTrusted,
+            &SandboxPolicy::ReadOnly,
+            &approved,
+            &exec_policy,
+            false,
+        );
+        assert_eq!(safety_check, SafetyCheck::AskUser);
+    }
+
+    #[test]
+    fn safe_commands_are_sandboxed_when_paths_are_protected() {
+        let (exec_policy, errors) = ExecPolicy::load(&[]);
+        assert!(errors.is_empty(), "{errors:?}");
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![".env".to_string()],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        for (command, exec_policy) in [
+            (
+                vec!["cat".to_string(), ".env".to_string()],
+                &ExecPolicy::default(),
+            ),
+            (vec!["printenv".to_string()], &exec_policy),
+        ] {
+            let safety_check = assess_command_safety(
+                &command,
+                AskForApproval::UnlessTrusted,
+                &sandbox_policy,
+                &approved,
+                exec_policy,
+                false,
+            );
+            assert_eq!(safety_check, expected, "{command:?}");
+        }
+    }
 }
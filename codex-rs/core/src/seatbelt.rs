@@ -1,3 +1,4 @@
+use codex_protocol::path_pattern::PathPattern;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
@@ -65,14 +66,25 @@ fn create_seatbelt_command_args(
                     canonical_root.to_string_lossy()
                 ));
 
-                if wr.read_only_subpaths.is_empty() {
+                let read_only_subpaths: Vec<PathBuf> = wr
+                    .read_only_subpaths
+                    .iter()
+                    .cloned()
+                    .chain(
+                        wr.read_only_patterns
+                            .iter()
+                            .flat_map(PathPattern::existing_paths)
+                            .filter(|path| path.starts_with(&wr.root)),
+                    )
+                    .collect();
+                if read_only_subpaths.is_empty() {
                     writable_folder_policies.push(format!("(subpath (param \"{root_param}\"))"));
                 } else {
                     // Add parameters for each read-only subpath and generate
                     // the `(require-not ...)` clauses.
                     let mut require_parts: Vec<String> = Vec::new();
                     require_parts.push(format!("(subpath (param \"{root_param}\"))"));
-                    for (subpath_index, ro) in wr.read_only_subpaths.iter().enumerate() {
+                    for (subpath_index, ro) in read_only_subpaths.iter().enumerate() {
                         let canonical_ro = ro.canonicalize().unwrap_or_else(|_| ro.clone());
                         let ro_param = format!("WRITABLE_ROOT_{index}_RO_{subpath_index}");
                         cli_args.push(format!("-D{ro_param}={}", canonical_ro.to_string_lossy()));
@@ -96,10 +108,28 @@ fn create_seatbelt_command_args(
         }
     };
 
-    let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
+    let deny_read_paths: Vec<PathBuf> = sandbox_policy
+        .deny_read_patterns(cwd)
+        .iter()
+        .flat_map(PathPattern::existing_paths)
+        .collect();
+    let mut extra_cli_args = extra_cli_args;
+    let file_read_policy = if !sandbox_policy.has_full_disk_read_access() {
+        String::new()
+    } else if deny_read_paths.is_empty() {
+        "; allow read-only file operations\n(allow file-read*)".to_string()
     } else {
-        ""
+        let mut require_parts = vec![r#"(subpath "/")"#.to_string()];
+        for (index, denied) in deny_read_paths.iter().enumerate() {
+            let canonical = denied.canonicalize().unwrap_or_else(|_| denied.clone());
+            let param = format!("DENY_READ_{index}");
+            extra_cli_args.push(format!("-D{param}={}", canonical.to_string_lossy()));
+            require_parts.push(format!("(require-not (subpath (param \"{param}\")))"));
+        }
+        format!(
+            "; allow read-only file operations outside deny_read_paths\n(allow file-read* (require-all {} ))",
+            require_parts.join(" ")
+        )
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
@@ -155,6 +185,9 @@ mod tests {
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![root_with_git, root_without_git],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -230,6 +263,9 @@ mod tests {
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         };
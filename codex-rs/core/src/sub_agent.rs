@@ -0,0 +1,767 @@
+//! Per-launch configuration for `sub_agent_launch`.
+//!
+//! A sub-agent starts from its parent's [`TurnContext`] and may narrow it:
+//! pick a different (typically cheaper) model or reasoning effort, run under
+//! a tighter sandbox or approval policy, work from another directory, or see
+//! only a subset of the parent's tools. A launch may name a predefined agent
+//! (see [`AgentConfig`]) whose settings act as defaults for these overrides.
+//! Overrides can never grant the sub-agent more filesystem or network access
+//! than the parent has, nor a larger budget than `sub_agent_budget`.
+
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use serde::Deserialize;
+
+use crate::codex::TurnContext;
+use crate::config_types::AgentConfig;
+use crate::config_types::Budget;
+use crate::model_family::find_family_for_model;
+use crate::openai_tools::ConfigShellToolType;
+use crate::openai_tools::ToolsConfig;
+use crate::openai_tools::ToolsConfigParams;
+use crate::openai_tools::get_openai_tools;
+use crate::protocol::AskForApproval;
+use crate::protocol::SandboxPolicy;
+
+/// Arguments accepted by the `sub_agent_launch` tool.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SubAgentLaunchArgs {
+    pub(crate) prompt: String,
+    /// When true, return immediately and let the sub-agent run
+    /// concurrently; its summary is collected with `sub_agent_await`.
+    #[serde(default)]
+    pub(crate) background: bool,
+    /// Name of a predefined agent to delegate to.
+    #[serde(default)]
+    pub(crate) agent: Option<String>,
+    #[serde(flatten)]
+    pub(crate) overrides: SubAgentOverrides,
+    /// Limits for this launch; combined with the configured
+    /// `sub_agent_budget`, keeping the stricter of each.
+    #[serde(flatten)]
+    pub(crate) budget: Budget,
+}
+
+/// Optional overrides of the parent's turn settings. Unset fields inherit
+/// the parent's value.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SubAgentOverrides {
+    pub(crate) model: Option<String>,
+    pub(crate) effort: Option<ReasoningEffortConfig>,
+    pub(crate) sandbox_policy: Option<SandboxPolicyArg>,
+    pub(crate) approval_policy: Option<AskForApproval>,
+    /// Working directory, resolved against the parent's cwd when relative.
+    pub(crate) cwd: Option<String>,
+    /// Names of the tools the sub-agent may use. `None` keeps all of them.
+    pub(crate) allowed_tools: Option<Vec<String>>,
+}
+
+/// A sandbox policy given either by mode name (`"read-only"`,
+/// `"workspace-write"`, `"danger-full-access"`) or as a full
+/// [`SandboxPolicy`] object.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SandboxPolicyArg {
+    String(String),
+    Object(SandboxPolicy),
+}
+
+impl SandboxPolicyArg {
+    fn into_policy(self) -> Result<SandboxPolicy, String> {
+        match self {
+            SandboxPolicyArg::Object(policy) => Ok(policy),
+            SandboxPolicyArg::String(mode) => match mode.as_str() {
+                "read-only" => Ok(SandboxPolicy::new_read_only_policy()),
+                "workspace-write" => Ok(SandboxPolicy::new_workspace_write_policy()),
+                "danger-full-access" => Ok(SandboxPolicy::DangerFullAccess),
+                other => Err(format!(
+                    "unknown sandbox_policy `{other}`; expected `read-only`, `workspace-write` or `danger-full-access`"
+                )),
+            },
+        }
+    }
+}
+
+impl SubAgentOverrides {
+    /// Fills the overrides left unset with the settings of `agent`.
+    fn with_agent_defaults(mut self, agent: &AgentConfig) -> Self {
+        self.model = self.model.or_else(|| agent.model.clone());
+        self.sandbox_policy = self.sandbox_policy.or_else(|| {
+            agent
+                .sandbox_mode
+                .map(|mode| SandboxPolicyArg::String(mode.to_string()))
+        });
+        self.allowed_tools = self.allowed_tools.or_else(|| agent.tools.clone());
+        self
+    }
+}
+
+/// Builds the turn context a sub-agent runs with, applying the named `agent`
+/// and `overrides` on top of `parent`. Returns a message suitable for a
+/// function-call error when the agent is unknown or the overrides are invalid
+/// or would widen the parent's permissions.
+pub(crate) fn derive_turn_context(
+    parent: &TurnContext,
+    agent: Option<&str>,
+    overrides: SubAgentOverrides,
+    mcp_tools: Option<HashMap<String, mcp_types::Tool>>,
+) -> Result<TurnContext, String> {
+    let (overrides, base_instructions) = match agent {
+        Some(name) => {
+            let Some(agent) = parent.tools_config.agents.get(name) else {
+                let available: Vec<&str> = parent
+                    .tools_config
+                    .agents
+                    .keys()
+                    .map(String::as_str)
+                    .collect();
+                return Err(if available.is_empty() {
+                    format!("unknown agent `{name}`; no agents are defined")
+                } else {
+                    format!(
+                        "unknown agent `{name}`; available agents: {}",
+                        available.join(", ")
+                    )
+                });
+            };
+            (
+                overrides.with_agent_defaults(agent),
+                agent
+                    .instructions
+                    .clone()
+                    .or_else(|| parent.base_instructions.clone()),
+            )
+        }
+        None => (overrides, parent.base_instructions.clone()),
+    };
+    let SubAgentOverrides {
+        model,
+        effort,
+        sandbox_policy,
+        approval_policy,
+        cwd,
+        allowed_tools,
+    } = overrides;
+
+    let cwd_overridden = cwd.is_some();
+    let cwd = match cwd {
+        Some(cwd) => resolve_cwd(&parent.sandbox_policy, &parent.cwd, &cwd)?,
+        None => parent.cwd.clone(),
+    };
+
+    let sandbox_overridden = sandbox_policy.is_some();
+    let mut sandbox_policy = match sandbox_policy {
+        Some(arg) => arg.into_policy()?,
+        None => parent.sandbox_policy.clone(),
+    };
+    if cwd_overridden || sandbox_overridden {
+        inherit_protected_paths(&parent.sandbox_policy, &parent.cwd, &mut sandbox_policy);
+        ensure_sandbox_within_parent(&parent.sandbox_policy, &parent.cwd, &sandbox_policy, &cwd)?;
+    }
+
+    let client = if model.is_some() || effort.is_some() {
+        let model_family = model
+            .as_deref()
+            .and_then(find_family_for_model)
+            .unwrap_or_else(|| parent.client.get_model_family());
+        if effort.is_some() && !model_family.supports_reasoning_summaries {
+            return Err(format!(
+                "model `{}` does not support setting a reasoning effort",
+                model.as_deref().unwrap_or(&model_family.slug)
+            ));
+        }
+        parent
+            .client
+            .clone_with_overrides(model.clone(), effort, None)
+    } else {
+        parent.client.clone()
+    };
+    let approval_policy = approval_policy.unwrap_or(parent.approval_policy);
+    ensure_approval_within_parent(parent.approval_policy, approval_policy)?;
+
+    let mut tools_config =
+        if model.is_some() || sandbox_overridden || approval_policy != parent.approval_policy {
+            rebuild_tools_config(
+                &parent.tools_config,
+                &client.get_model_family(),
+                approval_policy,
+                sandbox_policy.clone(),
+            )
+        } else {
+            parent.tools_config.clone()
+        };
+    // Sub-agents may not launch or await sub-agents of their own.
+    tools_config.sub_agent_tools = false;
+
+    if let Some(allowed_tools) = allowed_tools {
+        let available: Vec<String> = get_openai_tools(&tools_config, mcp_tools)
+            .iter()
+            .map(|tool| tool.name().to_string())
+            .collect();
+        let unknown: Vec<&str> = allowed_tools
+            .iter()
+            .filter(|name| !available.contains(*name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!(
+                "unknown tools in allowed_tools: {}; available tools: {}",
+                unknown.join(", "),
+                available.join(", ")
+            ));
+        }
+        tools_config.allowed_tools = Some(allowed_tools);
+    }
+
+    Ok(TurnContext {
+        client,
+        tools_config,
+        user_instructions: parent.user_instructions.clone(),
+        base_instructions,
+        approval_policy,
+        sandbox_policy,
+        shell_environment_policy: parent.shell_environment_policy.clone(),
+        cwd,
+        default_exec_timeout_ms: parent.default_exec_timeout_ms,
+    })
+}
+
+/// Recomputes the shell and apply_patch tool flavours for a different model,
+/// approval policy or sandbox while keeping the parent's optional tools.
+fn rebuild_tools_config(
+    parent: &ToolsConfig,
+    model_family: &crate::model_family::ModelFamily,
+    approval_policy: AskForApproval,
+    sandbox_policy: SandboxPolicy,
+) -> ToolsConfig {
+    let mut tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family,
+        approval_policy,
+        sandbox_policy,
+        include_plan_tool: parent.plan_tool,
+        include_apply_patch_tool: parent.apply_patch_tool_type.is_some(),
+        include_web_search_request: parent.web_search_request,
+        use_streamable_shell_tool: matches!(
+            parent.shell_type,
+            ConfigShellToolType::StreamableShell
+        ),
+        include_view_image_tool: parent.include_view_image_tool,
+        experimental_unified_exec_tool: parent.experimental_unified_exec_tool,
+        agents: &parent.agents,
+    });
+    tools_config.allowed_tools = parent.allowed_tools.clone();
+    tools_config
+}
+
+/// Rejects a sub-agent approval policy that would run commands without
+/// asking where the parent's policy asks.
+fn ensure_approval_within_parent(
+    parent: AskForApproval,
+    child: AskForApproval,
+) -> Result<(), String> {
+    if child.is_looser_than(parent) {
+        return Err(format!(
+            "approval_policy `{child}` asks for approval less often than the parent's `{parent}`"
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves a sub-agent `cwd` against the parent's, following `..` and
+/// symbolic links, and rejects directories outside the parent's writable
+/// roots (or, for a read-only parent, outside its cwd).
+fn resolve_cwd(
+    parent_policy: &SandboxPolicy,
+    parent_cwd: &Path,
+    cwd: &str,
+) -> Result<PathBuf, String> {
+    let joined = parent_cwd.join(cwd);
+    let resolved = match joined.canonicalize() {
+        Ok(resolved) if resolved.is_dir() => resolved,
+        _ => return Err(format!("cwd `{}` is not a directory", joined.display())),
+    };
+    if parent_policy.has_full_disk_write_access() {
+        return Ok(resolved);
+    }
+    let mut roots = writable_root_paths(parent_policy, parent_cwd);
+    roots.push(parent_cwd.to_path_buf());
+    if roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| resolved.starts_with(root))
+    {
+        Ok(resolved)
+    } else {
+        Err(format!(
+            "cwd `{}` is outside the parent's `{parent_policy}` sandbox",
+            resolved.display()
+        ))
+    }
+}
+
+/// Adds the parent's `read_only_paths` and `deny_read_paths` to a
+/// workspace-write `child`, anchored at the parent's writable roots, so they
+/// keep covering the same files when the sub-agent works from another
+/// directory.
+fn inherit_protected_paths(parent: &SandboxPolicy, parent_cwd: &Path, child: &mut SandboxPolicy) {
+    let SandboxPolicy::WorkspaceWrite {
+        read_only_paths: parent_read_only,
+        deny_read_paths: parent_deny_read,
+        ..
+    } = parent
+    else {
+        return;
+    };
+    let SandboxPolicy::WorkspaceWrite {
+        read_only_paths,
+        deny_read_paths,
+        ..
+    } = child
+    else {
+        return;
+    };
+    let roots = writable_root_paths(parent, parent_cwd);
+    for (inherited, paths) in [
+        (parent_read_only, read_only_paths),
+        (parent_deny_read, deny_read_paths),
+    ] {
+        for pattern in anchored_patterns(inherited, &roots) {
+            if !paths.contains(&pattern) {
+                paths.push(pattern);
+            }
+        }
+    }
+}
+
+/// Rejects a sub-agent sandbox that could write somewhere, read a denied
+/// path, or reach the network, when the parent's sandbox could not.
+fn ensure_sandbox_within_parent(
+    parent: &SandboxPolicy,
+    parent_cwd: &Path,
+    child: &SandboxPolicy,
+    child_cwd: &Path,
+) -> Result<(), String> {
+    if parent.has_full_disk_write_access() {
+        return Ok(());
+    }
+    if child.has_full_disk_write_access() {
+        return Err(format!(
+            "sandbox_policy `{child}` grants more access than the parent's `{parent}` sandbox"
+        ));
+    }
+    if child.has_full_network_access() && !parent.has_full_network_access() {
+        return Err(format!(
+            "sandbox_policy enables network access, which the parent's `{parent}` sandbox does not allow"
+        ));
+    }
+    if !parent.has_full_network_access()
+        && let Some(host) = child
+            .network_allowlist()
+            .iter()
+            .find(|host| !parent.network_allowlist().contains(host))
+    {
+        return Err(format!(
+            "sandbox_policy allows network access to `{host}`, which the parent's `{parent}` sandbox does not allow"
+        ));
+    }
+    if let SandboxPolicy::WorkspaceWrite {
+        read_only_paths,
+        deny_read_paths,
+        ..
+    } = parent
+    {
+        // Relative patterns are anchored at each policy's own writable roots,
+        // so compare them once anchored.
+        let parent_roots = writable_root_paths(parent, parent_cwd);
+        let child_roots = writable_root_paths(child, child_cwd);
+        let (child_read_only, child_deny_read) = match child {
+            SandboxPolicy::WorkspaceWrite {
+                read_only_paths,
+                deny_read_paths,
+                ..
+            } => (
+                anchored_patterns(read_only_paths, &child_roots),
+                anchored_patterns(deny_read_paths, &child_roots),
+            ),
+            _ => (Vec::new(), Vec::new()),
+        };
+        if let Some(path) = anchored_patterns(deny_read_paths, &parent_roots)
+            .into_iter()
+            .find(|path| !child_deny_read.contains(path))
+        {
+            return Err(format!(
+                "sandbox_policy must keep `{path}` in deny_read_paths like the parent's `{parent}` sandbox"
+            ));
+        }
+        if matches!(child, SandboxPolicy::WorkspaceWrite { .. })
+            && let Some(path) = anchored_patterns(read_only_paths, &parent_roots)
+                .into_iter()
+                .find(|path| !child_read_only.contains(path) && !child_deny_read.contains(path))
+        {
+            return Err(format!(
+                "sandbox_policy must keep `{path}` in read_only_paths like the parent's `{parent}` sandbox"
+            ));
+        }
+    }
+    // `is_path_writable` compares paths lexically, so compare them with
+    // symbolic links and `..` resolved.
+    let parent_roots: Vec<_> = parent
+        .get_writable_roots_with_cwd(parent_cwd)
+        .into_iter()
+        .map(|mut writable_root| {
+            writable_root.root = resolve_path(&writable_root.root).unwrap_or(writable_root.root);
+            writable_root.read_only_subpaths = writable_root
+                .read_only_subpaths
+                .into_iter()
+                .map(|subpath| resolve_path(&subpath).unwrap_or(subpath))
+                .collect();
+            writable_root
+        })
+        .collect();
+    for root in child.get_writable_roots_with_cwd(child_cwd) {
+        let Some(path) = resolve_path(&root.root) else {
+            return Err(format!(
+                "writable root `{}` uses `..` below a directory that does not exist",
+                root.root.display()
+            ));
+        };
+        if !parent_roots
+            .iter()
+            .any(|parent_root| parent_root.is_path_writable(&path))
+        {
+            return Err(format!(
+                "the sub-agent could write to `{}`, which the parent's `{parent}` sandbox does not allow",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn writable_root_paths(policy: &SandboxPolicy, cwd: &Path) -> Vec<PathBuf> {
+    policy
+        .get_writable_roots_with_cwd(cwd)
+        .into_iter()
+        .map(|writable_root| writable_root.root)
+        .collect()
+}
+
+/// Anchors relative `patterns` at each of `roots` the way the sandbox does,
+/// as absolute patterns.
+fn anchored_patterns(patterns: &[String], roots: &[PathBuf]) -> Vec<String> {
+    let mut anchored = Vec::new();
+    for pattern in patterns {
+        if pattern.starts_with('/') {
+            anchored.push(normalize_pattern(Path::new(pattern)));
+        } else {
+            anchored.extend(
+                roots
+                    .iter()
+                    .map(|root| normalize_pattern(&root.join(pattern))),
+            );
+        }
+    }
+    anchored
+}
+
+/// Drops `.` components and trailing separators, which do not change what a
+/// pattern matches.
+fn normalize_pattern(pattern: &Path) -> String {
+    pattern
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// `path` with symbolic links and `..` resolved as far as it exists. Returns
+/// `None` when the part that does not exist yet uses `..`, which could later
+/// lead anywhere.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    for ancestor in path.ancestors() {
+        let Ok(resolved) = ancestor.canonicalize() else {
+            continue;
+        };
+        let rest = path.strip_prefix(ancestor).ok()?;
+        if rest
+            .components()
+            .any(|component| matches!(component, Component::ParentDir))
+        {
+            return None;
+        }
+        return Some(if rest.as_os_str().is_empty() {
+            resolved
+        } else {
+            resolved.join(rest)
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn workspace_write(writable_roots: Vec<PathBuf>, network_access: bool) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots,
+            network_access,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    fn protecting(read_only: &[&str], deny_read: &[&str]) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: read_only.iter().map(|path| path.to_string()).collect(),
+            deny_read_paths: deny_read.iter().map(|path| path.to_string()).collect(),
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    #[test]
+    fn parses_sandbox_policy_strings_and_objects() {
+        let args: SubAgentLaunchArgs = serde_json::from_str(
+            r#"{"prompt":"p","sandbox_policy":"read-only","approval_policy":"never"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            args.overrides.sandbox_policy.unwrap().into_policy(),
+            Ok(SandboxPolicy::ReadOnly)
+        );
+        assert_eq!(args.overrides.approval_policy, Some(AskForApproval::Never));
+
+        let args: SubAgentLaunchArgs = serde_json::from_str(
+            r#"{"prompt":"p","sandbox_policy":{"mode":"workspace-write","network_access":true}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            args.overrides.sandbox_policy.unwrap().into_policy(),
+            Ok(SandboxPolicy::WorkspaceWrite {
+                writable_roots: vec![],
+                network_access: true,
+                network_allowlist: vec![],
+                read_only_paths: vec![],
+                deny_read_paths: vec![],
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            })
+        );
+
+        let err = SandboxPolicyArg::String("wide-open".to_string())
+            .into_policy()
+            .unwrap_err();
+        assert!(err.contains("unknown sandbox_policy `wide-open`"), "{err}");
+    }
+
+    #[test]
+    fn approval_policy_may_only_tighten() {
+        use AskForApproval::*;
+
+        assert_eq!(
+            ensure_approval_within_parent(OnRequest, UnlessTrusted),
+            Ok(())
+        );
+        assert_eq!(ensure_approval_within_parent(OnRequest, OnFailure), Ok(()));
+        assert_eq!(ensure_approval_within_parent(Never, Never), Ok(()));
+        let err = ensure_approval_within_parent(OnRequest, Never).unwrap_err();
+        assert!(err.contains("parent's `on-request`"), "{err}");
+        assert!(ensure_approval_within_parent(UnlessTrusted, OnRequest).is_err());
+    }
+
+    #[test]
+    fn sandbox_may_only_narrow() {
+        let repo = Path::new("/repo");
+        let parent = workspace_write(vec![], false);
+
+        assert_eq!(
+            ensure_sandbox_within_parent(&parent, repo, &SandboxPolicy::ReadOnly, repo),
+            Ok(())
+        );
+        assert_eq!(
+            ensure_sandbox_within_parent(&parent, repo, &parent, &repo.join("crate")),
+            Ok(())
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &SandboxPolicy::DangerFullAccess, repo)
+                .is_err()
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &workspace_write(vec![], true), repo)
+                .is_err()
+        );
+        assert!(ensure_sandbox_within_parent(&parent, repo, &parent, Path::new("/etc")).is_err());
+        assert!(
+            ensure_sandbox_within_parent(
+                &SandboxPolicy::ReadOnly,
+                repo,
+                &workspace_write(vec![], false),
+                repo
+            )
+            .is_err()
+        );
+        assert_eq!(
+            ensure_sandbox_within_parent(
+                &SandboxPolicy::DangerFullAccess,
+                repo,
+                &workspace_write(vec![PathBuf::from("/etc")], true),
+                repo
+            ),
+            Ok(())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cwd_may_not_leave_the_parent_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(repo.join("sub")).unwrap();
+        std::fs::create_dir(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, repo.join("link")).unwrap();
+        let parent = workspace_write(vec![], false);
+
+        assert_eq!(
+            resolve_cwd(&parent, &repo, "sub"),
+            Ok(repo.join("sub").canonicalize().unwrap())
+        );
+        for cwd in ["..", "sub/../..", "link", "link/."] {
+            let err = resolve_cwd(&parent, &repo, cwd).unwrap_err();
+            assert!(err.contains("is outside the parent's"), "{cwd}: {err}");
+        }
+        assert!(resolve_cwd(&parent, &repo, "missing").is_err());
+        assert!(resolve_cwd(&SandboxPolicy::ReadOnly, &repo, "..").is_err());
+        assert!(resolve_cwd(&SandboxPolicy::DangerFullAccess, &repo, "..").is_ok());
+
+        // Writable roots are checked where their links and `..` lead.
+        assert!(
+            ensure_sandbox_within_parent(
+                &parent,
+                &repo,
+                &workspace_write(vec![repo.join("link")], false),
+                &repo
+            )
+            .is_err()
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, &repo, &parent, &repo.join("sub/..")).is_ok()
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, &repo, &parent, &repo.join("missing/../.."))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn protected_paths_are_inherited() {
+        let repo = Path::new("/repo");
+        let parent = protecting(&["infra/prod/**"], &[".env"]);
+
+        assert_eq!(
+            ensure_sandbox_within_parent(
+                &parent,
+                repo,
+                &protecting(&[], &["infra/prod/**", ".env"]),
+                repo
+            ),
+            Ok(())
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &protecting(&["infra/prod/**"], &[]), repo)
+                .is_err()
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &protecting(&[], &[".env"]), repo).is_err()
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &SandboxPolicy::ReadOnly, repo).is_err()
+        );
+        assert_eq!(
+            ensure_sandbox_within_parent(
+                &protecting(&["infra/prod/**"], &[]),
+                repo,
+                &SandboxPolicy::ReadOnly,
+                repo
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn protected_paths_are_compared_where_they_are_anchored() {
+        let repo = Path::new("/repo");
+        let sub = repo.join("sub");
+        let parent = protecting(&["infra/prod/**"], &[".env"]);
+
+        // Anchored at the sub-agent's cwd, `.env` no longer covers `/repo/.env`.
+        let err = ensure_sandbox_within_parent(&parent, repo, &parent, &sub).unwrap_err();
+        assert!(err.contains("/repo/.env"), "{err}");
+
+        let mut child = parent.clone();
+        inherit_protected_paths(&parent, repo, &mut child);
+        assert_eq!(
+            child,
+            protecting(
+                &["infra/prod/**", "/repo/infra/prod/**"],
+                &[".env", "/repo/.env"]
+            )
+        );
+        assert_eq!(
+            ensure_sandbox_within_parent(&parent, repo, &child, &sub),
+            Ok(())
+        );
+
+        let mut child = workspace_write(vec![], false);
+        inherit_protected_paths(&parent, repo, &mut child);
+        assert_eq!(
+            ensure_sandbox_within_parent(&parent, repo, &child, repo),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn network_allowlist_may_only_narrow() {
+        let repo = Path::new("/repo");
+        let allowing = |hosts: &[&str]| SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            network_allowlist: hosts.iter().map(|host| host.to_string()).collect(),
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let parent = allowing(&["crates.example.com", "localhost:8080"]);
+
+        assert_eq!(
+            ensure_sandbox_within_parent(&parent, repo, &allowing(&["localhost:8080"]), repo),
+            Ok(())
+        );
+        assert!(
+            ensure_sandbox_within_parent(&parent, repo, &allowing(&["github.com"]), repo).is_err()
+        );
+        assert!(
+            ensure_sandbox_within_parent(
+                &workspace_write(vec![], false),
+                repo,
+                &allowing(&["localhost:8080"]),
+                repo
+            )
+            .is_err()
+        );
+    }
+}
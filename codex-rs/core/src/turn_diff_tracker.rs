@@ -15,6 +15,56 @@ use crate::protocol::FileChange;
 const ZERO_OID: &str = "0000000000000000000000000000000000000000";
 const DEV_NULL: &str = "/dev/null";
 
+/// Contents and mode of a file on disk, as captured for undo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileSnapshot {
+    mode: FileMode,
+    content: Vec<u8>,
+}
+
+impl FileSnapshot {
+    /// Reads the file at `path`, or returns `None` if it does not exist.
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        let mode = file_mode_for_path(path)?;
+        let content = blob_bytes(path, &mode)?;
+        Some(Self { mode, content })
+    }
+
+    /// Writes the snapshot back to `path`, replacing whatever is there.
+    pub(crate) fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::symlink_metadata(path).is_ok() {
+            fs::remove_file(path)?;
+        }
+        match self.mode {
+            #[cfg(unix)]
+            FileMode::Symlink => {
+                use std::os::unix::ffi::OsStrExt;
+                std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(&self.content), path)
+            }
+            #[cfg(unix)]
+            FileMode::Executable => {
+                use std::os::unix::fs::PermissionsExt;
+                fs::write(path, &self.content)?;
+                fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            }
+            _ => fs::write(path, &self.content),
+        }
+    }
+}
+
+/// A file `apply_patch` touched during the turn.
+pub(crate) struct TrackedFile {
+    /// Path of the file before the turn's first change to it.
+    pub(crate) baseline_path: PathBuf,
+    /// Contents before the turn's first change; `None` if the turn created it.
+    pub(crate) baseline: Option<FileSnapshot>,
+    /// Path of the file after all of the turn's changes, following renames.
+    pub(crate) current_path: PathBuf,
+}
+
 struct BaselineFileInfo {
     path: PathBuf,
     content: Vec<u8>,
@@ -127,6 +177,23 @@ impl TurnDiffTracker {
         }
     }
 
+    /// Returns the baseline of every file touched so far, for undoing the turn.
+    pub(crate) fn tracked_files(&self) -> Vec<TrackedFile> {
+        self.baseline_file_info
+            .iter()
+            .map(|(internal, info)| TrackedFile {
+                baseline_path: info.path.clone(),
+                baseline: (info.oid != ZERO_OID).then(|| FileSnapshot {
+                    mode: info.mode,
+                    content: info.content.clone(),
+                }),
+                current_path: self
+                    .get_path_for_internal(internal)
+                    .unwrap_or_else(|| info.path.clone()),
+            })
+            .collect()
+    }
+
     fn get_path_for_internal(&self, internal: &str) -> Option<PathBuf> {
         self.temp_name_to_current_path
             .get(internal)
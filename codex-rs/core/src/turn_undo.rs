@@ -0,0 +1,534 @@
+//! Undo support for a single user turn.
+//!
+//! Two sources of truth are combined when a turn finishes:
+//!   1. The baselines [`TurnDiffTracker`] collected for files edited through
+//!      `apply_patch`, which also covers files outside any git repository.
+//!   2. A snapshot of the git worktree taken at turn start, which covers
+//!      changes made by shell commands. Like `git stash create`, it writes a
+//!      tree object through a throwaway index so neither the real index nor
+//!      the worktree is touched.
+//!
+//! Undo refuses to run if anything the turn changed was modified afterwards,
+//! so edits made outside Codex are never silently overwritten.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::Duration;
+use tokio::time::timeout;
+
+use crate::turn_diff_tracker::FileSnapshot;
+use crate::turn_diff_tracker::TrackedFile;
+use crate::turn_diff_tracker::TurnDiffTracker;
+
+/// Upper bound for a single git invocation while snapshotting or restoring.
+const GIT_SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of changed paths listed when undo is refused.
+const MAX_REPORTED_PATHS: usize = 5;
+
+/// Untracked files larger than this are left out of worktree snapshots, so
+/// checkpoints do not keep build artifacts or data dumps alive in the object
+/// database.
+const MAX_UNTRACKED_SNAPSHOT_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Tree object capturing every non-ignored file in a git worktree.
+pub(crate) struct WorktreeSnapshot {
+    pub(crate) root: PathBuf,
+    pub(crate) tree: String,
+}
+
+impl WorktreeSnapshot {
+    /// Snapshots the worktree containing `cwd`. Returns `None` outside a git
+    /// repository or when git fails.
+    pub(crate) async fn capture(cwd: &Path) -> Option<Self> {
+        let root = run_git(cwd, &["rev-parse", "--show-toplevel"], None)
+            .await
+            .ok()?;
+        let root = PathBuf::from(root.trim());
+        match write_tree(&root).await {
+            Ok(tree) => Some(Self { root, tree }),
+            Err(e) => {
+                tracing::warn!("failed to snapshot worktree {}: {e:#}", root.display());
+                None
+            }
+        }
+    }
+}
+
+/// A file edited through `apply_patch` along with its contents at turn end.
+struct UndoFile {
+    tracked: TrackedFile,
+    after: Option<FileSnapshot>,
+}
+
+/// Worktree trees from the start and the end of the turn.
+struct WorktreeUndo {
+    root: PathBuf,
+    before: String,
+    after: String,
+}
+
+/// Everything needed to put the workspace back the way it was before a turn.
+pub(crate) struct TurnUndo {
+    files: Vec<UndoFile>,
+    worktree: Option<WorktreeUndo>,
+}
+
+impl TurnUndo {
+    /// Records the end state of a turn that started at `start`. Returns `None`
+    /// if the turn did not change anything.
+    pub(crate) async fn finish(
+        tracker: &TurnDiffTracker,
+        start: Option<&WorktreeSnapshot>,
+    ) -> Option<Self> {
+        let files: Vec<UndoFile> = tracker
+            .tracked_files()
+            .into_iter()
+            .map(|tracked| UndoFile {
+                after: FileSnapshot::read(&tracked.current_path),
+                tracked,
+            })
+            .filter(|file| {
+                file.tracked.current_path != file.tracked.baseline_path
+                    || file.after != file.tracked.baseline
+            })
+            .collect();
+
+        let worktree = match start {
+            Some(start) => match write_tree(&start.root).await {
+                Ok(after) if after != start.tree => Some(WorktreeUndo {
+                    root: start.root.clone(),
+                    before: start.tree.clone(),
+                    after,
+                }),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!("failed to snapshot worktree after turn: {e:#}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        if files.is_empty() && worktree.is_none() {
+            None
+        } else {
+            Some(Self { files, worktree })
+        }
+    }
+
+    /// Restores the workspace to the start of the turn and returns the paths
+    /// that were touched. Fails without changing anything if a file was
+    /// modified after the turn finished.
+    pub(crate) async fn restore(&self) -> Result<Vec<PathBuf>> {
+        let mut modified: BTreeSet<PathBuf> = self
+            .files
+            .iter()
+            .filter(|file| FileSnapshot::read(&file.tracked.current_path) != file.after)
+            .map(|file| file.tracked.current_path.clone())
+            .collect();
+        if let Some(worktree) = &self.worktree {
+            let current = write_tree(&worktree.root).await?;
+            if current != worktree.after {
+                // Only changes to files the turn itself touched block undo;
+                // the rest of the worktree is left as it is.
+                let touched: BTreeSet<PathBuf> =
+                    changed_paths(&worktree.root, &worktree.before, &worktree.after)
+                        .await?
+                        .into_iter()
+                        .map(|(_, path)| path)
+                        .collect();
+                modified.extend(
+                    changed_paths(&worktree.root, &worktree.after, &current)
+                        .await?
+                        .into_iter()
+                        .filter(|(_, path)| touched.contains(path))
+                        .map(|(_, path)| worktree.root.join(path)),
+                );
+            }
+        }
+        if !modified.is_empty() {
+            let mut listed: Vec<String> = modified
+                .iter()
+                .take(MAX_REPORTED_PATHS)
+                .map(|path| path.display().to_string())
+                .collect();
+            if modified.len() > MAX_REPORTED_PATHS {
+                listed.push(format!("and {} more", modified.len() - MAX_REPORTED_PATHS));
+            }
+            bail!(
+                "files changed since the turn finished: {}",
+                listed.join(", ")
+            );
+        }
+
+        let mut restored = BTreeSet::new();
+        if let Some(worktree) = &self.worktree {
+            restored.extend(restore_tree(&worktree.root, &worktree.after, &worktree.before).await?);
+        }
+        for file in &self.files {
+            let TrackedFile {
+                baseline_path,
+                baseline,
+                current_path,
+            } = &file.tracked;
+            if (current_path != baseline_path || baseline.is_none())
+                && let Err(e) = std::fs::remove_file(current_path)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                return Err(e)
+                    .with_context(|| format!("failed to remove {}", current_path.display()));
+            }
+            if let Some(baseline) = baseline {
+                baseline
+                    .write(baseline_path)
+                    .with_context(|| format!("failed to restore {}", baseline_path.display()))?;
+            }
+            restored.insert(current_path.clone());
+            restored.insert(baseline_path.clone());
+        }
+        Ok(restored.into_iter().collect())
+    }
+}
+
+/// Brings the files in the worktree at `root` from tree `current` back to
+/// tree `target`: paths missing from `target` are removed, the rest are
+/// checked out from it. The index is left alone.
+pub(crate) async fn restore_tree(root: &Path, current: &str, target: &str) -> Result<Vec<PathBuf>> {
+    let changes = changed_paths(root, target, current).await?;
+    let mut from_tree = Vec::new();
+    for (status, path) in &changes {
+        if status == "A" {
+            let path = root.join(path);
+            if let Err(e) = std::fs::remove_file(&path)
+                && e.kind() != std::io::ErrorKind::NotFound
+            {
+                return Err(e).with_context(|| format!("failed to remove {}", path.display()));
+            }
+        } else {
+            from_tree.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if !from_tree.is_empty() {
+        // Paths go through stdin so that no number of them overflows argv.
+        let source = format!("--source={target}");
+        let args = [
+            "restore",
+            source.as_str(),
+            "--worktree",
+            "--pathspec-from-file=-",
+            "--pathspec-file-nul",
+        ];
+        let pathspecs = from_tree.join("\0");
+        run_git_with_input(root, &args, None, Some(pathspecs.as_bytes())).await?;
+    }
+    Ok(changes
+        .into_iter()
+        .map(|(_, path)| root.join(path))
+        .collect())
+}
+
+/// Writes a tree object for every non-ignored file in the worktree at `root`,
+/// except untracked files over [`MAX_UNTRACKED_SNAPSHOT_FILE_BYTES`].
+pub(crate) async fn write_tree(root: &Path) -> Result<String> {
+    let index = run_git(root, &["rev-parse", "--git-path", "index"], None).await?;
+    let index = root.join(index.trim());
+    // Stage into a copy of the real index so neither it nor the worktree is
+    // modified, and unchanged files are not hashed again. Without a real
+    // index the scratch one must not exist yet: git rejects an empty file.
+    let scratch_dir = tempfile::tempdir()?;
+    let scratch = scratch_dir.path().join("index");
+    if index.exists() {
+        tokio::fs::copy(&index, &scratch).await?;
+    }
+    run_git(root, &["add", "--update", "--", "."], Some(&scratch)).await?;
+    let untracked = run_git(
+        root,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+        Some(&scratch),
+    )
+    .await?;
+    let untracked: Vec<&str> = untracked
+        .split('\0')
+        .filter(|path| {
+            !path.is_empty()
+                && std::fs::symlink_metadata(root.join(path))
+                    .is_ok_and(|metadata| metadata.len() <= MAX_UNTRACKED_SNAPSHOT_FILE_BYTES)
+        })
+        .collect();
+    if !untracked.is_empty() {
+        let pathspecs = untracked.join("\0");
+        run_git_with_input(
+            root,
+            &["add", "--pathspec-from-file=-", "--pathspec-file-nul"],
+            Some(&scratch),
+            Some(pathspecs.as_bytes()),
+        )
+        .await?;
+    }
+    let tree = run_git(root, &["write-tree"], Some(&scratch)).await?;
+    Ok(tree.trim().to_string())
+}
+
+/// Lists `(status, path)` for every file that differs between two trees,
+/// with paths relative to the worktree root.
+async fn changed_paths(root: &Path, from: &str, to: &str) -> Result<Vec<(String, PathBuf)>> {
+    let output = run_git(
+        root,
+        &[
+            "diff-tree",
+            "-r",
+            "-z",
+            "--no-renames",
+            "--name-status",
+            from,
+            to,
+        ],
+        None,
+    )
+    .await?;
+    let mut fields = output.split('\0').filter(|field| !field.is_empty());
+    let mut changes = Vec::new();
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        changes.push((status.to_string(), PathBuf::from(path)));
+    }
+    Ok(changes)
+}
+
+/// Runs git in `cwd`, optionally against a scratch index, and returns stdout.
+pub(crate) async fn run_git(
+    cwd: &Path,
+    args: &[&str],
+    index_file: Option<&Path>,
+) -> Result<String> {
+    run_git_with_input(cwd, args, index_file, None).await
+}
+
+/// Like [`run_git`], additionally writing `input` to git's stdin. Pathspecs
+/// are always taken literally, never as globs or magic.
+pub(crate) async fn run_git_with_input(
+    cwd: &Path,
+    args: &[&str],
+    index_file: Option<&Path>,
+    input: Option<&[u8]>,
+) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(cwd)
+        .env("GIT_LITERAL_PATHSPECS", "1")
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(index_file) = index_file {
+        command.env("GIT_INDEX_FILE", index_file);
+    }
+    let mut child = command.spawn()?;
+    let stdin = child.stdin.take();
+    let run = async {
+        let write_input = async {
+            if let (Some(mut stdin), Some(input)) = (stdin, input) {
+                stdin.write_all(input).await?;
+            }
+            Ok::<_, std::io::Error>(())
+        };
+        let (written, output) = tokio::join!(write_input, child.wait_with_output());
+        written?;
+        output
+    };
+    let output = timeout(GIT_SNAPSHOT_TIMEOUT, run)
+        .await
+        .map_err(|_| anyhow!("git {} timed out", args.join(" ")))??;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::FileChange;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git(root: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .status()
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["config", "user.email", "codex@example.com"]);
+        git(root, &["config", "user.name", "Codex"]);
+        fs::write(root.join("tracked.txt"), "tracked\n").expect("write");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        dir
+    }
+
+    fn update(move_path: Option<PathBuf>) -> FileChange {
+        FileChange::Update {
+            unified_diff: String::new(),
+            move_path,
+        }
+    }
+
+    #[tokio::test]
+    async fn restores_patch_and_shell_changes() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        fs::write(root.join("untracked.txt"), "before\n").expect("write");
+        fs::write(root.join("old.txt"), "move me\n").expect("write");
+        let start = WorktreeSnapshot::capture(&root).await;
+        assert!(start.is_some());
+
+        // apply_patch: edit a file, add one and rename another.
+        let mut tracker = TurnDiffTracker::new();
+        tracker.on_patch_begin(&HashMap::from([
+            (root.join("tracked.txt"), update(None)),
+            (
+                root.join("added.txt"),
+                FileChange::Add {
+                    content: "added\n".to_string(),
+                },
+            ),
+            (root.join("old.txt"), update(Some(root.join("new.txt")))),
+        ]));
+        fs::write(root.join("tracked.txt"), "edited\n").expect("write");
+        fs::write(root.join("added.txt"), "added\n").expect("write");
+        fs::rename(root.join("old.txt"), root.join("new.txt")).expect("rename");
+        // Shell commands: change an untracked file and create another.
+        fs::write(root.join("untracked.txt"), "after\n").expect("write");
+        fs::write(root.join("generated.txt"), "generated\n").expect("write");
+
+        let undo = TurnUndo::finish(&tracker, start.as_ref())
+            .await
+            .expect("turn changed files");
+        let restored = undo.restore().await.expect("restore");
+
+        let read = |name: &str| fs::read_to_string(root.join(name)).ok();
+        assert_eq!(read("tracked.txt").as_deref(), Some("tracked\n"));
+        assert_eq!(read("untracked.txt").as_deref(), Some("before\n"));
+        assert_eq!(read("old.txt").as_deref(), Some("move me\n"));
+        assert_eq!(read("added.txt"), None);
+        assert_eq!(read("new.txt"), None);
+        assert_eq!(read("generated.txt"), None);
+        for name in ["tracked.txt", "untracked.txt", "added.txt", "generated.txt"] {
+            assert!(restored.contains(&root.join(name)), "{name} not reported");
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_when_files_changed_after_the_turn() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        let start = WorktreeSnapshot::capture(&root).await;
+
+        let mut tracker = TurnDiffTracker::new();
+        tracker.on_patch_begin(&HashMap::from([(root.join("tracked.txt"), update(None))]));
+        fs::write(root.join("tracked.txt"), "edited\n").expect("write");
+        let undo = TurnUndo::finish(&tracker, start.as_ref())
+            .await
+            .expect("turn changed files");
+
+        fs::write(root.join("tracked.txt"), "edited by hand\n").expect("write");
+        let err = undo.restore().await.expect_err("undo should be refused");
+        assert!(err.to_string().contains("tracked.txt"), "{err}");
+        assert_eq!(
+            fs::read_to_string(root.join("tracked.txt")).expect("read"),
+            "edited by hand\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn snapshots_a_repository_without_an_index() {
+        let dir = TempDir::new().expect("tempdir");
+        git(dir.path(), &["init", "-q"]);
+        fs::write(dir.path().join("file.txt"), "contents\n").expect("write");
+        assert!(WorktreeSnapshot::capture(dir.path()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn later_changes_to_other_files_do_not_block_undo() {
+        let dir = init_repo();
+        let root = dir.path().canonicalize().expect("canonicalize");
+        fs::write(root.join("a.txt"), "a\n").expect("write");
+        fs::write(root.join("[ab].txt"), "glob\n").expect("write");
+        git(&root, &["add", "."]);
+        git(&root, &["commit", "-q", "-m", "more"]);
+        let start = WorktreeSnapshot::capture(&root).await;
+
+        // A shell command edits a file whose name is also a glob.
+        fs::write(root.join("[ab].txt"), "edited\n").expect("write");
+        let undo = TurnUndo::finish(&TurnDiffTracker::new(), start.as_ref())
+            .await
+            .expect("turn changed files");
+
+        // A file the turn did not touch, but the glob matches, is edited later.
+        fs::write(root.join("a.txt"), "edited by hand\n").expect("write");
+        let restored = undo.restore().await.expect("restore");
+
+        assert_eq!(restored, vec![root.join("[ab].txt")]);
+        let read = |name: &str| fs::read_to_string(root.join(name)).expect("read");
+        assert_eq!(read("[ab].txt"), "glob\n");
+        assert_eq!(read("a.txt"), "edited by hand\n");
+    }
+
+    #[tokio::test]
+    async fn large_untracked_files_are_left_out_of_snapshots() {
+        let dir = init_repo();
+        let root = dir.path();
+        fs::write(root.join("small.txt"), "small\n").expect("write");
+        let large = fs::File::create(root.join("large.bin")).expect("create");
+        large
+            .set_len(MAX_UNTRACKED_SNAPSHOT_FILE_BYTES + 1)
+            .expect("set_len");
+
+        let tree = write_tree(root).await.expect("write tree");
+        let listed = run_git(root, &["ls-tree", "--name-only", &tree], None)
+            .await
+            .expect("ls-tree");
+        assert_eq!(
+            listed.lines().collect::<Vec<_>>(),
+            vec!["small.txt", "tracked.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn unchanged_turn_has_nothing_to_undo() {
+        let dir = init_repo();
+        let start = WorktreeSnapshot::capture(dir.path()).await;
+        assert!(
+            TurnUndo::finish(&TurnDiffTracker::new(), start.as_ref())
+                .await
+                .is_none()
+        );
+    }
+}
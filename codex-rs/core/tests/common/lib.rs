@@ -92,6 +92,50 @@ pub fn load_sse_fixture_with_id(path: impl AsRef<std::path::Path>, id: &str) ->
         .collect()
 }
 
+/// SSE body with a single assistant message followed by `response.completed`.
+pub fn sse_assistant_message(text: &str) -> String {
+    let json = r#"[
+  {
+    "type": "response.output_item.done",
+    "item": {
+      "type": "message",
+      "role": "assistant",
+      "content": [{"type": "output_text", "text": "__TEXT__"}]
+    }
+  },
+  {
+    "type": "response.completed",
+    "response": {"id": "__ID__", "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}}
+  }
+]"#;
+    load_sse_fixture_with_id_from_str(&json.replace("__TEXT__", text), "resp-msg")
+}
+
+/// A `200` response streaming `body` as server-sent events.
+pub fn sse_template(body: String) -> wiremock::ResponseTemplate {
+    wiremock::ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(body, "text/event-stream")
+}
+
+/// Returns the output the model received for the function call `call_id`.
+pub async fn function_call_output(server: &wiremock::MockServer, call_id: &str) -> String {
+    let requests = server.received_requests().await.expect("recorded requests");
+    requests
+        .iter()
+        .rev()
+        .find_map(|req| {
+            let body = req
+                .body_json::<serde_json::Value>()
+                .expect("request body is JSON");
+            body["input"].as_array()?.iter().find_map(|item| {
+                (item["type"] == "function_call_output" && item["call_id"] == call_id)
+                    .then(|| item["output"].as_str().unwrap_or_default().to_string())
+            })
+        })
+        .expect("function call output sent to the model")
+}
+
 pub async fn wait_for_event<F>(
     codex: &CodexConversation,
     predicate: F,
@@ -0,0 +1,324 @@
+use std::sync::Arc;
+
+use codex_core::ContentItem;
+use codex_core::ModelClient;
+use codex_core::ModelProviderInfo;
+use codex_core::Prompt;
+use codex_core::ReasoningItemContent;
+use codex_core::ResponseEvent;
+use codex_core::ResponseItem;
+use codex_core::WireApi;
+use codex_core::model_family::find_family_for_model;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::models::FunctionCallOutputPayload;
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+const MODEL: &str = "claude-sonnet-4-5";
+
+fn network_disabled() -> bool {
+    std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
+}
+
+fn user_message(text: &str) -> ResponseItem {
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: text.to_string(),
+        }],
+    }
+}
+
+/// Streams `sse_body` back for a single Messages request built from `input`
+/// and returns the resulting events along with the request body.
+async fn run_stream(input: Vec<ResponseItem>, sse_body: &str) -> (Vec<ResponseEvent>, Value) {
+    let server = MockServer::start().await;
+
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse_body.to_string(), "text/event-stream");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(template)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = ModelProviderInfo {
+        name: "anthropic".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Anthropic,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    };
+
+    let codex_home = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => panic!("failed to create TempDir: {e}"),
+    };
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model = MODEL.to_string();
+    config.model_family = match find_family_for_model(MODEL) {
+        Some(family) => family,
+        None => panic!("no model family for {MODEL}"),
+    };
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let config = Arc::new(config);
+
+    let client = ModelClient::new(
+        Arc::clone(&config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = input;
+
+    let mut stream = match client.stream(&prompt).await {
+        Ok(s) => s,
+        Err(e) => panic!("stream messages failed: {e}"),
+    };
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(ev) => events.push(ev),
+            Err(e) => panic!("stream event error: {e}"),
+        }
+    }
+
+    let requests = match server.received_requests().await {
+        Some(requests) => requests,
+        None => panic!("request recording is disabled"),
+    };
+    let body = match requests.first().map(|r| r.body_json::<Value>()) {
+        Some(Ok(body)) => body,
+        other => panic!("expected a JSON request body, got {other:?}"),
+    };
+    (events, body)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_thinking_and_text() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"usage\":{\"input_tokens\":10,\"cache_read_input_tokens\":5,\"output_tokens\":1}}}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"hmm\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"signature_delta\",\"signature\":\"sig\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: ping\n",
+        "data: {\"type\":\"ping\"}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"hi\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\" there\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":7}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    let (events, body) = run_stream(vec![user_message("hello")], sse).await;
+
+    assert_eq!(body["model"], MODEL);
+    assert_eq!(body["stream"], true);
+    assert_eq!(body["max_tokens"], 64_000);
+    assert_eq!(
+        body["thinking"],
+        json!({"type": "enabled", "budget_tokens": 16_384})
+    );
+    assert!(body["system"].as_str().is_some_and(|s| !s.is_empty()));
+    assert_eq!(
+        body["messages"],
+        json!([{"role": "user", "content": [{"type": "text", "text": "hello"}]}])
+    );
+
+    assert_eq!(events.len(), 6, "unexpected events: {events:?}");
+    match &events[0] {
+        ResponseEvent::ReasoningContentDelta(text) => assert_eq!(text, "hmm"),
+        other => panic!("expected reasoning delta, got {other:?}"),
+    }
+    match &events[1] {
+        ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+            content: Some(content),
+            encrypted_content,
+            ..
+        }) => {
+            assert_eq!(
+                content,
+                &vec![ReasoningItemContent::ReasoningText {
+                    text: "hmm".to_string()
+                }]
+            );
+            assert_eq!(encrypted_content.as_deref(), Some("sig"));
+        }
+        other => panic!("expected reasoning item, got {other:?}"),
+    }
+    match (&events[2], &events[3]) {
+        (ResponseEvent::OutputTextDelta(first), ResponseEvent::OutputTextDelta(second)) => {
+            assert_eq!(first, "hi");
+            assert_eq!(second, " there");
+        }
+        other => panic!("expected text deltas, got {other:?}"),
+    }
+    match &events[4] {
+        ResponseEvent::OutputItemDone(ResponseItem::Message { role, content, .. }) => {
+            assert_eq!(role, "assistant");
+            assert_eq!(
+                content,
+                &vec![ContentItem::OutputText {
+                    text: "hi there".to_string()
+                }]
+            );
+        }
+        other => panic!("expected message item, got {other:?}"),
+    }
+    match &events[5] {
+        ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(usage),
+        } => {
+            assert_eq!(response_id, "msg_1");
+            assert_eq!(usage.input_tokens, 15);
+            assert_eq!(usage.cached_input_tokens, 5);
+            assert_eq!(usage.output_tokens, 7);
+            assert_eq!(usage.total_tokens, 22);
+        }
+        other => panic!("expected completed with usage, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_tool_use_as_function_call() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_2\",\"usage\":{\"input_tokens\":3}}}\n\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"shell\",\"input\":{}}}\n\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"command\\\": \"}}\n\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"[\\\"ls\\\"]}\"}}\n\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\"},\"usage\":{\"output_tokens\":4}}\n\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    let (events, _body) = run_stream(vec![user_message("list files")], sse).await;
+
+    assert_eq!(events.len(), 2, "unexpected events: {events:?}");
+    match &events[0] {
+        ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        }) => {
+            assert_eq!(name, "shell");
+            assert_eq!(arguments, "{\"command\": [\"ls\"]}");
+            assert_eq!(call_id, "toolu_1");
+        }
+        other => panic!("expected function call, got {other:?}"),
+    }
+    assert!(matches!(events[1], ResponseEvent::Completed { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn replays_tool_turn_with_thinking_signature() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let input = vec![
+        user_message("list files"),
+        ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText {
+                text: "run ls".to_string(),
+            }]),
+            encrypted_content: Some("sig".to_string()),
+        },
+        ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: "{\"command\":[\"ls\"]}".to_string(),
+            call_id: "toolu_1".to_string(),
+        },
+        ResponseItem::FunctionCallOutput {
+            call_id: "toolu_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "README.md".to_string(),
+                success: Some(true),
+            },
+        },
+    ];
+    let sse = concat!(
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_3\",\"usage\":{}}}\n\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    let (_events, body) = run_stream(input, sse).await;
+
+    assert_eq!(
+        body["messages"],
+        json!([
+            {"role": "user", "content": [{"type": "text", "text": "list files"}]},
+            {"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "run ls", "signature": "sig"},
+                {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {"command": ["ls"]}},
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "README.md"},
+            ]},
+        ])
+    );
+}
@@ -5,11 +5,13 @@ use codex_core::ConversationManager;
 use codex_core::ModelProviderInfo;
 use codex_core::NewConversation;
 use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::RolloutItem;
 use codex_core::protocol::RolloutLine;
+use codex_core::protocol::SandboxPolicy;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use core_test_support::load_default_config_for_test;
 use core_test_support::wait_for_event;
@@ -52,6 +54,30 @@ fn ev_completed(id: &str) -> Value {
     })
 }
 
+/// Convenience: SSE event for a completed response that used `total_tokens`.
+fn ev_completed_with_tokens(id: &str, total_tokens: u64) -> Value {
+    serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {"input_tokens":total_tokens,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":total_tokens}
+        }
+    })
+}
+
+/// Convenience: SSE event for a `shell` function call running `script`.
+fn ev_shell_call(call_id: &str, script: &str) -> Value {
+    serde_json::json!({
+        "type": "response.output_item.done",
+        "item": {
+            "type": "function_call",
+            "name": "shell",
+            "arguments": serde_json::json!({"command": ["bash", "-lc", script]}).to_string(),
+            "call_id": call_id
+        }
+    })
+}
+
 /// Convenience: SSE event for a single assistant message output item.
 fn ev_assistant_message(id: &str, text: &str) -> Value {
     serde_json::json!({
@@ -296,3 +322,151 @@ async fn summarize_context_three_requests_and_instructions() {
         "expected a Compacted entry containing the summarizer output"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn auto_compacts_mid_task_when_context_window_fills() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    const CONTINUE_MARKER: &str = "Continue working on the current task";
+
+    let server = MockServer::start().await;
+
+    // SSE 1: a tool call whose request used 90% of the context window.
+    let sse1 = sse(vec![
+        ev_shell_call("call-1", "echo hi"),
+        ev_completed_with_tokens("r1", 900),
+    ]);
+    // SSE 2: the automatic summarization request.
+    let sse2 = sse(vec![
+        ev_assistant_message("m2", SUMMARY_TEXT),
+        ev_completed_with_tokens("r2", 100),
+    ]);
+    // SSE 3: the task continues from the summary and finishes.
+    let sse3 = sse(vec![
+        ev_assistant_message("m3", "done"),
+        ev_completed_with_tokens("r3", 200),
+    ]);
+
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            let body = std::str::from_utf8(&req.body).unwrap_or("");
+            body.contains("hello world") && !body.contains("call-1")
+        },
+        sse1,
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            let body = std::str::from_utf8(&req.body).unwrap_or("");
+            body.contains(&format!("\"text\":\"{SUMMARIZE_TRIGGER}\""))
+        },
+        sse2,
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        |req: &wiremock::Request| {
+            let body = std::str::from_utf8(&req.body).unwrap_or("");
+            body.contains(CONTINUE_MARKER)
+        },
+        sse3,
+    )
+    .await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_context_window = Some(1_000);
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let NewConversation {
+        conversation: codex,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await.unwrap();
+    let rollout_path = session_configured.rollout_path;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut background_messages = Vec::new();
+    loop {
+        let event = codex.next_event().await.unwrap();
+        match event.msg {
+            EventMsg::BackgroundEvent(ev) => background_messages.push(ev.message),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        background_messages,
+        vec![
+            "Context window is 90% full; compacting conversation history…".to_string(),
+            "Conversation history compacted.".to_string(),
+        ]
+    );
+
+    // The request after compaction carries only the summary and the
+    // continuation prompt.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected exactly three requests");
+    let body3 = requests[2].body_json::<serde_json::Value>().unwrap();
+    let messages: Vec<(String, String)> = body3["input"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["type"].as_str() == Some("message"))
+        .map(|item| {
+            (
+                item["role"].as_str().unwrap_or_default().to_string(),
+                item["content"][0]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        })
+        .collect();
+    assert!(
+        messages
+            .iter()
+            .any(|(r, t)| r == "assistant" && t == SUMMARY_TEXT),
+        "continued request should include the summary"
+    );
+    assert!(
+        !messages.iter().any(|(_, t)| t.contains("hello world")),
+        "continued request should not include the original user input"
+    );
+
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+
+    let text = std::fs::read_to_string(&rollout_path).unwrap();
+    let saw_compacted_summary = text.lines().any(|line| {
+        matches!(
+            serde_json::from_str::<RolloutLine>(line).map(|entry| entry.item),
+            Ok(RolloutItem::Compacted(ci)) if ci.message == SUMMARY_TEXT
+        )
+    });
+    assert!(
+        saw_compacted_summary,
+        "expected a Compacted entry containing the summarizer output"
+    );
+}
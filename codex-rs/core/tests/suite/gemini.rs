@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use codex_core::ContentItem;
+use codex_core::ModelClient;
+use codex_core::ModelProviderInfo;
+use codex_core::Prompt;
+use codex_core::ReasoningItemContent;
+use codex_core::ResponseEvent;
+use codex_core::ResponseItem;
+use codex_core::WireApi;
+use codex_core::model_family::find_family_for_model;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::models::FunctionCallOutputPayload;
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::query_param;
+
+const MODEL: &str = "gemini-2.5-pro";
+
+fn network_disabled() -> bool {
+    std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
+}
+
+fn user_message(text: &str) -> ResponseItem {
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: text.to_string(),
+        }],
+    }
+}
+
+/// Streams `sse_body` back for a single `streamGenerateContent` request built
+/// from `input` and returns the resulting events along with the request body.
+async fn run_stream(input: Vec<ResponseItem>, sse_body: &str) -> (Vec<ResponseEvent>, Value) {
+    let server = MockServer::start().await;
+
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse_body.to_string(), "text/event-stream");
+
+    Mock::given(method("POST"))
+        .and(path(format!(
+            "/v1beta/models/{MODEL}:streamGenerateContent"
+        )))
+        .and(query_param("alt", "sse"))
+        .respond_with(template)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = ModelProviderInfo {
+        name: "gemini".into(),
+        base_url: Some(format!("{}/v1beta", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Gemini,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    };
+
+    let codex_home = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => panic!("failed to create TempDir: {e}"),
+    };
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model = MODEL.to_string();
+    config.model_family = match find_family_for_model(MODEL) {
+        Some(family) => family,
+        None => panic!("no model family for {MODEL}"),
+    };
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let config = Arc::new(config);
+
+    let client = ModelClient::new(
+        Arc::clone(&config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = input;
+
+    let mut stream = match client.stream(&prompt).await {
+        Ok(s) => s,
+        Err(e) => panic!("stream generateContent failed: {e}"),
+    };
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(ev) => events.push(ev),
+            Err(e) => panic!("stream event error: {e}"),
+        }
+    }
+
+    let requests = match server.received_requests().await {
+        Some(requests) => requests,
+        None => panic!("request recording is disabled"),
+    };
+    let body = match requests.first().map(|r| r.body_json::<Value>()) {
+        Some(Ok(body)) => body,
+        other => panic!("expected a JSON request body, got {other:?}"),
+    };
+    (events, body)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_thoughts_and_text() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"hmm\",\"thought\":true}]}}],\"responseId\":\"resp_1\"}\n\n",
+        "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"hi\"}]}}],\"responseId\":\"resp_1\"}\n\n",
+        "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\" there\"}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":10,\"cachedContentTokenCount\":4,\"candidatesTokenCount\":2,\"thoughtsTokenCount\":3,\"totalTokenCount\":15},\"responseId\":\"resp_1\"}\n\n",
+    );
+
+    let (events, body) = run_stream(vec![user_message("hello")], sse).await;
+
+    assert_eq!(
+        body["contents"],
+        json!([{"role": "user", "parts": [{"text": "hello"}]}])
+    );
+    assert!(
+        body["systemInstruction"]["parts"][0]["text"]
+            .as_str()
+            .is_some_and(|s| !s.is_empty())
+    );
+    assert_eq!(
+        body["generationConfig"],
+        json!({"thinkingConfig": {"includeThoughts": true}})
+    );
+
+    assert_eq!(events.len(), 6, "unexpected events: {events:?}");
+    match &events[0] {
+        ResponseEvent::ReasoningContentDelta(text) => assert_eq!(text, "hmm"),
+        other => panic!("expected reasoning delta, got {other:?}"),
+    }
+    match (&events[1], &events[2]) {
+        (ResponseEvent::OutputTextDelta(first), ResponseEvent::OutputTextDelta(second)) => {
+            assert_eq!(first, "hi");
+            assert_eq!(second, " there");
+        }
+        other => panic!("expected text deltas, got {other:?}"),
+    }
+    match &events[3] {
+        ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+            content: Some(content),
+            ..
+        }) => assert_eq!(
+            content,
+            &vec![ReasoningItemContent::ReasoningText {
+                text: "hmm".to_string()
+            }]
+        ),
+        other => panic!("expected reasoning item, got {other:?}"),
+    }
+    match &events[4] {
+        ResponseEvent::OutputItemDone(ResponseItem::Message { content, .. }) => assert_eq!(
+            content,
+            &vec![ContentItem::OutputText {
+                text: "hi there".to_string()
+            }]
+        ),
+        other => panic!("expected message item, got {other:?}"),
+    }
+    match &events[5] {
+        ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(usage),
+        } => {
+            assert_eq!(response_id, "resp_1");
+            assert_eq!(usage.input_tokens, 10);
+            assert_eq!(usage.cached_input_tokens, 4);
+            assert_eq!(usage.output_tokens, 5);
+            assert_eq!(usage.reasoning_output_tokens, 3);
+            assert_eq!(usage.total_tokens, 15);
+        }
+        other => panic!("expected completed with usage, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_function_call_with_thought_signature() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"functionCall\":{\"name\":\"shell\",\"args\":{\"command\":[\"ls\"]}},\"thoughtSignature\":\"sig\"}]},\"finishReason\":\"STOP\"}]}\n\n";
+
+    let (events, _body) = run_stream(vec![user_message("list files")], sse).await;
+
+    assert_eq!(events.len(), 3, "unexpected events: {events:?}");
+    match &events[0] {
+        ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+            content,
+            encrypted_content,
+            ..
+        }) => {
+            assert_eq!(content, &None);
+            assert_eq!(encrypted_content.as_deref(), Some("sig"));
+        }
+        other => panic!("expected reasoning item, got {other:?}"),
+    }
+    match &events[1] {
+        ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        }) => {
+            assert_eq!(name, "shell");
+            assert_eq!(arguments, "{\"command\":[\"ls\"]}");
+            assert!(!call_id.is_empty());
+        }
+        other => panic!("expected function call, got {other:?}"),
+    }
+    assert!(matches!(events[2], ResponseEvent::Completed { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn replays_function_response_by_name() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let input = vec![
+        user_message("list files"),
+        ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: None,
+            encrypted_content: Some("sig".to_string()),
+        },
+        ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: "{\"command\":[\"ls\"]}".to_string(),
+            call_id: "call_1".to_string(),
+        },
+        ResponseItem::FunctionCallOutput {
+            call_id: "call_1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "README.md".to_string(),
+                success: Some(true),
+            },
+        },
+    ];
+    let sse = "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"done\"}]},\"finishReason\":\"STOP\"}]}\n\n";
+
+    let (_events, body) = run_stream(input, sse).await;
+
+    assert_eq!(
+        body["contents"],
+        json!([
+            {"role": "user", "parts": [{"text": "list files"}]},
+            {"role": "model", "parts": [
+                {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}, "thoughtSignature": "sig"},
+            ]},
+            {"role": "user", "parts": [
+                {"functionResponse": {"name": "shell", "response": {"content": "README.md"}}},
+            ]},
+        ])
+    );
+}
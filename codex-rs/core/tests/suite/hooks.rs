@@ -0,0 +1,219 @@
+#![cfg(unix)]
+#![allow(clippy::expect_used)]
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::config::Config;
+use codex_core::config_types::HookCommand;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::function_call_output;
+use core_test_support::load_default_config_for_test;
+use core_test_support::load_sse_fixture_with_id_from_str;
+use core_test_support::sse_assistant_message;
+use core_test_support::sse_template;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// SSE body with a call to the tool `name` with `arguments`, followed by
+/// `response.completed`.
+fn sse_tool_call(call_id: &str, name: &str, arguments: serde_json::Value) -> String {
+    let json = r#"[
+  {
+    "type": "response.output_item.done",
+    "item": {
+      "type": "function_call",
+      "id": "fc-__CALL_ID__",
+      "name": "__NAME__",
+      "arguments": "__ARGS__",
+      "call_id": "__CALL_ID__"
+    }
+  },
+  {
+    "type": "response.completed",
+    "response": {"id": "__ID__", "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}}
+  }
+]"#;
+    let json = json
+        .replace("__CALL_ID__", call_id)
+        .replace("__NAME__", name)
+        .replace("__ARGS__", &arguments.to_string().replace('"', "\\\""));
+    load_sse_fixture_with_id_from_str(&json, &format!("resp-{call_id}"))
+}
+
+/// SSE body with a `shell` call running `script`, followed by `response.completed`.
+fn sse_shell_call(call_id: &str, script: &str) -> String {
+    sse_tool_call(
+        call_id,
+        "shell",
+        serde_json::json!({"command": ["bash", "-lc", script], "timeout": 2000}),
+    )
+}
+
+/// Mounts the two requests of a turn that makes one tool call: the initial
+/// request, answered with `tool_call`, and the follow-up carrying the call's
+/// output.
+async fn mount_tool_turn(server: &MockServer, call_id: &'static str, tool_call: String) {
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(move |req: &wiremock::Request| !String::from_utf8_lossy(&req.body).contains(call_id))
+        .respond_with(sse_template(tool_call))
+        .expect(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(move |req: &wiremock::Request| String::from_utf8_lossy(&req.body).contains(call_id))
+        .respond_with(sse_template(sse_assistant_message("done")))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn sh(script: &str) -> HookCommand {
+    HookCommand {
+        command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        timeout_ms: None,
+    }
+}
+
+/// Runs one user turn whose shell call runs `script` in `cwd`.
+async fn run_shell_turn(
+    call_id: &'static str,
+    script: &str,
+    cwd: &TempDir,
+    configure: impl FnOnce(&mut Config),
+) -> (MockServer, Vec<EventMsg>) {
+    run_tool_turn(call_id, sse_shell_call(call_id, script), cwd, configure).await
+}
+
+/// Runs one user turn in `cwd` whose model response is `tool_call`.
+async fn run_tool_turn(
+    call_id: &'static str,
+    tool_call: String,
+    cwd: &TempDir,
+    configure: impl FnOnce(&mut Config),
+) -> (MockServer, Vec<EventMsg>) {
+    let server = MockServer::start().await;
+    mount_tool_turn(&server, call_id, tool_call).await;
+
+    let codex_home = TempDir::new().expect("codex home");
+    let mut config = load_default_config_for_test(&codex_home);
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    config.cwd = cwd.path().to_path_buf();
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    configure(&mut config);
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run it".into(),
+            }],
+        })
+        .await
+        .expect("submit");
+
+    let mut events = Vec::new();
+    loop {
+        let event = codex.next_event().await.expect("event");
+        let done = matches!(event.msg, EventMsg::TaskComplete(_));
+        events.push(event.msg);
+        if done {
+            break;
+        }
+    }
+    (server, events)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn failing_pre_exec_hook_blocks_the_command() {
+    let cwd = TempDir::new().expect("cwd");
+    let (server, events) = run_shell_turn("call-blocked", "touch ran", &cwd, |config| {
+        config.hooks.pre_exec = vec![sh("echo 'touch is not allowed' >&2; exit 1")];
+    })
+    .await;
+
+    assert_eq!(
+        function_call_output(&server, "call-blocked").await,
+        "touch is not allowed"
+    );
+    assert!(!cwd.path().join("ran").exists());
+    assert!(
+        !events
+            .iter()
+            .any(|msg| matches!(msg, EventMsg::ExecCommandBegin(_))),
+        "blocked command should not start"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_exec_hook_receives_the_exit_code() {
+    let cwd = TempDir::new().expect("cwd");
+    let (server, _events) = run_shell_turn("call-allowed", "exit 7", &cwd, |config| {
+        config.hooks.pre_exec = vec![sh("true")];
+        config.hooks.post_exec = vec![sh("cat > post_exec.json")];
+    })
+    .await;
+    server.verify().await;
+
+    let payload: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(cwd.path().join("post_exec.json")).expect("post_exec payload"),
+    )
+    .expect("payload is JSON");
+    assert_eq!(payload["event"], "post_exec");
+    assert_eq!(payload["call_id"], "call-allowed");
+    assert_eq!(
+        payload["command"],
+        serde_json::json!(["bash", "-lc", "exit 7"])
+    );
+    assert_eq!(payload["exit_code"], 7);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pre_exec_hook_also_guards_exec_command() {
+    let cwd = TempDir::new().expect("cwd");
+    let tool_call = sse_tool_call(
+        "call-exec-command",
+        "exec_command",
+        serde_json::json!({"cmd": "touch ran", "login": false}),
+    );
+    let (server, _events) = run_tool_turn("call-exec-command", tool_call, &cwd, |config| {
+        config.use_experimental_streamable_shell_tool = true;
+        config.hooks.pre_exec = vec![sh("cat > pre_exec.json; echo 'no' >&2; exit 1")];
+    })
+    .await;
+
+    assert_eq!(
+        function_call_output(&server, "call-exec-command").await,
+        "no"
+    );
+    assert!(!cwd.path().join("ran").exists());
+    let payload: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(cwd.path().join("pre_exec.json")).expect("pre_exec payload"),
+    )
+    .expect("payload is JSON");
+    assert_eq!(
+        payload["command"],
+        serde_json::json!(["/bin/bash", "-c", "touch ran"])
+    );
+}
@@ -1,15 +1,20 @@
 // Aggregates all former standalone integration tests as modules.
 
+mod anthropic_messages;
 mod cli_stream;
 mod client;
 mod compact;
 mod exec;
 mod exec_stream_events;
 mod fork_conversation;
+mod gemini;
+mod hooks;
 mod live_cli;
 mod model_overrides;
 mod prompt_caching;
+mod provider_fallback;
 mod seatbelt;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
 mod sub_agent_exec;
+mod undo_turn;
@@ -197,6 +197,7 @@ async fn prompt_tools_are_consistent_across_requests() {
         "apply_patch",
         "view_image",
         "sub_agent_launch",
+        "sub_agent_await",
     ];
     let body0 = requests[0].body_json::<serde_json::Value>().unwrap();
     assert_eq!(
@@ -389,6 +390,9 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
             sandbox_policy: Some(SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
                 network_access: true,
+                network_allowlist: vec![],
+                read_only_paths: vec![],
+                deny_read_paths: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             }),
@@ -522,6 +526,9 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
             sandbox_policy: SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
                 network_access: true,
+                network_allowlist: vec![],
+                read_only_paths: vec![],
+                deny_read_paths: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
@@ -0,0 +1,372 @@
+use std::time::Duration;
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::FallbackProvider;
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_default_config_for_test;
+use core_test_support::load_sse_fixture_with_id;
+use core_test_support::load_sse_fixture_with_id_from_str;
+use core_test_support::sse_template;
+use core_test_support::wait_for_event_with_timeout;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn sse_completed(id: &str) -> String {
+    load_sse_fixture_with_id("tests/fixtures/completed_template.json", id)
+}
+
+/// Responses API turn with an encrypted reasoning item and a call to a tool
+/// that does not exist, so the turn continues without running anything.
+fn sse_reasoning_and_tool_call() -> String {
+    let events = json!([
+        {
+            "type": "response.output_item.done",
+            "item": {
+                "type": "reasoning",
+                "id": "rs_1",
+                "summary": [],
+                "encrypted_content": "openai_encrypted_reasoning"
+            }
+        },
+        {
+            "type": "response.output_item.done",
+            "item": {
+                "type": "function_call",
+                "name": "nonexistent_tool",
+                "arguments": "{}",
+                "call_id": "call_1"
+            }
+        },
+        {
+            "type": "response.completed",
+            "response": { "id": "__ID__", "output": [] }
+        }
+    ]);
+    load_sse_fixture_with_id_from_str(&events.to_string(), "resp_primary")
+}
+
+/// Anthropic Messages turn with a signed thinking block and a text answer.
+fn sse_anthropic_thinking_and_text(text: &str) -> String {
+    let events = [
+        json!({"type": "message_start", "message": {"id": "msg_1", "usage": {"input_tokens": 1}}}),
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "hmm"}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "anthropic_signature"}}),
+        json!({"type": "content_block_stop", "index": 0}),
+        json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": text}}),
+        json!({"type": "content_block_stop", "index": 1}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 1}}),
+        json!({"type": "message_stop"}),
+    ];
+    events
+        .iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {event}\n\n",
+                event["type"].as_str().unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+fn mock_provider(name: &str, base_url: String) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: name.into(),
+        base_url: Some(base_url),
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: WireApi::Responses,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(2_000),
+        requires_openai_auth: false,
+    }
+}
+
+fn anthropic_fallback(base_url: String) -> FallbackProvider {
+    FallbackProvider {
+        provider_id: "anthropic".into(),
+        provider: ModelProviderInfo {
+            wire_api: WireApi::Anthropic,
+            ..mock_provider("anthropic", base_url)
+        },
+        model: Some("claude-sonnet-4-5".into()),
+    }
+}
+
+async fn request_bodies(server: &MockServer, request_path: &str) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|request| request.url.path() == request_path)
+        .map(|request| String::from_utf8_lossy(&request.body).into_owned())
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn switches_to_fallback_provider_and_stays_there() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // The primary provider is down: only the first turn should reach it, the
+    // second one goes straight to the fallback while the cooldown runs.
+    Mock::given(method("POST"))
+        .and(path("/primary/v1/responses"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/fallback/v1/responses"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(sse_completed("resp_fallback"), "text/event-stream"),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = mock_provider("primary", format!("{}/primary/v1", server.uri()));
+    config.fallback_providers = vec![FallbackProvider {
+        provider_id: "fallback".into(),
+        provider: mock_provider("fallback", format!("{}/fallback/v1", server.uri())),
+        model: None,
+    }];
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    for text in ["first message", "follow up"] {
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+
+        if text == "first message" {
+            let event = wait_for_event_with_timeout(
+                &codex,
+                |ev| matches!(ev, EventMsg::BackgroundEvent(ev) if ev.message.contains("switching")),
+                Duration::from_secs(5),
+            )
+            .await;
+            match event {
+                EventMsg::BackgroundEvent(event) => assert!(
+                    event
+                        .message
+                        .starts_with("model provider `primary` unavailable")
+                        && event.message.contains("switching to `fallback`"),
+                    "unexpected message: {}",
+                    event.message
+                ),
+                other => panic!("expected background event, got {other:?}"),
+            }
+        }
+
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::TaskComplete(_)),
+            Duration::from_secs(5),
+        )
+        .await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fallback_to_another_wire_api_drops_foreign_reasoning() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // The primary provider answers the first request of the turn with
+    // encrypted reasoning and a tool call, then goes down.
+    Mock::given(method("POST"))
+        .and(path("/primary/v1/responses"))
+        .respond_with(sse_template(sse_reasoning_and_tool_call()))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/primary/v1/responses"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/anthropic/v1/messages"))
+        .respond_with(sse_template(sse_anthropic_thinking_and_text("done")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = mock_provider("primary", format!("{}/primary/v1", server.uri()));
+    config.fallback_providers = vec![anthropic_fallback(format!("{}/anthropic/v1", server.uri()))];
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "first message".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let completed = wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        Duration::from_secs(5),
+    )
+    .await;
+    assert!(matches!(
+        completed,
+        EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: Some(message),
+        }) if message == "done"
+    ));
+
+    let bodies = request_bodies(&server, "/anthropic/v1/messages").await;
+    assert_eq!(bodies.len(), 1);
+    assert!(
+        bodies[0].contains("call_1"),
+        "tool call missing: {}",
+        bodies[0]
+    );
+    assert!(
+        !bodies[0].contains("openai_encrypted_reasoning"),
+        "Responses reasoning replayed to Anthropic: {}",
+        bodies[0]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn returns_to_primary_after_cooldown_without_fallback_reasoning() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // The primary provider is down for the first turn only.
+    Mock::given(method("POST"))
+        .and(path("/primary/v1/responses"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/primary/v1/responses"))
+        .respond_with(sse_template(sse_completed("resp_primary")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/anthropic/v1/messages"))
+        .respond_with(sse_template(sse_anthropic_thinking_and_text(
+            "answer from the fallback",
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = mock_provider("primary", format!("{}/primary/v1", server.uri()));
+    config.fallback_providers = vec![anthropic_fallback(format!("{}/anthropic/v1", server.uri()))];
+    config.fallback_cooldown = Duration::ZERO;
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    for text in ["first message", "follow up"] {
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+        if text == "follow up" {
+            wait_for_event_with_timeout(
+                &codex,
+                |ev| {
+                    matches!(ev, EventMsg::BackgroundEvent(ev)
+                        if ev.message.contains("returning to primary model provider `primary`"))
+                },
+                Duration::from_secs(5),
+            )
+            .await;
+        }
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::TaskComplete(_)),
+            Duration::from_secs(5),
+        )
+        .await;
+    }
+
+    let bodies = request_bodies(&server, "/primary/v1/responses").await;
+    assert_eq!(bodies.len(), 2);
+    assert!(
+        bodies[1].contains("answer from the fallback"),
+        "history missing: {}",
+        bodies[1]
+    );
+    assert!(
+        !bodies[1].contains("anthropic_signature"),
+        "Anthropic thinking replayed to the Responses API: {}",
+        bodies[1]
+    );
+}
@@ -78,6 +78,9 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_parent.clone()],
         network_access: false,
+        network_allowlist: vec![],
+        read_only_paths: vec![],
+        deny_read_paths: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -104,6 +107,9 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_root.clone()],
         network_access: false,
+        network_allowlist: vec![],
+        read_only_paths: vec![],
+        deny_read_paths: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -1,21 +1,37 @@
+#![allow(clippy::expect_used)]
+
+use codex_core::AuthManager;
 use codex_core::CodexAuth;
+use codex_core::CodexConversation;
 use codex_core::ConversationManager;
 use codex_core::ModelProviderInfo;
 use codex_core::built_in_model_providers;
+use codex_core::config::Config;
+use codex_core::config_types::AgentConfig;
+use codex_core::config_types::Budget;
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SubAgentCompletedEvent;
+use codex_core::protocol::SubAgentFailedEvent;
+use codex_core::protocol::SubAgentStartedEvent;
+use core_test_support::function_call_output;
 use core_test_support::load_default_config_for_test;
 use core_test_support::load_sse_fixture_with_id_from_str;
+use core_test_support::sse_assistant_message;
+use core_test_support::sse_template;
 use core_test_support::wait_for_event;
+use core_test_support::wait_for_event_with_timeout;
+use std::sync::Arc;
+use std::time::Duration;
 use tempfile::TempDir;
 use wiremock::Mock;
 use wiremock::MockServer;
 use wiremock::ResponseTemplate;
-use wiremock::matchers::body_string_contains;
 use wiremock::matchers::method;
 use wiremock::matchers::path;
 
@@ -87,57 +103,124 @@ fn sse_sub_runs_shell_with_escalated(script: &str, with_escalated: bool) -> Stri
     load_sse_fixture_with_id_from_str(&json, "resp-sub")
 }
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn sub_agent_exec_inherits_policies_and_runs() {
-    // Two sequential SSE responses: main turn (launch) then sub-agent turn (shell)
-    let server = MockServer::start().await;
+/// SSE body containing a single function call followed by `response.completed`.
+fn sse_function_call(call_id: &str, name: &str, arguments: &str) -> String {
+    let json = r#"[
+  {
+    "type": "response.output_item.done",
+    "item": {
+      "type": "function_call",
+      "id": "fc-__CALL_ID__",
+      "name": "__NAME__",
+      "arguments": "__ARGS__",
+      "call_id": "__CALL_ID__"
+    }
+  },
+  {
+    "type": "response.completed",
+    "response": {"id": "__ID__", "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}}
+  }
+]"#;
+    let json = json
+        .replace("__CALL_ID__", call_id)
+        .replace("__NAME__", name)
+        .replace("__ARGS__", &arguments.replace('"', "\\\""));
+    load_sse_fixture_with_id_from_str(&json, &format!("resp-{call_id}"))
+}
 
-    let sse1 = sse_main_launch();
-    let first = ResponseTemplate::new(200)
-        .insert_header("content-type", "text/event-stream")
-        .set_body_raw(sse1, "text/event-stream");
+/// Mounts a responder for `/v1/responses` requests whose body satisfies `matcher`.
+async fn mount_sse<F>(server: &MockServer, matcher: F, template: ResponseTemplate)
+where
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+{
     Mock::given(method("POST"))
-        .and(body_string_contains("trigger sub-agent"))
         .and(path("/v1/responses"))
-        .respond_with(first)
+        .and(move |req: &wiremock::Request| matcher(&String::from_utf8_lossy(&req.body)))
+        .respond_with(template)
         .expect(1)
-        .mount(&server)
+        .mount(server)
         .await;
+}
 
-    // Simple command that should succeed under inherited policies.
-    let sse2 = sse_sub_runs_shell_with_escalated("echo ok", false);
-    let second = ResponseTemplate::new(200)
-        .insert_header("content-type", "text/event-stream")
-        .set_body_raw(sse2, "text/event-stream");
-    Mock::given(method("POST"))
-        .and(body_string_contains("run the command"))
-        .and(path("/v1/responses"))
-        .respond_with(second)
-        .expect(1)
-        .mount(&server)
-        .await;
+/// Mounts the four requests of a single blocking sub-agent launch: the main
+/// agent's launch, the sub-agent's shell call, the sub-agent's follow-up after
+/// the shell output, and the main agent's follow-up after the summary.
+async fn mount_single_launch(server: &MockServer, script: &str) {
+    mount_sse(
+        server,
+        |body| body.contains("trigger sub-agent") && !body.contains("call-main-1"),
+        sse_template(sse_main_launch()),
+    )
+    .await;
+    mount_sse(
+        server,
+        |body| {
+            body.contains("run the command")
+                && !body.contains("trigger sub-agent")
+                && !body.contains("call-sub-1")
+        },
+        sse_template(sse_sub_runs_shell_with_escalated(script, false)),
+    )
+    .await;
+    mount_sse(
+        server,
+        |body| body.contains("call-sub-1") && !body.contains("trigger sub-agent"),
+        sse_template(sse_assistant_message("sub-agent done")),
+    )
+    .await;
+    mount_sse(
+        server,
+        |body| body.contains("trigger sub-agent") && body.contains("call-main-1"),
+        sse_template(sse_assistant_message("all done")),
+    )
+    .await;
+}
+
+async fn new_full_access_conversation(
+    server: &MockServer,
+    cwd: &TempDir,
+    codex_home: &TempDir,
+) -> Arc<CodexConversation> {
+    new_full_access_conversation_with(server, cwd, codex_home, |_| {}).await
+}
 
+async fn new_full_access_conversation_with(
+    server: &MockServer,
+    cwd: &TempDir,
+    codex_home: &TempDir,
+    customize: impl FnOnce(&mut Config),
+) -> Arc<CodexConversation> {
     let model_provider = ModelProviderInfo {
         base_url: Some(format!("{}/v1", server.uri())),
         ..built_in_model_providers()["openai"].clone()
     };
 
-    let cwd = TempDir::new().unwrap();
-    let codex_home = TempDir::new().unwrap();
-    let mut config = load_default_config_for_test(&codex_home);
+    let mut config = load_default_config_for_test(codex_home);
     // Ensure permissive policies so the sub-agent can run without per-call overrides.
     config.approval_policy = AskForApproval::Never;
     config.sandbox_policy = SandboxPolicy::DangerFullAccess;
     config.cwd = cwd.path().to_path_buf();
     config.model_provider = model_provider;
+    customize(&mut config);
 
     let conversation_manager =
         ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
-    let codex = conversation_manager
+    conversation_manager
         .new_conversation(config)
         .await
         .expect("create new conversation")
-        .conversation;
+        .conversation
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sub_agent_exec_inherits_policies_and_runs() {
+    let server = MockServer::start().await;
+    // Simple command that should succeed under inherited policies.
+    mount_single_launch(&server, "echo ok").await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
 
     codex
         .submit(Op::UserInput {
@@ -148,7 +231,6 @@ async fn sub_agent_exec_inherits_policies_and_runs() {
         .await
         .unwrap();
 
-    // Drive the stream and capture the approval request so we can respond to it.
     let mut saw_started = false;
     loop {
         let ev = codex.next_event().await.expect("event");
@@ -156,65 +238,25 @@ async fn sub_agent_exec_inherits_policies_and_runs() {
             EventMsg::SubAgentStarted(_) => {
                 saw_started = true;
             }
-            EventMsg::SubAgentCompleted(_) => {
+            EventMsg::SubAgentCompleted(SubAgentCompletedEvent { summary, .. }) => {
                 assert!(saw_started, "expected sub-agent to have started");
+                assert_eq!(summary, "sub-agent done");
                 break;
             }
             _ => {}
         }
     }
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
 }
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn sub_agent_exec_succeeds_with_danger_full_access() {
-    // Two sequential SSE responses: main turn (launch with danger) then sub-agent turn (shell)
     let server = MockServer::start().await;
-
-    let sse1 = sse_main_launch();
-    let first = ResponseTemplate::new(200)
-        .insert_header("content-type", "text/event-stream")
-        .set_body_raw(sse1, "text/event-stream");
-    Mock::given(method("POST"))
-        .and(body_string_contains("trigger sub-agent"))
-        .and(path("/v1/responses"))
-        .respond_with(first)
-        .expect(1)
-        .mount(&server)
-        .await;
-
-    let sse2 = sse_sub_runs_shell_with_escalated("echo from sub-agent", false);
-    let second = ResponseTemplate::new(200)
-        .insert_header("content-type", "text/event-stream")
-        .set_body_raw(sse2, "text/event-stream");
-    Mock::given(method("POST"))
-        .and(body_string_contains("run the command"))
-        .and(path("/v1/responses"))
-        .respond_with(second)
-        .expect(1)
-        .mount(&server)
-        .await;
-
-    let model_provider = ModelProviderInfo {
-        base_url: Some(format!("{}/v1", server.uri())),
-        ..built_in_model_providers()["openai"].clone()
-    };
+    mount_single_launch(&server, "echo from sub-agent").await;
 
     let cwd = TempDir::new().unwrap();
     let codex_home = TempDir::new().unwrap();
-    let mut config = load_default_config_for_test(&codex_home);
-    // Ensure permissive policies so the sub-agent can run without per-call overrides.
-    config.approval_policy = AskForApproval::Never;
-    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
-    config.cwd = cwd.path().to_path_buf();
-    config.model_provider = model_provider;
-
-    let conversation_manager =
-        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
-    let codex = conversation_manager
-        .new_conversation(config)
-        .await
-        .expect("create new conversation")
-        .conversation;
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
 
     codex
         .submit(Op::UserInput {
@@ -237,4 +279,562 @@ async fn sub_agent_exec_succeeds_with_danger_full_access() {
 
     // And the sub-agent should complete afterwards.
     wait_for_event(&codex, |ev| matches!(ev, EventMsg::SubAgentCompleted(_))).await;
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn background_sub_agents_run_in_parallel_and_are_awaited() {
+    let server = MockServer::start().await;
+
+    // Main turn 1: launch two background sub-agents in a single response.
+    let launch_a = sse_function_call(
+        "call-launch-a",
+        "sub_agent_launch",
+        r#"{"prompt":"inspect module A","background":true}"#,
+    );
+    let launch_b = sse_function_call(
+        "call-launch-b",
+        "sub_agent_launch",
+        r#"{"prompt":"inspect module B","background":true}"#,
+    );
+    // Concatenating two SSE bodies yields two output items; only the second
+    // `response.completed` is ever read because the first ends the turn, so
+    // strip the first body's completion event.
+    let launch_a = launch_a
+        .split("event: response.completed")
+        .next()
+        .unwrap()
+        .to_string();
+    mount_sse(
+        &server,
+        |body| body.contains("trigger parallel") && !body.contains("call-launch-a"),
+        sse_template(format!("{launch_a}{launch_b}")),
+    )
+    .await;
+
+    // Each sub-agent answers after a delay, so if they ran sequentially the
+    // second would not start until the first had completed.
+    mount_sse(
+        &server,
+        |body| body.contains("inspect module A") && !body.contains("trigger parallel"),
+        sse_template(sse_assistant_message("module A summary"))
+            .set_delay(Duration::from_millis(300)),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("inspect module B") && !body.contains("trigger parallel"),
+        sse_template(sse_assistant_message("module B summary"))
+            .set_delay(Duration::from_millis(300)),
+    )
+    .await;
+
+    // Main turn 2: await all background sub-agents.
+    mount_sse(
+        &server,
+        |body| {
+            body.contains("trigger parallel")
+                && body.contains("call-launch-b")
+                && !body.contains("call-await")
+        },
+        sse_template(sse_function_call(
+            "call-await",
+            "sub_agent_await",
+            r#"{"mode":"all"}"#,
+        )),
+    )
+    .await;
+
+    // Main turn 3: final answer after receiving both summaries.
+    mount_sse(
+        &server,
+        |body| body.contains("trigger parallel") && body.contains("call-await"),
+        sse_template(sse_assistant_message("both done")),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger parallel".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let mut started = Vec::new();
+    let mut completed = Vec::new();
+    loop {
+        let ev = codex.next_event().await.expect("event");
+        match ev.msg {
+            EventMsg::SubAgentStarted(SubAgentStartedEvent { sub_id, .. }) => {
+                assert!(
+                    completed.is_empty(),
+                    "sub-agent {sub_id} started after another had completed"
+                );
+                started.push(sub_id);
+            }
+            EventMsg::SubAgentCompleted(SubAgentCompletedEvent {
+                sub_id, summary, ..
+            }) => completed.push((sub_id, summary)),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+    completed.sort();
+    assert_eq!(started, vec!["call-launch-a", "call-launch-b"]);
+    assert_eq!(
+        completed,
+        vec![
+            ("call-launch-a".to_string(), "module A summary".to_string()),
+            ("call-launch-b".to_string(), "module B summary".to_string()),
+        ]
+    );
+
+    // The await output handed back to the main agent carries both summaries.
+    let await_output = function_call_output(&server, "call-await").await;
+    let output: serde_json::Value = serde_json::from_str(&await_output).unwrap();
+    assert_eq!(output["running"], serde_json::json!([]));
+    let mut summaries: Vec<String> = output["finished"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|agent| {
+            assert_eq!(agent["status"], "completed");
+            agent["summary"].as_str().unwrap().to_string()
+        })
+        .collect();
+    summaries.sort();
+    assert_eq!(summaries, vec!["module A summary", "module B summary"]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sub_agent_launch_applies_model_and_tool_overrides() {
+    let server = MockServer::start().await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger overrides") && !body.contains("call-launch"),
+        sse_template(sse_function_call(
+            "call-launch",
+            "sub_agent_launch",
+            r#"{"prompt":"explore cheaply","model":"gpt-4.1","sandbox_policy":"read-only","allowed_tools":["shell"]}"#,
+        )),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("explore cheaply") && !body.contains("trigger overrides"),
+        sse_template(sse_assistant_message("explored")),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger overrides") && body.contains("call-launch"),
+        sse_template(sse_assistant_message("done")),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger overrides".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let sub_request = requests
+        .iter()
+        .map(|req| req.body_json::<serde_json::Value>().unwrap())
+        .find(|body| {
+            let body = body.to_string();
+            body.contains("explore cheaply") && !body.contains("trigger overrides")
+        })
+        .expect("sub-agent request");
+    assert_eq!(sub_request["model"], "gpt-4.1");
+    let tool_names: Vec<&str> = sub_request["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tool_names, vec!["shell"]);
+    assert_eq!(
+        function_call_output(&server, "call-launch").await,
+        "explored"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sub_agent_launch_rejects_invalid_overrides() {
+    let server = MockServer::start().await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger invalid") && !body.contains("call-launch-bad"),
+        sse_template(sse_function_call(
+            "call-launch-bad",
+            "sub_agent_launch",
+            r#"{"prompt":"never runs","allowed_tools":["shell","sub_agent_launch"]}"#,
+        )),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger invalid") && body.contains("call-launch-bad"),
+        sse_template(sse_assistant_message("gave up")),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger invalid".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    loop {
+        let ev = codex.next_event().await.expect("event");
+        match ev.msg {
+            EventMsg::SubAgentStarted(_) => panic!("sub-agent should not start"),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    let output = function_call_output(&server, "call-launch-bad").await;
+    assert!(
+        output.starts_with(
+            "invalid sub_agent_launch arguments: unknown tools in allowed_tools: sub_agent_launch;"
+        ),
+        "{output}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn named_agent_supplies_instructions_model_and_tools() {
+    let server = MockServer::start().await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger reviewer") && !body.contains("call-review"),
+        sse_template(sse_function_call(
+            "call-review",
+            "sub_agent_launch",
+            r#"{"prompt":"review the diff","agent":"reviewer"}"#,
+        )),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("review the diff") && !body.contains("trigger reviewer"),
+        sse_template(sse_assistant_message("looks good")),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger reviewer") && body.contains("call-review"),
+        sse_template(sse_assistant_message("done")),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation_with(&server, &cwd, &codex_home, |config| {
+        config.agents.insert(
+            "reviewer".to_string(),
+            AgentConfig {
+                description: Some("Reviews the current diff".to_string()),
+                instructions: Some("You are a careful reviewer.".to_string()),
+                model: Some("gpt-4.1".to_string()),
+                sandbox_mode: None,
+                tools: Some(vec!["shell".to_string()]),
+            },
+        );
+    })
+    .await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger reviewer".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let ev = wait_for_event(&codex, |ev| matches!(ev, EventMsg::SubAgentStarted(_))).await;
+    let EventMsg::SubAgentStarted(SubAgentStartedEvent { label, .. }) = ev else {
+        unreachable!()
+    };
+    assert_eq!(label, "reviewer");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let bodies: Vec<serde_json::Value> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|req| req.body_json::<serde_json::Value>().unwrap())
+        .collect();
+
+    // The main agent is told which agents exist.
+    let launch_tool = bodies[0]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|tool| tool["name"] == "sub_agent_launch")
+        .expect("sub_agent_launch tool");
+    assert!(
+        launch_tool["description"]
+            .as_str()
+            .unwrap()
+            .contains("- reviewer: Reviews the current diff")
+    );
+
+    // The sub-agent runs with the agent's prompt, model and tools.
+    let sub_request = bodies
+        .iter()
+        .find(|body| {
+            let body = body.to_string();
+            body.contains("review the diff") && !body.contains("trigger reviewer")
+        })
+        .expect("sub-agent request");
+    assert_eq!(sub_request["instructions"], "You are a careful reviewer.");
+    assert_eq!(sub_request["model"], "gpt-4.1");
+    let tool_names: Vec<&str> = sub_request["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tool_names, vec!["shell"]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sub_agent_transcript_is_recorded_and_resumable() {
+    let server = MockServer::start().await;
+    mount_single_launch(&server, "echo ok").await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger sub-agent".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let ev = wait_for_event(&codex, |ev| matches!(ev, EventMsg::SubAgentStarted(_))).await;
+    let EventMsg::SubAgentStarted(SubAgentStartedEvent { rollout_path, .. }) = ev else {
+        unreachable!()
+    };
+    let rollout_path = rollout_path.expect("sub-agent rollout path");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    // The child rollout links back to its parent and holds the full transcript.
+    let text = std::fs::read_to_string(&rollout_path).unwrap();
+    let lines: Vec<serde_json::Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0]["type"], "session_meta");
+    assert_eq!(lines[0]["payload"]["sub_agent"]["sub_id"], "call-main-1");
+    assert_eq!(lines[0]["payload"]["sub_agent"]["label"], "sub-agent");
+    let payload_types: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| line["payload"]["type"].as_str())
+        .collect();
+    assert!(payload_types.contains(&"function_call"));
+    assert!(payload_types.contains(&"function_call_output"));
+    assert!(text.contains("run the command"));
+    assert!(text.contains("sub-agent done"));
+
+    // And it can be resumed like any other conversation.
+    let mut config = load_default_config_for_test(&codex_home);
+    config.cwd = cwd.path().to_path_buf();
+    let resumed = ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"))
+        .resume_conversation_from_rollout(
+            config,
+            rollout_path,
+            AuthManager::from_auth_for_testing(CodexAuth::from_api_key("Test API Key")),
+        )
+        .await
+        .expect("resume sub-agent transcript");
+    let initial_messages = resumed
+        .session_configured
+        .initial_messages
+        .expect("replayed messages");
+    assert!(initial_messages.iter().any(|msg| matches!(
+        msg,
+        EventMsg::UserMessage(ev) if ev.message == "run the command"
+    )));
+    assert!(initial_messages.iter().any(|msg| matches!(
+        msg,
+        EventMsg::AgentMessage(ev) if ev.message == "sub-agent done"
+    )));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sub_agent_is_stopped_at_its_turn_limit() {
+    let server = MockServer::start().await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger sub-agent") && !body.contains("call-main-1"),
+        sse_template(sse_function_call(
+            "call-main-1",
+            "sub_agent_launch",
+            r#"{"prompt":"run the command","max_turns":1}"#,
+        )),
+    )
+    .await;
+    // The sub-agent's first turn requests a tool call; its follow-up turn is
+    // never sent because the turn limit trips first.
+    mount_sse(
+        &server,
+        |body| body.contains("run the command") && !body.contains("trigger sub-agent"),
+        sse_template(sse_sub_runs_shell_with_escalated("echo ok", false)),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("trigger sub-agent") && body.contains("call-main-1"),
+        sse_template(sse_assistant_message("all done")),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation(&server, &cwd, &codex_home).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger sub-agent".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let ev = wait_for_event(&codex, |ev| matches!(ev, EventMsg::SubAgentFailed(_))).await;
+    let EventMsg::SubAgentFailed(SubAgentFailedEvent { error, .. }) = ev else {
+        unreachable!()
+    };
+    assert_eq!(error, "sub-agent budget exceeded: turn limit of 1 reached");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    assert_eq!(
+        function_call_output(&server, "call-main-1").await,
+        "sub-agent failed: sub-agent budget exceeded: turn limit of 1 reached"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_budget_covers_sub_agents_and_stops_the_task() {
+    let server = MockServer::start().await;
+    // Turn 1: the main agent launches a sub-agent. Turn 2: the sub-agent runs
+    // a command. Both further turns are refused by the session budget.
+    mount_sse(
+        &server,
+        |body| body.contains("trigger sub-agent") && !body.contains("call-main-1"),
+        sse_template(sse_main_launch()),
+    )
+    .await;
+    mount_sse(
+        &server,
+        |body| body.contains("run the command") && !body.contains("trigger sub-agent"),
+        sse_template(sse_sub_runs_shell_with_escalated("echo ok", false)),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation_with(&server, &cwd, &codex_home, |config| {
+        config.session_budget = Budget {
+            max_turns: Some(2),
+            ..Default::default()
+        };
+    })
+    .await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "trigger sub-agent".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let ev = wait_for_event(&codex, |ev| matches!(ev, EventMsg::SubAgentFailed(_))).await;
+    let EventMsg::SubAgentFailed(SubAgentFailedEvent { error, .. }) = ev else {
+        unreachable!()
+    };
+    assert_eq!(error, "session budget exceeded: turn limit of 2 reached");
+
+    let ev = wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+    let EventMsg::Error(ErrorEvent { message }) = ev else {
+        unreachable!()
+    };
+    assert_eq!(message, "session budget exceeded: turn limit of 2 reached");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_time_limit_cuts_off_a_running_turn() {
+    let server = MockServer::start().await;
+    // The model takes far longer to answer than the session may run.
+    mount_sse(
+        &server,
+        |body| body.contains("take your time"),
+        sse_template(sse_assistant_message("done")).set_delay(Duration::from_secs(60)),
+    )
+    .await;
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let codex = new_full_access_conversation_with(&server, &cwd, &codex_home, |config| {
+        config.session_budget = Budget {
+            max_duration_secs: Some(2),
+            ..Default::default()
+        };
+    })
+    .await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "take your time".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let ev = wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::Error(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    let EventMsg::Error(ErrorEvent { message }) = ev else {
+        unreachable!()
+    };
+    assert_eq!(message, "session budget exceeded: time limit of 2s reached");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
 }
@@ -0,0 +1,158 @@
+#![cfg(unix)]
+#![allow(clippy::expect_used)]
+
+use std::process::Command;
+use std::time::Duration;
+
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::TurnAbortReason;
+use core_test_support::load_default_config_for_test;
+use core_test_support::load_sse_fixture_with_id_from_str;
+use core_test_support::sse_assistant_message;
+use core_test_support::sse_template;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// SSE body with a `shell` call running `script`, followed by `response.completed`.
+fn sse_shell_call(call_id: &str, script: &str) -> String {
+    let arguments = serde_json::json!({"command": ["bash", "-lc", script], "timeout": 2000});
+    let json = r#"[
+  {
+    "type": "response.output_item.done",
+    "item": {
+      "type": "function_call",
+      "id": "fc-__CALL_ID__",
+      "name": "shell",
+      "arguments": "__ARGS__",
+      "call_id": "__CALL_ID__"
+    }
+  },
+  {
+    "type": "response.completed",
+    "response": {"id": "__ID__", "usage": {"input_tokens":0,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":0}}
+  }
+]"#;
+    let json = json
+        .replace("__CALL_ID__", call_id)
+        .replace("__ARGS__", &arguments.to_string().replace('"', "\\\""));
+    load_sse_fixture_with_id_from_str(&json, &format!("resp-{call_id}"))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn interrupted_turn_can_be_undone() {
+    let cwd = TempDir::new().expect("cwd");
+    let git_init = Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(cwd.path())
+        .status()
+        .expect("run git init");
+    assert!(git_init.success());
+    std::fs::write(cwd.path().join("a.txt"), "original\n").expect("write a.txt");
+
+    // The turn changes the worktree through a shell command, then waits on a
+    // model response long enough to be interrupted.
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(|req: &wiremock::Request| !String::from_utf8_lossy(&req.body).contains("call-edit"))
+        .respond_with(sse_template(sse_shell_call(
+            "call-edit",
+            "echo changed > a.txt && echo new > b.txt",
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(|req: &wiremock::Request| String::from_utf8_lossy(&req.body).contains("call-edit"))
+        .respond_with(
+            sse_template(sse_assistant_message("done")).set_delay(Duration::from_secs(60)),
+        )
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().expect("codex home");
+    let mut config = load_default_config_for_test(&codex_home);
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    config.cwd = cwd.path().to_path_buf();
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "edit the files".into(),
+            }],
+        })
+        .await
+        .expect("submit");
+    wait_for_event_with_timeout(
+        &codex,
+        |msg| matches!(msg, EventMsg::ExecCommandEnd(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(
+        std::fs::read_to_string(cwd.path().join("a.txt")).expect("read a.txt"),
+        "changed\n"
+    );
+
+    codex.submit(Op::Interrupt).await.expect("interrupt");
+    let aborted = wait_for_event_with_timeout(
+        &codex,
+        |msg| matches!(msg, EventMsg::TurnAborted(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    let EventMsg::TurnAborted(aborted) = aborted else {
+        unreachable!("waited for TurnAborted");
+    };
+    assert_eq!(aborted.reason, TurnAbortReason::Interrupted);
+
+    codex.submit(Op::UndoTurn).await.expect("undo");
+    let undone = wait_for_event_with_timeout(
+        &codex,
+        |msg| matches!(msg, EventMsg::TurnUndone(_) | EventMsg::Error(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    let EventMsg::TurnUndone(undone) = undone else {
+        panic!("expected the interrupted turn to be undone, got {undone:?}");
+    };
+    let mut paths = undone.paths;
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![cwd.path().join("a.txt"), cwd.path().join("b.txt")]
+    );
+    assert_eq!(
+        std::fs::read_to_string(cwd.path().join("a.txt")).expect("read a.txt"),
+        "original\n"
+    );
+    assert!(!cwd.path().join("b.txt").exists());
+}
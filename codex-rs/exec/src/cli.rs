@@ -6,6 +6,10 @@ use std::path::PathBuf;
 #[derive(Parser, Debug)]
 #[command(version)]
 pub struct Cli {
+    /// Action to perform. If omitted, runs a new non-interactive session.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Optional image(s) to attach to the initial prompt.
     #[arg(long = "image", short = 'i', value_name = "FILE", value_delimiter = ',', num_args = 1..)]
     pub images: Vec<PathBuf>,
@@ -63,6 +67,14 @@ pub struct Cli {
     #[arg(long = "output-last-message")]
     pub last_message_file: Option<PathBuf>,
 
+    /// JSON Schema file the final message must match. The validated JSON is
+    /// written to `--output-last-message` and, with `--quiet`, to stdout; the
+    /// run fails if the model cannot produce a match. Only `type`, `enum`,
+    /// `const`, `anyOf`, `oneOf`, `properties`, `required`,
+    /// `additionalProperties` and `items` are supported.
+    #[arg(long = "output-schema", value_name = "FILE")]
+    pub output_schema_file: Option<PathBuf>,
+
     /// Only print the model's final output. Suppresses config summary and
     /// intermediate logs (reasoning, tool calls, diffs, etc.).
     #[arg(long = "quiet", short = 'q', default_value_t = false)]
@@ -88,6 +100,51 @@ pub struct Cli {
     pub prompt: Option<String>,
 }
 
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Continue a recorded session with a follow-up prompt. Options such as
+    /// `--json` or `-m` go before `resume`.
+    Resume(ResumeArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ResumeArgs {
+    /// Id of the session to resume, as printed by `--session-summary`.
+    #[arg(value_name = "SESSION_ID")]
+    pub session_id: Option<String>,
+
+    /// Resume the most recent session instead of naming one.
+    #[arg(long = "last", default_value_t = false)]
+    pub last: bool,
+
+    /// Follow-up prompt. If not provided as an argument (or if `-` is used),
+    /// it is read from stdin.
+    #[arg(value_name = "PROMPT")]
+    pub prompt: Option<String>,
+}
+
+/// Which recorded session `codex exec resume` continues.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResumeTarget {
+    Last,
+    Id(String),
+}
+
+impl ResumeArgs {
+    /// Splits the positional arguments into the session to resume and the
+    /// prompt. With `--last` the only positional argument is the prompt.
+    pub fn into_target_and_prompt(self) -> Result<(ResumeTarget, Option<String>), String> {
+        match (self.last, self.session_id, self.prompt) {
+            (true, Some(_), Some(_)) => {
+                Err("`--last` cannot be combined with a session id".to_string())
+            }
+            (true, prompt, None) | (true, None, prompt) => Ok((ResumeTarget::Last, prompt)),
+            (false, Some(id), prompt) => Ok((ResumeTarget::Id(id), prompt)),
+            (false, None, _) => Err("specify a session id or `--last`".to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum Color {
@@ -15,6 +15,7 @@ use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpToolApprovalRequestEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
@@ -24,6 +25,7 @@ use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::TurnUndoneEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
 use codex_protocol::num_format::format_with_separators;
@@ -80,6 +82,10 @@ pub(crate) struct EventProcessorWithHumanOutput {
     /// When true, suppresses all intermediate logs and config printing,
     /// emitting only the final assistant output to stdout.
     result_only: bool,
+    /// Whether the final message must match `--output-schema`. In quiet mode
+    /// nothing is streamed then; the validated value is printed once the task
+    /// completes.
+    structured_output: bool,
 }
 
 impl EventProcessorWithHumanOutput {
@@ -119,6 +125,7 @@ impl EventProcessorWithHumanOutput {
                 rollout_path: None,
                 model: None,
                 result_only,
+                structured_output: config.output_schema.is_some(),
             }
         } else {
             Self {
@@ -144,6 +151,7 @@ impl EventProcessorWithHumanOutput {
                 rollout_path: None,
                 model: None,
                 result_only,
+                structured_output: config.output_schema.is_some(),
             }
         }
     }
@@ -210,11 +218,22 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             // Only emit the final assistant output; suppress all other logs.
             match msg {
                 EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                    if self.structured_output
+                        && let Some(message) = &last_agent_message
+                    {
+                        println!("{message}");
+                    }
                     if let Some(output_file) = self.last_message_path.as_deref() {
                         handle_last_message(last_agent_message.as_deref(), output_file);
                     }
                     return CodexStatus::InitiateShutdown;
                 }
+                EventMsg::AgentMessageDelta(_) | EventMsg::AgentMessage(_)
+                    if self.structured_output =>
+                {
+                    // Only the validated value is printed, on completion.
+                    return CodexStatus::Running;
+                }
                 EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
                     // Stream the answer without any headers.
                     print!("{delta}");
@@ -236,6 +255,13 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     self.maybe_emit_summary();
                     return CodexStatus::Shutdown;
                 }
+                EventMsg::SessionConfigured(ev) => {
+                    // Still needed for the end-of-run summary.
+                    self.session_id = Some(ev.session_id);
+                    self.rollout_path = Some(ev.rollout_path);
+                    self.model = Some(ev.model);
+                    return CodexStatus::Running;
+                }
                 _ => {
                     // Ignore all other events in quiet mode.
                     return CodexStatus::Running;
@@ -573,6 +599,19 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::McpToolApprovalRequest(McpToolApprovalRequestEvent {
+                call_id: _,
+                invocation,
+                reason: _,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} {} {}",
+                    "tool".style(self.magenta),
+                    format_mcp_invocation(&invocation).style(self.bold),
+                    "needs approval; declined".style(self.red),
+                );
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     if !self.reasoning_started {
@@ -624,9 +663,15 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::McpListToolsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::McpListResourcesResponse(_) => {
+                // Currently ignored in exec output.
+            }
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::GetMcpPromptResponse(_) => {
+                // Currently ignored in exec output.
+            }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
                 TurnAbortReason::Interrupted => {
                     ts_println!(self, "task interrupted");
@@ -635,6 +680,16 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     ts_println!(self, "task aborted: replaced by a new task");
                 }
             },
+            EventMsg::TurnUndone(TurnUndoneEvent { paths }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    "turn undone".style(self.italic).style(self.magenta)
+                );
+                for path in paths {
+                    ts_println!(self, "{}", path.display().style(self.dimmed));
+                }
+            }
             EventMsg::ShutdownComplete => {
                 self.maybe_emit_summary();
                 return CodexStatus::Shutdown;
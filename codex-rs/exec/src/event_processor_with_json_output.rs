@@ -2,25 +2,85 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use codex_core::config::Config;
+use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::AgentReasoningEvent;
+use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecCommandBeginEvent;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::FileChange;
+use codex_core::protocol::McpToolCallBeginEvent;
+use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::PatchApplyBeginEvent;
+use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::SessionConfiguredEvent;
+use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
-use codex_protocol::mcp_protocol::ConversationId;
-use serde_json::json;
+use codex_core::protocol::TokenCountEvent;
+use codex_core::protocol::TurnAbortReason;
+use codex_core::protocol::WebSearchEndEvent;
+use codex_protocol::exec_events::AgentMessageItem;
+use codex_protocol::exec_events::CommandExecutionItem;
+use codex_protocol::exec_events::EXEC_EVENTS_SCHEMA_VERSION;
+use codex_protocol::exec_events::ErrorItem;
+use codex_protocol::exec_events::FileChangeItem;
+use codex_protocol::exec_events::FileUpdateChange;
+use codex_protocol::exec_events::ItemCompletedEvent;
+use codex_protocol::exec_events::ItemStartedEvent;
+use codex_protocol::exec_events::ItemStatus;
+use codex_protocol::exec_events::ItemUpdatedEvent;
+use codex_protocol::exec_events::McpToolCallItem;
+use codex_protocol::exec_events::PatchChangeKind;
+use codex_protocol::exec_events::ReasoningItem;
+use codex_protocol::exec_events::SchemaEvent;
+use codex_protocol::exec_events::SessionSummaryEvent;
+use codex_protocol::exec_events::ThreadError;
+use codex_protocol::exec_events::ThreadEvent;
+use codex_protocol::exec_events::ThreadItem;
+use codex_protocol::exec_events::ThreadItemDetails;
+use codex_protocol::exec_events::ThreadStartedEvent;
+use codex_protocol::exec_events::TodoItem;
+use codex_protocol::exec_events::TodoListItem;
+use codex_protocol::exec_events::TurnCompletedEvent;
+use codex_protocol::exec_events::TurnFailedEvent;
+use codex_protocol::exec_events::TurnStartedEvent;
+use codex_protocol::exec_events::Usage;
+use codex_protocol::exec_events::WebSearchItem;
+use codex_protocol::plan_tool::StepStatus;
+use codex_protocol::plan_tool::UpdatePlanArgs;
+use shlex::try_join;
 
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
 use crate::event_processor::handle_last_message;
-use codex_common::create_config_summary_entries;
 
+/// Prints the versioned event format from
+/// [`codex_protocol::exec_events`], one [`ThreadEvent`] per line.
 pub(crate) struct EventProcessorWithJsonOutput {
     last_message_path: Option<PathBuf>,
     want_summary: bool,
     summary_file: Option<PathBuf>,
+    schema_printed: bool,
     // cached from SessionConfigured
-    session_id: Option<ConversationId>,
+    session_id: Option<String>,
     rollout_path: Option<PathBuf>,
     model: Option<String>,
+    next_turn: u64,
+    next_item: u64,
+    turn: Option<TurnState>,
+    /// Items that started and have not completed yet, by call id.
+    running_commands: HashMap<String, (String, String)>,
+    running_patches: HashMap<String, (String, Vec<FileUpdateChange>)>,
+    running_mcp_calls: HashMap<String, (String, String, String)>,
+}
+
+#[derive(Default)]
+struct TurnState {
+    id: String,
+    usage: Option<Usage>,
+    error: Option<String>,
+    todo_list: Option<ThreadItem>,
 }
 
 impl EventProcessorWithJsonOutput {
@@ -33,107 +93,392 @@ impl EventProcessorWithJsonOutput {
             last_message_path,
             want_summary,
             summary_file,
+            schema_printed: false,
             session_id: None,
             rollout_path: None,
             model: None,
+            next_turn: 0,
+            next_item: 0,
+            turn: None,
+            running_commands: HashMap::new(),
+            running_patches: HashMap::new(),
+            running_mcp_calls: HashMap::new(),
         }
     }
-}
 
-impl EventProcessor for EventProcessorWithJsonOutput {
-    fn print_config_summary(&mut self, config: &Config, prompt: &str) {
-        let entries = create_config_summary_entries(config)
-            .into_iter()
-            .map(|(key, value)| (key.to_string(), value))
-            .collect::<HashMap<String, String>>();
-        #[expect(clippy::expect_used)]
-        let config_json =
-            serde_json::to_string(&entries).expect("Failed to serialize config summary to JSON");
-        println!("{config_json}");
-
-        let prompt_json = json!({
-            "prompt": prompt,
-        });
-        println!("{prompt_json}");
+    fn print(&mut self, events: Vec<ThreadEvent>) {
+        for event in events {
+            if !self.schema_printed {
+                self.schema_printed = true;
+                print_event(&schema_event());
+            }
+            print_event(&event);
+        }
     }
 
-    fn process_event(&mut self, event: Event) -> CodexStatus {
-        let printable = event.clone();
-        match event.msg {
-            EventMsg::AgentMessageDelta(_) | EventMsg::AgentReasoningDelta(_) => {
-                // Suppress streaming events in JSON mode.
-                CodexStatus::Running
+    fn next_item_id(&mut self) -> String {
+        let id = format!("item_{}", self.next_item);
+        self.next_item += 1;
+        id
+    }
+
+    fn new_item(&mut self, details: ThreadItemDetails) -> ThreadItem {
+        ThreadItem {
+            id: self.next_item_id(),
+            details,
+        }
+    }
+
+    /// Translates an internal event into the public events it stands for.
+    pub(crate) fn collect_thread_events(&mut self, event: &Event) -> Vec<ThreadEvent> {
+        match &event.msg {
+            EventMsg::SessionConfigured(ev) => self.handle_session_configured(ev),
+            EventMsg::TaskStarted(_) => self.start_turn(),
+            EventMsg::TaskComplete(_) => self.finish_turn(),
+            EventMsg::TurnAborted(ev) => {
+                if let Some(turn) = self.turn.as_mut() {
+                    turn.error = Some(turn_aborted_message(&ev.reason).to_string());
+                }
+                self.finish_turn()
             }
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
-                if let Some(output_file) = self.last_message_path.as_deref() {
-                    handle_last_message(last_agent_message.as_deref(), output_file);
+            EventMsg::TokenCount(TokenCountEvent { info: Some(info) }) => {
+                if let Some(turn) = self.turn.as_mut() {
+                    let last = &info.last_token_usage;
+                    let usage = turn.usage.get_or_insert_with(Usage::default);
+                    usage.input_tokens += last.input_tokens;
+                    usage.cached_input_tokens += last.cached_input_tokens;
+                    usage.output_tokens += last.output_tokens;
                 }
-                CodexStatus::InitiateShutdown
+                Vec::new()
             }
-            EventMsg::ShutdownComplete => {
-                self.maybe_emit_summary();
-                CodexStatus::Shutdown
+            EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                let item = self.new_item(ThreadItemDetails::AgentMessage(AgentMessageItem {
+                    text: message.clone(),
+                }));
+                vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
             }
-            EventMsg::SessionConfigured(ev) => {
-                self.session_id = Some(ev.session_id);
-                self.rollout_path = Some(ev.rollout_path);
-                self.model = Some(ev.model);
-                // Print the event now (to preserve the original id) and continue.
-                if let Ok(line) = serde_json::to_string(&printable) {
-                    println!("{line}");
-                }
-                CodexStatus::Running
+            EventMsg::AgentReasoning(AgentReasoningEvent { text }) => {
+                let item = self.new_item(ThreadItemDetails::Reasoning(ReasoningItem {
+                    text: text.clone(),
+                }));
+                vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
             }
-            _ => {
-                if let Ok(line) = serde_json::to_string(&printable) {
-                    println!("{line}");
-                }
-                CodexStatus::Running
+            EventMsg::ExecCommandBegin(ev) => self.handle_exec_command_begin(ev),
+            EventMsg::ExecCommandEnd(ev) => self.handle_exec_command_end(ev),
+            EventMsg::PatchApplyBegin(ev) => self.handle_patch_apply_begin(ev),
+            EventMsg::PatchApplyEnd(ev) => self.handle_patch_apply_end(ev),
+            EventMsg::McpToolCallBegin(ev) => self.handle_mcp_tool_call_begin(ev),
+            EventMsg::McpToolCallEnd(ev) => self.handle_mcp_tool_call_end(ev),
+            EventMsg::WebSearchEnd(WebSearchEndEvent { query, .. }) => {
+                let item = self.new_item(ThreadItemDetails::WebSearch(WebSearchItem {
+                    query: query.clone(),
+                }));
+                vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
             }
+            EventMsg::PlanUpdate(args) => self.handle_plan_update(args),
+            EventMsg::StreamError(StreamErrorEvent { message }) => {
+                let item = self.new_item(ThreadItemDetails::Error(ErrorItem {
+                    message: message.clone(),
+                }));
+                vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+            }
+            EventMsg::Error(ErrorEvent { message }) => match self.turn.as_mut() {
+                // Reported as `turn.failed` once the turn is over.
+                Some(turn) => {
+                    turn.error = Some(message.clone());
+                    Vec::new()
+                }
+                None => {
+                    let item = self.new_item(ThreadItemDetails::Error(ErrorItem {
+                        message: message.clone(),
+                    }));
+                    vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+                }
+            },
+            EventMsg::ShutdownComplete => self.session_summary().into_iter().collect(),
+            _ => Vec::new(),
         }
     }
-}
 
-impl EventProcessorWithJsonOutput {
-    fn maybe_emit_summary(&self) {
-        if !self.want_summary {
-            return;
+    fn handle_session_configured(&mut self, ev: &SessionConfiguredEvent) -> Vec<ThreadEvent> {
+        let thread_id = ev.session_id.to_string();
+        self.session_id = Some(thread_id.clone());
+        self.rollout_path = Some(ev.rollout_path.clone());
+        self.model = Some(ev.model.clone());
+        vec![ThreadEvent::ThreadStarted(ThreadStartedEvent { thread_id })]
+    }
+
+    fn start_turn(&mut self) -> Vec<ThreadEvent> {
+        let turn_id = format!("turn_{}", self.next_turn);
+        self.next_turn += 1;
+        self.turn = Some(TurnState {
+            id: turn_id.clone(),
+            ..TurnState::default()
+        });
+        vec![ThreadEvent::TurnStarted(TurnStartedEvent { turn_id })]
+    }
+
+    fn finish_turn(&mut self) -> Vec<ThreadEvent> {
+        let Some(turn) = self.turn.take() else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+        if let Some(item) = turn.todo_list {
+            events.push(ThreadEvent::ItemCompleted(ItemCompletedEvent { item }));
         }
-        let Some(id) = self.session_id.as_ref() else {
-            return;
+        events.push(match turn.error {
+            Some(message) => ThreadEvent::TurnFailed(TurnFailedEvent {
+                turn_id: turn.id,
+                error: ThreadError { message },
+            }),
+            None => ThreadEvent::TurnCompleted(TurnCompletedEvent {
+                turn_id: turn.id,
+                usage: turn.usage,
+            }),
+        });
+        events
+    }
+
+    fn handle_exec_command_begin(&mut self, ev: &ExecCommandBeginEvent) -> Vec<ThreadEvent> {
+        let command = try_join(ev.command.iter().map(String::as_str))
+            .unwrap_or_else(|_| ev.command.join(" "));
+        let item = self.new_item(ThreadItemDetails::CommandExecution(CommandExecutionItem {
+            command: command.clone(),
+            aggregated_output: String::new(),
+            exit_code: None,
+            status: ItemStatus::InProgress,
+        }));
+        self.running_commands
+            .insert(ev.call_id.clone(), (item.id.clone(), command));
+        vec![ThreadEvent::ItemStarted(ItemStartedEvent { item })]
+    }
+
+    fn handle_exec_command_end(&mut self, ev: &ExecCommandEndEvent) -> Vec<ThreadEvent> {
+        let Some((id, command)) = self.running_commands.remove(&ev.call_id) else {
+            return Vec::new();
         };
-        let Some(path) = self.rollout_path.as_ref() else {
-            return;
+        let status = if ev.exit_code == 0 {
+            ItemStatus::Completed
+        } else {
+            ItemStatus::Failed
+        };
+        let item = ThreadItem {
+            id,
+            details: ThreadItemDetails::CommandExecution(CommandExecutionItem {
+                command,
+                aggregated_output: ev.aggregated_output.clone(),
+                exit_code: Some(ev.exit_code),
+                status,
+            }),
         };
-        let model = self.model.clone().unwrap_or_default();
+        vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+    }
 
-        let obj = json!({
-            "type": "session_summary",
-            "session_id": id,
-            "rollout_path": format!("{}", path.display()),
-            "model": model,
-        });
-        if let Ok(line) = serde_json::to_string(&obj) {
-            println!("{line}");
+    fn handle_patch_apply_begin(&mut self, ev: &PatchApplyBeginEvent) -> Vec<ThreadEvent> {
+        let mut changes: Vec<FileUpdateChange> = ev
+            .changes
+            .iter()
+            .map(|(path, change)| FileUpdateChange {
+                path: path.display().to_string(),
+                kind: match change {
+                    FileChange::Add { .. } => PatchChangeKind::Add,
+                    FileChange::Delete { .. } => PatchChangeKind::Delete,
+                    FileChange::Update { .. } => PatchChangeKind::Update,
+                },
+            })
+            .collect();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        let item = self.new_item(ThreadItemDetails::FileChange(FileChangeItem {
+            changes: changes.clone(),
+            status: ItemStatus::InProgress,
+        }));
+        self.running_patches
+            .insert(ev.call_id.clone(), (item.id.clone(), changes));
+        vec![ThreadEvent::ItemStarted(ItemStartedEvent { item })]
+    }
+
+    fn handle_patch_apply_end(&mut self, ev: &PatchApplyEndEvent) -> Vec<ThreadEvent> {
+        let Some((id, changes)) = self.running_patches.remove(&ev.call_id) else {
+            return Vec::new();
+        };
+        let status = if ev.success {
+            ItemStatus::Completed
+        } else {
+            ItemStatus::Failed
+        };
+        let item = ThreadItem {
+            id,
+            details: ThreadItemDetails::FileChange(FileChangeItem { changes, status }),
+        };
+        vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+    }
+
+    fn handle_mcp_tool_call_begin(&mut self, ev: &McpToolCallBeginEvent) -> Vec<ThreadEvent> {
+        let server = ev.invocation.server.clone();
+        let tool = ev.invocation.tool.clone();
+        let item = self.new_item(ThreadItemDetails::McpToolCall(McpToolCallItem {
+            server: server.clone(),
+            tool: tool.clone(),
+            status: ItemStatus::InProgress,
+        }));
+        self.running_mcp_calls
+            .insert(ev.call_id.clone(), (item.id.clone(), server, tool));
+        vec![ThreadEvent::ItemStarted(ItemStartedEvent { item })]
+    }
+
+    fn handle_mcp_tool_call_end(&mut self, ev: &McpToolCallEndEvent) -> Vec<ThreadEvent> {
+        let Some((id, server, tool)) = self.running_mcp_calls.remove(&ev.call_id) else {
+            return Vec::new();
+        };
+        let status = if ev.is_success() {
+            ItemStatus::Completed
+        } else {
+            ItemStatus::Failed
+        };
+        let item = ThreadItem {
+            id,
+            details: ThreadItemDetails::McpToolCall(McpToolCallItem {
+                server,
+                tool,
+                status,
+            }),
+        };
+        vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+    }
+
+    /// The plan is one todo list item per turn: started on the first update,
+    /// updated on later ones and completed with the turn.
+    fn handle_plan_update(&mut self, args: &UpdatePlanArgs) -> Vec<ThreadEvent> {
+        let items = args
+            .plan
+            .iter()
+            .map(|step| TodoItem {
+                text: step.step.clone(),
+                completed: matches!(step.status, StepStatus::Completed),
+            })
+            .collect();
+        let details = ThreadItemDetails::TodoList(TodoListItem { items });
+        let existing = self
+            .turn
+            .as_ref()
+            .and_then(|turn| turn.todo_list.as_ref())
+            .map(|item| item.id.clone());
+        let is_new = existing.is_none();
+        let item = match existing {
+            Some(id) => ThreadItem { id, details },
+            None => self.new_item(details),
+        };
+        if let Some(turn) = self.turn.as_mut() {
+            turn.todo_list = Some(item.clone());
+        }
+        if is_new {
+            vec![ThreadEvent::ItemStarted(ItemStartedEvent { item })]
+        } else {
+            vec![ThreadEvent::ItemUpdated(ItemUpdatedEvent { item })]
+        }
+    }
+
+    fn session_summary(&self) -> Option<ThreadEvent> {
+        if !self.want_summary {
+            return None;
         }
+        Some(ThreadEvent::SessionSummary(SessionSummaryEvent {
+            session_id: self.session_id.clone()?,
+            rollout_path: self.rollout_path.as_ref()?.display().to_string(),
+            model: self.model.clone().unwrap_or_default(),
+        }))
+    }
+
+    fn maybe_write_summary_file(&self, summary: &ThreadEvent) {
         if let Some(file) = self.summary_file.as_ref()
-            && let Ok(s) = serde_json::to_string_pretty(&obj)
+            && let Ok(s) = serde_json::to_string_pretty(summary)
         {
             let _ = std::fs::write(file, s);
         }
     }
 }
 
+fn schema_event() -> ThreadEvent {
+    ThreadEvent::Schema(SchemaEvent {
+        version: EXEC_EVENTS_SCHEMA_VERSION,
+    })
+}
+
+/// Error message of the `turn.failed` event for an aborted turn. These
+/// strings are part of the format, so they are spelled out rather than
+/// derived from the internal enum.
+fn turn_aborted_message(reason: &TurnAbortReason) -> &'static str {
+    match reason {
+        TurnAbortReason::Interrupted => "turn aborted: interrupted",
+        TurnAbortReason::Replaced => "turn aborted: replaced",
+    }
+}
+
+fn print_event(event: &ThreadEvent) {
+    if let Ok(line) = serde_json::to_string(event) {
+        println!("{line}");
+    }
+}
+
+impl EventProcessor for EventProcessorWithJsonOutput {
+    fn print_config_summary(&mut self, _config: &Config, _prompt: &str) {
+        // The config summary is not part of the event format; only make sure
+        // the schema line comes first.
+        if !self.schema_printed {
+            self.schema_printed = true;
+            print_event(&schema_event());
+        }
+    }
+
+    fn process_event(&mut self, event: Event) -> CodexStatus {
+        let events = self.collect_thread_events(&event);
+        if let Some(summary @ ThreadEvent::SessionSummary(_)) = events.last() {
+            self.maybe_write_summary_file(summary);
+        }
+        self.print(events);
+        match event.msg {
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                if let Some(output_file) = self.last_message_path.as_deref() {
+                    handle_last_message(last_agent_message.as_deref(), output_file);
+                }
+                CodexStatus::InitiateShutdown
+            }
+            EventMsg::ShutdownComplete => CodexStatus::Shutdown,
+            _ => CodexStatus::Running,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::EventProcessorWithJsonOutput;
     use crate::event_processor::EventProcessor;
+    use codex_core::protocol::AgentMessageEvent;
+    use codex_core::protocol::AgentReasoningEvent;
+    use codex_core::protocol::ErrorEvent;
     use codex_core::protocol::Event;
     use codex_core::protocol::EventMsg;
+    use codex_core::protocol::ExecCommandBeginEvent;
+    use codex_core::protocol::ExecCommandEndEvent;
+    use codex_core::protocol::FileChange;
+    use codex_core::protocol::PatchApplyBeginEvent;
+    use codex_core::protocol::PatchApplyEndEvent;
     use codex_core::protocol::SessionConfiguredEvent;
     use codex_core::protocol::TaskCompleteEvent;
+    use codex_core::protocol::TaskStartedEvent;
+    use codex_core::protocol::TokenCountEvent;
+    use codex_core::protocol::TokenUsage;
+    use codex_core::protocol::TokenUsageInfo;
+    use codex_core::protocol::TurnAbortReason;
+    use codex_core::protocol::TurnAbortedEvent;
     use codex_protocol::mcp_protocol::ConversationId;
+    use codex_protocol::plan_tool::PlanItemArg;
+    use codex_protocol::plan_tool::StepStatus;
+    use codex_protocol::plan_tool::UpdatePlanArgs;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn writes_summary_file_on_shutdown() {
@@ -181,4 +526,190 @@ mod tests {
         );
         assert!(v.get("session_id").is_some(), "missing session_id");
     }
+
+    fn event(msg: EventMsg) -> Event {
+        Event {
+            id: "1".into(),
+            msg,
+        }
+    }
+
+    fn plan(steps: &[(&str, StepStatus)]) -> EventMsg {
+        EventMsg::PlanUpdate(UpdatePlanArgs {
+            explanation: None,
+            plan: steps
+                .iter()
+                .map(|(step, status)| PlanItemArg {
+                    step: step.to_string(),
+                    status: status.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    fn token_count(input_tokens: u64, output_tokens: u64) -> EventMsg {
+        let usage = TokenUsage {
+            input_tokens,
+            cached_input_tokens: 1,
+            output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + output_tokens,
+        };
+        EventMsg::TokenCount(TokenCountEvent {
+            info: Some(TokenUsageInfo {
+                total_token_usage: usage.clone(),
+                last_token_usage: usage,
+                model_context_window: None,
+            }),
+        })
+    }
+
+    /// Version 1 output for a run with a completed, a failed, an interrupted
+    /// and a replaced turn.
+    const V1_LINES: &[&str] = &[
+        r#"{"type":"thread.started","thread_id":"67e55044-10b1-426f-9247-bb680e5fe0c8"}"#,
+        r#"{"type":"turn.started","turn_id":"turn_0"}"#,
+        r#"{"type":"item.started","item":{"id":"item_0","item_type":"todo_list","items":[{"text":"inspect","completed":false},{"text":"fix","completed":false}]}}"#,
+        r#"{"type":"item.completed","item":{"id":"item_1","item_type":"reasoning","text":"Looking around"}}"#,
+        r#"{"type":"item.started","item":{"id":"item_2","item_type":"command_execution","command":"ls 'my dir'","aggregated_output":"","exit_code":null,"status":"in_progress"}}"#,
+        r#"{"type":"item.completed","item":{"id":"item_2","item_type":"command_execution","command":"ls 'my dir'","aggregated_output":"a.txt\n","exit_code":0,"status":"completed"}}"#,
+        r#"{"type":"item.started","item":{"id":"item_3","item_type":"file_change","changes":[{"path":"a.txt","kind":"add"}],"status":"in_progress"}}"#,
+        r#"{"type":"item.completed","item":{"id":"item_3","item_type":"file_change","changes":[{"path":"a.txt","kind":"add"}],"status":"failed"}}"#,
+        r#"{"type":"item.updated","item":{"id":"item_0","item_type":"todo_list","items":[{"text":"inspect","completed":true},{"text":"fix","completed":true}]}}"#,
+        r#"{"type":"item.completed","item":{"id":"item_4","item_type":"agent_message","text":"Done."}}"#,
+        r#"{"type":"item.completed","item":{"id":"item_0","item_type":"todo_list","items":[{"text":"inspect","completed":true},{"text":"fix","completed":true}]}}"#,
+        r#"{"type":"turn.completed","turn_id":"turn_0","usage":{"input_tokens":30,"cached_input_tokens":2,"output_tokens":7}}"#,
+        r#"{"type":"turn.started","turn_id":"turn_1"}"#,
+        r#"{"type":"turn.failed","turn_id":"turn_1","error":{"message":"stream disconnected"}}"#,
+        r#"{"type":"turn.started","turn_id":"turn_2"}"#,
+        r#"{"type":"turn.failed","turn_id":"turn_2","error":{"message":"turn aborted: interrupted"}}"#,
+        r#"{"type":"turn.started","turn_id":"turn_3"}"#,
+        r#"{"type":"turn.failed","turn_id":"turn_3","error":{"message":"turn aborted: replaced"}}"#,
+        r#"{"type":"session_summary","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","rollout_path":"/tmp/rollout.jsonl","model":"gpt-5"}"#,
+    ];
+
+    /// Pins the version 1 output format. If this test has to change, so does
+    /// `EXEC_EVENTS_SCHEMA_VERSION`.
+    #[test]
+    fn pins_v1_event_format() {
+        let session_id: ConversationId = "67e55044-10b1-426f-9247-bb680e5fe0c8"
+            .parse()
+            .expect("conversation id");
+        let msgs = vec![
+            EventMsg::SessionConfigured(SessionConfiguredEvent {
+                session_id,
+                model: "gpt-5".to_string(),
+                reasoning_effort: codex_core::protocol_config_types::ReasoningEffort::Medium,
+                history_log_id: 0,
+                history_entry_count: 0,
+                initial_messages: None,
+                rollout_path: PathBuf::from("/tmp/rollout.jsonl"),
+            }),
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+            plan(&[
+                ("inspect", StepStatus::InProgress),
+                ("fix", StepStatus::Pending),
+            ]),
+            EventMsg::AgentReasoning(AgentReasoningEvent {
+                text: "Looking around".to_string(),
+            }),
+            EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id: "call_1".to_string(),
+                command: vec!["ls".to_string(), "my dir".to_string()],
+                cwd: PathBuf::from("/repo"),
+                parsed_cmd: Vec::new(),
+            }),
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id: "call_1".to_string(),
+                stdout: "a.txt\n".to_string(),
+                stderr: String::new(),
+                aggregated_output: "a.txt\n".to_string(),
+                exit_code: 0,
+                duration: Duration::from_millis(5),
+                formatted_output: "a.txt\n".to_string(),
+            }),
+            EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+                call_id: "call_2".to_string(),
+                auto_approved: true,
+                changes: HashMap::from([(
+                    PathBuf::from("a.txt"),
+                    FileChange::Add {
+                        content: "a\n".to_string(),
+                    },
+                )]),
+            }),
+            EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+                call_id: "call_2".to_string(),
+                stdout: String::new(),
+                stderr: "conflict".to_string(),
+                success: false,
+            }),
+            token_count(10, 5),
+            plan(&[
+                ("inspect", StepStatus::Completed),
+                ("fix", StepStatus::Completed),
+            ]),
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: "Done.".to_string(),
+            }),
+            token_count(20, 2),
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: Some("Done.".to_string()),
+            }),
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+            EventMsg::Error(ErrorEvent {
+                message: "stream disconnected".to_string(),
+            }),
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: None,
+            }),
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+            EventMsg::TurnAborted(TurnAbortedEvent {
+                reason: TurnAbortReason::Interrupted,
+            }),
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+            EventMsg::TurnAborted(TurnAbortedEvent {
+                reason: TurnAbortReason::Replaced,
+            }),
+            EventMsg::ShutdownComplete,
+        ];
+
+        let mut proc = EventProcessorWithJsonOutput::new(None, true, None);
+        let lines: Vec<String> = msgs
+            .into_iter()
+            .flat_map(|msg| proc.collect_thread_events(&event(msg)))
+            .map(|event| serde_json::to_string(&event).expect("serialize"))
+            .collect();
+
+        assert_eq!(lines, V1_LINES);
+    }
+
+    /// The JSON Schema written by `codex generate-ts` accepts the pinned
+    /// format, including the leading schema line.
+    #[test]
+    fn exec_events_schema_accepts_v1_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = codex_protocol_ts::generate_exec_events_json_schema(dir.path())
+            .expect("generate schema");
+        let schema: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).expect("read schema"))
+                .expect("parse schema");
+        let validator = jsonschema::validator_for(&schema).expect("compile schema");
+
+        let schema_line = serde_json::to_string(&super::schema_event()).expect("serialize");
+        for line in std::iter::once(schema_line.as_str()).chain(V1_LINES.iter().copied()) {
+            let value: serde_json::Value = serde_json::from_str(line).expect("parse line");
+            if let Err(err) = validator.validate(&value) {
+                panic!("{line} does not match the schema: {err}");
+            }
+        }
+    }
 }
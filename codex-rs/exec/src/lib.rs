@@ -3,26 +3,37 @@ mod event_processor;
 mod event_processor_with_human_output;
 mod event_processor_with_json_output;
 
+use anyhow::Context;
 use std::io::IsTerminal;
 use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
 
 pub use cli::Cli;
+use cli::Command;
+use cli::ResumeTarget;
 use codex_core::AuthManager;
 use codex_core::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
+use codex_core::RolloutRecorder;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
 use codex_core::git_info::get_git_repo_root;
+use codex_core::output_schema::check_supported_schema;
+use codex_core::output_schema::validate_output;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::RolloutItem;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
+use codex_protocol::mcp_protocol::ConversationId;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
 use event_processor_with_json_output::EventProcessorWithJsonOutput;
 use tracing::debug;
@@ -35,6 +46,7 @@ use crate::event_processor::EventProcessor;
 
 pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
     let Cli {
+        command,
         images,
         model: model_cli_arg,
         oss,
@@ -45,6 +57,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         skip_git_repo_check,
         color,
         last_message_file,
+        output_schema_file,
         json: json_mode,
         quiet,
         sandbox_mode: sandbox_mode_cli_arg,
@@ -55,6 +68,17 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         session_summary_file,
     } = cli;
 
+    let (resume_target, prompt) = match command {
+        Some(Command::Resume(args)) => match args.into_target_and_prompt() {
+            Ok((target, prompt)) => (Some(target), prompt),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        None => (None, prompt),
+    };
+
     // Determine the prompt based on CLI arg and/or stdin.
     let prompt = match prompt {
         Some(p) if p != "-" => p,
@@ -121,6 +145,18 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         sandbox_mode_cli_arg.map(Into::<SandboxMode>::into)
     };
 
+    // A resumed session keeps the working directory and model it last ran
+    // with unless they are set explicitly for this run.
+    let resumed = match resume_target {
+        Some(target) => Some(resolve_resumed_session(&find_codex_home()?, target).await?),
+        None => None,
+    };
+    let model_configured = config_profile.is_some()
+        || config_overrides.raw_overrides.iter().any(|kv| {
+            kv.split_once('=')
+                .is_some_and(|(key, _)| key.trim() == "model")
+        });
+
     // When using `--oss`, let the bootstrapper pick the model (defaulting to
     // gpt-oss:20b) and ensure it is present locally. Also, force the built‑in
     // `oss` model provider.
@@ -128,9 +164,12 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         Some(model)
     } else if oss {
         Some(DEFAULT_OSS_MODEL.to_owned())
+    } else if !model_configured {
+        resumed.as_ref().and_then(|resumed| resumed.model.clone())
     } else {
         None // No model specified, will use the default.
     };
+    let cwd = cwd.or_else(|| resumed.as_ref().and_then(|resumed| resumed.cwd.clone()));
 
     let model_provider = if oss {
         Some(BUILT_IN_OSS_MODEL_PROVIDER_ID.to_string())
@@ -165,7 +204,11 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    if let Some(path) = output_schema_file.as_deref() {
+        config.output_schema = Some(load_output_schema(path)?);
+    }
+    let output_schema = config.output_schema.clone();
     let mut event_processor: Box<dyn EventProcessor> = if json_mode {
         Box::new(EventProcessorWithJsonOutput::new(
             last_message_file.clone(),
@@ -200,14 +243,27 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         std::process::exit(1);
     }
 
-    let conversation_manager =
-        ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    let auth_manager = AuthManager::shared(config.codex_home.clone());
+    let conversation_manager = ConversationManager::new(auth_manager.clone());
     let NewConversation {
         conversation_id: _,
         conversation,
         session_configured,
-    } = conversation_manager.new_conversation(config).await?;
+    } = match resumed {
+        Some(resumed) => {
+            conversation_manager
+                .resume_conversation_from_rollout(config, resumed.path, auth_manager)
+                .await?
+        }
+        None => conversation_manager.new_conversation(config).await?,
+    };
     info!("Codex initialized with event: {session_configured:?}");
+    // `new_conversation` consumes the SessionConfigured event; hand it to the
+    // processor so it can report the session id.
+    event_processor.process_event(Event {
+        id: String::new(),
+        msg: EventMsg::SessionConfigured(session_configured),
+    });
 
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
     {
@@ -276,7 +332,35 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
     // Run the loop until the task is complete.
-    while let Some(event) = rx.recv().await {
+    let mut output_schema_error: Option<String> = None;
+    while let Some(mut event) = rx.recv().await {
+        // There is no one to ask, so MCP tool calls that need approval are
+        // declined.
+        if matches!(event.msg, EventMsg::McpToolApprovalRequest(_)) {
+            conversation
+                .submit(Op::McpToolApproval {
+                    id: event.id.clone(),
+                    decision: ReviewDecision::Denied,
+                })
+                .await?;
+        }
+        // Hand the validated JSON on as the final message so that is what
+        // `--output-last-message` and quiet mode receive; output that does
+        // not match is not handed on at all.
+        if let Some(schema) = &output_schema
+            && let EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) = &mut event.msg
+        {
+            match validate_output(schema, last_agent_message.as_deref().unwrap_or_default()) {
+                Ok(value) => {
+                    *last_agent_message = Some(value.to_string());
+                    output_schema_error = None;
+                }
+                Err(e) => {
+                    *last_agent_message = None;
+                    output_schema_error = Some(e);
+                }
+            }
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -289,5 +373,69 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     }
 
+    if let Some(e) = output_schema_error {
+        eprintln!("Final message does not match --output-schema: {e}");
+        std::process::exit(1);
+    }
+
     Ok(())
 }
+
+fn load_output_schema(path: &Path) -> anyhow::Result<serde_json::Value> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read output schema {}", path.display()))?;
+    let schema: serde_json::Value = serde_json::from_str(&text)
+        .with_context(|| format!("output schema {} is not valid JSON", path.display()))?;
+    check_supported_schema(&schema)
+        .map_err(|e| anyhow::anyhow!("output schema {} is not supported: {e}", path.display()))?;
+    Ok(schema)
+}
+
+/// Rollout picked by `codex exec resume` along with the working directory and
+/// model the session last ran with.
+struct ResumedSession {
+    path: PathBuf,
+    cwd: Option<PathBuf>,
+    model: Option<String>,
+}
+
+async fn resolve_resumed_session(
+    codex_home: &Path,
+    target: ResumeTarget,
+) -> anyhow::Result<ResumedSession> {
+    let path = match target {
+        ResumeTarget::Last => RolloutRecorder::list_conversations(codex_home, 25, None)
+            .await?
+            .items
+            .into_iter()
+            // Sub-agent transcripts are listed too but are not resumable.
+            .find(|item| item.sub_agent().is_none())
+            .map(|item| item.path)
+            .ok_or_else(|| anyhow::anyhow!("no recorded session to resume"))?,
+        ResumeTarget::Id(id) => {
+            let conversation_id: ConversationId = id
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid session id `{id}`"))?;
+            RolloutRecorder::find_conversation_path(codex_home, conversation_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("no recorded session with id `{id}`"))?
+        }
+    };
+
+    let history = RolloutRecorder::get_rollout_history(&path).await?;
+    let mut cwd = None;
+    let mut model = None;
+    for item in history.get_rollout_items() {
+        match item {
+            RolloutItem::SessionMeta(meta) => {
+                cwd.get_or_insert(meta.meta.cwd);
+            }
+            RolloutItem::TurnContext(context) => {
+                cwd = Some(context.cwd);
+                model = Some(context.model);
+            }
+            _ => {}
+        }
+    }
+    Ok(ResumedSession { path, cwd, model })
+}
@@ -1,4 +1,6 @@
 // Aggregates all former standalone integration tests as modules.
 mod apply_patch;
 mod common;
+mod output_schema;
+mod resume;
 mod sandbox;
@@ -0,0 +1,183 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+use assert_cmd::prelude::*;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_sse_fixture_with_id_from_str;
+use serde_json::json;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn sse_message(text: &str) -> String {
+    let events = json!([
+        {
+            "type": "response.output_item.done",
+            "item": {
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "output_text", "text": text }]
+            }
+        },
+        {
+            "type": "response.completed",
+            "response": { "id": "__ID__", "output": [] }
+        }
+    ]);
+    load_sse_fixture_with_id_from_str(&events.to_string(), "resp_1")
+}
+
+async fn mount_message(server: &MockServer, text: &str, expected_requests: u64) {
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(sse_message(text), "text/event-stream"),
+        )
+        .expect(expected_requests)
+        .mount(server)
+        .await;
+}
+
+fn codex_exec(home: &Path, uri: &str, schema_path: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("codex-exec").expect("should find binary for codex-exec");
+    cmd.current_dir(home)
+        .env("CODEX_HOME", home)
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{uri}/v1"))
+        .arg("--skip-git-repo-check")
+        .arg("--output-schema")
+        .arg(schema_path);
+    cmd
+}
+
+fn write_schema(dir: &Path) -> std::path::PathBuf {
+    let schema = json!({
+        "type": "object",
+        "properties": { "status": { "type": "string", "enum": ["pass", "fail"] } },
+        "required": ["status"],
+        "additionalProperties": false
+    });
+    let schema_path = dir.join("schema.json");
+    std::fs::write(&schema_path, schema.to_string()).unwrap();
+    schema_path
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn output_schema_is_sent_and_enforced() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_message(&server, "```json\n{\"status\": \"pass\"}\n```", 1).await;
+
+    let home = TempDir::new().unwrap();
+    let schema_path = write_schema(home.path());
+    let last_message = home.path().join("last.json");
+    codex_exec(home.path(), &server.uri(), &schema_path)
+        .arg("--output-last-message")
+        .arg(&last_message)
+        .arg("report the status")
+        .assert()
+        .success();
+
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["text"]["format"]["type"], "json_schema");
+    assert_eq!(body["text"]["format"]["strict"], true);
+    assert_eq!(
+        body["text"]["format"]["schema"]["required"],
+        json!(["status"])
+    );
+    assert_eq!(
+        std::fs::read_to_string(&last_message).unwrap(),
+        r#"{"status":"pass"}"#
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn quiet_mode_prints_only_the_validated_value() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_message(&server, "```json\n{\"status\": \"pass\"}\n```", 1).await;
+
+    let home = TempDir::new().unwrap();
+    let schema_path = write_schema(home.path());
+    let output = codex_exec(home.path(), &server.uri(), &schema_path)
+        .arg("--quiet")
+        .arg("report the status")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"status\":\"pass\"}\n"
+    );
+}
+
+#[test]
+fn unsupported_schema_keywords_are_rejected() {
+    let home = TempDir::new().unwrap();
+    let schema_path = home.path().join("schema.json");
+    let schema = json!({ "type": "integer", "minimum": 0 });
+    std::fs::write(&schema_path, schema.to_string()).unwrap();
+
+    let output = codex_exec(home.path(), "http://127.0.0.1:9", &schema_path)
+        .arg("report the status")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "expected failure: {output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("unsupported keyword `minimum`"),
+        "{output:?}"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn mismatched_output_is_retried_then_fails() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    // The first answer plus two retries.
+    mount_message(&server, "{\"status\": \"ok\"}", 3).await;
+
+    let home = TempDir::new().unwrap();
+    let schema_path = write_schema(home.path());
+    let output = codex_exec(home.path(), &server.uri(), &schema_path)
+        .arg("report the status")
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "expected failure: {output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("does not match --output-schema"),
+        "{output:?}"
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    let last = String::from_utf8_lossy(&requests[2].body).into_owned();
+    assert!(
+        last.contains("does not match the required JSON Schema"),
+        "missing retry message in {last}"
+    );
+}
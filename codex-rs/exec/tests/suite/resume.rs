@@ -0,0 +1,99 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+use assert_cmd::prelude::*;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use core_test_support::load_sse_fixture_with_id_from_str;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn codex_exec(home: &Path, cwd: &Path, uri: &str) -> Command {
+    let mut cmd = Command::cargo_bin("codex-exec").expect("should find binary for codex-exec");
+    cmd.current_dir(cwd)
+        .env("CODEX_HOME", home)
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{uri}/v1"))
+        .arg("--skip-git-repo-check")
+        .arg("-s")
+        .arg("danger-full-access");
+    cmd
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn resume_continues_previous_session() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(
+                    load_sse_fixture_with_id_from_str(
+                        include_str!("../fixtures/sse_response_completed.json"),
+                        "resp_1",
+                    ),
+                    "text/event-stream",
+                ),
+        )
+        .expect(3)
+        .mount(&server)
+        .await;
+    let uri = server.uri();
+
+    let home = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    let elsewhere = TempDir::new().unwrap();
+
+    let output = codex_exec(home.path(), project.path(), &uri)
+        .arg("--json")
+        .arg("--session-summary")
+        .arg("first prompt")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "first run failed: {output:?}");
+    let session_id = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|value| value["type"] == "session_summary")
+        .and_then(|value| value["session_id"].as_str().map(str::to_string))
+        .expect("session summary with id");
+
+    // Run from another directory: the session keeps its original cwd.
+    codex_exec(home.path(), elsewhere.path(), &uri)
+        .arg("resume")
+        .arg("--last")
+        .arg("second prompt")
+        .assert()
+        .success();
+    codex_exec(home.path(), elsewhere.path(), &uri)
+        .arg("resume")
+        .arg(&session_id)
+        .arg("third prompt")
+        .assert()
+        .success();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let last = String::from_utf8_lossy(&requests[2].body).into_owned();
+    for prompt in ["first prompt", "second prompt", "third prompt"] {
+        assert!(last.contains(prompt), "missing {prompt} in {last}");
+    }
+    let project_dir = project.path().canonicalize().unwrap();
+    assert!(
+        last.contains(&format!("<cwd>{}</cwd>", project_dir.display())),
+        "expected original cwd in {last}"
+    );
+}
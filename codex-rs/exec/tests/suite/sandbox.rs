@@ -37,6 +37,7 @@ async fn spawn_command_under_sandbox(
         cwd,
         stdio_policy,
         env,
+        None,
     )
     .await
 }
@@ -56,6 +57,9 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots,
         network_access: false,
+        network_allowlist: vec![],
+        read_only_paths: vec![],
+        deny_read_paths: vec![],
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
     };
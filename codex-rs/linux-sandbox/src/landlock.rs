@@ -27,12 +27,25 @@ use seccompiler::apply_filter;
 
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
+///
+/// When `network_proxied` is set, the process is already in a network
+/// namespace whose only reachable endpoint is the sandbox network proxy (see
+/// [`crate::network_namespace`]), so TCP sockets stay allowed.
+///
+/// The policy's `read_only_paths` and `deny_read_paths` are not enforced
+/// here but by [`crate::mount_namespace::protect_paths`].
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    network_proxied: bool,
 ) -> Result<()> {
     if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+        let filter = if network_proxied {
+            NetworkFilter::TcpOnly
+        } else {
+            NetworkFilter::UnixOnly
+        };
+        install_network_seccomp_filter_on_current_thread(filter)?;
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
@@ -82,9 +95,22 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
+/// Which sockets the network seccomp filter lets through.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NetworkFilter {
+    /// Only AF_UNIX sockets, which cannot connect or send.
+    UnixOnly,
+    /// Only TCP sockets, for a process whose network namespace leads nowhere
+    /// but the proxy. AF_UNIX sockets are refused so local daemons cannot be
+    /// used to get around that; `socketpair` keeps working.
+    TcpOnly,
+}
+
 /// Installs a seccomp filter that blocks outbound network access except for
-/// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+/// the sockets `filter` allows.
+fn install_network_seccomp_filter_on_current_thread(
+    filter: NetworkFilter,
+) -> std::result::Result<(), SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -93,25 +119,27 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules.insert(nr, vec![]); // empty rule vec = unconditional match
     };
 
-    deny_syscall(libc::SYS_connect);
     deny_syscall(libc::SYS_accept);
     deny_syscall(libc::SYS_accept4);
     deny_syscall(libc::SYS_bind);
     deny_syscall(libc::SYS_listen);
-    deny_syscall(libc::SYS_getpeername);
-    deny_syscall(libc::SYS_getsockname);
-    deny_syscall(libc::SYS_shutdown);
-    deny_syscall(libc::SYS_sendto);
-    deny_syscall(libc::SYS_sendmsg);
-    deny_syscall(libc::SYS_sendmmsg);
-    // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
-    // with their socketpair + child processes for sub-proc management
-    // deny_syscall(libc::SYS_recvfrom);
-    deny_syscall(libc::SYS_recvmsg);
-    deny_syscall(libc::SYS_recvmmsg);
-    deny_syscall(libc::SYS_getsockopt);
-    deny_syscall(libc::SYS_setsockopt);
     deny_syscall(libc::SYS_ptrace);
+    if filter == NetworkFilter::UnixOnly {
+        deny_syscall(libc::SYS_connect);
+        deny_syscall(libc::SYS_getpeername);
+        deny_syscall(libc::SYS_getsockname);
+        deny_syscall(libc::SYS_shutdown);
+        deny_syscall(libc::SYS_sendto);
+        deny_syscall(libc::SYS_sendmsg);
+        deny_syscall(libc::SYS_sendmmsg);
+        // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
+        // with their socketpair + child processes for sub-proc management
+        // deny_syscall(libc::SYS_recvfrom);
+        deny_syscall(libc::SYS_recvmsg);
+        deny_syscall(libc::SYS_recvmmsg);
+        deny_syscall(libc::SYS_getsockopt);
+        deny_syscall(libc::SYS_setsockopt);
+    }
 
     // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
     let unix_only_rule = SeccompRule::new(vec![SeccompCondition::new(
@@ -121,7 +149,11 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         libc::AF_UNIX as u64,
     )?])?;
 
-    rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
+    let socket_rules = match filter {
+        NetworkFilter::UnixOnly => vec![unix_only_rule.clone()],
+        NetworkFilter::TcpOnly => tcp_only_socket_rules()?,
+    };
+    rules.insert(libc::SYS_socket, socket_rules);
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
     let filter = SeccompFilter::new(
@@ -143,3 +175,45 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
 
     Ok(())
 }
+
+/// Rules matching every `socket` call except AF_INET/AF_INET6 stream sockets.
+fn tcp_only_socket_rules() -> std::result::Result<Vec<SeccompRule>, SandboxErr> {
+    let domain = |op: SeccompCmpOp, family: i32| {
+        SeccompCondition::new(0, SeccompCmpArgLen::Dword, op, family as u64)
+    };
+
+    // Neither IPv4 nor IPv6.
+    let mut rules = vec![SeccompRule::new(vec![
+        domain(SeccompCmpOp::Ne, libc::AF_INET)?,
+        domain(SeccompCmpOp::Ne, libc::AF_INET6)?,
+    ])?];
+
+    // IPv4 or IPv6 with any type other than SOCK_STREAM. The low bits of the
+    // second argument hold the type; the rest are flags like SOCK_CLOEXEC.
+    // Stream protocols other than TCP (e.g. SCTP) are refused as well.
+    const SOCK_TYPE_MASK: u64 = 0xf;
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        rules.push(SeccompRule::new(vec![
+            domain(SeccompCmpOp::Eq, family)?,
+            SeccompCondition::new(2, SeccompCmpArgLen::Dword, SeccompCmpOp::Ne, 0)?,
+            SeccompCondition::new(
+                2,
+                SeccompCmpArgLen::Dword,
+                SeccompCmpOp::Ne,
+                libc::IPPROTO_TCP as u64,
+            )?,
+        ])?);
+        for sock_type in (0..=SOCK_TYPE_MASK).filter(|t| *t != libc::SOCK_STREAM as u64) {
+            rules.push(SeccompRule::new(vec![
+                domain(SeccompCmpOp::Eq, family)?,
+                SeccompCondition::new(
+                    1,
+                    SeccompCmpArgLen::Dword,
+                    SeccompCmpOp::MaskedEq(SOCK_TYPE_MASK),
+                    sock_type,
+                )?,
+            ])?);
+        }
+    }
+    Ok(rules)
+}
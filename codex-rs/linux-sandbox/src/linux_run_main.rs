@@ -3,6 +3,11 @@ use std::ffi::CString;
 use std::path::PathBuf;
 
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::mount_namespace::protect_paths;
+use crate::mount_namespace::protected_paths;
+use crate::network_namespace::bridge_to_proxy;
+use crate::user_namespace::drop_capabilities;
+use crate::user_namespace::enter_user_namespace;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
@@ -12,6 +17,17 @@ pub struct LandlockCommand {
 
     pub sandbox_policy: codex_core::protocol::SandboxPolicy,
 
+    /// Loopback port of the proxy serving the policy's `network_allowlist`.
+    /// When set, the command runs in a network namespace of its own in which
+    /// this port leads to `--network-proxy-socket` and nothing else is
+    /// reachable.
+    #[arg(long, requires = "network_proxy_socket")]
+    pub network_proxy_port: Option<u16>,
+
+    /// Unix socket the proxy listens on.
+    #[arg(long, requires = "network_proxy_port")]
+    pub network_proxy_socket: Option<PathBuf>,
+
     /// Full command args to run under landlock.
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
@@ -21,10 +37,53 @@ pub fn run_main() -> ! {
     let LandlockCommand {
         sandbox_policy_cwd,
         sandbox_policy,
+        network_proxy_port,
+        network_proxy_socket,
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    let (read_only_paths, deny_read_paths) = protected_paths(&sandbox_policy, &sandbox_policy_cwd);
+    let protect_paths_needed = !read_only_paths.is_empty() || !deny_read_paths.is_empty();
+    let network_proxy = network_proxy_port.zip(network_proxy_socket);
+
+    let mut namespaces = 0;
+    if protect_paths_needed {
+        namespaces |= libc::CLONE_NEWNS;
+    }
+    if network_proxy.is_some() {
+        namespaces |= libc::CLONE_NEWNET;
+    }
+    if namespaces != 0
+        && let Err(e) = enter_user_namespace(namespaces)
+    {
+        panic!("error creating the sandbox namespaces: {e:?}");
+    }
+
+    if protect_paths_needed && let Err(e) = protect_paths(&read_only_paths, &deny_read_paths) {
+        panic!("error protecting paths: {e:?}");
+    }
+
+    let network_proxied = match network_proxy {
+        Some((port, socket)) => {
+            if let Err(e) = bridge_to_proxy(port, &socket) {
+                panic!("error isolating the network: {e:?}");
+            }
+            true
+        }
+        None => false,
+    };
+
+    if namespaces != 0
+        && let Err(e) = drop_capabilities()
+    {
+        panic!("error dropping capabilities: {e:?}");
+    }
+
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &sandbox_policy_cwd,
+        network_proxied,
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
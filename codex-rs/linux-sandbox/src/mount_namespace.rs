@@ -0,0 +1,196 @@
+//! Enforcement of the sandbox's `read_only_paths` and `deny_read_paths`.
+//!
+//! Landlock rules cover whole hierarchies, so Landlock cannot keep a file
+//! such as `.env` out of a writable root without also taking away the right
+//! to create files next to it. Instead the command runs in a mount namespace
+//! of its own, created by [`crate::user_namespace::enter_user_namespace`], in
+//! which read-only paths are bind mounted read-only and denied paths are
+//! covered by an empty, unreadable file or directory.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::path_pattern::PathPattern;
+
+/// Existing paths that `sandbox_policy` makes read-only and unreadable,
+/// respectively. Read-only paths inside an unreadable one are left out.
+pub(crate) fn protected_paths(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut deny_read: Vec<PathBuf> = sandbox_policy
+        .deny_read_patterns(cwd)
+        .iter()
+        .flat_map(PathPattern::existing_paths)
+        .collect();
+    deny_read.sort();
+    deny_read.dedup();
+
+    let mut read_only: Vec<PathBuf> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .iter()
+        .flat_map(|writable_root| &writable_root.read_only_patterns)
+        .flat_map(PathPattern::existing_paths)
+        .filter(|path| !deny_read.iter().any(|denied| path.starts_with(denied)))
+        .collect();
+    read_only.sort();
+    read_only.dedup();
+
+    (read_only, deny_read)
+}
+
+/// Makes `read_only` read-only and `deny_read` unreadable in the current,
+/// freshly created mount namespace.
+pub(crate) fn protect_paths(read_only: &[PathBuf], deny_read: &[PathBuf]) -> io::Result<()> {
+    // Keep the mounts below from propagating back to the parent namespace.
+    mount(
+        None,
+        Path::new("/"),
+        None,
+        libc::MS_REC | libc::MS_PRIVATE,
+        None,
+    )?;
+
+    // Cover denied paths first so that the read-only bind mounts, being
+    // recursive, carry those covers along.
+    if !deny_read.is_empty() {
+        cover_denied_paths(deny_read)?;
+    }
+    for path in read_only {
+        bind_read_only(path)?;
+    }
+
+    // The working directory still refers to what it was before the mounts;
+    // look it up again so that relative paths see them.
+    let cwd = std::env::current_dir()?;
+    std::env::set_current_dir(cwd)
+}
+
+/// Mounts an empty directory with no permissions, or an empty file with no
+/// permissions, over each of `paths`. Both live on a read-only tmpfs so the
+/// command cannot change their permissions back.
+fn cover_denied_paths(paths: &[PathBuf]) -> io::Result<()> {
+    // Open the targets before anything is mounted over the staging
+    // directory, which may well contain some of them.
+    let targets = paths
+        .iter()
+        .map(|path| {
+            let target = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+                .open(path)?;
+            let is_dir = target.metadata()?.is_dir();
+            Ok((target, is_dir))
+        })
+        .collect::<io::Result<Vec<(File, bool)>>>()?;
+
+    let staging = std::env::temp_dir();
+    let locked_down = libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC;
+    mount(
+        Some(Path::new("tmpfs")),
+        &staging,
+        Some("tmpfs"),
+        locked_down,
+        Some("mode=700"),
+    )?;
+    let empty_dir = staging.join("dir");
+    let empty_file = staging.join("file");
+    std::fs::create_dir(&empty_dir)?;
+    File::create(&empty_file)?;
+    for path in [&empty_dir, &empty_file] {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o000))?;
+    }
+    mount(
+        None,
+        &staging,
+        None,
+        libc::MS_REMOUNT | libc::MS_RDONLY | locked_down,
+        None,
+    )?;
+
+    for (target, is_dir) in &targets {
+        let source = if *is_dir { &empty_dir } else { &empty_file };
+        let target = PathBuf::from(format!("/proc/self/fd/{}", target.as_raw_fd()));
+        mount(Some(source), &target, None, libc::MS_BIND, None)?;
+    }
+
+    // The covers stay mounted after the staging tmpfs is detached.
+    let staging_c = c_path(&staging)?;
+    if unsafe { libc::umount2(staging_c.as_ptr(), libc::MNT_DETACH) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Bind mounts `path` onto itself and makes the new mount read-only.
+fn bind_read_only(path: &Path) -> io::Result<()> {
+    mount(Some(path), path, None, libc::MS_BIND | libc::MS_REC, None)?;
+    // Flags of the original mount that a user namespace may not clear.
+    let path_c = c_path(path)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path_c.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let locked = [
+        (libc::ST_NOSUID, libc::MS_NOSUID),
+        (libc::ST_NODEV, libc::MS_NODEV),
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ]
+    .into_iter()
+    .filter(|(st_flag, _)| stat.f_flag & st_flag != 0)
+    .fold(0, |flags, (_, ms_flag)| flags | ms_flag);
+    mount(
+        None,
+        path,
+        None,
+        libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked,
+        None,
+    )
+}
+
+fn mount(
+    source: Option<&Path>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+    data: Option<&str>,
+) -> io::Result<()> {
+    let source = source.map(c_path).transpose()?;
+    let target_c = c_path(target)?;
+    let fstype = fstype.map(CString::new).transpose()?;
+    let data = data.map(CString::new).transpose()?;
+    let rc = unsafe {
+        libc::mount(
+            source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            target_c.as_ptr(),
+            fstype.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            flags,
+            data.as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr().cast()),
+        )
+    };
+    if rc != 0 {
+        let err = io::Error::last_os_error();
+        return Err(io::Error::new(
+            err.kind(),
+            format!("mounting over {}: {err}", target.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    Ok(CString::new(path.as_os_str().as_bytes())?)
+}
@@ -0,0 +1,131 @@
+//! Network isolation for commands whose only way out is the sandbox network
+//! proxy.
+//!
+//! Landlock network rules match ports, not addresses, so they cannot stop a
+//! command from reaching an outside host on the proxy's port. Instead the
+//! command runs in a network namespace of its own whose only interface is
+//! loopback. A bridge listening on the proxy's port inside that namespace
+//! forwards every connection to the proxy's Unix socket, which, being a path
+//! on disk, is reachable from any namespace.
+//!
+//! The namespace itself is created by
+//! [`crate::user_namespace::enter_user_namespace`].
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::Shutdown;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Makes `127.0.0.1:port` in the current, freshly created network namespace
+/// lead to the proxy listening on `proxy_socket`, then forks. Only the child
+/// returns: the parent serves the bridge until the child exits and then
+/// exits the same way.
+pub(crate) fn bridge_to_proxy(port: u16, proxy_socket: &Path) -> io::Result<()> {
+    bring_up_loopback()?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+
+    let bridge_pid = unsafe { libc::getpid() };
+    // The process is single-threaded here, so the child may keep running
+    // Rust code after the fork.
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            // The command must not outlive the bridge.
+            unsafe {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                if libc::getppid() != bridge_pid {
+                    libc::_exit(1);
+                }
+            }
+            drop(listener);
+            Ok(())
+        }
+        child => {
+            let proxy_socket = proxy_socket.to_path_buf();
+            std::thread::spawn(move || serve_bridge(listener, proxy_socket));
+            exit_like(child)
+        }
+    }
+}
+
+/// Sets the `lo` interface of the current network namespace up.
+fn bring_up_loopback() -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in request.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as libc::c_char;
+    }
+    unsafe {
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCGIFFLAGS as _, &mut request) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        if libc::ioctl(socket.as_raw_fd(), libc::SIOCSIFFLAGS as _, &request) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn serve_bridge(listener: TcpListener, proxy_socket: PathBuf) {
+    for client in listener.incoming().flatten() {
+        let proxy_socket = proxy_socket.clone();
+        std::thread::spawn(move || {
+            if let Ok(proxy) = UnixStream::connect(&proxy_socket) {
+                let _ = forward(client, proxy);
+            }
+        });
+    }
+}
+
+/// Copies bytes both ways until each side has closed its half.
+fn forward(mut client: TcpStream, mut proxy: UnixStream) -> io::Result<()> {
+    let mut client_reader = client.try_clone()?;
+    let mut proxy_writer = proxy.try_clone()?;
+    let upload = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut proxy_writer);
+        let _ = proxy_writer.shutdown(Shutdown::Write);
+    });
+    let _ = io::copy(&mut proxy, &mut client);
+    let _ = client.shutdown(Shutdown::Write);
+    let _ = upload.join();
+    Ok(())
+}
+
+/// Waits for `child` and exits with its exit code, or dies from the signal
+/// that killed it.
+fn exit_like(child: libc::pid_t) -> ! {
+    let mut status = 0;
+    loop {
+        let rc = unsafe { libc::waitpid(child, &mut status, 0) };
+        if rc == child {
+            break;
+        }
+        if rc < 0 && io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            std::process::exit(1);
+        }
+    }
+    if libc::WIFSIGNALED(status) {
+        let signal = libc::WTERMSIG(status);
+        // Restore the default action so raising the signal kills this
+        // process the same way.
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+        std::process::exit(128 + signal);
+    }
+    std::process::exit(libc::WEXITSTATUS(status));
+}
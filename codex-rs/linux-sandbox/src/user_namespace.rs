@@ -0,0 +1,49 @@
+use std::io;
+
+/// Moves the current process into a new user namespace along with the other
+/// `namespaces` (`CLONE_NEW*` flags). The process keeps its uid and gid but
+/// gains the capabilities needed to set the new namespaces up; call
+/// [`drop_capabilities`] once that is done.
+///
+/// Must be called while the process is single-threaded.
+pub(crate) fn enter_user_namespace(namespaces: libc::c_int) -> io::Result<()> {
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | namespaces) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Map the caller's ids onto themselves so file ownership and permission
+    // checks are unchanged.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+    Ok(())
+}
+
+/// Makes sure the command starts without capabilities, even when run as
+/// root, so it can neither undo the namespaces' setup nor bypass file
+/// permissions.
+pub(crate) fn drop_capabilities() -> io::Result<()> {
+    for cap in 0.. {
+        if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap) } != 0 {
+            let err = io::Error::last_os_error();
+            // Past the last capability the kernel knows about.
+            if err.raw_os_error() == Some(libc::EINVAL) {
+                break;
+            }
+            return Err(err);
+        }
+    }
+    if unsafe {
+        libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_CLEAR_ALL,
+            0,
+            0,
+            0,
+        )
+    } != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -47,6 +47,9 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
     let sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: writable_roots.to_vec(),
         network_access: false,
+        network_allowlist: vec![],
+        read_only_paths: vec![],
+        deny_read_paths: vec![],
         // Exclude tmp-related folders from writable roots because we need a
         // folder that is writable by tests but that we intentionally disallow
         // writing to in the sandbox.
@@ -228,3 +231,218 @@ async fn sandbox_blocks_dev_tcp_redirection() {
     // all images ship bash, so we guard against 127 as well.
     assert_network_blocked(&["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"]).await;
 }
+
+/// Starts an HTTP server on loopback that answers every request with `hello`.
+#[expect(clippy::expect_used)]
+async fn serve_hello() -> u16 {
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind test server");
+    let port = listener.local_addr().expect("local addr").port();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                )
+                .await;
+        }
+    });
+    port
+}
+
+/// Runs `cmd` under a workspace-write policy that allows `allowlist` and
+/// returns its exit code and stdout.
+#[expect(clippy::expect_used)]
+async fn run_with_network_allowlist(cmd: &[&str], allowlist: &[String]) -> (i32, String) {
+    let params = ExecParams {
+        command: cmd.iter().map(|s| s.to_string()).collect(),
+        cwd: std::env::current_dir().expect("cwd should exist"),
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: false,
+        network_allowlist: allowlist.to_vec(),
+        read_only_paths: vec![],
+        deny_read_paths: vec![],
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+    match result {
+        Ok(output) => (output.exit_code, output.stdout.text),
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, stdout, _))) => (exit_code, stdout),
+        _ => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn network_allowlist_reaches_allowed_host_through_proxy() {
+    let port = serve_hello().await;
+    let url = format!("http://127.0.0.1:{port}/");
+    let (exit_code, stdout) =
+        run_with_network_allowlist(&["curl", "-sS", &url], &[format!("127.0.0.1:{port}")]).await;
+    if exit_code == 127 {
+        // curl is not installed.
+        return;
+    }
+    assert_eq!((exit_code, stdout.as_str()), (0, "hello"));
+}
+
+#[tokio::test]
+async fn network_allowlist_blocks_other_hosts() {
+    let port = serve_hello().await;
+    let url = format!("http://127.0.0.1:{port}/");
+
+    // Through the proxy, which refuses the host.
+    let (exit_code, stdout) = run_with_network_allowlist(
+        &["curl", "-sS", "--fail", &url],
+        &["example.com".to_string()],
+    )
+    .await;
+    assert_ne!(
+        exit_code, 0,
+        "proxy allowed a host outside the allowlist: {stdout}"
+    );
+
+    // Around the proxy, which the sandbox refuses.
+    let (exit_code, stdout) = run_with_network_allowlist(
+        &["curl", "-sS", "--noproxy", "*", &url],
+        &[format!("127.0.0.1:{port}")],
+    )
+    .await;
+    assert_ne!(
+        exit_code, 0,
+        "sandbox allowed a direct connection: {stdout}"
+    );
+}
+
+/// Landlock cannot tell hosts apart, so the only way to stop a command from
+/// reaching any host on the proxy's port is to leave it no route out at all.
+#[tokio::test]
+async fn network_allowlist_leaves_only_loopback() {
+    let (exit_code, stdout) = run_with_network_allowlist(
+        &[
+            "sh",
+            "-c",
+            "tail -n +3 /proc/net/dev | cut -d: -f1 | tr -d ' '",
+        ],
+        &["example.com".to_string()],
+    )
+    .await;
+    assert_eq!((exit_code, stdout.as_str()), (0, "lo\n"));
+}
+
+/// Runs `script` with `sh -c` in `root`, which is writable apart from the
+/// given patterns, and returns its exit code.
+async fn run_with_protected_paths(
+    script: &str,
+    root: &std::path::Path,
+    read_only_paths: &[&str],
+    deny_read_paths: &[&str],
+) -> i32 {
+    let params = ExecParams {
+        command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        cwd: root.to_path_buf(),
+        timeout_ms: Some(LONG_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: false,
+        network_allowlist: vec![],
+        read_only_paths: read_only_paths.iter().map(|p| p.to_string()).collect(),
+        deny_read_paths: deny_read_paths.iter().map(|p| p.to_string()).collect(),
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await;
+    match result {
+        Ok(output) => output.exit_code,
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, _, _))) => exit_code,
+        _ => panic!("unexpected result: {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn read_only_and_deny_read_paths_are_enforced() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let root = tmpdir.path();
+    std::fs::create_dir_all(root.join("infra/prod")).unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join(".env"), "TOKEN=secret\n").unwrap();
+    std::fs::write(root.join("infra/prod/main.tf"), "").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "").unwrap();
+
+    let run = |script: &'static str| {
+        run_with_protected_paths(script, root, &["infra/prod/**"], &[".env"])
+    };
+
+    assert_eq!(run("echo ok >> src/lib.rs && cat src/lib.rs").await, 0);
+    assert_eq!(run("cat infra/prod/main.tf").await, 0);
+    assert_ne!(run("echo bad >> infra/prod/main.tf").await, 0);
+    assert_ne!(run("touch infra/prod/new.tf").await, 0);
+    assert_ne!(run("cat .env").await, 0);
+    assert_ne!(run("echo TOKEN=leaked > .env").await, 0);
+    assert_ne!(run("rm .env").await, 0);
+    assert_ne!(run("cd infra/prod && echo bad >> main.tf").await, 0);
+    assert_eq!(
+        std::fs::read_to_string(root.join(".env")).unwrap(),
+        "TOKEN=secret\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("infra/prod/main.tf")).unwrap(),
+        ""
+    );
+}
+
+/// Protecting a file must not stop commands from creating files next to it.
+#[tokio::test]
+async fn new_files_can_be_created_next_to_protected_paths() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let root = tmpdir.path();
+    std::fs::write(root.join(".env"), "TOKEN=secret\n").unwrap();
+
+    let exit_code = run_with_protected_paths(
+        "echo ok > new.txt && mkdir build && touch build/out && cat new.txt",
+        root,
+        &[],
+        &[".env"],
+    )
+    .await;
+    assert_eq!(exit_code, 0);
+    assert_eq!(
+        std::fs::read_to_string(root.join("new.txt")).unwrap(),
+        "ok\n"
+    );
+    assert!(root.join("build/out").exists());
+}
@@ -2,7 +2,8 @@
 //!
 //! The client is intentionally lightweight – it is only capable of:
 //!   1. Spawning a subprocess that launches a conforming MCP server that
-//!      communicates over stdio.
+//!      communicates over stdio, or connecting to a server over Streamable
+//!      HTTP (see [`crate::streamable_http`]).
 //!   2. Sending MCP requests and pairing them with their corresponding
 //!      responses.
 //!   3. Offering a convenience helper for the common `tools/list` request.
@@ -12,8 +13,11 @@
 //! issue requests and receive strongly-typed results.
 
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::ffi::OsString;
+use std::future::Future;
 use std::sync::Arc;
+use std::sync::PoisonError;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -23,6 +27,9 @@ use anyhow::Result;
 use anyhow::anyhow;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::GetPromptRequest;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
@@ -31,11 +38,20 @@ use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequest;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequest;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
@@ -46,26 +62,32 @@ use tokio::process::Command;
 use tokio::sync::Mutex;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::time;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
 
+use crate::streamable_http;
+
 /// Capacity of the bounded channels used for transporting messages between the
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
 
+/// Number of stderr lines kept from a stdio server for diagnostics.
+const STDERR_TAIL_LINES: usize = 20;
+
 /// Internal representation of a pending request sender.
-type PendingSender = oneshot::Sender<JSONRPCMessage>;
+pub(crate) type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
 /// A running MCP client instance.
 pub struct McpClient {
     /// Retain this child process until the client is dropped. The Tokio runtime
     /// will make a "best effort" to reap the process after it exits, but it is
     /// not a guarantee. See the `kill_on_drop` documentation for details.
-    #[allow(dead_code)]
-    child: tokio::process::Child,
+    /// `None` for servers reached over HTTP.
+    child: Option<tokio::process::Child>,
 
     /// Channel for sending JSON-RPC messages *to* the background writer task.
     outgoing_tx: mpsc::Sender<JSONRPCMessage>,
@@ -76,6 +98,17 @@ pub struct McpClient {
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
+
+    /// Flips to `true` once the server has gone away. Requests sent after
+    /// that fail immediately instead of waiting for a reply.
+    closed_rx: watch::Receiver<bool>,
+
+    /// Held so `closed_rx` stays open for as long as the client lives, even
+    /// for transports that never close on their own.
+    _closed_tx: watch::Sender<bool>,
+
+    /// The last lines the server wrote to stderr, oldest first.
+    stderr_tail: Arc<std::sync::Mutex<VecDeque<String>>>,
 }
 
 impl McpClient {
@@ -93,7 +126,7 @@ impl McpClient {
             .envs(create_env_for_mcp_server(env))
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
             // As noted in the `kill_on_drop` documentation, the Tokio runtime makes
             // a "best effort" to reap-after-exit to avoid zombie processes, but it
             // is not a guarantee.
@@ -108,9 +141,15 @@ impl McpClient {
             .stdout
             .take()
             .ok_or_else(|| std::io::Error::other("failed to capture child stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| std::io::Error::other("failed to capture child stderr"))?;
 
         let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
         let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (closed_tx, closed_rx) = watch::channel(false);
+        let stderr_tail = Arc::new(std::sync::Mutex::new(VecDeque::new()));
 
         // Spawn writer task. It listens on the `outgoing_rx` channel and
         // writes messages to the child's STDIN.
@@ -138,52 +177,129 @@ impl McpClient {
         };
 
         // Spawn reader task. It reads line-delimited JSON from the child's
-        // STDOUT and dispatches responses to the pending map.
+        // STDOUT and dispatches responses to the pending map. The server is
+        // considered gone once STDOUT closes, which normally means it exited.
         let reader_handle = {
             let pending = pending.clone();
+            let closed_tx = closed_tx.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
                 while let Ok(Some(line)) = lines.next_line().await {
                     debug!("MCP message from server: {line}");
                     match serde_json::from_str::<JSONRPCMessage>(&line) {
-                        Ok(JSONRPCMessage::Response(resp)) => {
-                            Self::dispatch_response(resp, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Error(err)) => {
-                            Self::dispatch_error(err, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Notification(JSONRPCNotification { .. })) => {
-                            // For now we only log server-initiated notifications.
-                            info!("<- notification: {}", line);
-                        }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
-                        }
+                        Ok(message) => Self::dispatch_message(message, &pending).await,
                         Err(e) => {
                             error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
                         }
                     }
                 }
+                warn!("MCP server closed its stdout");
+                // Set the flag before failing the outstanding requests so a
+                // request registered concurrently either sees the flag or is
+                // failed here.
+                closed_tx.send_replace(true);
+                pending.lock().await.clear();
+            })
+        };
+
+        // Spawn stderr task. It keeps the most recent lines for diagnostics.
+        let stderr_handle = {
+            let stderr_tail = stderr_tail.clone();
+            let mut lines = BufReader::new(stderr).lines();
+
+            tokio::spawn(async move {
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("MCP server stderr: {line}");
+                    let mut tail = stderr_tail.lock().unwrap_or_else(PoisonError::into_inner);
+                    if tail.len() == STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
             })
         };
 
         // We intentionally *detach* the tasks. They will keep running in the
         // background as long as their respective resources (channels/stdin/
-        // stdout) are alive. Dropping `McpClient` cancels the tasks due to
-        // dropped resources.
-        let _ = (writer_handle, reader_handle);
+        // stdout/stderr) are alive. Dropping `McpClient` cancels the tasks due
+        // to dropped resources.
+        let _ = (writer_handle, reader_handle, stderr_handle);
+
+        Ok(Self {
+            child: Some(child),
+            outgoing_tx,
+            pending,
+            id_counter: AtomicI64::new(1),
+            closed_rx,
+            _closed_tx: closed_tx,
+            stderr_tail,
+        })
+    }
+
+    /// Establish an MCP session with the server whose Streamable HTTP endpoint
+    /// is `url`, sending `headers` with every request. As with
+    /// [`new_stdio_client`](Self::new_stdio_client), the caller is responsible
+    /// for sending the `initialize` request.
+    pub fn new_streamable_http_client(
+        url: String,
+        headers: HashMap<String, String>,
+    ) -> Result<Self> {
+        let http = streamable_http::build_http_client(headers)?;
+        let (outgoing_tx, outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+        let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+
+        // Like the stdio tasks, the writer stops once `outgoing_tx` is dropped
+        // together with the client.
+        tokio::spawn(streamable_http::run_writer(
+            http,
+            url,
+            outgoing_rx,
+            pending.clone(),
+        ));
+
+        // Each request is a separate HTTP exchange, so there is no connection
+        // whose loss would close the client.
+        let (closed_tx, closed_rx) = watch::channel(false);
 
         Ok(Self {
-            child,
+            child: None,
             outgoing_tx,
             pending,
             id_counter: AtomicI64::new(1),
+            closed_rx,
+            _closed_tx: closed_tx,
+            stderr_tail: Arc::default(),
         })
     }
 
+    /// Resolves once the server has gone away, which for a stdio server means
+    /// its process closed STDOUT (normally by exiting). Also resolves when the
+    /// client is dropped. The returned future does not borrow the client.
+    pub fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut closed_rx = self.closed_rx.clone();
+        async move {
+            // An error means the client was dropped, which counts as closed.
+            let _ = closed_rx.wait_for(|closed| *closed).await;
+        }
+    }
+
+    /// Whether the server has gone away. See [`closed`](Self::closed).
+    pub fn is_closed(&self) -> bool {
+        *self.closed_rx.borrow()
+    }
+
+    /// The last lines the server wrote to stderr, oldest first. Always empty
+    /// for HTTP servers.
+    pub fn stderr_tail(&self) -> Vec<String> {
+        self.stderr_tail
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -230,6 +346,10 @@ impl McpClient {
             let mut guard = self.pending.lock().await;
             guard.insert(id, tx);
         }
+        if self.is_closed() {
+            self.pending.lock().await.remove(&id);
+            return Err(anyhow!("MCP server has exited"));
+        }
 
         // Send to writer task.
         if self.outgoing_tx.send(message).await.is_err() {
@@ -347,6 +467,68 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Convenience wrapper around `resources/list`.
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        self.send_request::<ListResourcesRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `resources/read`.
+    pub async fn read_resource(
+        &self,
+        uri: String,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let params = ReadResourceRequestParams { uri };
+        self.send_request::<ReadResourceRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/list`.
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        self.send_request::<ListPromptsRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let params = GetPromptRequestParams { arguments, name };
+        self.send_request::<GetPromptRequest>(params, timeout).await
+    }
+
+    /// Internal helper: route a message received from the server.
+    pub(crate) async fn dispatch_message(
+        message: JSONRPCMessage,
+        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
+    ) {
+        match message {
+            JSONRPCMessage::Response(resp) => Self::dispatch_response(resp, pending).await,
+            JSONRPCMessage::Error(err) => Self::dispatch_error(err, pending).await,
+            JSONRPCMessage::Notification(notification) => {
+                // For now we only log server-initiated notifications.
+                info!("<- notification: {notification:?}");
+            }
+            other => {
+                // Requests are currently not expected from the server – log
+                // and ignore.
+                info!("<- unhandled message: {:?}", other);
+            }
+        }
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
     async fn dispatch_response(
         resp: JSONRPCResponse,
@@ -375,7 +557,7 @@ impl McpClient {
     }
 
     /// Internal helper: route a JSON-RPC *error* object to the pending map.
-    async fn dispatch_error(
+    pub(crate) async fn dispatch_error(
         err: mcp_types::JSONRPCError,
         pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
     ) {
@@ -400,7 +582,9 @@ impl Drop for McpClient {
         // `kill_on_drop(true)` above, this extra check has the benefit of
         // forcing the process to be reaped immediately if it has already exited
         // instead of waiting for the Tokio runtime to reap it later.
-        let _ = self.child.try_wait();
+        if let Some(child) = &mut self.child {
+            let _ = child.try_wait();
+        }
     }
 }
 
@@ -0,0 +1,245 @@
+//! Streamable HTTP transport for [`McpClient`](crate::McpClient).
+//!
+//! Every outgoing JSON-RPC message is POSTed to the server's endpoint. The
+//! server answers with either a single JSON message or a `text/event-stream`
+//! whose `data:` payloads are JSON-RPC messages; both are dispatched to the
+//! same pending-request map the stdio transport uses. The `Mcp-Session-Id`
+//! returned by the server is echoed on every subsequent request.
+//!
+//! Server-initiated streams (`GET` on the endpoint) are not opened since the
+//! client does not act on server notifications or requests.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use reqwest::StatusCode;
+use reqwest::header::ACCEPT;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::error;
+
+use crate::mcp_client::McpClient;
+use crate::mcp_client::PendingSender;
+
+const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// JSON-RPC "internal error" code, used to fail a request whose HTTP exchange
+/// broke down before the server produced a reply.
+const INTERNAL_ERROR_CODE: i64 = -32603;
+
+/// Builds the HTTP client for a server, sending `headers` with every request.
+pub(crate) fn build_http_client(headers: HashMap<String, String>) -> Result<reqwest::Client> {
+    let mut default_headers = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::try_from(name.as_str())
+            .map_err(|e| anyhow!("invalid HTTP header name `{name}`: {e}"))?;
+        let header_value = HeaderValue::try_from(value)
+            .map_err(|e| anyhow!("invalid value for HTTP header `{name}`: {e}"))?;
+        default_headers.insert(header_name, header_value);
+    }
+    Ok(reqwest::Client::builder()
+        .default_headers(default_headers)
+        .build()?)
+}
+
+/// Forwards messages from `outgoing_rx` to the server at `url` until the
+/// channel closes.
+///
+/// Notifications and responses are sent one after the other so the server
+/// sees them in order. Each request gets its own task because the server may
+/// keep the reply stream open for as long as the request runs.
+pub(crate) async fn run_writer(
+    http: reqwest::Client,
+    url: String,
+    mut outgoing_rx: mpsc::Receiver<JSONRPCMessage>,
+    pending: Arc<Mutex<HashMap<i64, PendingSender>>>,
+) {
+    let session_id: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    while let Some(message) = outgoing_rx.recv().await {
+        let is_request = matches!(message, JSONRPCMessage::Request(_));
+        let exchange = send_message(
+            http.clone(),
+            url.clone(),
+            session_id.clone(),
+            message,
+            pending.clone(),
+        );
+        if is_request {
+            tokio::spawn(exchange);
+        } else {
+            exchange.await;
+        }
+    }
+}
+
+/// POSTs `message` and dispatches whatever the server replies with. A request
+/// whose exchange fails is completed with a JSON-RPC error describing why.
+async fn send_message(
+    http: reqwest::Client,
+    url: String,
+    session_id: Arc<Mutex<Option<String>>>,
+    message: JSONRPCMessage,
+    pending: Arc<Mutex<HashMap<i64, PendingSender>>>,
+) {
+    let Err(e) = post_message(&http, &url, &session_id, &message, &pending).await else {
+        return;
+    };
+    error!("MCP message to {url} failed: {e:#}");
+    if let JSONRPCMessage::Request(request) = message {
+        let error = JSONRPCError {
+            error: JSONRPCErrorError {
+                code: INTERNAL_ERROR_CODE,
+                data: None,
+                message: format!("{e:#}"),
+            },
+            id: request.id,
+            jsonrpc: JSONRPC_VERSION.to_string(),
+        };
+        McpClient::dispatch_error(error, &pending).await;
+    }
+}
+
+async fn post_message(
+    http: &reqwest::Client,
+    url: &str,
+    session_id: &Mutex<Option<String>>,
+    message: &JSONRPCMessage,
+    pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
+) -> Result<()> {
+    debug!("MCP message to server: {}", serde_json::to_string(message)?);
+    let mut request = http
+        .post(url)
+        .header(ACCEPT, "application/json, text/event-stream")
+        .json(message);
+    if let Some(id) = session_id.lock().await.clone() {
+        request = request.header(SESSION_ID_HEADER, id);
+    }
+    let mut response = request.send().await?;
+
+    if let Some(id) = response
+        .headers()
+        .get(SESSION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        *session_id.lock().await = Some(id.to_string());
+    }
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("HTTP {status}: {}", body.trim());
+    }
+    if status == StatusCode::ACCEPTED {
+        return Ok(());
+    }
+
+    let is_event_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if is_event_stream {
+        let mut parser = SseParser::default();
+        while let Some(chunk) = response.chunk().await? {
+            for data in parser.push(&chunk) {
+                dispatch_payload(&data, pending).await;
+            }
+        }
+    } else {
+        let body = response.text().await?;
+        if !body.trim().is_empty() {
+            dispatch_payload(&body, pending).await;
+        }
+    }
+    Ok(())
+}
+
+async fn dispatch_payload(payload: &str, pending: &Arc<Mutex<HashMap<i64, PendingSender>>>) {
+    debug!("MCP message from server: {payload}");
+    match serde_json::from_str::<JSONRPCMessage>(payload) {
+        Ok(message) => McpClient::dispatch_message(message, pending).await,
+        Err(e) => error!("failed to deserialize JSONRPCMessage: {e}; payload = {payload}"),
+    }
+}
+
+/// Incremental parser for a `text/event-stream` body that yields the `data`
+/// of each `message` event.
+#[derive(Default)]
+struct SseParser {
+    /// Bytes after the last complete line. Chunks may split a UTF-8
+    /// sequence, so lines are only decoded once complete.
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                let event = self.event.take();
+                let data = std::mem::take(&mut self.data);
+                if !data.is_empty() && event.as_deref().is_none_or(|event| event == "message") {
+                    events.push(data.join("\n"));
+                }
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "data" => self.data.push(value.to_string()),
+                "event" => self.event = Some(value.to_string()),
+                // Comments, `id` and `retry` do not affect the payload.
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sse_parser_handles_split_chunks_and_multiline_data() {
+        let mut parser = SseParser::default();
+        assert_eq!(
+            parser.push(b": keep-alive\n\nevent: message\r\nda"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            parser.push(b"ta: {\"a\":\r\ndata: 1}\r\n\r\n"),
+            vec!["{\"a\":\n1}"]
+        );
+        assert_eq!(
+            parser.push(b"event: ping\ndata: x\n\ndata:{}\n\n"),
+            vec!["{}".to_string()]
+        );
+
+        // A multi-byte character split across chunks.
+        let snowman = "data: \u{2603}\n\n".as_bytes();
+        assert_eq!(parser.push(&snowman[..7]), Vec::<String>::new());
+        assert_eq!(parser.push(&snowman[7..]), vec!["\u{2603}".to_string()]);
+    }
+}
@@ -0,0 +1,3 @@
+// Aggregates all former standalone integration tests as modules.
+mod stdio;
+mod streamable_http;
@@ -0,0 +1,41 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use codex_mcp_client::McpClient;
+use pretty_assertions::assert_eq;
+
+#[tokio::test]
+async fn exited_server_is_reported_closed_with_its_stderr() {
+    let client = McpClient::new_stdio_client(
+        "sh".into(),
+        vec![
+            "-c".into(),
+            "echo starting >&2; echo boom >&2; exit 1".into(),
+        ],
+        None,
+    )
+    .await
+    .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), client.closed())
+        .await
+        .unwrap();
+    assert!(client.is_closed());
+
+    // Without a timeout the request would wait forever on a live server.
+    let err = tokio::time::timeout(Duration::from_secs(5), client.list_tools(None, None))
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "MCP server has exited");
+
+    // stderr is read independently of stdout, so give it a moment to drain.
+    for _ in 0..50 {
+        if client.stderr_tail().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(client.stderr_tail(), vec!["starting", "boom"]);
+}
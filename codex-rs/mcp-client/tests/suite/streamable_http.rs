@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::Json;
+use axum::Router;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::routing::post;
+use codex_mcp_client::McpClient;
+use mcp_types::ClientCapabilities;
+use mcp_types::Implementation;
+use mcp_types::InitializeRequestParams;
+use mcp_types::JSONRPCMessage;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+
+const SESSION_ID: &str = "session-1";
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+/// Messages the stand-in server received, as `(method, session id)`.
+type Received = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+/// A stand-in MCP server that answers `initialize` with JSON, `tools/list`
+/// with an event stream, and rejects requests without the expected token.
+#[expect(clippy::unwrap_used)]
+async fn handle(
+    State(received): State<Received>,
+    headers: HeaderMap,
+    Json(message): Json<JSONRPCMessage>,
+) -> Response {
+    if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Bearer secret") {
+        return (StatusCode::UNAUTHORIZED, "missing token").into_response();
+    }
+    let session_id = headers
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let request = match message {
+        JSONRPCMessage::Request(request) => request,
+        JSONRPCMessage::Notification(notification) => {
+            received
+                .lock()
+                .unwrap()
+                .push((notification.method, session_id));
+            return StatusCode::ACCEPTED.into_response();
+        }
+        _ => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    received
+        .lock()
+        .unwrap()
+        .push((request.method.clone(), session_id));
+
+    match request.method.as_str() {
+        "initialize" => {
+            let result = json!({
+                "capabilities": {"tools": {}},
+                "protocolVersion": mcp_types::MCP_SCHEMA_VERSION,
+                "serverInfo": {"name": "stand-in", "version": "1.0.0"},
+            });
+            let body = json!({"jsonrpc": "2.0", "id": request.id, "result": result});
+            ([("mcp-session-id", SESSION_ID)], Json(body)).into_response()
+        }
+        "tools/list" => {
+            let result = json!({
+                "tools": [{"name": "echo", "inputSchema": {"type": "object"}}],
+            });
+            let body = json!({"jsonrpc": "2.0", "id": request.id, "result": result});
+            let stream = format!(
+                ": ping\n\nevent: message\ndata: {{\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\"}}\n\nevent: message\ndata: {body}\n\n"
+            );
+            ([(CONTENT_TYPE, "text/event-stream")], stream).into_response()
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "boom").into_response(),
+    }
+}
+
+#[expect(clippy::unwrap_used)]
+async fn start_server() -> (String, Received) {
+    let received = Received::default();
+    let app = Router::new()
+        .route("/mcp", post(handle))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (format!("http://{addr}/mcp"), received)
+}
+
+fn initialize_params() -> InitializeRequestParams {
+    InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: None,
+            sampling: None,
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: "0.0.0".to_owned(),
+            title: None,
+            user_agent: None,
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn initializes_and_lists_tools_over_streamable_http() {
+    let (url, received) = start_server().await;
+    let headers = HashMap::from([("Authorization".to_string(), "Bearer secret".to_string())]);
+    let client = McpClient::new_streamable_http_client(url, headers).unwrap();
+
+    let initialized = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(initialized.server_info.name, "stand-in");
+
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    let names: Vec<&str> = tools.tools.iter().map(|tool| tool.name.as_str()).collect();
+    assert_eq!(names, vec!["echo"]);
+
+    let session = Some(SESSION_ID.to_string());
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            ("initialize".to_string(), None),
+            ("notifications/initialized".to_string(), session.clone()),
+            ("tools/list".to_string(), session),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn http_errors_fail_the_request() {
+    let (url, _received) = start_server().await;
+
+    let unauthorized = McpClient::new_streamable_http_client(url.clone(), HashMap::new()).unwrap();
+    let err = unauthorized
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+
+    let headers = HashMap::from([("Authorization".to_string(), "Bearer secret".to_string())]);
+    let client = McpClient::new_streamable_http_client(url, headers).unwrap();
+    let err = client
+        .call_tool("echo".to_string(), Some(Value::Null), TIMEOUT)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("HTTP 500"), "{err}");
+}
+
+#[test]
+fn rejects_invalid_headers() {
+    let headers = HashMap::from([("bad header".to_string(), "x".to_string())]);
+    assert!(
+        McpClient::new_streamable_http_client("http://127.0.0.1:1/mcp".to_string(), headers)
+            .is_err()
+    );
+}
@@ -31,6 +31,7 @@ use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem as CoreInputItem;
+use codex_core::protocol::McpToolApprovalRequestEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use codex_login::ServerOptions as LoginServerOptions;
@@ -64,6 +65,9 @@ use codex_protocol::mcp_protocol::LoginApiKeyParams;
 use codex_protocol::mcp_protocol::LoginApiKeyResponse;
 use codex_protocol::mcp_protocol::LoginChatGptCompleteNotification;
 use codex_protocol::mcp_protocol::LoginChatGptResponse;
+use codex_protocol::mcp_protocol::MCP_TOOL_CALL_APPROVAL_METHOD;
+use codex_protocol::mcp_protocol::McpToolCallApprovalParams;
+use codex_protocol::mcp_protocol::McpToolCallApprovalResponse;
 use codex_protocol::mcp_protocol::NewConversationParams;
 use codex_protocol::mcp_protocol::NewConversationResponse;
 use codex_protocol::mcp_protocol::RemoveConversationListenerParams;
@@ -1211,6 +1215,28 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        EventMsg::McpToolApprovalRequest(McpToolApprovalRequestEvent {
+            call_id,
+            invocation,
+            reason,
+        }) => {
+            let params = McpToolCallApprovalParams {
+                conversation_id,
+                call_id,
+                server: invocation.server,
+                tool: invocation.tool,
+                arguments: invocation.arguments,
+                reason,
+            };
+            let value = serde_json::to_value(&params).unwrap_or_default();
+            let rx = outgoing
+                .send_request(MCP_TOOL_CALL_APPROVAL_METHOD, Some(value))
+                .await;
+
+            tokio::spawn(async move {
+                on_mcp_tool_approval_response(event_id, rx, conversation).await;
+            });
+        }
         // If this is a TurnAborted, reply to any pending interrupt requests.
         EventMsg::TurnAborted(turn_aborted_event) => {
             let pending = {
@@ -1349,6 +1375,37 @@ async fn on_exec_approval_response(
     }
 }
 
+async fn on_mcp_tool_approval_response(
+    event_id: String,
+    receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
+    conversation: Arc<CodexConversation>,
+) {
+    let decision = match receiver.await {
+        Ok(value) => serde_json::from_value::<McpToolCallApprovalResponse>(value)
+            .map(|response| response.decision)
+            .unwrap_or_else(|err| {
+                error!("failed to deserialize McpToolCallApprovalResponse: {err}");
+                // If we cannot deserialize the response, we deny the request to be
+                // conservative.
+                ReviewDecision::Denied
+            }),
+        Err(err) => {
+            error!("request failed: {err:?}");
+            return;
+        }
+    };
+
+    if let Err(err) = conversation
+        .submit(Op::McpToolApproval {
+            id: event_id,
+            decision,
+        })
+        .await
+    {
+        error!("failed to submit McpToolApproval: {err}");
+    }
+}
+
 fn extract_conversation_summary(
     path: PathBuf,
     head: &[serde_json::Value],
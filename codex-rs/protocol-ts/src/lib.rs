@@ -11,6 +11,7 @@ use std::process::Command;
 use ts_rs::TS;
 
 const HEADER: &str = "// GENERATED CODE! DO NOT MODIFY BY HAND!\n\n";
+const EXEC_EVENTS_SCHEMA_FILE: &str = "ExecEvents.schema.json";
 
 pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     ensure_dir(out_dir)?;
@@ -39,6 +40,7 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     codex_protocol::mcp_protocol::GetAuthStatusResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ApplyPatchApprovalResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ExecCommandApprovalResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::McpToolCallApprovalResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GetUserSavedConfigResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::SetDefaultModelResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GetUserAgentResponse::export_all_to(out_dir)?;
@@ -48,6 +50,10 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     // induction, so they do not need to be listed individually.
     codex_protocol::mcp_protocol::ServerNotification::export_all_to(out_dir)?;
 
+    // The `codex exec --json` event format, which also gets a JSON Schema.
+    codex_protocol::exec_events::ThreadEvent::export_all_to(out_dir)?;
+    generate_exec_events_json_schema(out_dir)?;
+
     generate_index_ts(out_dir)?;
 
     // Prepend header to each generated .ts file
@@ -73,6 +79,15 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     Ok(())
 }
 
+/// Writes the JSON Schema for one line of `codex exec --json` output.
+pub fn generate_exec_events_json_schema(out_dir: &Path) -> Result<PathBuf> {
+    let schema = schemars::schema_for!(codex_protocol::exec_events::ThreadEvent);
+    let path = out_dir.join(EXEC_EVENTS_SCHEMA_FILE);
+    let content = serde_json::to_string_pretty(&schema)?;
+    fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
 fn ensure_dir(dir: &Path) -> Result<()> {
     fs::create_dir_all(dir)
         .with_context(|| format!("Failed to create output directory {}", dir.display()))
@@ -0,0 +1,264 @@
+//! Public JSONL event format printed by `codex exec --json`.
+//!
+//! Unlike [`crate::protocol::EventMsg`], which mirrors the internals of the
+//! agent and changes with them, these types are a documented contract for
+//! consumers of `codex exec --json`. Each output line is one [`ThreadEvent`]
+//! and the first line is always [`ThreadEvent::Schema`] carrying
+//! [`EXEC_EVENTS_SCHEMA_VERSION`].
+//!
+//! Adding optional fields, item types or event types is backwards compatible.
+//! Removing or renaming anything, or changing what a field means, requires
+//! bumping [`EXEC_EVENTS_SCHEMA_VERSION`].
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// Version of the `codex exec --json` event format.
+pub const EXEC_EVENTS_SCHEMA_VERSION: u32 = 1;
+
+/// One line of `codex exec --json` output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(tag = "type")]
+pub enum ThreadEvent {
+    /// Always the first line; identifies the format of the lines that follow.
+    #[serde(rename = "schema")]
+    Schema(SchemaEvent),
+    /// The thread (session) was created or resumed.
+    #[serde(rename = "thread.started")]
+    ThreadStarted(ThreadStartedEvent),
+    /// The agent started working on a prompt.
+    #[serde(rename = "turn.started")]
+    TurnStarted(TurnStartedEvent),
+    /// The agent finished working on a prompt.
+    #[serde(rename = "turn.completed")]
+    TurnCompleted(TurnCompletedEvent),
+    /// The turn ended because of an error.
+    #[serde(rename = "turn.failed")]
+    TurnFailed(TurnFailedEvent),
+    /// An item began, e.g. a command started running.
+    #[serde(rename = "item.started")]
+    ItemStarted(ItemStartedEvent),
+    /// An item that already started changed, e.g. the todo list was updated.
+    #[serde(rename = "item.updated")]
+    ItemUpdated(ItemUpdatedEvent),
+    /// An item reached its final state.
+    #[serde(rename = "item.completed")]
+    ItemCompleted(ItemCompletedEvent),
+    /// Summary of the session, printed last when `--session-summary` is set.
+    #[serde(rename = "session_summary")]
+    SessionSummary(SessionSummaryEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct SchemaEvent {
+    pub version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ThreadStartedEvent {
+    /// Identifier of the thread; pass it to `codex exec resume` to continue.
+    pub thread_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct TurnStartedEvent {
+    /// `turn_0`, `turn_1`, ... in the order the turns started.
+    pub turn_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct TurnCompletedEvent {
+    pub turn_id: String,
+    /// Tokens used by the turn, when the provider reported them.
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct TurnFailedEvent {
+    pub turn_id: String,
+    pub error: ThreadError,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ThreadError {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ItemStartedEvent {
+    pub item: ThreadItem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ItemUpdatedEvent {
+    pub item: ThreadItem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ItemCompletedEvent {
+    pub item: ThreadItem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct SessionSummaryEvent {
+    pub session_id: String,
+    pub rollout_path: String,
+    pub model: String,
+}
+
+/// Something the agent produced or did during a turn. Events about the same
+/// item share its `id` (`item_0`, `item_1`, ... in the order items started).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ThreadItem {
+    pub id: String,
+    #[serde(flatten)]
+    pub details: ThreadItemDetails,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(tag = "item_type", rename_all = "snake_case")]
+pub enum ThreadItemDetails {
+    /// A message from the agent; the last one of a turn is its answer.
+    AgentMessage(AgentMessageItem),
+    /// A summary of the agent's reasoning.
+    Reasoning(ReasoningItem),
+    /// A shell command run by the agent.
+    CommandExecution(CommandExecutionItem),
+    /// Files changed by a patch.
+    FileChange(FileChangeItem),
+    /// A call to a tool of an MCP server.
+    McpToolCall(McpToolCallItem),
+    /// A web search run by the model.
+    WebSearch(WebSearchItem),
+    /// The agent's plan for the turn.
+    TodoList(TodoListItem),
+    /// A non-fatal error, e.g. a retried stream failure.
+    Error(ErrorItem),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct AgentMessageItem {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ReasoningItem {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct CommandExecutionItem {
+    /// The command as a single shell-quoted string.
+    pub command: String,
+    /// Interleaved stdout and stderr; empty until the command finishes.
+    pub aggregated_output: String,
+    pub exit_code: Option<i32>,
+    pub status: ItemStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct FileChangeItem {
+    pub changes: Vec<FileUpdateChange>,
+    pub status: ItemStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct FileUpdateChange {
+    pub path: String,
+    pub kind: PatchChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchChangeKind {
+    Add,
+    Delete,
+    Update,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct McpToolCallItem {
+    pub server: String,
+    pub tool: String,
+    pub status: ItemStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct WebSearchItem {
+    pub query: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct TodoListItem {
+    pub items: Vec<TodoItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct TodoItem {
+    pub text: String,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, TS)]
+pub struct ErrorItem {
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Lines written by version 1 must keep parsing into the same events.
+    #[test]
+    fn v1_lines_round_trip() {
+        let lines = [
+            r#"{"type":"schema","version":1}"#,
+            r#"{"type":"thread.started","thread_id":"67e55044-10b1-426f-9247-bb680e5fe0c8"}"#,
+            r#"{"type":"turn.started","turn_id":"turn_0"}"#,
+            r#"{"type":"item.started","item":{"id":"item_0","item_type":"command_execution","command":"ls","aggregated_output":"","exit_code":null,"status":"in_progress"}}"#,
+            r#"{"type":"item.updated","item":{"id":"item_1","item_type":"todo_list","items":[{"text":"fix","completed":true}]}}"#,
+            r#"{"type":"item.completed","item":{"id":"item_2","item_type":"mcp_tool_call","server":"docs","tool":"search","status":"failed"}}"#,
+            r#"{"type":"item.completed","item":{"id":"item_3","item_type":"web_search","query":"rust"}}"#,
+            r#"{"type":"item.completed","item":{"id":"item_4","item_type":"error","message":"retrying"}}"#,
+            r#"{"type":"turn.completed","turn_id":"turn_0","usage":null}"#,
+            r#"{"type":"turn.failed","turn_id":"turn_1","error":{"message":"boom"}}"#,
+            r#"{"type":"session_summary","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","rollout_path":"/tmp/rollout.jsonl","model":"gpt-5"}"#,
+        ];
+        for line in lines {
+            let event: ThreadEvent = serde_json::from_str(line).expect(line);
+            assert_eq!(serde_json::to_string(&event).expect("serialize"), line);
+        }
+        assert_eq!(
+            serde_json::from_str::<ThreadEvent>(lines[3]).expect("parse"),
+            ThreadEvent::ItemStarted(ItemStartedEvent {
+                item: ThreadItem {
+                    id: "item_0".to_string(),
+                    details: ThreadItemDetails::CommandExecution(CommandExecutionItem {
+                        command: "ls".to_string(),
+                        aggregated_output: String::new(),
+                        exit_code: None,
+                        status: ItemStatus::InProgress,
+                    }),
+                },
+            })
+        );
+    }
+}
@@ -1,9 +1,11 @@
 pub mod config_types;
 pub mod custom_prompts;
+pub mod exec_events;
 pub mod mcp_protocol;
 pub mod message_history;
 pub mod models;
 pub mod num_format;
 pub mod parse_command;
+pub mod path_pattern;
 pub mod plan_tool;
 pub mod protocol;
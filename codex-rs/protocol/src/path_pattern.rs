@@ -0,0 +1,225 @@
+//! Path patterns for the sandbox's `read_only_paths` and `deny_read_paths`.
+//!
+//! A pattern is a `/`-separated path whose components may use `*`, `?` and
+//! `[...]` wildcards, plus `**` for any number of directories. Relative
+//! patterns are anchored at a writable root; absolute ones at `/`. A pattern
+//! covers what it matches and everything beneath it, so `secrets/`,
+//! `secrets` and `secrets/**` are equivalent.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use wildmatch::WildMatch;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    /// Absolute directory the pattern is anchored at.
+    base: PathBuf,
+    components: Vec<String>,
+}
+
+impl PathPattern {
+    /// Anchors `pattern` at `base` unless `pattern` is absolute.
+    pub fn new(base: &Path, pattern: &str) -> Self {
+        let (base, rest) = match pattern.strip_prefix('/') {
+            Some(rest) => (PathBuf::from("/"), rest),
+            None => (base.to_path_buf(), pattern),
+        };
+        let components = rest
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .map(str::to_string)
+            .collect();
+        Self { base, components }
+    }
+
+    /// Whether `path`, or one of its ancestors, matches the pattern. Both
+    /// `path` as written and the path its symbolic links lead to are checked,
+    /// so a link cannot be used to reach a protected path.
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.matches_under(&self.base, path) {
+            return true;
+        }
+        let Some(resolved) = resolve_symlinks(path) else {
+            return false;
+        };
+        self.matches_under(&self.base, &resolved)
+            || self
+                .base
+                .canonicalize()
+                .is_ok_and(|base| self.matches_under(&base, &resolved))
+    }
+
+    fn matches_under(&self, base: &Path, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(base) else {
+            return false;
+        };
+        let mut names = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => names.push(name.to_string_lossy()),
+                // Refuse to guess what `..` resolves to.
+                _ => return false,
+            }
+        }
+        let names: Vec<&str> = names.iter().map(AsRef::as_ref).collect();
+        matches_prefix(&self.components, &names)
+    }
+
+    /// Paths that currently exist and match the pattern. Matches nested
+    /// inside another match are left out since the outer one covers them.
+    /// Symbolic links are not followed while walking directories.
+    pub fn existing_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        collect_existing(self.base.clone(), &self.components, &mut paths);
+        paths.sort();
+        paths.dedup();
+        let mut outermost: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if !outermost.iter().any(|outer| path.starts_with(outer)) {
+                outermost.push(path);
+            }
+        }
+        outermost
+    }
+}
+
+/// Resolves the symbolic links in the longest existing ancestor of `path`
+/// and appends the rest of `path` unchanged.
+fn resolve_symlinks(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find_map(|ancestor| {
+        let resolved = ancestor.canonicalize().ok()?;
+        let rest = path.strip_prefix(ancestor).ok()?;
+        Some(resolved.join(rest))
+    })
+}
+
+fn matches_prefix(pattern: &[String], names: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((first, rest)) if first == "**" => {
+            (0..=names.len()).any(|skip| matches_prefix(rest, &names[skip..]))
+        }
+        Some((first, rest)) => match names.split_first() {
+            Some((name, tail)) => WildMatch::new(first).matches(name) && matches_prefix(rest, tail),
+            None => false,
+        },
+    }
+}
+
+fn collect_existing(path: PathBuf, pattern: &[String], out: &mut Vec<PathBuf>) {
+    let Some((first, rest)) = pattern.split_first() else {
+        out.push(path);
+        return;
+    };
+    if first == "**" {
+        collect_existing(path.clone(), rest, out);
+        for child in child_directories(&path) {
+            collect_existing(child, pattern, out);
+        }
+    } else if !first.contains(['*', '?', '[']) {
+        let child = path.join(first);
+        if child.symlink_metadata().is_ok() {
+            collect_existing(child, rest, out);
+        }
+    } else {
+        let matcher = WildMatch::new(first);
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if matcher.matches(&entry.file_name().to_string_lossy()) {
+                collect_existing(entry.path(), rest, out);
+            }
+        }
+    }
+}
+
+fn child_directories(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn matches_paths_and_their_descendants() {
+        let root = Path::new("/repo");
+        let env = PathPattern::new(root, ".env");
+        assert!(env.matches(Path::new("/repo/.env")));
+        assert!(!env.matches(Path::new("/repo/app/.env")));
+        assert!(!env.matches(Path::new("/repo/.envrc")));
+
+        let secrets = PathPattern::new(root, "secrets/");
+        assert!(secrets.matches(Path::new("/repo/secrets")));
+        assert!(secrets.matches(Path::new("/repo/secrets/db/password")));
+        assert!(!secrets.matches(Path::new("/elsewhere/secrets")));
+
+        let prod = PathPattern::new(root, "infra/prod/**");
+        assert!(prod.matches(Path::new("/repo/infra/prod/main.tf")));
+        assert!(!prod.matches(Path::new("/repo/infra/staging/main.tf")));
+
+        let anywhere = PathPattern::new(root, "**/*.pem");
+        assert!(anywhere.matches(Path::new("/repo/key.pem")));
+        assert!(anywhere.matches(Path::new("/repo/a/b/key.pem")));
+        assert!(!anywhere.matches(Path::new("/repo/a/b/key.pem.txt")));
+
+        let absolute = PathPattern::new(root, "/home/*/.aws");
+        assert!(absolute.matches(Path::new("/home/me/.aws/credentials")));
+        assert!(!absolute.matches(Path::new("/repo/home/me/.aws")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn matches_paths_reached_through_symlinks() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir(root.join("secrets")).unwrap();
+        std::fs::write(root.join(".env"), "").unwrap();
+        std::os::unix::fs::symlink(root.join(".env"), root.join("env-link")).unwrap();
+        std::os::unix::fs::symlink(root.join("secrets"), root.join("vault")).unwrap();
+
+        assert!(PathPattern::new(root, ".env").matches(&root.join("env-link")));
+        assert!(PathPattern::new(root, "secrets/").matches(&root.join("vault/new-key")));
+        assert!(!PathPattern::new(root, "secrets/").matches(&root.join("env-link")));
+    }
+
+    #[test]
+    fn existing_paths_returns_outermost_matches() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        for dir in ["a/.env", "b/c", "secrets/nested"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join(".env"), "").unwrap();
+        std::fs::write(root.join("b/c/.env"), "").unwrap();
+
+        assert_eq!(
+            PathPattern::new(root, "**/.env").existing_paths(),
+            vec![
+                root.join(".env"),
+                root.join("a/.env"),
+                root.join("b/c/.env")
+            ]
+        );
+        assert_eq!(
+            PathPattern::new(root, "secrets/**").existing_paths(),
+            vec![root.join("secrets")]
+        );
+        assert_eq!(
+            PathPattern::new(root, "missing/*").existing_paths(),
+            Vec::<PathBuf>::new()
+        );
+    }
+}
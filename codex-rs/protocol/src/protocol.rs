@@ -18,8 +18,12 @@ use crate::message_history::HistoryEntry;
 use crate::models::ResponseItem;
 use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
+use crate::path_pattern::PathPattern;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt as McpPrompt;
+use mcp_types::Resource as McpResource;
 use mcp_types::Tool as McpTool;
 use serde::Deserialize;
 use serde::Serialize;
@@ -145,6 +149,14 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve an MCP tool call
+    McpToolApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -165,13 +177,31 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::McpListToolsResponse`.
     ListMcpTools,
 
+    /// Request the list of MCP resources available across all configured
+    /// servers. Reply is delivered via `EventMsg::McpListResourcesResponse`.
+    ListMcpResources,
+
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Render the prompt `name` offered by the MCP server `server`.
+    /// Reply is delivered via `EventMsg::GetMcpPromptResponse`.
+    GetMcpPrompt {
+        server: String,
+        name: String,
+        arguments: HashMap<String, String>,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
     Compact,
+
+    /// Restore the files changed by the most recent turn to how they were
+    /// before it. Reply is delivered via `EventMsg::TurnUndone`, or
+    /// `EventMsg::Error` if a turn is running or the files changed since.
+    UndoTurn,
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -204,6 +234,23 @@ pub enum AskForApproval {
     Never,
 }
 
+impl AskForApproval {
+    /// Whether this policy runs commands without asking where `other` would
+    /// ask. `OnFailure` and `OnRequest` count as equally strict since both ask
+    /// before a command runs outside the sandbox.
+    pub fn is_looser_than(self, other: AskForApproval) -> bool {
+        self.leniency() > other.leniency()
+    }
+
+    fn leniency(self) -> u8 {
+        match self {
+            AskForApproval::UnlessTrusted => 0,
+            AskForApproval::OnFailure | AskForApproval::OnRequest => 1,
+            AskForApproval::Never => 2,
+        }
+    }
+}
+
 /// Determines execution restrictions for model shell commands.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Display, TS)]
 #[strum(serialize_all = "kebab-case")]
@@ -231,6 +278,22 @@ pub enum SandboxPolicy {
         #[serde(default)]
         network_access: bool,
 
+        /// Hosts that remain reachable when `network_access` is `false`, as
+        /// `host`, `host:port` or `*.domain`. Connections go through an HTTP
+        /// proxy on loopback that only forwards to these hosts.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        network_allowlist: Vec<String>,
+
+        /// Paths inside the writable roots that must stay read-only, as
+        /// [`PathPattern`]s. Relative patterns apply to every writable root.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        read_only_paths: Vec<String>,
+
+        /// Paths that may be neither read nor written, as [`PathPattern`]s.
+        /// Relative patterns apply to every writable root.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        deny_read_paths: Vec<String>,
+
         /// When set to `true`, will NOT include the per-user `TMPDIR`
         /// environment variable among the default writable roots. Defaults to
         /// `false`.
@@ -255,6 +318,11 @@ pub struct WritableRoot {
 
     /// Also absolute paths, by construction.
     pub read_only_subpaths: Vec<PathBuf>,
+
+    /// The policy's `read_only_paths` and `deny_read_paths`, with relative
+    /// patterns anchored at every writable root. Unlike `read_only_subpaths`, these also cover paths that do not
+    /// exist yet.
+    pub read_only_patterns: Vec<PathPattern>,
 }
 
 impl WritableRoot {
@@ -271,8 +339,31 @@ impl WritableRoot {
             }
         }
 
-        true
+        !self
+            .read_only_patterns
+            .iter()
+            .any(|pattern| pattern.matches(path))
+    }
+}
+
+/// Anchors relative `patterns` at each of `roots`; absolute ones are kept once.
+fn anchor_path_patterns<'a>(
+    patterns: impl IntoIterator<Item = &'a String>,
+    roots: &[PathBuf],
+) -> Vec<PathPattern> {
+    let mut anchored = Vec::new();
+    for pattern in patterns {
+        if pattern.starts_with('/') {
+            anchored.push(PathPattern::new(Path::new("/"), pattern));
+        } else {
+            anchored.extend(
+                roots
+                    .iter()
+                    .map(|writable_root| PathPattern::new(writable_root, pattern)),
+            );
+        }
     }
+    anchored
 }
 
 impl FromStr for SandboxPolicy {
@@ -296,6 +387,9 @@ impl SandboxPolicy {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
             network_access: false,
+            network_allowlist: vec![],
+            read_only_paths: vec![],
+            deny_read_paths: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         }
@@ -322,6 +416,50 @@ impl SandboxPolicy {
         }
     }
 
+    /// Hosts reachable through the sandbox's network proxy. Empty unless the
+    /// policy restricts network access and lists hosts to allow.
+    pub fn network_allowlist(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                network_allowlist,
+                ..
+            } => network_allowlist,
+            _ => &[],
+        }
+    }
+
+    /// Whether the policy lists `read_only_paths` or `deny_read_paths`, which
+    /// only a sandbox can enforce.
+    pub fn has_protected_paths(&self) -> bool {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                read_only_paths,
+                deny_read_paths,
+                ..
+            } => !read_only_paths.is_empty() || !deny_read_paths.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Patterns for the paths that may not be read, each relative
+    /// `deny_read_paths` entry anchored at every writable root.
+    pub fn deny_read_patterns(&self, cwd: &Path) -> Vec<PathPattern> {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                deny_read_paths, ..
+            } => {
+                let roots: Vec<PathBuf> = self
+                    .get_writable_roots_with_cwd(cwd)
+                    .into_iter()
+                    .map(|writable_root| writable_root.root)
+                    .collect();
+                anchor_path_patterns(deny_read_paths, &roots)
+            }
+            _ => Vec::new(),
+        }
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
@@ -334,6 +472,9 @@ impl SandboxPolicy {
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
                 network_access: _,
+                network_allowlist: _,
+                read_only_paths,
+                deny_read_paths,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -365,6 +506,12 @@ impl SandboxPolicy {
                     roots.push(PathBuf::from(tmpdir));
                 }
 
+                // Paths that may not be read may not be written either. Every
+                // root gets the patterns of all roots so that a nested root
+                // cannot bypass the patterns of the root it lives in.
+                let read_only_patterns =
+                    anchor_path_patterns(read_only_paths.iter().chain(deny_read_paths), &roots);
+
                 // For each root, compute subpaths that should remain read-only.
                 roots
                     .into_iter()
@@ -377,6 +524,7 @@ impl SandboxPolicy {
                         WritableRoot {
                             root: writable_root,
                             read_only_subpaths: subpaths,
+                            read_only_patterns: read_only_patterns.clone(),
                         }
                     })
                     .collect()
@@ -488,6 +636,8 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    McpToolApprovalRequest(McpToolApprovalRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that a model stream experienced an error or disconnect
@@ -509,13 +659,22 @@ pub enum EventMsg {
     /// List of MCP tools available to the agent.
     McpListToolsResponse(McpListToolsResponseEvent),
 
+    /// List of MCP resources available to the agent.
+    McpListResourcesResponse(McpListResourcesResponseEvent),
+
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// Response to GetMcpPrompt.
+    GetMcpPromptResponse(GetMcpPromptResponseEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
 
+    /// Files restored in response to `Op::UndoTurn`.
+    TurnUndone(TurnUndoneEvent),
+
     /// Notification that the agent is shutting down.
     ShutdownComplete,
 
@@ -826,6 +985,9 @@ pub struct SubAgentStartedEvent {
     pub sub_id: String,
     /// Human-readable label for UI display.
     pub label: String,
+    /// Rollout file holding the sub-agent's own transcript, when persisted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -943,6 +1105,20 @@ pub struct SessionMeta {
     pub originator: String,
     pub cli_version: String,
     pub instructions: Option<String>,
+    /// Set when this rollout records a sub-agent launched by another conversation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_agent: Option<SubAgentSource>,
+}
+
+/// Links a sub-agent rollout back to the conversation that launched it.
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+pub struct SubAgentSource {
+    /// Conversation that launched the sub-agent.
+    pub parent_id: ConversationId,
+    /// Sub-agent instance id, matching `SubAgentStartedEvent::sub_id`.
+    pub sub_id: String,
+    /// Label shown for the sub-agent.
+    pub label: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
@@ -961,6 +1137,7 @@ pub enum RolloutItem {
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
+    Checkpoint(CheckpointItem),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
@@ -978,6 +1155,16 @@ pub struct TurnContextItem {
     pub summary: ReasoningSummaryConfig,
 }
 
+/// Snapshot of the git worktree taken before a user turn ran, stored as a
+/// commit on a hidden ref so backtracking can bring the files back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, TS)]
+pub struct CheckpointItem {
+    /// Top level of the git repository the snapshot was taken in.
+    pub root: PathBuf,
+    /// Commit whose tree holds the worktree contents.
+    pub commit: String,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RolloutLine {
     pub timestamp: String,
@@ -1075,6 +1262,17 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpToolApprovalRequestEvent {
+    /// Responses API call id for the associated MCP tool call.
+    pub call_id: String,
+    /// The tool call awaiting approval.
+    pub invocation: McpInvocation,
+    /// Optional human-readable reason for the approval (e.g. the tool is not read-only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -1126,12 +1324,54 @@ pub struct GetHistoryEntryResponseEvent {
 pub struct McpListToolsResponseEvent {
     /// Fully qualified tool name -> tool definition.
     pub tools: std::collections::HashMap<String, McpTool>,
+    /// Server name -> connection status, for every configured server.
+    #[serde(default)]
+    pub servers: HashMap<String, McpServerStatus>,
+}
+
+/// Connection status of a configured MCP server.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct McpServerStatus {
+    pub state: McpServerState,
+    /// The last lines the server wrote to stderr, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stderr_tail: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum McpServerState {
+    Connected,
+    /// The server exited and is being started again.
+    Restarting { attempt: u32 },
+    /// The server could not be started, or could not be restarted after it
+    /// exited.
+    Failed { error: String },
+}
+
+/// Response payload for `Op::ListMcpResources`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpListResourcesResponseEvent {
+    /// Server name -> resources offered by that server.
+    pub resources: HashMap<String, Vec<McpResource>>,
 }
 
 /// Response payload for `Op::ListCustomPrompts`.
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
+    /// Server name -> prompts offered by that MCP server.
+    #[serde(default)]
+    pub mcp_prompts: HashMap<String, Vec<McpPrompt>>,
+}
+
+/// Response payload for `Op::GetMcpPrompt`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct GetMcpPromptResponseEvent {
+    pub server: String,
+    pub name: String,
+    /// The rendered prompt, or why it could not be rendered.
+    pub result: Result<GetPromptResult, String>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
@@ -1209,6 +1449,12 @@ pub struct TurnAbortedEvent {
     pub reason: TurnAbortReason,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct TurnUndoneEvent {
+    /// Paths that were restored, removed, or moved back.
+    pub paths: Vec<PathBuf>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "snake_case")]
 pub enum TurnAbortReason {
@@ -10,6 +10,7 @@ use crate::tui::TuiEvent;
 use codex_ansi_escape::ansi_escape_line;
 use codex_core::AuthManager;
 use codex_core::ConversationManager;
+use codex_core::checkpoint::restore_checkpoint;
 use codex_core::config::Config;
 use codex_core::config::TopLevelConfigUpdate;
 use codex_core::config::apply_top_level_config_update;
@@ -280,6 +281,14 @@ impl App {
             AppEvent::ConversationHistory(ev) => {
                 self.on_conversation_history_for_backtrack(tui, ev).await?;
             }
+            AppEvent::RestoreCheckpoint(checkpoint) => {
+                // Save the files being replaced under the current conversation.
+                let conversation_id = self.chat_widget.conversation_id().unwrap_or_default();
+                let result = restore_checkpoint(&checkpoint, conversation_id)
+                    .await
+                    .map_err(|e| format!("{e:#}"));
+                self.chat_widget.on_checkpoint_restored(result);
+            }
             AppEvent::ExitRequest => {
                 return Ok(false);
             }
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::path::PathBuf;
 
 use crate::app::App;
@@ -5,6 +6,7 @@ use crate::backtrack_helpers;
 use crate::pager_overlay::Overlay;
 use crate::tui;
 use crate::tui::TuiEvent;
+use codex_core::checkpoint::checkpoint_before_nth_last_user_message;
 use codex_core::protocol::ConversationPathResponseEvent;
 use codex_protocol::mcp_protocol::ConversationId;
 use color_eyre::eyre::Result;
@@ -294,12 +296,23 @@ impl App {
             .await;
         match result {
             Ok(new_conv) => {
-                self.install_forked_conversation(tui, cfg, new_conv, drop_count, &prefill)
+                self.install_forked_conversation(tui, cfg, new_conv, drop_count, &prefill);
+                self.offer_checkpoint_restore(&ev.path, drop_count).await;
             }
             Err(e) => tracing::error!("error forking conversation: {e:#}"),
         }
     }
 
+    /// If the rollout has a worktree checkpoint for the message we backtracked
+    /// to, ask whether the files should go back to it as well.
+    async fn offer_checkpoint_restore(&mut self, path: &Path, drop_count: usize) {
+        match checkpoint_before_nth_last_user_message(path, drop_count).await {
+            Ok(Some(checkpoint)) => self.chat_widget.open_restore_checkpoint_popup(checkpoint),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("failed to look up checkpoint: {e:#}"),
+        }
+    }
+
     /// Thin wrapper around ConversationManager::fork_conversation.
     async fn perform_fork(
         &self,
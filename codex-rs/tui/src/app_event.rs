@@ -1,3 +1,4 @@
+use codex_core::protocol::CheckpointItem;
 use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::Event;
 use codex_file_search::FileMatch;
@@ -63,4 +64,7 @@ pub(crate) enum AppEvent {
 
     /// Forwarded conversation history snapshot from the current conversation.
     ConversationHistory(ConversationPathResponseEvent),
+
+    /// Restore the worktree to a checkpoint picked after backtracking.
+    RestoreCheckpoint(CheckpointItem),
 }
@@ -29,10 +29,13 @@ use ratatui::widgets::WidgetRef;
 use super::chat_composer_history::ChatComposerHistory;
 use super::command_popup::CommandItem;
 use super::command_popup::CommandPopup;
+use super::command_popup::McpPromptEntry;
 use super::file_search_popup::FileSearchPopup;
 use super::model_search_popup::ModelSearchPopup;
 use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
+use super::resource_search_popup::McpResourceEntry;
+use super::resource_search_popup::ResourceSearchPopup;
 use crate::bottom_pane::paste_burst::FlushResult;
 use crate::slash_command::SlashCommand;
 use codex_protocol::custom_prompts::CustomPrompt;
@@ -64,6 +67,12 @@ const ESC_CLEAR_WINDOW: Duration = Duration::from_secs(2);
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    /// Run the prompt `name` of the MCP server `server`.
+    McpPrompt {
+        server: String,
+        name: String,
+        arguments: HashMap<String, String>,
+    },
     None,
 }
 
@@ -99,6 +108,8 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPromptEntry>,
+    mcp_resources: Vec<McpResourceEntry>,
     // Double-Esc clear: timestamp when Esc was last pressed to prime clearing.
     esc_clear_primed_at: Option<Instant>,
 }
@@ -109,6 +120,7 @@ enum ActivePopup {
     Command(CommandPopup),
     File(FileSearchPopup),
     Model(ModelSearchPopup),
+    Resource(ResourceSearchPopup),
 }
 
 impl ChatComposer {
@@ -142,6 +154,8 @@ impl ChatComposer {
             enter_suppress_until: None,
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            mcp_prompts: Vec::new(),
+            mcp_resources: Vec::new(),
             esc_clear_primed_at: None,
         };
         // Apply configuration via the setter to keep side-effects centralized.
@@ -156,6 +170,7 @@ impl ChatComposer {
                 ActivePopup::Command(c) => c.calculate_required_height(),
                 ActivePopup::File(c) => c.calculate_required_height(),
                 ActivePopup::Model(c) => c.calculate_required_height(),
+                ActivePopup::Resource(c) => c.calculate_required_height(),
             }
     }
 
@@ -164,6 +179,7 @@ impl ChatComposer {
             ActivePopup::Command(popup) => popup.calculate_required_height(),
             ActivePopup::File(popup) => popup.calculate_required_height(),
             ActivePopup::Model(popup) => popup.calculate_required_height(),
+            ActivePopup::Resource(popup) => popup.calculate_required_height(),
             ActivePopup::None => 1,
         };
         let [textarea_rect, _] =
@@ -347,6 +363,7 @@ impl ChatComposer {
             ActivePopup::Command(_) => self.handle_key_event_with_slash_popup(key_event),
             ActivePopup::File(_) => self.handle_key_event_with_file_popup(key_event),
             ActivePopup::Model(_) => self.handle_key_event_with_model_popup(key_event),
+            ActivePopup::Resource(_) => self.handle_key_event_with_resource_popup(key_event),
             ActivePopup::None => self.handle_key_event_without_popup(key_event),
         };
 
@@ -419,6 +436,16 @@ impl ChatComposer {
                                 }
                             }
                         }
+                        CommandItem::McpPrompt(idx) => {
+                            if let Some(entry) = popup.mcp_prompt(idx) {
+                                let command = entry.command();
+                                let starts_with_cmd =
+                                    first_line.trim_start().starts_with(&format!("/{command}"));
+                                if !starts_with_cmd {
+                                    self.textarea.set_text(&format!("/{command} "));
+                                }
+                            }
+                        }
                     }
                     // After completing the command, move cursor to the end.
                     if !self.textarea.text().is_empty() {
@@ -434,6 +461,11 @@ impl ChatComposer {
                 ..
             } => {
                 if let Some(sel) = popup.selected_item() {
+                    if let CommandItem::McpPrompt(idx) = sel
+                        && let Some(entry) = popup.mcp_prompt(idx).cloned()
+                    {
+                        return self.submit_mcp_prompt(entry);
+                    }
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
                     // Capture any needed data from popup before clearing it.
@@ -450,7 +482,7 @@ impl ChatComposer {
                         CommandItem::Builtin(cmd) => {
                             return (InputResult::Command(cmd), true);
                         }
-                        CommandItem::UserPrompt(_) => {
+                        CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_) => {
                             if let Some(contents) = prompt_content {
                                 return (InputResult::Submitted(contents), true);
                             }
@@ -642,6 +674,104 @@ impl ChatComposer {
         }
     }
 
+    /// Handle key event when the MCP resource popup is visible.
+    fn handle_key_event_with_resource_popup(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
+        let ActivePopup::Resource(popup) = &mut self.active_popup else {
+            unreachable!();
+        };
+
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Up, ..
+            } => {
+                popup.move_up();
+                (InputResult::None, true)
+            }
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            } => {
+                popup.move_down();
+                (InputResult::None, true)
+            }
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => {
+                if let Some(tok) = Self::current_at_token(&self.textarea) {
+                    self.dismissed_file_popup_token = Some(tok);
+                }
+                self.active_popup = ActivePopup::None;
+                (InputResult::None, true)
+            }
+            KeyEvent {
+                code: KeyCode::Tab, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+                ..
+            } => {
+                if let Some(mention) = popup.selected_mention() {
+                    self.insert_selected_path(&mention);
+                }
+                self.active_popup = ActivePopup::None;
+                (InputResult::None, true)
+            }
+            input => self.handle_input_basic(input),
+        }
+    }
+
+    /// Run the selected MCP prompt with the `key=value` arguments typed after
+    /// the command. When a required argument is missing, the command is
+    /// rewritten with a placeholder for each argument so the user can fill
+    /// them in and press Enter again.
+    fn submit_mcp_prompt(&mut self, entry: McpPromptEntry) -> (InputResult, bool) {
+        let first_line = self.textarea.text().lines().next().unwrap_or("");
+        let mut arguments = parse_prompt_arguments(first_line);
+        let declared = entry.prompt.arguments.clone().unwrap_or_default();
+        let missing_required = declared.iter().any(|arg| {
+            arg.required.unwrap_or(false)
+                && arguments
+                    .get(&arg.name)
+                    .is_none_or(|value| value.is_empty())
+        });
+        if missing_required {
+            let mut text = format!("/{}", entry.command());
+            for arg in &declared {
+                let value = arguments.get(&arg.name).map(String::as_str).unwrap_or("");
+                text.push_str(&format!(" {}={value}", arg.name));
+            }
+            self.textarea.set_text(&text);
+            // Leave the cursor after the first argument still to be filled.
+            let cursor = declared
+                .iter()
+                .find(|arg| {
+                    arguments
+                        .get(&arg.name)
+                        .is_none_or(|value| value.is_empty())
+                })
+                .and_then(|arg| {
+                    text.find(&format!(" {}=", arg.name))
+                        .map(|i| i + arg.name.len() + 2)
+                })
+                .unwrap_or(text.len());
+            self.textarea.set_cursor(cursor);
+            return (InputResult::None, true);
+        }
+
+        arguments.retain(|_, value| !value.is_empty());
+        self.textarea.set_text("");
+        self.active_popup = ActivePopup::None;
+        (
+            InputResult::McpPrompt {
+                server: entry.server,
+                name: entry.prompt.name,
+                arguments,
+            },
+            true,
+        )
+    }
+
     fn is_image_path(path: &str) -> bool {
         let lower = path.to_ascii_lowercase();
         lower.ends_with(".png") || lower.ends_with(".jpg") || lower.ends_with(".jpeg")
@@ -1257,6 +1387,7 @@ impl ChatComposer {
             _ => {
                 if input_starts_with_slash {
                     let mut command_popup = CommandPopup::new(self.custom_prompts.clone());
+                    command_popup.set_mcp_prompts(self.mcp_prompts.clone());
                     command_popup.on_composer_text_change(first_line.to_string());
                     self.active_popup = ActivePopup::Command(command_popup);
                 }
@@ -1271,8 +1402,20 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptEntry>) {
+        self.mcp_prompts = prompts.clone();
+        if let ActivePopup::Command(popup) = &mut self.active_popup {
+            popup.set_mcp_prompts(prompts);
+        }
+    }
+
+    pub(crate) fn set_mcp_resources(&mut self, resources: Vec<McpResourceEntry>) {
+        self.mcp_resources = resources;
+    }
+
     /// Synchronize the @-token popup with the current text in the textarea.
-    /// Chooses between model-selector and file-search heuristically.
+    /// Chooses between MCP resources, model-selector and file-search
+    /// heuristically.
     /// Note this is only called when self.active_popup is NOT Command.
     fn sync_file_search_popup(&mut self) {
         // Determine if there is an @token underneath the cursor.
@@ -1290,6 +1433,21 @@ impl ChatComposer {
             return;
         }
 
+        // `@server:` tokens complete the resources of that MCP server.
+        if ResourceSearchPopup::has_matches(&query, &self.mcp_resources) {
+            match &mut self.active_popup {
+                ActivePopup::Resource(popup) => popup.set_query(&query, &self.mcp_resources),
+                _ => {
+                    let mut popup = ResourceSearchPopup::new();
+                    popup.set_query(&query, &self.mcp_resources);
+                    self.active_popup = ActivePopup::Resource(popup);
+                }
+            }
+            self.current_file_query = Some(query);
+            self.dismissed_file_popup_token = None;
+            return;
+        }
+
         // Heuristic: if token looks file-like (contains '/', '\\', or '.') use file search;
         // otherwise, if it has at least 1 char and matches known model presets by prefix, show model selector.
         let looks_like_file = query.contains('/') || query.contains('\\') || query.contains('.');
@@ -1346,12 +1504,33 @@ impl ChatComposer {
     }
 }
 
+/// Parses the `key=value` arguments that follow the command on `line`.
+/// Values may be quoted to include spaces, as in `topic="error handling"`.
+fn parse_prompt_arguments(line: &str) -> HashMap<String, String> {
+    let rest = line
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .map(|(_, rest)| rest)
+        .unwrap_or("");
+    let tokens =
+        shlex::split(rest).unwrap_or_else(|| rest.split_whitespace().map(str::to_string).collect());
+    tokens
+        .into_iter()
+        .filter_map(|token| {
+            token
+                .split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
 impl WidgetRef for ChatComposer {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         let popup_height = match &self.active_popup {
             ActivePopup::Command(popup) => popup.calculate_required_height(),
             ActivePopup::File(popup) => popup.calculate_required_height(),
             ActivePopup::Model(popup) => popup.calculate_required_height(),
+            ActivePopup::Resource(popup) => popup.calculate_required_height(),
             ActivePopup::None => 1,
         };
         let [textarea_rect, popup_rect] =
@@ -1366,6 +1545,9 @@ impl WidgetRef for ChatComposer {
             ActivePopup::Model(popup) => {
                 popup.render_ref(popup_rect, buf);
             }
+            ActivePopup::Resource(popup) => {
+                popup.render_ref(popup_rect, buf);
+            }
             ActivePopup::None => {
                 let bottom_line_rect = popup_rect;
 
@@ -1936,7 +2118,7 @@ mod tests {
                 Some(CommandItem::Builtin(cmd)) => {
                     assert_eq!(cmd.command(), "model")
                 }
-                Some(CommandItem::UserPrompt(_)) => {
+                Some(CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_)) => {
                     panic!("unexpected prompt selected for '/mo'")
                 }
                 None => panic!("no selected command for '/mo'"),
@@ -1989,7 +2171,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/init'"),
+            InputResult::McpPrompt { .. } | InputResult::None => {
+                panic!("expected Command result for '/init'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
     }
@@ -2047,7 +2231,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/mention'"),
+            InputResult::McpPrompt { .. } | InputResult::None => {
+                panic!("expected Command result for '/mention'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
         composer.insert_str("@");
@@ -2480,6 +2666,61 @@ mod tests {
         assert_eq!(InputResult::Submitted(prompt_text.to_string()), result);
     }
 
+    #[test]
+    fn selecting_mcp_prompt_asks_for_required_arguments() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        composer.set_mcp_prompts(vec![McpPromptEntry {
+            server: "gh".to_string(),
+            prompt: mcp_types::Prompt {
+                arguments: Some(vec![
+                    mcp_types::PromptArgument {
+                        description: None,
+                        name: "pr".to_string(),
+                        required: Some(true),
+                        title: None,
+                    },
+                    mcp_types::PromptArgument {
+                        description: None,
+                        name: "tone".to_string(),
+                        required: None,
+                        title: None,
+                    },
+                ]),
+                description: None,
+                name: "review".to_string(),
+                title: None,
+            },
+        }]);
+
+        type_chars_humanlike(&mut composer, &['/', 'g', 'h', ':', 'r', 'e', 'v']);
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(InputResult::None, result);
+        assert_eq!(composer.textarea.text(), "/gh:review pr= tone=");
+        assert_eq!(composer.textarea.cursor(), "/gh:review pr=".len());
+
+        type_chars_humanlike(&mut composer, &['4', '2']);
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(
+            InputResult::McpPrompt {
+                server: "gh".to_string(),
+                name: "review".to_string(),
+                arguments: HashMap::from([("pr".to_string(), "42".to_string())]),
+            },
+            result
+        );
+        assert!(composer.textarea.is_empty());
+    }
+
     #[test]
     fn burst_paste_fast_small_buffers_and_flushes_on_stop() {
         use crossterm::event::KeyCode;
@@ -10,20 +10,38 @@ use crate::slash_command::SlashCommand;
 use crate::slash_command::built_in_slash_commands;
 use codex_common::fuzzy_match::fuzzy_match;
 use codex_protocol::custom_prompts::CustomPrompt;
+use mcp_types::Prompt;
 use std::collections::HashSet;
 
-/// A selectable item in the popup: either a built-in command or a user prompt.
+/// A selectable item in the popup: a built-in command, a user prompt or a
+/// prompt offered by an MCP server.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum CommandItem {
     Builtin(SlashCommand),
     // Index into `prompts`
     UserPrompt(usize),
+    // Index into `mcp_prompts`
+    McpPrompt(usize),
+}
+
+/// A prompt offered by an MCP server, invoked as `/server:prompt`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct McpPromptEntry {
+    pub server: String,
+    pub prompt: Prompt,
+}
+
+impl McpPromptEntry {
+    pub(crate) fn command(&self) -> String {
+        format!("{}:{}", self.server, self.prompt.name)
+    }
 }
 
 pub(crate) struct CommandPopup {
     command_filter: String,
     builtins: Vec<(&'static str, SlashCommand)>,
     prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPromptEntry>,
     state: ScrollState,
 }
 
@@ -38,10 +56,20 @@ impl CommandPopup {
             command_filter: String::new(),
             builtins,
             prompts,
+            mcp_prompts: Vec::new(),
             state: ScrollState::new(),
         }
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, mut prompts: Vec<McpPromptEntry>) {
+        prompts.sort_by_key(McpPromptEntry::command);
+        self.mcp_prompts = prompts;
+    }
+
+    pub(crate) fn mcp_prompt(&self, idx: usize) -> Option<&McpPromptEntry> {
+        self.mcp_prompts.get(idx)
+    }
+
     pub(crate) fn set_prompts(&mut self, mut prompts: Vec<CustomPrompt>) {
         let exclude: HashSet<String> = self
             .builtins
@@ -113,6 +141,9 @@ impl CommandPopup {
             for idx in 0..self.prompts.len() {
                 out.push((CommandItem::UserPrompt(idx), None, 0));
             }
+            for idx in 0..self.mcp_prompts.len() {
+                out.push((CommandItem::McpPrompt(idx), None, 0));
+            }
             return out;
         }
 
@@ -126,23 +157,27 @@ impl CommandPopup {
                 out.push((CommandItem::UserPrompt(idx), Some(indices), score));
             }
         }
+        for (idx, p) in self.mcp_prompts.iter().enumerate() {
+            if let Some((indices, score)) = fuzzy_match(&p.command(), filter) {
+                out.push((CommandItem::McpPrompt(idx), Some(indices), score));
+            }
+        }
         // When filtering, sort by ascending score and then by name for stability.
         out.sort_by(|a, b| {
-            a.2.cmp(&b.2).then_with(|| {
-                let an = match a.0 {
-                    CommandItem::Builtin(c) => c.command(),
-                    CommandItem::UserPrompt(i) => &self.prompts[i].name,
-                };
-                let bn = match b.0 {
-                    CommandItem::Builtin(c) => c.command(),
-                    CommandItem::UserPrompt(i) => &self.prompts[i].name,
-                };
-                an.cmp(bn)
-            })
+            a.2.cmp(&b.2)
+                .then_with(|| self.item_name(a.0).cmp(&self.item_name(b.0)))
         });
         out
     }
 
+    fn item_name(&self, item: CommandItem) -> String {
+        match item {
+            CommandItem::Builtin(c) => c.command().to_string(),
+            CommandItem::UserPrompt(i) => self.prompts[i].name.clone(),
+            CommandItem::McpPrompt(i) => self.mcp_prompts[i].command(),
+        }
+    }
+
     fn filtered_items(&self) -> Vec<CommandItem> {
         self.filtered().into_iter().map(|(c, _, _)| c).collect()
     }
@@ -192,6 +227,21 @@ impl WidgetRef for CommandPopup {
                         is_current: false,
                         description: Some("send saved prompt".to_string()),
                     },
+                    CommandItem::McpPrompt(i) => {
+                        let entry = &self.mcp_prompts[i];
+                        GenericDisplayRow {
+                            name: format!("/{}", entry.command()),
+                            match_indices: indices.map(|v| v.into_iter().map(|i| i + 1).collect()),
+                            is_current: false,
+                            description: Some(
+                                entry
+                                    .prompt
+                                    .description
+                                    .clone()
+                                    .unwrap_or_else(|| "send MCP prompt".to_string()),
+                            ),
+                        }
+                    }
                 })
                 .collect()
         };
@@ -223,7 +273,7 @@ mod tests {
         let matches = popup.filtered_items();
         let has_init = matches.iter().any(|item| match item {
             CommandItem::Builtin(cmd) => cmd.command() == "init",
-            CommandItem::UserPrompt(_) => false,
+            CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_) => false,
         });
         assert!(
             has_init,
@@ -241,7 +291,9 @@ mod tests {
         let selected = popup.selected_item();
         match selected {
             Some(CommandItem::Builtin(cmd)) => assert_eq!(cmd.command(), "init"),
-            Some(CommandItem::UserPrompt(_)) => panic!("unexpected prompt selected for '/init'"),
+            Some(CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_)) => {
+                panic!("unexpected prompt selected for '/init'")
+            }
             None => panic!("expected a selected command for exact match"),
         }
     }
@@ -253,7 +305,7 @@ mod tests {
         let matches = popup.filtered_items();
         match matches.first() {
             Some(CommandItem::Builtin(cmd)) => assert_eq!(cmd.command(), "model"),
-            Some(CommandItem::UserPrompt(_)) => {
+            Some(CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_)) => {
                 panic!("unexpected prompt ranked before '/model' for '/mo'")
             }
             None => panic!("expected at least one match for '/mo'"),
@@ -301,6 +353,35 @@ mod tests {
         assert_eq!(prompt_names, vec!["bar".to_string(), "foo".to_string()]);
     }
 
+    #[test]
+    fn mcp_prompts_are_listed_as_server_commands() {
+        let prompt = |name: &str| Prompt {
+            arguments: None,
+            description: None,
+            name: name.to_string(),
+            title: None,
+        };
+        let mut popup = CommandPopup::new(Vec::new());
+        popup.set_mcp_prompts(vec![
+            McpPromptEntry {
+                server: "github".to_string(),
+                prompt: prompt("summarize_pr"),
+            },
+            McpPromptEntry {
+                server: "docs".to_string(),
+                prompt: prompt("explain"),
+            },
+        ]);
+        popup.on_composer_text_change("/github:sum".to_string());
+        match popup.selected_item() {
+            Some(CommandItem::McpPrompt(i)) => assert_eq!(
+                popup.mcp_prompt(i).map(McpPromptEntry::command),
+                Some("github:summarize_pr".to_string())
+            ),
+            other => panic!("expected the MCP prompt to be selected, got {other:?}"),
+        }
+    }
+
     #[test]
     fn prompt_name_collision_with_builtin_is_ignored() {
         // Create a prompt named like a builtin (e.g. "init").
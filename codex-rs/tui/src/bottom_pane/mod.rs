@@ -28,6 +28,7 @@ mod list_selection_view;
 mod model_search_popup;
 mod paste_burst;
 mod popup_consts;
+mod resource_search_popup;
 mod scroll_state;
 mod selection_popup_common;
 mod text_input_view;
@@ -45,6 +46,8 @@ pub(crate) enum CancellationEvent {
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
 use codex_protocol::custom_prompts::CustomPrompt;
+pub(crate) use command_popup::McpPromptEntry;
+pub(crate) use resource_search_popup::McpResourceEntry;
 
 use crate::status_indicator_widget::StatusIndicatorWidget;
 use approval_modal_view::ApprovalModalView;
@@ -568,6 +571,17 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update MCP prompts available for the slash popup.
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptEntry>) {
+        self.composer.set_mcp_prompts(prompts);
+        self.request_redraw();
+    }
+
+    /// Update MCP resources offered when completing `@server:` mentions.
+    pub(crate) fn set_mcp_resources(&mut self, resources: Vec<McpResourceEntry>) {
+        self.composer.set_mcp_resources(resources);
+    }
+
     pub(crate) fn composer_is_empty(&self) -> bool {
         self.composer.is_empty()
     }
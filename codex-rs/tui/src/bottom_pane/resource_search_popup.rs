@@ -0,0 +1,179 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::WidgetRef;
+
+use super::popup_consts::MAX_POPUP_ROWS;
+use super::scroll_state::ScrollState;
+use super::selection_popup_common::GenericDisplayRow;
+use super::selection_popup_common::render_rows;
+use codex_common::fuzzy_match::fuzzy_match;
+use mcp_types::Resource;
+
+/// A resource offered by an MCP server, mentioned as `@server:uri`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct McpResourceEntry {
+    pub server: String,
+    pub resource: Resource,
+}
+
+impl McpResourceEntry {
+    fn mention(&self) -> String {
+        format!("@{}:{}", self.server, self.resource.uri)
+    }
+}
+
+/// Completes `@server:` tokens with the resources that server offers.
+pub(crate) struct ResourceSearchPopup {
+    query: String,
+    matches: Vec<McpResourceEntry>,
+    state: ScrollState,
+}
+
+impl ResourceSearchPopup {
+    pub(crate) fn new() -> Self {
+        Self {
+            query: String::new(),
+            matches: Vec::new(),
+            state: ScrollState::new(),
+        }
+    }
+
+    /// Whether `query` (the `@` token without its `@`) names a server that
+    /// offers resources, as in `docs:` or `docs:read`.
+    pub(crate) fn has_matches(query: &str, resources: &[McpResourceEntry]) -> bool {
+        let Some((server, _)) = query.split_once(':') else {
+            return false;
+        };
+        resources.iter().any(|entry| entry.server == server)
+    }
+
+    pub(crate) fn set_query(&mut self, query: &str, resources: &[McpResourceEntry]) {
+        self.query.clear();
+        self.query.push_str(query);
+
+        let (server, filter) = query.split_once(':').unwrap_or((query, ""));
+        let mut scored: Vec<(i32, &McpResourceEntry)> = resources
+            .iter()
+            .filter(|entry| entry.server == server)
+            .filter_map(|entry| {
+                if filter.is_empty() {
+                    return Some((0, entry));
+                }
+                let by_name = fuzzy_match(&entry.resource.name, filter).map(|(_, s)| s);
+                let by_uri = fuzzy_match(&entry.resource.uri, filter).map(|(_, s)| s);
+                by_name.into_iter().chain(by_uri).min().map(|s| (s, entry))
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| a.1.resource.uri.cmp(&b.1.resource.uri))
+        });
+
+        let old_sel = self.state.selected_idx;
+        self.matches = scored.into_iter().map(|(_, entry)| entry.clone()).collect();
+        let len = self.matches.len();
+        self.state.clamp_selection(len);
+        if old_sel.is_none() && len > 0 {
+            self.state.selected_idx = Some(0);
+        }
+        self.state.ensure_visible(len, len.min(MAX_POPUP_ROWS));
+    }
+
+    pub(crate) fn move_up(&mut self) {
+        let len = self.matches.len();
+        self.state.move_up_wrap(len);
+        self.state.ensure_visible(len, len.min(MAX_POPUP_ROWS));
+    }
+
+    pub(crate) fn move_down(&mut self) {
+        let len = self.matches.len();
+        self.state.move_down_wrap(len);
+        self.state.ensure_visible(len, len.min(MAX_POPUP_ROWS));
+    }
+
+    /// The `@server:uri` mention for the selected resource.
+    pub(crate) fn selected_mention(&self) -> Option<String> {
+        self.state
+            .selected_idx
+            .and_then(|idx| self.matches.get(idx))
+            .map(McpResourceEntry::mention)
+    }
+
+    pub(crate) fn calculate_required_height(&self) -> u16 {
+        self.matches.len().clamp(1, MAX_POPUP_ROWS) as u16
+    }
+}
+
+impl WidgetRef for &ResourceSearchPopup {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let rows_all: Vec<GenericDisplayRow> = self
+            .matches
+            .iter()
+            .map(|entry| GenericDisplayRow {
+                name: entry.mention(),
+                match_indices: None,
+                is_current: false,
+                description: Some(
+                    entry
+                        .resource
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| entry.resource.name.clone()),
+                ),
+            })
+            .collect();
+        render_rows(
+            area,
+            buf,
+            &rows_all,
+            &self.state,
+            MAX_POPUP_ROWS,
+            false,
+            "no matching resources",
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn entry(server: &str, uri: &str, name: &str) -> McpResourceEntry {
+        McpResourceEntry {
+            server: server.to_string(),
+            resource: Resource {
+                annotations: None,
+                description: None,
+                mime_type: None,
+                name: name.to_string(),
+                size: None,
+                title: None,
+                uri: uri.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn completes_resources_of_the_named_server() {
+        let resources = vec![
+            entry("docs", "file:///README.md", "readme"),
+            entry("docs", "file:///CHANGELOG.md", "changelog"),
+            entry("db", "postgres://users", "users"),
+        ];
+        assert!(ResourceSearchPopup::has_matches("docs:", &resources));
+        assert!(!ResourceSearchPopup::has_matches("docs", &resources));
+        assert!(!ResourceSearchPopup::has_matches("wiki:", &resources));
+
+        let mut popup = ResourceSearchPopup::new();
+        popup.set_query("docs:", &resources);
+        assert_eq!(popup.matches.len(), 2);
+        popup.set_query("docs:read", &resources);
+        assert_eq!(
+            popup.selected_mention(),
+            Some("@docs:file:///README.md".to_string())
+        );
+        popup.set_query("db:nothing", &resources);
+        assert_eq!(popup.selected_mention(), None);
+    }
+}
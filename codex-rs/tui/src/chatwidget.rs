@@ -12,16 +12,20 @@ use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::CheckpointItem;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::GetMcpPromptResponseEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
+use codex_core::protocol::McpListResourcesResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpToolApprovalRequestEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
@@ -36,6 +40,7 @@ use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::TurnUndoneEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
@@ -44,6 +49,10 @@ use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
 use crossterm::event::KeyModifiers;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResource;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
 use rand::Rng;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
@@ -60,6 +69,8 @@ use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
 use crate::bottom_pane::InputResult;
+use crate::bottom_pane::McpPromptEntry;
+use crate::bottom_pane::McpResourceEntry;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
 use crate::clipboard_paste::paste_image_to_temp_png;
@@ -178,8 +189,9 @@ impl ChatWidget {
             event,
             self.show_welcome_banner,
         ));
-        // Ask codex-core to enumerate custom prompts for this session.
+        // Ask codex-core to enumerate custom prompts and MCP resources for this session.
         self.submit_op(Op::ListCustomPrompts);
+        self.submit_op(Op::ListMcpResources);
         if let Some(user_message) = self.initial_user_message.take() {
             self.submit_user_message(user_message);
         }
@@ -338,6 +350,15 @@ impl ChatWidget {
         );
     }
 
+    fn on_mcp_tool_approval_request(&mut self, id: String, ev: McpToolApprovalRequestEvent) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_tool_approval(id, ev),
+            |s| s.handle_mcp_tool_approval_now(id2, ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -421,6 +442,43 @@ impl ChatWidget {
         debug!("BackgroundEvent: {message}");
     }
 
+    fn on_turn_undone(&mut self, event: TurnUndoneEvent) {
+        let paths = self.display_paths(&event.paths);
+        self.add_to_history(history_cell::new_info_event(format!(
+            "Undid the last turn: {paths}"
+        )));
+        self.request_redraw();
+    }
+
+    pub(crate) fn on_checkpoint_restored(&mut self, result: Result<Vec<PathBuf>, String>) {
+        let cell = match result {
+            Ok(paths) if paths.is_empty() => {
+                history_cell::new_info_event("Files already match the checkpoint".to_string())
+            }
+            Ok(paths) => {
+                let paths = self.display_paths(&paths);
+                history_cell::new_info_event(format!("Restored files to the checkpoint: {paths}"))
+            }
+            Err(e) => history_cell::new_error_event(format!("Failed to restore checkpoint: {e}")),
+        };
+        self.add_to_history(cell);
+        self.request_redraw();
+    }
+
+    /// Comma-separated `paths`, relative to the session cwd where possible.
+    fn display_paths(&self, paths: &[PathBuf]) -> String {
+        paths
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&self.config.cwd)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     fn on_stream_error(&mut self, message: String) {
         // Show stream errors in the transcript so users see retry/backoff info.
         self.add_to_history(history_cell::new_stream_error_event(message));
@@ -560,6 +618,26 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    pub(crate) fn handle_mcp_tool_approval_now(
+        &mut self,
+        id: String,
+        ev: McpToolApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(history_cell::new_proposed_mcp_tool_call(
+            ev.invocation.clone(),
+        ));
+
+        let request = ApprovalRequest::McpTool {
+            id,
+            server: ev.invocation.server,
+            tool: ev.invocation.tool,
+            reason: ev.reason,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
         // Ensure the status indicator is visible while the command runs.
         self.running_commands.insert(
@@ -806,6 +884,17 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::McpPrompt {
+                        server,
+                        name,
+                        arguments,
+                    } => {
+                        self.submit_op(Op::GetMcpPrompt {
+                            server,
+                            name,
+                            arguments,
+                        });
+                    }
                     InputResult::None => {}
                 }
             }
@@ -852,6 +941,9 @@ impl ChatWidget {
                 self.clear_token_usage();
                 self.app_event_tx.send(AppEvent::CodexOp(Op::Compact));
             }
+            SlashCommand::Undo => {
+                self.app_event_tx.send(AppEvent::CodexOp(Op::UndoTurn));
+            }
             SlashCommand::Model => {
                 self.open_model_popup();
             }
@@ -1221,6 +1313,9 @@ impl ChatWidget {
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::McpToolApprovalRequest(ev) => {
+                self.on_mcp_tool_approval_request(id.unwrap_or_default(), ev)
+            }
             EventMsg::ExecCommandBegin(ev) => {
                 if self.bottom_pane.has_active_subagents() {
                     let line = format!("exec: {}", ev.command.join(" "));
@@ -1277,7 +1372,18 @@ impl ChatWidget {
             EventMsg::WebSearchBegin(ev) => self.on_web_search_begin(ev),
             EventMsg::WebSearchEnd(ev) => self.on_web_search_end(ev),
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
-            EventMsg::SubAgentStarted(SubAgentStartedEvent { sub_id, label }) => {
+            EventMsg::SubAgentStarted(SubAgentStartedEvent {
+                label,
+                rollout_path,
+                ..
+            }) if from_replay => {
+                // The sub-agent finished long ago; point at its transcript,
+                // which can be opened from the resume picker.
+                if let Some(path) = rollout_path {
+                    self.add_info_message(format!("{label} transcript: {}", path.display()));
+                }
+            }
+            EventMsg::SubAgentStarted(SubAgentStartedEvent { sub_id, label, .. }) => {
                 self.bottom_pane.subagent_started(sub_id, label);
             }
             EventMsg::SubAgentStatus(SubAgentStatusEvent {
@@ -1298,7 +1404,9 @@ impl ChatWidget {
                 self.bottom_pane.clear_subagent_status(&sub_id);
             }
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
+            EventMsg::McpListResourcesResponse(ev) => self.on_list_mcp_resources(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::GetMcpPromptResponse(ev) => self.on_get_mcp_prompt(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
@@ -1310,6 +1418,7 @@ impl ChatWidget {
                     self.on_user_message_event(ev);
                 }
             }
+            EventMsg::TurnUndone(ev) => self.on_turn_undone(ev),
             EventMsg::ConversationPath(ev) => {
                 self.app_event_tx
                     .send(crate::app_event::AppEvent::ConversationHistory(ev));
@@ -1484,6 +1593,34 @@ impl ChatWidget {
         );
     }
 
+    /// Offer to bring the worktree back to `checkpoint` after a backtrack.
+    pub(crate) fn open_restore_checkpoint_popup(&mut self, checkpoint: CheckpointItem) {
+        let items = vec![
+            SelectionItem {
+                name: "Restore files".to_string(),
+                description: Some(
+                    "Put the files back as they were when this message was sent".to_string(),
+                ),
+                is_current: false,
+                actions: vec![Box::new(move |tx| {
+                    tx.send(AppEvent::RestoreCheckpoint(checkpoint.clone()));
+                })],
+            },
+            SelectionItem {
+                name: "Keep current files".to_string(),
+                description: Some("Only rewind the conversation".to_string()),
+                is_current: false,
+                actions: Vec::new(),
+            },
+        ];
+        self.bottom_pane.show_selection_view(
+            "Restore files to this checkpoint?".to_string(),
+            None,
+            Some("Press Enter to confirm or Esc to keep current files".to_string()),
+            items,
+        );
+    }
+
     /// Open a popup with configuration options.
     pub(crate) fn open_configuration_popup(&mut self) {
         // Show a text input for the default exec timeout.
@@ -1653,7 +1790,11 @@ impl ChatWidget {
     }
 
     fn on_list_mcp_tools(&mut self, ev: McpListToolsResponseEvent) {
-        self.add_to_history(history_cell::new_mcp_tools_output(&self.config, ev.tools));
+        self.add_to_history(history_cell::new_mcp_tools_output(
+            &self.config,
+            ev.tools,
+            ev.servers,
+        ));
     }
 
     fn on_list_custom_prompts(&mut self, ev: ListCustomPromptsResponseEvent) {
@@ -1661,6 +1802,64 @@ impl ChatWidget {
         debug!("received {len} custom prompts");
         // Forward to bottom pane so the slash popup can show them now.
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
+        let mcp_prompts = ev
+            .mcp_prompts
+            .into_iter()
+            .flat_map(|(server, prompts)| {
+                prompts.into_iter().map(move |prompt| McpPromptEntry {
+                    server: server.clone(),
+                    prompt,
+                })
+            })
+            .collect();
+        self.bottom_pane.set_mcp_prompts(mcp_prompts);
+    }
+
+    fn on_list_mcp_resources(&mut self, ev: McpListResourcesResponseEvent) {
+        let resources = ev
+            .resources
+            .into_iter()
+            .flat_map(|(server, resources)| {
+                resources.into_iter().map(move |resource| McpResourceEntry {
+                    server: server.clone(),
+                    resource,
+                })
+            })
+            .collect();
+        self.bottom_pane.set_mcp_resources(resources);
+    }
+
+    /// Send a rendered MCP prompt as if the user had typed it.
+    fn on_get_mcp_prompt(&mut self, ev: GetMcpPromptResponseEvent) {
+        let GetMcpPromptResponseEvent {
+            server,
+            name,
+            result,
+        } = ev;
+        let text = match result {
+            Ok(result) => mcp_prompt_text(&result),
+            Err(e) => {
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "Failed to run /{server}:{name}: {e}"
+                )));
+                self.request_redraw();
+                return;
+            }
+        };
+        if text.is_empty() {
+            self.add_to_history(history_cell::new_error_event(format!(
+                "/{server}:{name} returned no text"
+            )));
+            self.request_redraw();
+            return;
+        }
+        let user_message: UserMessage = text.into();
+        if self.bottom_pane.is_task_running() {
+            self.queued_user_messages.push_back(user_message);
+            self.refresh_queued_user_messages();
+        } else {
+            self.submit_user_message(user_message);
+        }
     }
 
     /// Programmatically submit a user text message as if typed in the
@@ -1755,5 +1954,23 @@ fn extract_first_bold(s: &str) -> Option<String> {
     None
 }
 
+// The text of a rendered MCP prompt: its text messages and embedded text
+// resources, separated by blank lines. Images and other media are dropped.
+fn mcp_prompt_text(result: &GetPromptResult) -> String {
+    result
+        .messages
+        .iter()
+        .filter_map(|message| match &message.content {
+            ContentBlock::TextContent(text) => Some(text.text.as_str()),
+            ContentBlock::EmbeddedResource(EmbeddedResource {
+                resource: EmbeddedResourceResource::TextResourceContents(contents),
+                ..
+            }) => Some(contents.text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
 #[cfg(test)]
 pub(crate) mod tests;
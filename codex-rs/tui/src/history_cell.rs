@@ -17,6 +17,7 @@ use codex_common::elapsed::format_duration;
 use codex_core::auth::get_auth_file;
 use codex_core::auth::try_read_auth_json;
 use codex_core::config::Config;
+use codex_core::config_types::McpServerTransportConfig;
 use codex_core::config_types::ReasoningSummaryFormat;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
@@ -24,6 +25,8 @@ use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::project_doc::discover_project_doc_paths;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpServerState;
+use codex_core::protocol::McpServerStatus;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
@@ -1011,6 +1014,7 @@ pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
 pub(crate) fn new_mcp_tools_output(
     config: &Config,
     tools: std::collections::HashMap<String, mcp_types::Tool>,
+    servers: std::collections::HashMap<String, McpServerStatus>,
 ) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = vec![
         "/mcp".magenta().into(),
@@ -1019,7 +1023,7 @@ pub(crate) fn new_mcp_tools_output(
         "".into(),
     ];
 
-    if tools.is_empty() {
+    if tools.is_empty() && servers.is_empty() {
         lines.push("  • No MCP tools available.".italic().into());
         lines.push("".into());
         return PlainHistoryCell { lines };
@@ -1036,10 +1040,17 @@ pub(crate) fn new_mcp_tools_output(
 
         lines.push(vec!["  • Server: ".into(), server.clone().into()].into());
 
-        if !cfg.command.is_empty() {
-            let cmd_display = format!("{} {}", cfg.command, cfg.args.join(" "));
+        match &cfg.transport {
+            McpServerTransportConfig::Stdio { command, args, .. } => {
+                if !command.is_empty() {
+                    let cmd_display = format!("{} {}", command, args.join(" "));
 
-            lines.push(vec!["    • Command: ".into(), cmd_display.into()].into());
+                    lines.push(vec!["    • Command: ".into(), cmd_display.into()].into());
+                }
+            }
+            McpServerTransportConfig::StreamableHttp { url, .. } => {
+                lines.push(vec!["    • URL: ".into(), url.clone().into()].into());
+            }
         }
 
         if names.is_empty() {
@@ -1047,6 +1058,23 @@ pub(crate) fn new_mcp_tools_output(
         } else {
             lines.push(vec!["    • Tools: ".into(), names.join(", ").into()].into());
         }
+
+        if let Some(status) = servers.get(server) {
+            let state: Span<'static> = match &status.state {
+                McpServerState::Connected => "connected".green(),
+                McpServerState::Restarting { attempt } => {
+                    format!("restarting (attempt {attempt})").cyan()
+                }
+                McpServerState::Failed { error } => format!("failed: {error}").red(),
+            };
+            lines.push(vec!["    • Status: ".into(), state].into());
+            if !status.stderr_tail.is_empty() {
+                lines.push("    • Recent stderr:".into());
+                for line in &status.stderr_tail {
+                    lines.push(format!("        {line}").dim().into());
+                }
+            }
+        }
         lines.push(Line::from(""));
     }
 
@@ -1199,6 +1227,16 @@ pub(crate) fn new_proposed_command(command: &[String]) -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_proposed_mcp_tool_call(invocation: McpInvocation) -> PlainHistoryCell {
+    let mut invocation_line = format_mcp_invocation(invocation);
+    invocation_line.spans.insert(0, "  └ ".dim());
+    let lines: Vec<Line<'static>> = vec![
+        Line::from(vec!["• ".into(), "Proposed Tool Call".bold()]),
+        invocation_line,
+    ];
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_reasoning_block(
     full_reasoning_buffer: String,
     config: &Config,
@@ -1372,6 +1410,50 @@ mod tests {
         render_lines(&cell.transcript_lines())
     }
 
+    #[test]
+    fn mcp_tools_output_shows_server_status_and_stderr() {
+        let mut config = test_config();
+        config.mcp_servers.insert(
+            "docs".to_string(),
+            codex_core::config_types::McpServerConfig {
+                transport: McpServerTransportConfig::Stdio {
+                    command: "docs-server".to_string(),
+                    args: Vec::new(),
+                    env: None,
+                },
+                startup_timeout_ms: None,
+                approval: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                tools: HashMap::new(),
+            },
+        );
+        let servers = HashMap::from([(
+            "docs".to_string(),
+            McpServerStatus {
+                state: McpServerState::Failed {
+                    error: "exited 5 times shortly after starting".to_string(),
+                },
+                stderr_tail: vec!["panic: index out of range".to_string()],
+            },
+        )]);
+
+        let cell = new_mcp_tools_output(&config, HashMap::new(), servers);
+        let rendered = render_lines(&cell.display_lines(80));
+        assert_eq!(
+            rendered[4..],
+            [
+                "  • Server: docs",
+                "    • Command: docs-server ",
+                "    • Tools: (none)",
+                "    • Status: failed: exited 5 times shortly after starting",
+                "    • Recent stderr:",
+                "        panic: index out of range",
+                "",
+            ]
+        );
+    }
+
     #[test]
     fn coalesces_sequential_reads_within_one_call() {
         // Build one exec cell with a Search followed by two Reads
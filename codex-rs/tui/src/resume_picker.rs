@@ -7,6 +7,8 @@ use codex_core::ConversationItem;
 use codex_core::ConversationsPage;
 use codex_core::Cursor;
 use codex_core::RolloutRecorder;
+use codex_core::checkpoint::TurnCheckpoint;
+use codex_core::checkpoint::list_checkpoints;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -38,7 +40,8 @@ pub enum ResumeSelection {
 
 /// Interactive session picker that lists recorded rollout files with simple
 /// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
+/// time (e.g., "5 seconds ago"), and the absolute path. Tab lists the worktree
+/// checkpoints recorded for the selected session.
 pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let mut state = PickerState::new(codex_home.to_path_buf(), alt.tui.frame_requester());
@@ -97,6 +100,8 @@ struct PickerState {
     selected: usize,
     // search
     query: String,
+    // worktree checkpoints of the selected session, shown instead of the list
+    checkpoints: Option<Vec<TurnCheckpoint>>,
 }
 
 #[derive(Debug, Clone)]
@@ -129,6 +134,7 @@ impl PickerState {
             filtered_rows: Vec::new(),
             selected: 0,
             query: String::new(),
+            checkpoints: None,
         }
     }
 
@@ -138,6 +144,10 @@ impl PickerState {
 
     async fn handle_key(&mut self, key: KeyEvent) -> Result<Option<ResumeSelection>> {
         match key.code {
+            KeyCode::Esc if self.checkpoints.is_some() => {
+                self.checkpoints = None;
+                self.request_frame();
+            }
             KeyCode::Esc => return Ok(Some(ResumeSelection::StartFresh)),
             KeyCode::Char('c')
                 if key
@@ -151,16 +161,21 @@ impl PickerState {
                     return Ok(Some(ResumeSelection::Resume(row.path.clone())));
                 }
             }
+            KeyCode::Tab => {
+                self.toggle_checkpoints().await;
+            }
             KeyCode::Up => {
                 if self.selected > 0 {
                     self.selected -= 1;
                 }
+                self.checkpoints = None;
                 self.request_frame();
             }
             KeyCode::Down => {
                 if self.selected + 1 < self.filtered_rows.len() {
                     self.selected += 1;
                 }
+                self.checkpoints = None;
                 self.request_frame();
             }
             KeyCode::Left | KeyCode::Char('a') => {
@@ -189,6 +204,21 @@ impl PickerState {
         Ok(None)
     }
 
+    async fn toggle_checkpoints(&mut self) {
+        if self.checkpoints.take().is_none()
+            && let Some(row) = self.filtered_rows.get(self.selected)
+        {
+            self.checkpoints = Some(list_checkpoints(&row.path).await.unwrap_or_else(|e| {
+                tracing::warn!(
+                    "failed to read checkpoints from {}: {e}",
+                    row.path.display()
+                );
+                Vec::new()
+            }));
+        }
+        self.request_frame();
+    }
+
     async fn prev_page(&mut self) -> Result<()> {
         if self.pagination.page_index == 0 {
             return Ok(());
@@ -231,6 +261,7 @@ impl PickerState {
         self.apply_filter();
         // reset selection on new page
         self.selected = 0;
+        self.checkpoints = None;
         Ok(())
     }
 
@@ -266,10 +297,15 @@ fn head_to_row(item: &ConversationItem) -> Row {
         ts = Some(parsed.with_timezone(&Utc));
     }
 
-    let preview = preview_from_head(&item.head)
+    let mut preview = preview_from_head(&item.head)
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| String::from("(no message yet)"));
+    // Sub-agent transcripts are listed alongside sessions; tag them so they
+    // can be told apart (and found by searching for the label).
+    if let Some(source) = item.sub_agent() {
+        preview = format!("[{}] {preview}", source.label);
+    }
 
     Row {
         path: item.path.clone(),
@@ -357,7 +393,9 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
             "←/a".into(),
             " prev  ".dim(),
             "→/d".into(),
-            " next".dim(),
+            " next  ".dim(),
+            "Tab".into(),
+            " checkpoints".dim(),
         ]
         .into();
         frame.render_widget_ref(hint_line, hint);
@@ -365,6 +403,10 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
 }
 
 fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &PickerState) {
+    if let Some(checkpoints) = &state.checkpoints {
+        render_checkpoints(frame, area, checkpoints);
+        return;
+    }
     let rows = &state.filtered_rows;
     if rows.is_empty() {
         frame.render_widget_ref(Line::from("No sessions found".italic().dim()), area);
@@ -395,6 +437,50 @@ fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &Pi
     }
 }
 
+/// Lists checkpoints newest first: time, short commit and the message sent
+/// after each one.
+fn render_checkpoints(
+    frame: &mut crate::custom_terminal::Frame,
+    area: Rect,
+    checkpoints: &[TurnCheckpoint],
+) {
+    if checkpoints.is_empty() {
+        frame.render_widget_ref(
+            Line::from("No checkpoints recorded for this session".italic().dim()),
+            area,
+        );
+        return;
+    }
+
+    let max_cols = area.width.saturating_sub(2) as usize;
+    let mut y = area.y;
+    for checkpoint in checkpoints.iter().rev().take(area.height as usize) {
+        let ts = chrono::DateTime::parse_from_rfc3339(&checkpoint.timestamp)
+            .map(|ts| human_time_ago(ts.with_timezone(&Utc)))
+            .unwrap_or_default();
+        let line: Line = vec![
+            "  ".into(),
+            ts.dim(),
+            "  ".into(),
+            truncate_text(&checkpoint_summary(checkpoint), max_cols).into(),
+        ]
+        .into();
+        frame.render_widget_ref(line, Rect::new(area.x, y, area.width, 1));
+        y = y.saturating_add(1);
+    }
+}
+
+fn checkpoint_summary(checkpoint: &TurnCheckpoint) -> String {
+    let commit = &checkpoint.checkpoint.commit;
+    let short = commit.get(..7).unwrap_or(commit);
+    let message = checkpoint.message.lines().next().unwrap_or_default().trim();
+    if message.is_empty() {
+        short.to_string()
+    } else {
+        format!("{short}  {message}")
+    }
+}
+
 fn human_time_ago(ts: DateTime<Utc>) -> String {
     let now = Utc::now();
     let delta = now - ts;
@@ -494,4 +580,32 @@ mod tests {
         assert!(rows[0].preview.contains('A'));
         assert!(rows[1].preview.contains('B'));
     }
+
+    #[test]
+    fn sub_agent_rows_are_tagged_with_label() {
+        let mut head = head_with_ts_and_user_text("2025-01-01T00:00:00Z", &["fix the tests"]);
+        head[0]["sub_agent"] = json!({
+            "parent_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "sub_id": "call_1",
+            "label": "reviewer",
+        });
+        let row = head_to_row(&ConversationItem {
+            path: PathBuf::from("/tmp/child.jsonl"),
+            head,
+        });
+        assert_eq!(row.preview, "[reviewer] fix the tests");
+    }
+
+    #[test]
+    fn checkpoint_summary_shows_short_commit_and_first_line() {
+        let checkpoint = TurnCheckpoint {
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            message: "fix the tests\nand the docs".to_string(),
+            checkpoint: codex_core::protocol::CheckpointItem {
+                root: PathBuf::from("/repo"),
+                commit: "0123456789abcdef".to_string(),
+            },
+        };
+        assert_eq!(checkpoint_summary(&checkpoint), "0123456  fix the tests");
+    }
 }
@@ -19,6 +19,7 @@ pub enum SlashCommand {
     Init,
     Compact,
     Diff,
+    Undo,
     AiGitCommitPush,
     Mention,
     Status,
@@ -38,6 +39,7 @@ impl SlashCommand {
             SlashCommand::Compact => "summarize conversation to prevent hitting the context limit",
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
+            SlashCommand::Undo => "restore the files changed by the last turn",
             SlashCommand::AiGitCommitPush => {
                 "git pull/add/commit/push; message optional (AI can draft)"
             }
@@ -65,6 +67,7 @@ impl SlashCommand {
             SlashCommand::New
             | SlashCommand::Init
             | SlashCommand::Compact
+            | SlashCommand::Undo
             | SlashCommand::Model
             | SlashCommand::Approvals
             | SlashCommand::Configuration
@@ -44,6 +44,12 @@ pub(crate) enum ApprovalRequest {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     },
+    McpTool {
+        id: String,
+        server: String,
+        tool: String,
+        reason: Option<String>,
+    },
 }
 
 /// Options displayed in the *select* mode.
@@ -96,6 +102,29 @@ static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     ]
 });
 
+static MCP_TOOL_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Approve and call the tool",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["A".underlined(), "lways".into()]),
+            description: "Approve calls to this tool for the remainder of this session",
+            key: KeyCode::Char('a'),
+            decision: ReviewDecision::ApprovedForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o, provide feedback".into()]),
+            description: "Do not call the tool; provide feedback",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Abort,
+        },
+    ]
+});
+
 /// A modal prompting the user to approve or deny the pending request.
 pub(crate) struct UserApprovalWidget {
     approval_request: ApprovalRequest,
@@ -114,7 +143,7 @@ pub(crate) struct UserApprovalWidget {
 impl UserApprovalWidget {
     pub(crate) fn new(approval_request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
         let confirmation_prompt = match &approval_request {
-            ApprovalRequest::Exec { reason, .. } => {
+            ApprovalRequest::Exec { reason, .. } | ApprovalRequest::McpTool { reason, .. } => {
                 let mut contents: Vec<Line> = vec![];
                 if let Some(reason) = reason {
                     contents.push(Line::from(reason.clone().italic()));
@@ -148,6 +177,7 @@ impl UserApprovalWidget {
             select_options: match &approval_request {
                 ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
                 ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
+                ApprovalRequest::McpTool { .. } => &MCP_TOOL_SELECT_OPTIONS,
             },
             approval_request,
             app_event_tx,
@@ -223,77 +253,84 @@ impl UserApprovalWidget {
     }
 
     fn send_decision_with_feedback(&mut self, decision: ReviewDecision, feedback: String) {
-        match &self.approval_request {
+        // What the decision is about, as a verb and a concise summary.
+        let subject = match &self.approval_request {
             ApprovalRequest::Exec { command, .. } => {
                 let full_cmd = strip_bash_lc_and_escape(command);
                 // Construct a concise, single-line summary of the command:
                 // - If multi-line, take the first line and append " ...".
                 // - Truncate to 80 graphemes.
-                let mut snippet = match full_cmd.split_once('\n') {
+                let snippet = match full_cmd.split_once('\n') {
                     Some((first, _)) => format!("{first} ..."),
                     None => full_cmd.clone(),
                 };
                 // Enforce the 80 character length limit.
-                snippet = truncate_text(&snippet, 80);
-
-                let mut result_spans: Vec<Span<'static>> = Vec::new();
-                match decision {
-                    ReviewDecision::Approved => {
-                        result_spans.extend(vec![
-                            "✔ ".fg(Color::Green),
-                            "You ".into(),
-                            "approved".bold(),
-                            " codex to run ".into(),
-                            snippet.dim(),
-                            " this time".bold(),
-                        ]);
-                    }
-                    ReviewDecision::ApprovedForSession => {
-                        result_spans.extend(vec![
-                            "✔ ".fg(Color::Green),
-                            "You ".into(),
-                            "approved".bold(),
-                            " codex to run ".into(),
-                            snippet.dim(),
-                            " every time this session".bold(),
-                        ]);
-                    }
-                    ReviewDecision::Denied => {
-                        result_spans.extend(vec![
-                            "✗ ".fg(Color::Red),
-                            "You ".into(),
-                            "did not approve".bold(),
-                            " codex to run ".into(),
-                            snippet.dim(),
-                        ]);
-                    }
-                    ReviewDecision::Abort => {
-                        result_spans.extend(vec![
-                            "✗ ".fg(Color::Red),
-                            "You ".into(),
-                            "canceled".bold(),
-                            " the request to run ".into(),
-                            snippet.dim(),
-                        ]);
-                    }
+                Some(("run", truncate_text(&snippet, 80)))
+            }
+            ApprovalRequest::McpTool { server, tool, .. } => {
+                Some(("call", format!("{server}.{tool}")))
+            }
+            ApprovalRequest::ApplyPatch { .. } => {
+                // No history line for patch approval decisions.
+                None
+            }
+        };
+
+        if let Some((verb, snippet)) = subject {
+            let mut result_spans: Vec<Span<'static>> = Vec::new();
+            match decision {
+                ReviewDecision::Approved => {
+                    result_spans.extend(vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "approved".bold(),
+                        format!(" codex to {verb} ").into(),
+                        snippet.dim(),
+                        " this time".bold(),
+                    ]);
                 }
+                ReviewDecision::ApprovedForSession => {
+                    result_spans.extend(vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "approved".bold(),
+                        format!(" codex to {verb} ").into(),
+                        snippet.dim(),
+                        " every time this session".bold(),
+                    ]);
+                }
+                ReviewDecision::Denied => {
+                    result_spans.extend(vec![
+                        "✗ ".fg(Color::Red),
+                        "You ".into(),
+                        "did not approve".bold(),
+                        format!(" codex to {verb} ").into(),
+                        snippet.dim(),
+                    ]);
+                }
+                ReviewDecision::Abort => {
+                    result_spans.extend(vec![
+                        "✗ ".fg(Color::Red),
+                        "You ".into(),
+                        "canceled".bold(),
+                        format!(" the request to {verb} ").into(),
+                        snippet.dim(),
+                    ]);
+                }
+            }
 
-                let mut lines: Vec<Line<'static>> = vec![Line::from(result_spans)];
+            let mut lines: Vec<Line<'static>> = vec![Line::from(result_spans)];
 
-                if !feedback.trim().is_empty() {
-                    lines.push(Line::from("feedback:"));
-                    for l in feedback.lines() {
-                        lines.push(Line::from(l.to_string()));
-                    }
+            if !feedback.trim().is_empty() {
+                lines.push(Line::from("feedback:"));
+                for l in feedback.lines() {
+                    lines.push(Line::from(l.to_string()));
                 }
-
-                self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
-                    history_cell::new_user_approval_decision(lines),
-                )));
-            }
-            ApprovalRequest::ApplyPatch { .. } => {
-                // No history line for patch approval decisions.
             }
+
+            self.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                history_cell::new_user_approval_decision(lines),
+            )));
         }
 
         let op = match &self.approval_request {
@@ -305,6 +342,10 @@ impl UserApprovalWidget {
                 id: id.clone(),
                 decision,
             },
+            ApprovalRequest::McpTool { id, .. } => Op::McpToolApproval {
+                id: id.clone(),
+                decision,
+            },
         };
 
         self.app_event_tx.send(AppEvent::CodexOp(op));
@@ -319,7 +360,7 @@ impl UserApprovalWidget {
 
     pub(crate) fn desired_height(&self, width: u16) -> u16 {
         // Reserve space for:
-        // - 1 title line ("Allow command?" or "Apply changes?")
+        // - 1 title line ("Allow command?", "Apply changes?" or "Allow tool call?")
         // - 1 buttons line (options rendered horizontally on a single row)
         // - 1 description line (context for the currently selected option)
         self.get_confirmation_prompt_height(width) + 3
@@ -357,6 +398,7 @@ impl WidgetRef for &UserApprovalWidget {
         let title = match &self.approval_request {
             ApprovalRequest::Exec { .. } => "Allow command?",
             ApprovalRequest::ApplyPatch { .. } => "Apply changes?",
+            ApprovalRequest::McpTool { .. } => "Allow tool call?",
         };
         Line::from(title).render(title_area, buf);
 
@@ -445,4 +487,30 @@ mod tests {
             })
         )));
     }
+
+    #[test]
+    fn mcp_tool_approval_for_session_is_sent() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::McpTool {
+            id: "3".to_string(),
+            server: "tickets".to_string(),
+            tool: "delete_ticket".to_string(),
+            reason: None,
+        };
+        let mut widget = UserApprovalWidget::new(req, tx);
+        widget.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        assert!(widget.is_complete());
+        let mut events: Vec<AppEvent> = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            events.push(ev);
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            AppEvent::CodexOp(Op::McpToolApproval {
+                id,
+                decision: ReviewDecision::ApprovedForSession,
+            }) if id == "3"
+        )));
+    }
 }
@@ -0,0 +1,358 @@
+//! Bearer-token authentication for the API and WebSocket routes.
+//!
+//! Every `/api` request must present a token, either as an
+//! `Authorization: Bearer <token>` header or, for WebSocket upgrades where
+//! browsers cannot set headers, as a `token` query parameter. Tokens come
+//! from `[web.tokens]` in `config.toml`; when none are configured a single
+//! unrestricted token is generated at startup.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::http::header::AUTHORIZATION;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use codex_core::config::Config;
+use codex_core::config_types::WebConfig;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::config_types::SandboxMode;
+
+use crate::AppState;
+
+/// Name of the token generated when `config.toml` defines none.
+const GENERATED_TOKEN_NAME: &str = "default";
+
+/// Loosest approval policy a restricted token's sessions may use. Their
+/// sandbox is likewise limited to `workspace-write`.
+const RESTRICTED_APPROVAL_POLICY: AskForApproval = AskForApproval::OnRequest;
+
+/// What the caller presenting a token may do. Inserted into the request
+/// extensions by [`require_token`].
+#[derive(Clone, Debug)]
+pub(crate) struct Grant {
+    /// Name of the token. Sessions are only visible to the token that
+    /// created them.
+    pub(crate) name: String,
+    /// Canonical directories sessions may run in; `None` allows any.
+    cwd_roots: Option<Vec<PathBuf>>,
+}
+
+impl Grant {
+    /// Unrestricted tokens may also manage server-wide state such as login.
+    pub(crate) fn is_unrestricted(&self) -> bool {
+        self.cwd_roots.is_none()
+    }
+
+    /// Resolves the working directory for a session. A requested directory
+    /// must lie within one of the token's roots; without a request, a
+    /// restricted token starts in its first root.
+    pub(crate) fn resolve_cwd(
+        &self,
+        requested: Option<PathBuf>,
+    ) -> Result<Option<PathBuf>, String> {
+        let Some(roots) = &self.cwd_roots else {
+            return Ok(requested);
+        };
+        match requested {
+            Some(cwd) => self.check_cwd(&cwd).map(Some),
+            None => Ok(roots.first().cloned()),
+        }
+    }
+
+    /// Checks that `cwd` lies within the token's roots, returning it
+    /// canonicalized.
+    pub(crate) fn check_cwd(&self, cwd: &Path) -> Result<PathBuf, String> {
+        let canonical = cwd
+            .canonicalize()
+            .map_err(|e| format!("invalid cwd {}: {e}", cwd.display()))?;
+        match &self.cwd_roots {
+            Some(roots) if !roots.iter().any(|root| canonical.starts_with(root)) => Err(format!(
+                "cwd {} is outside the directories allowed for this token",
+                cwd.display()
+            )),
+            _ => Ok(canonical),
+        }
+    }
+
+    /// Checks that a requested approval policy and sandbox mode are within
+    /// what the token allows.
+    pub(crate) fn check_permissions(
+        &self,
+        approval_policy: Option<AskForApproval>,
+        sandbox_mode: Option<SandboxMode>,
+    ) -> Result<(), String> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+        if let Some(approval_policy) = approval_policy
+            && approval_policy.is_looser_than(RESTRICTED_APPROVAL_POLICY)
+        {
+            return Err(format!(
+                "approval policy `{approval_policy}` is not allowed for this token"
+            ));
+        }
+        if sandbox_mode == Some(SandboxMode::DangerFullAccess) {
+            return Err(
+                "sandbox mode `danger-full-access` is not allowed for this token".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Tightens the approval policy and sandbox that `config` picked up from
+    /// `config.toml` to what the token allows.
+    pub(crate) fn clamp_config(&self, config: &mut Config) {
+        if self.is_unrestricted() {
+            return;
+        }
+        if config
+            .approval_policy
+            .is_looser_than(RESTRICTED_APPROVAL_POLICY)
+        {
+            config.approval_policy = RESTRICTED_APPROVAL_POLICY;
+        }
+        if config.sandbox_policy.has_full_disk_write_access() {
+            config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        }
+    }
+}
+
+#[cfg(test)]
+impl Grant {
+    pub(crate) fn restricted(name: &str, root: &Path) -> Self {
+        Self {
+            name: name.to_string(),
+            cwd_roots: Some(vec![root.canonicalize().unwrap()]),
+        }
+    }
+
+    pub(crate) fn unrestricted(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            cwd_roots: None,
+        }
+    }
+}
+
+/// The tokens accepted by the server.
+pub(crate) struct AccessTokens {
+    tokens: Vec<(String, Grant)>,
+}
+
+impl AccessTokens {
+    /// Loads the tokens from `[web.tokens]`. When none are configured, one
+    /// unrestricted token is generated and also returned so that it can be
+    /// shown to the user.
+    pub(crate) fn from_config(web: &WebConfig) -> anyhow::Result<(Self, Option<String>)> {
+        if web.tokens.is_empty() {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            let grant = Grant {
+                name: GENERATED_TOKEN_NAME.to_string(),
+                cwd_roots: None,
+            };
+            let tokens = Self {
+                tokens: vec![(token.clone(), grant)],
+            };
+            return Ok((tokens, Some(token)));
+        }
+
+        let mut tokens = Vec::with_capacity(web.tokens.len());
+        for (name, config) in &web.tokens {
+            if config.token.is_empty() {
+                anyhow::bail!("web token `{name}` has an empty `token`");
+            }
+            let cwd_roots = match &config.cwd_roots {
+                Some(roots) => Some(
+                    roots
+                        .iter()
+                        .map(|root| {
+                            root.canonicalize().map_err(|e| {
+                                anyhow::anyhow!(
+                                    "web token `{name}`: invalid cwd root {}: {e}",
+                                    root.display()
+                                )
+                            })
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                ),
+                None => None,
+            };
+            let grant = Grant {
+                name: name.clone(),
+                cwd_roots,
+            };
+            tokens.push((config.token.clone(), grant));
+        }
+        Ok((Self { tokens }, None))
+    }
+
+    fn lookup(&self, presented: &str) -> Option<&Grant> {
+        // Compare against every token without short-circuiting so the
+        // response time does not reveal how much of a token matched.
+        let mut found = None;
+        for (token, grant) in &self.tokens {
+            if constant_time_eq(token.as_bytes(), presented.as_bytes()) {
+                found = Some(grant);
+            }
+        }
+        found
+    }
+}
+
+/// Middleware rejecting requests that do not present a known token.
+pub(crate) async fn require_token(
+    State(app): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let grant = presented_token(&req).and_then(|token| app.tokens.lookup(&token).cloned());
+    match grant {
+        Some(grant) => {
+            req.extensions_mut().insert(grant);
+            next.run(req).await
+        }
+        None => (StatusCode::UNAUTHORIZED, "missing or invalid access token").into_response(),
+    }
+}
+
+fn presented_token(req: &Request) -> Option<String> {
+    if let Some(value) = req.headers().get(AUTHORIZATION) {
+        return value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string());
+    }
+    let Query(mut params) = Query::<HashMap<String, String>>::try_from_uri(req.uri()).ok()?;
+    params.remove("token")
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::config_types::WebTokenConfig;
+
+    fn restricted(root: &Path) -> Grant {
+        Grant::restricted("alice", root)
+    }
+
+    #[test]
+    fn generates_a_token_when_none_configured() {
+        let (tokens, generated) = AccessTokens::from_config(&WebConfig::default()).unwrap();
+        let generated = generated.unwrap();
+        let grant = tokens.lookup(&generated).unwrap();
+        assert!(grant.is_unrestricted());
+        assert!(tokens.lookup("not-the-token").is_none());
+    }
+
+    #[test]
+    fn configured_tokens_map_to_their_grants() {
+        let root = tempfile::tempdir().unwrap();
+        let web = WebConfig {
+            tokens: HashMap::from([
+                (
+                    "admin".to_string(),
+                    WebTokenConfig {
+                        token: "admin-secret".to_string(),
+                        cwd_roots: None,
+                    },
+                ),
+                (
+                    "alice".to_string(),
+                    WebTokenConfig {
+                        token: "alice-secret".to_string(),
+                        cwd_roots: Some(vec![root.path().to_path_buf()]),
+                    },
+                ),
+            ]),
+        };
+        let (tokens, generated) = AccessTokens::from_config(&web).unwrap();
+        assert!(generated.is_none());
+        assert!(tokens.lookup("admin-secret").unwrap().is_unrestricted());
+        let alice = tokens.lookup("alice-secret").unwrap();
+        assert_eq!(alice.name, "alice");
+        assert!(!alice.is_unrestricted());
+        assert!(tokens.lookup("alice-secre").is_none());
+    }
+
+    #[test]
+    fn restricted_grants_only_accept_cwds_under_their_roots() {
+        let root = tempfile::tempdir().unwrap();
+        let inside = root.path().join("repo");
+        std::fs::create_dir(&inside).unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let grant = restricted(root.path());
+
+        assert_eq!(
+            grant.resolve_cwd(None).unwrap(),
+            Some(root.path().canonicalize().unwrap())
+        );
+        assert_eq!(
+            grant.resolve_cwd(Some(inside.clone())).unwrap(),
+            Some(inside.canonicalize().unwrap())
+        );
+        assert!(
+            grant
+                .resolve_cwd(Some(outside.path().to_path_buf()))
+                .is_err()
+        );
+        // `..` cannot be used to escape a root.
+        assert!(grant.resolve_cwd(Some(inside.join("../.."))).is_err());
+    }
+
+    #[test]
+    fn restricted_grants_are_limited_to_workspace_write_and_on_request() {
+        let root = tempfile::tempdir().unwrap();
+        let grant = restricted(root.path());
+
+        assert!(grant.check_permissions(None, None).is_ok());
+        assert!(
+            grant
+                .check_permissions(
+                    Some(AskForApproval::OnRequest),
+                    Some(SandboxMode::WorkspaceWrite)
+                )
+                .is_ok()
+        );
+        assert!(
+            grant
+                .check_permissions(Some(AskForApproval::UnlessTrusted), None)
+                .is_ok()
+        );
+        assert!(
+            grant
+                .check_permissions(Some(AskForApproval::Never), None)
+                .is_err()
+        );
+        assert!(
+            grant
+                .check_permissions(None, Some(SandboxMode::DangerFullAccess))
+                .is_err()
+        );
+
+        let unrestricted = Grant::unrestricted("admin");
+        assert!(
+            unrestricted
+                .check_permissions(
+                    Some(AskForApproval::Never),
+                    Some(SandboxMode::DangerFullAccess)
+                )
+                .is_ok()
+        );
+    }
+}
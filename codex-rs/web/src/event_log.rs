@@ -0,0 +1,209 @@
+//! Bounded, sequence-numbered log of the events a session has emitted, so
+//! WebSocket clients that connect late or reconnect can catch up before
+//! switching to the live stream.
+
+use std::collections::VecDeque;
+
+use codex_core::protocol::Event;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Number of events kept per session for replay.
+pub const EVENT_LOG_CAPACITY: usize = 4096;
+
+/// An event as sent to WebSocket clients: the protocol `Event` with its
+/// sequence number alongside `id` and `msg`.
+#[derive(Serialize)]
+struct SequencedEvent<'a> {
+    seq: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+#[derive(Clone, Debug)]
+pub struct LoggedEvent {
+    pub seq: u64,
+    pub json: String,
+}
+
+/// Sent to a client in place of events it asked for but that were already
+/// evicted from the log (or that this session never emitted).
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename = "replay_gap")]
+pub struct ReplayGap {
+    /// The `since` the client asked for.
+    pub requested: u64,
+    /// Oldest sequence number still available.
+    pub oldest: u64,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub gap: Option<ReplayGap>,
+    pub events: Vec<LoggedEvent>,
+    /// Sequence number of the newest event in the log; live events after the
+    /// replay start above it.
+    pub latest: u64,
+}
+
+pub struct EventLog {
+    capacity: usize,
+    /// The session's `SessionConfigured` event. It carries the conversation
+    /// history, so it is pinned as sequence 0 and never evicted.
+    initial: Option<LoggedEvent>,
+    events: VecDeque<LoggedEvent>,
+    next_seq: u64,
+    tx: broadcast::Sender<LoggedEvent>,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize, initial: &Event) -> Self {
+        let (tx, _rx) = broadcast::channel(1024);
+        Self {
+            capacity,
+            initial: encode(0, initial).map(|json| LoggedEvent { seq: 0, json }),
+            events: VecDeque::with_capacity(capacity),
+            next_seq: 1,
+            tx,
+        }
+    }
+
+    /// Appends `event` to the log and fans it out to live subscribers.
+    pub fn publish(&mut self, event: &Event) {
+        let seq = self.next_seq;
+        let Some(json) = encode(seq, event) else {
+            return;
+        };
+        self.next_seq += 1;
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        let logged = LoggedEvent { seq, json };
+        self.events.push_back(logged.clone());
+        let _ = self.tx.send(logged);
+    }
+
+    /// Events with a sequence number greater than `since`. `since == 0`
+    /// includes the pinned `SessionConfigured` event.
+    pub fn replay(&self, since: u64) -> Replay {
+        let latest = self.next_seq - 1;
+        let oldest = self.events.front().map_or(self.next_seq, |ev| ev.seq);
+        let gap = (since + 1 < oldest || since > latest).then_some(ReplayGap {
+            requested: since,
+            oldest,
+        });
+        let mut events = Vec::new();
+        if since == 0 {
+            events.extend(self.initial.clone());
+        }
+        events.extend(self.events.iter().filter(|ev| ev.seq > since).cloned());
+        Replay {
+            gap,
+            events,
+            latest,
+        }
+    }
+
+    /// Replays everything after `since` and subscribes to what follows.
+    /// Holding the log while doing both guarantees nothing is published in
+    /// between.
+    pub fn subscribe(&self, since: u64) -> (Replay, broadcast::Receiver<LoggedEvent>) {
+        (self.replay(since), self.tx.subscribe())
+    }
+}
+
+fn encode(seq: u64, event: &Event) -> Option<String> {
+    serde_json::to_string(&SequencedEvent { seq, event }).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::EventMsg;
+    use codex_core::protocol::TaskStartedEvent;
+
+    fn event(id: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            msg: EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+        }
+    }
+
+    fn seqs(replay: &Replay) -> Vec<u64> {
+        replay.events.iter().map(|ev| ev.seq).collect()
+    }
+
+    #[test]
+    fn replays_events_after_since_with_sequence_numbers() {
+        let mut log = EventLog::new(8, &event("init"));
+        for id in ["a", "b", "c"] {
+            log.publish(&event(id));
+        }
+
+        let all = log.replay(0);
+        assert_eq!(seqs(&all), vec![0, 1, 2, 3]);
+        assert_eq!(all.gap, None);
+        assert_eq!(all.latest, 3);
+        let value: serde_json::Value =
+            serde_json::from_str(&all.events[2].json).expect("valid json");
+        assert_eq!(value["seq"], 2);
+        assert_eq!(value["id"], "b");
+        assert_eq!(value["msg"]["type"], "task_started");
+
+        let tail = log.replay(2);
+        assert_eq!(seqs(&tail), vec![3]);
+        assert_eq!(tail.gap, None);
+        assert!(log.replay(3).events.is_empty());
+    }
+
+    #[test]
+    fn reports_gap_when_requested_events_were_evicted() {
+        let mut log = EventLog::new(2, &event("init"));
+        for id in ["a", "b", "c", "d"] {
+            log.publish(&event(id));
+        }
+
+        let replay = log.replay(1);
+        assert_eq!(seqs(&replay), vec![3, 4]);
+        assert_eq!(
+            replay.gap,
+            Some(ReplayGap {
+                requested: 1,
+                oldest: 3
+            })
+        );
+        // The pinned initial event survives eviction.
+        assert_eq!(seqs(&log.replay(0)), vec![0, 3, 4]);
+        assert_eq!(log.replay(2).gap, None);
+    }
+
+    #[test]
+    fn reports_gap_for_unknown_future_sequence() {
+        let mut log = EventLog::new(4, &event("init"));
+        log.publish(&event("a"));
+
+        let replay = log.replay(7);
+        assert!(replay.events.is_empty());
+        assert_eq!(
+            replay.gap,
+            Some(ReplayGap {
+                requested: 7,
+                oldest: 1
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_published_after_subscribing() {
+        let mut log = EventLog::new(4, &event("init"));
+        log.publish(&event("a"));
+        let (replay, mut rx) = log.subscribe(1);
+        assert!(replay.events.is_empty());
+
+        log.publish(&event("b"));
+        let live = rx.recv().await.expect("live event");
+        assert_eq!(live.seq, 2);
+    }
+}
@@ -5,31 +5,47 @@ use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use axum::Extension;
 use axum::Json;
 use axum::Router;
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::extract::State;
+use axum::http::StatusCode;
+use axum::middleware;
 use axum::routing::get;
 use axum::routing::post;
 use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::Cursor;
+use codex_core::RolloutRecorder;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SubAgentSource;
 use codex_protocol::config_types::SandboxMode;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
 use serde::Deserialize;
 use serde::Serialize;
 use tokio::net::TcpListener;
 use tokio::sync::RwLock;
-use tokio::sync::broadcast;
 use tracing::error;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
+mod auth;
+mod event_log;
+#[cfg(unix)]
+mod pty;
 mod server;
 mod session;
 
-use crate::session::ClientMsg;
+use crate::auth::AccessTokens;
+use crate::auth::Grant;
 use crate::session::SessionEntry;
+use crate::session::SessionInfo;
 
 #[derive(Clone, Debug)]
 pub struct ServerOpts {
@@ -72,17 +88,23 @@ pub async fn run_main(
         }
     };
 
-    // Shared state: auth manager, sessions, overrides.
-    let app_state = Arc::new(AppState::new(cli_kv_overrides, codex_linux_sandbox_exe));
+    let config =
+        Config::load_with_cli_overrides(cli_kv_overrides.clone(), ConfigOverrides::default())?;
+    let (tokens, generated_token) = AccessTokens::from_config(&config.web)?;
 
-    let mut app = Router::new()
-        .route("/healthz", get(|| async { "ok" }))
-        // Production static pages
-        .route("/", get(home_static))
-        .route("/session/:id", get(session_static))
-        .route("/pty", get(pty_static))
-        // APIs / WebSockets
-        .route("/api/sessions", post(create_session))
+    // Shared state: auth manager, sessions, overrides.
+    let auth_manager = AuthManager::shared(config.codex_home.clone());
+    let app_state = Arc::new(AppState::new(
+        cli_kv_overrides,
+        codex_linux_sandbox_exe,
+        tokens,
+        auth_manager,
+    ));
+
+    // APIs / WebSockets; all require an access token.
+    let api = Router::new()
+        .route("/api/sessions", get(list_sessions).post(create_session))
+        .route("/api/sessions/:id", get(get_session).delete(delete_session))
         .route("/api/sessions/:id/events", get(server::ws_events))
         .route("/api/pty", get(server::ws_pty))
         .route("/api/sessions/resume", post(resume_session))
@@ -93,6 +115,18 @@ pub async fn run_main(
         .route("/api/login/start", post(start_login))
         .route("/api/login/status", get(login_status))
         .route("/api/login/cancel", post(cancel_login))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::require_token,
+        ));
+
+    let mut app = Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        // Production static pages
+        .route("/", get(home_static))
+        .route("/session/:id", get(session_static))
+        .route("/pty", get(pty_static))
+        .merge(api)
         .with_state(app_state);
 
     if let Some(dir) = opts.static_dir.clone() {
@@ -106,10 +140,21 @@ pub async fn run_main(
     let addr: SocketAddr = format!("{}:{}", opts.host, opts.port).parse()?;
     info!("starting codex-web on http://{}", addr);
     let listener = TcpListener::bind(addr).await?;
+    if let Some(token) = generated_token {
+        print_generated_token(&addr, &token);
+    }
     axum::serve(listener, app).await?;
     Ok(())
 }
 
+/// Shows the generated access token once; it is not stored anywhere.
+#[allow(clippy::print_stderr)]
+fn print_generated_token(addr: &SocketAddr, token: &str) {
+    eprintln!("codex-web access token (set [web.tokens] in config.toml to use your own):");
+    eprintln!("  {token}");
+    eprintln!("Open http://{addr}/?token={token}");
+}
+
 #[allow(dead_code)]
 async fn home_page() -> axum::response::Html<String> {
     let html = r###"<!doctype html>
@@ -546,10 +591,23 @@ async fn home_page() -> axum::response::Html<String> {
         const input=el('input',{type:'text',placeholder:'Type and press Enter to send...'});
         const card=el('div',{class:'card'},[ out, input ]); app.append(el('div',{style:'height:8px'}), card);
         const ws = new WebSocket(`${location.protocol==='https:'?'wss':'ws'}://${location.host}/api/pty`);
-        ws.onmessage = ev => { try{ out.textContent += ev.data; out.scrollTop = out.scrollHeight; }catch{} };
+        // Binary frames are terminal output; text frames are JSON status messages.
+        ws.binaryType='arraybuffer';
+        const decoder=new TextDecoder(); const encoder=new TextEncoder();
+        ws.onmessage = ev => {
+          if(typeof ev.data!=='string'){ out.textContent += decoder.decode(ev.data,{stream:true}); }
+          else {
+            let msg; try{ msg=JSON.parse(ev.data); }catch{ return; }
+            if(msg.type==='attached') $('#ws-global').textContent=`connected (pty ${msg.id})`;
+            else if(msg.type==='exit') out.textContent += `\n[process exited${msg.code==null?'':` with code ${msg.code}`}]\n`;
+            else if(msg.type==='error') out.textContent += `\n[error: ${msg.message}]\n`;
+          }
+          out.scrollTop = out.scrollHeight;
+        };
         ws.onopen = ()=>$('#ws-global').textContent='connected (pty)';
         ws.onclose = ()=>$('#ws-global').textContent='disconnected (pty)';
-        input.addEventListener('keydown', e=>{ if(e.key==='Enter'){ ws.send(input.value+'\n'); input.value=''; }});
+        // Input goes out as binary so it is never mistaken for a control message.
+        input.addEventListener('keydown', e=>{ if(e.key==='Enter'){ ws.send(encoder.encode(input.value+'\n')); input.value=''; }});
       }
     </script>
   </body>
@@ -664,8 +722,21 @@ async fn pty_page() -> axum::response::Html<String> {
     <script type="module">
       const out=document.getElementById('out'); const input=document.getElementById('in');
       const ws=new WebSocket(`${location.protocol==='https:'?'wss':'ws'}://${location.host}/api/pty`);
-      ws.onmessage = ev => { out.textContent += ev.data; out.scrollTop=out.scrollHeight; };
-      input.addEventListener('keydown', e=>{ if(e.key==='Enter'){ ws.send(input.value+'\n'); input.value=''; } });
+      // Binary frames are terminal output; text frames are JSON status messages.
+      ws.binaryType='arraybuffer';
+      const decoder=new TextDecoder(); const encoder=new TextEncoder();
+      ws.onmessage = ev => {
+        if(typeof ev.data!=='string'){ out.textContent += decoder.decode(ev.data,{stream:true}); }
+        else {
+          let msg; try{ msg=JSON.parse(ev.data); }catch{ return; }
+          if(msg.type==='attached') document.title=`Terminal ${msg.id}`;
+          else if(msg.type==='exit') out.textContent += `\n[process exited${msg.code==null?'':` with code ${msg.code}`}]\n`;
+          else if(msg.type==='error') out.textContent += `\n[error: ${msg.message}]\n`;
+        }
+        out.scrollTop=out.scrollHeight;
+      };
+      // Input goes out as binary so it is never mistaken for a control message.
+      input.addEventListener('keydown', e=>{ if(e.key==='Enter'){ ws.send(encoder.encode(input.value+'\n')); input.value=''; } });
     </script>
   </body>
 </html>"###;
@@ -705,19 +776,34 @@ struct AppState {
     codex_linux_sandbox_exe: Option<PathBuf>,
     /// Session map: id -> entry
     sessions: Arc<RwLock<HashMap<uuid::Uuid, SessionEntry>>>,
+    /// Owns every live conversation started by this server.
+    conversations: Arc<ConversationManager>,
+    auth_manager: Arc<AuthManager>,
     login: Arc<RwLock<Option<LoginState>>>,
+    /// Access tokens accepted on `/api` routes.
+    tokens: Arc<AccessTokens>,
+    /// Detachable terminals started through `/api/pty`.
+    #[cfg(unix)]
+    ptys: pty::PtyRegistry,
 }
 
 impl AppState {
     fn new(
         cli_kv_overrides: Vec<(String, toml::Value)>,
         codex_linux_sandbox_exe: Option<PathBuf>,
+        tokens: AccessTokens,
+        auth_manager: Arc<AuthManager>,
     ) -> Self {
         Self {
             cli_kv_overrides,
             codex_linux_sandbox_exe,
+            tokens: Arc::new(tokens),
+            conversations: Arc::new(ConversationManager::new(auth_manager.clone())),
+            auth_manager,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             login: Arc::new(RwLock::new(None)),
+            #[cfg(unix)]
+            ptys: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -728,16 +814,34 @@ struct LoginStartResp {
     port: u16,
 }
 
+/// How long a started login may wait for the browser to finish it.
+const LOGIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
 struct LoginState {
-    _server: codex_login::LoginServer,
+    login_id: uuid::Uuid,
     shutdown: codex_login::ShutdownHandle,
     auth_url: String,
     port: u16,
 }
 
+/// Login changes the credentials used by every session on the server, so it
+/// is reserved for unrestricted tokens.
+fn require_unrestricted(grant: &Grant) -> Result<(), (StatusCode, String)> {
+    if grant.is_unrestricted() {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            format!("token `{}` may not manage login", grant.name),
+        ))
+    }
+}
+
 async fn start_login(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
 ) -> Result<Json<LoginStartResp>, (axum::http::StatusCode, String)> {
+    require_unrestricted(&grant)?;
     let config =
         Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), ConfigOverrides::default())
             .map_err(|e| {
@@ -772,9 +876,10 @@ async fn start_login(
         port: server.actual_port,
     };
     let shutdown = server.cancel_handle();
+    let login_id = uuid::Uuid::new_v4();
     let stored = LoginState {
-        _server: server,
-        shutdown,
+        login_id,
+        shutdown: shutdown.clone(),
         auth_url: resp.auth_url.clone(),
         port: resp.port,
     };
@@ -783,6 +888,25 @@ async fn start_login(
         *guard = Some(stored);
     }
 
+    // Sessions share `auth_manager`, so it must pick up the new credentials
+    // as soon as the login completes.
+    let login = app.login.clone();
+    let auth_manager = app.auth_manager.clone();
+    tokio::spawn(async move {
+        match tokio::time::timeout(LOGIN_TIMEOUT, server.block_until_done()).await {
+            Ok(Ok(())) => {
+                auth_manager.reload();
+            }
+            Ok(Err(e)) => error!("login server error: {e}"),
+            Err(_) => shutdown.shutdown(),
+        }
+        // Clear the login unless it has been replaced or cancelled since.
+        let mut guard = login.write().await;
+        if guard.as_ref().map(|state| state.login_id) == Some(login_id) {
+            *guard = None;
+        }
+    });
+
     Ok(Json(resp))
 }
 
@@ -796,7 +920,9 @@ enum LoginStatusResp {
 
 async fn login_status(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
 ) -> Result<Json<LoginStatusResp>, (axum::http::StatusCode, String)> {
+    require_unrestricted(&grant)?;
     if let Some(state) = app.login.read().await.as_ref() {
         return Ok(Json(LoginStatusResp::Pending {
             auth_url: state.auth_url.clone(),
@@ -835,7 +961,9 @@ async fn login_status(
 
 async fn cancel_login(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
 ) -> Result<(), (axum::http::StatusCode, String)> {
+    require_unrestricted(&grant)?;
     let mut guard = app.login.write().await;
     if let Some(state) = guard.take() {
         state.shutdown.shutdown();
@@ -845,12 +973,19 @@ async fn cancel_login(
 
 async fn create_session(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
     Json(req): Json<CreateSessionReq>,
 ) -> Result<Json<CreateSessionResp>, (axum::http::StatusCode, String)> {
+    let cwd = grant
+        .resolve_cwd(req.cwd)
+        .map_err(|e| (StatusCode::FORBIDDEN, e))?;
+    grant
+        .check_permissions(req.approval_policy, req.sandbox_mode)
+        .map_err(|e| (StatusCode::FORBIDDEN, e))?;
     // Merge per-session overrides with global -c overrides and build Config.
     let overrides = ConfigOverrides {
         model: req.model,
-        cwd: req.cwd,
+        cwd,
         approval_policy: req.approval_policy,
         sandbox_mode: req.sandbox_mode,
         model_provider: None,
@@ -865,138 +1000,115 @@ async fn create_session(
         tools_web_search_request: None,
     };
 
-    let config =
-        Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), overrides).map_err(|e| {
+    // Pick up credentials changed outside this server, e.g. by `codex login`
+    // run in one of its terminals.
+    app.auth_manager.reload();
+    let mut config = Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), overrides)
+        .map_err(|e| {
             (
                 axum::http::StatusCode::BAD_REQUEST,
                 format!("config error: {e}"),
             )
         })?;
+    grant.clamp_config(&mut config);
 
-    // Initialize AuthManager per server and spawn session.
-    let auth_manager = codex_core::AuthManager::shared(config.codex_home.clone());
+    let cwd = config.cwd.clone();
+    let conv = app
+        .conversations
+        .new_conversation(config)
+        .await
+        .map_err(|e| {
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("spawn error: {e}"),
+            )
+        })?;
+    Ok(Json(register_session(&app, grant.name, conv, cwd).await))
+}
 
-    let cm = codex_core::ConversationManager::new(auth_manager.clone());
-    let conv = cm.new_conversation(config).await.map_err(|e| {
-        (
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            format!("spawn error: {e}"),
-        )
-    })?;
+/// Starts pumping a new conversation's events and records it in the session
+/// map under `owner`.
+async fn register_session(
+    app: &AppState,
+    owner: String,
+    conv: codex_core::NewConversation,
+    cwd: PathBuf,
+) -> CreateSessionResp {
+    let session_id: uuid::Uuid = conv.conversation_id.into();
+    let entry = SessionEntry::spawn(owner, conv, cwd);
+    app.sessions.write().await.insert(session_id, entry);
+    CreateSessionResp { session_id }
+}
 
-    // Create a broadcaster and spawn tasks.
-    let (tx, _rx) = broadcast::channel::<String>(1024);
-    // Broadcast initial SessionConfigured (consumed by ConversationManager).
-    let initial = codex_core::protocol::Event {
-        id: "".to_string(),
-        msg: codex_core::protocol::EventMsg::SessionConfigured(conv.session_configured.clone()),
-    };
-    let initial_event_json = match serde_json::to_string(&initial) {
-        Ok(json) => {
-            let _ = tx.send(json.clone());
-            Some(json)
-        }
-        Err(_) => None,
-    };
-    // Event pump task (capture conversation without naming its type)
-    let conversation_for_events = conv.conversation.clone();
-    let tx_events = tx.clone();
-    let event_task = tokio::spawn(async move {
-        loop {
-            let ev = match conversation_for_events.next_event().await {
-                Ok(ev) => ev,
-                Err(_) => break,
-            };
-            if let Ok(json) = serde_json::to_string(&ev) {
-                let _ = tx_events.send(json);
-            }
-        }
-    });
+#[derive(Debug, Serialize)]
+struct SessionListResp {
+    items: Vec<SessionInfo>,
+}
 
-    // Ops consumer task
-    let (ops_tx, mut ops_rx) = tokio::sync::mpsc::unbounded_channel::<ClientMsg>();
-    let conversation_for_ops = conv.conversation.clone();
-    let ops_task = tokio::spawn(async move {
-        use codex_core::protocol::InputItem;
-        use codex_core::protocol::Op;
-        while let Some(msg) = ops_rx.recv().await {
-            match msg {
-                ClientMsg::UserMessage { text, images } => {
-                    let mut items = vec![InputItem::Text { text }];
-                    if let Some(imgs) = images {
-                        for image_url in imgs {
-                            items.push(InputItem::Image { image_url });
-                        }
-                    }
-                    let _ = conversation_for_ops.submit(Op::UserInput { items }).await;
-                }
-                ClientMsg::Interrupt => {
-                    let _ = conversation_for_ops.submit(Op::Interrupt).await;
-                }
-                ClientMsg::ExecApproval { id, decision } => {
-                    let _ = conversation_for_ops
-                        .submit(Op::ExecApproval { id, decision })
-                        .await;
-                }
-                ClientMsg::PatchApproval { id, decision } => {
-                    let _ = conversation_for_ops
-                        .submit(Op::PatchApproval { id, decision })
-                        .await;
-                }
-                ClientMsg::Compact => {
-                    let _ = conversation_for_ops.submit(Op::Compact).await;
-                }
-                ClientMsg::OverrideTurnContext {
-                    cwd,
-                    model,
-                    approval_policy,
-                    effort,
-                    sandbox_mode,
-                } => {
-                    use codex_core::protocol::SandboxPolicy;
-                    use codex_protocol::config_types::SandboxMode;
-                    let sandbox_policy = match sandbox_mode {
-                        Some(SandboxMode::ReadOnly) => Some(SandboxPolicy::new_read_only_policy()),
-                        Some(SandboxMode::WorkspaceWrite) => {
-                            Some(SandboxPolicy::new_workspace_write_policy())
-                        }
-                        Some(SandboxMode::DangerFullAccess) => {
-                            Some(SandboxPolicy::DangerFullAccess)
-                        }
-                        None => None,
-                    };
-                    let _ = conversation_for_ops
-                        .submit(Op::OverrideTurnContext {
-                            cwd,
-                            approval_policy,
-                            sandbox_policy,
-                            model,
-                            effort,
-                            summary: None,
-                            default_exec_timeout_ms: None,
-                        })
-                        .await;
-                }
-            }
-        }
-    });
+/// How long `DELETE /api/sessions/:id` waits for the conversation to finish
+/// shutting down before aborting its tasks.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
-    // Save session entry.
-    let entry = SessionEntry {
-        broadcaster: tx,
-        ops_tx,
-        _event_task: event_task,
-        _ops_task: ops_task,
-        initial_event_json,
-    };
-    {
+fn session_not_found(id: uuid::Uuid) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("no such session: {id}"))
+}
+
+/// Live sessions owned by the caller's token, most recently active first.
+async fn list_sessions(
+    State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
+) -> Json<SessionListResp> {
+    let guard = app.sessions.read().await;
+    let mut items = Vec::new();
+    for entry in guard.values().filter(|entry| entry.owner == grant.name) {
+        items.push(entry.info.read().await.clone());
+    }
+    items.sort_by_key(|info| std::cmp::Reverse(info.last_activity));
+    Json(SessionListResp { items })
+}
+
+async fn get_session(
+    State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Json<SessionInfo>, (StatusCode, String)> {
+    let guard = app.sessions.read().await;
+    match guard.get(&id) {
+        Some(entry) if entry.owner == grant.name => Ok(Json(entry.info.read().await.clone())),
+        _ => Err(session_not_found(id)),
+    }
+}
+
+/// Shuts the conversation down and drops the session; attached WebSockets
+/// are closed once its event stream ends.
+async fn delete_session(
+    State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mut entry = {
         let mut guard = app.sessions.write().await;
-        guard.insert(conv.conversation_id.into(), entry);
+        match guard.get(&id) {
+            Some(entry) if entry.owner == grant.name => guard.remove(&id),
+            _ => None,
+        }
     }
+    .ok_or_else(|| session_not_found(id))?;
 
-    Ok(Json(CreateSessionResp {
-        session_id: conv.conversation_id.into(),
-    }))
+    app.conversations.remove_conversation(&id.into()).await;
+    if let Err(e) = entry
+        .conversation
+        .submit(codex_core::protocol::Op::Shutdown)
+        .await
+    {
+        error!("failed to submit shutdown for session {id}: {e}");
+    }
+    // The event pump exits once the conversation has shut down and closed its
+    // event channel; don't let a stuck conversation hold up the request.
+    let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut entry.event_task).await;
+    entry.event_task.abort();
+    entry.ops_task.abort();
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[derive(Debug, Deserialize)]
@@ -1006,20 +1118,38 @@ struct ResumeReq {
 
 async fn resume_session(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
     Json(req): Json<ResumeReq>,
 ) -> Result<Json<CreateSessionResp>, (axum::http::StatusCode, String)> {
-    let config =
-        Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), ConfigOverrides::default())
-            .map_err(|e| {
+    // Restricted tokens may only resume conversations recorded in one of
+    // their directories, and the resumed session runs there too.
+    let mut overrides = ConfigOverrides::default();
+    if !grant.is_unrestricted() {
+        let cwd = rollout_cwd(&req.path).await.ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("not a rollout file: {}", req.path.display()),
+            )
+        })?;
+        overrides.cwd = Some(
+            grant
+                .check_cwd(&cwd)
+                .map_err(|e| (StatusCode::FORBIDDEN, e))?,
+        );
+    }
+    let mut config = Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), overrides)
+        .map_err(|e| {
             (
                 axum::http::StatusCode::BAD_REQUEST,
                 format!("config error: {e}"),
             )
         })?;
-    let auth_manager = codex_core::AuthManager::shared(config.codex_home.clone());
-    let cm = codex_core::ConversationManager::new(auth_manager.clone());
-    let conv = cm
-        .resume_conversation_from_rollout(config, req.path.clone(), auth_manager)
+    grant.clamp_config(&mut config);
+    let cwd = config.cwd.clone();
+    app.auth_manager.reload();
+    let conv = app
+        .conversations
+        .resume_conversation_from_rollout(config, req.path.clone(), app.auth_manager.clone())
         .await
         .map_err(|e| {
             (
@@ -1027,127 +1157,55 @@ async fn resume_session(
                 format!("resume error: {e}"),
             )
         })?;
+    Ok(Json(register_session(&app, grant.name, conv, cwd).await))
+}
 
-    let (tx, _rx) = broadcast::channel::<String>(1024);
-    let initial = codex_core::protocol::Event {
-        id: "".to_string(),
-        msg: codex_core::protocol::EventMsg::SessionConfigured(conv.session_configured.clone()),
-    };
-    let initial_event_json = match serde_json::to_string(&initial) {
-        Ok(json) => {
-            let _ = tx.send(json.clone());
-            Some(json)
-        }
-        Err(_) => None,
-    };
-    let conversation_for_events = conv.conversation.clone();
-    let tx_events = tx.clone();
-    let event_task = tokio::spawn(async move {
-        loop {
-            let ev = match conversation_for_events.next_event().await {
-                Ok(ev) => ev,
-                Err(_) => break,
-            };
-            if let Ok(json) = serde_json::to_string(&ev) {
-                let _ = tx_events.send(json);
-            }
-        }
-    });
-    let (ops_tx, mut ops_rx) = tokio::sync::mpsc::unbounded_channel::<ClientMsg>();
-    let conversation_for_ops = conv.conversation.clone();
-    let ops_task = tokio::spawn(async move {
-        use codex_core::protocol::InputItem;
-        use codex_core::protocol::Op;
-        while let Some(msg) = ops_rx.recv().await {
-            match msg {
-                ClientMsg::UserMessage { text, images } => {
-                    let mut items = vec![InputItem::Text { text }];
-                    if let Some(imgs) = images {
-                        for image_url in imgs {
-                            items.push(InputItem::Image { image_url });
-                        }
-                    }
-                    let _ = conversation_for_ops.submit(Op::UserInput { items }).await;
-                }
-                ClientMsg::Interrupt => {
-                    let _ = conversation_for_ops.submit(Op::Interrupt).await;
-                }
-                ClientMsg::ExecApproval { id, decision } => {
-                    let _ = conversation_for_ops
-                        .submit(Op::ExecApproval { id, decision })
-                        .await;
-                }
-                ClientMsg::PatchApproval { id, decision } => {
-                    let _ = conversation_for_ops
-                        .submit(Op::PatchApproval { id, decision })
-                        .await;
-                }
-                ClientMsg::Compact => {
-                    let _ = conversation_for_ops.submit(Op::Compact).await;
-                }
-                ClientMsg::OverrideTurnContext {
-                    cwd,
-                    model,
-                    approval_policy,
-                    effort,
-                    sandbox_mode,
-                } => {
-                    use codex_core::protocol::SandboxPolicy;
-                    use codex_protocol::config_types::SandboxMode;
-                    let sandbox_policy = match sandbox_mode {
-                        Some(SandboxMode::ReadOnly) => Some(SandboxPolicy::new_read_only_policy()),
-                        Some(SandboxMode::WorkspaceWrite) => {
-                            Some(SandboxPolicy::new_workspace_write_policy())
-                        }
-                        Some(SandboxMode::DangerFullAccess) => {
-                            Some(SandboxPolicy::DangerFullAccess)
-                        }
-                        None => None,
-                    };
-                    let _ = conversation_for_ops
-                        .submit(Op::OverrideTurnContext {
-                            cwd,
-                            approval_policy,
-                            sandbox_policy,
-                            model,
-                            effort,
-                            summary: None,
-                            default_exec_timeout_ms: None,
-                        })
-                        .await;
-                }
-            }
-        }
-    });
-    let entry = SessionEntry {
-        broadcaster: tx,
-        ops_tx,
-        _event_task: event_task,
-        _ops_task: ops_task,
-        initial_event_json,
-    };
-    {
-        let mut guard = app.sessions.write().await;
-        guard.insert(conv.conversation_id.into(), entry);
+/// Working directory recorded in the `SessionMeta` line of a rollout file.
+async fn rollout_cwd(path: &std::path::Path) -> Option<PathBuf> {
+    use tokio::io::AsyncBufReadExt;
+
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let first_line = tokio::io::BufReader::new(file)
+        .lines()
+        .next_line()
+        .await
+        .ok()??;
+    match serde_json::from_str::<RolloutLine>(&first_line).ok()?.item {
+        RolloutItem::SessionMeta(meta) => Some(meta.meta.cwd),
+        _ => None,
     }
-    Ok(Json(CreateSessionResp {
-        session_id: conv.conversation_id.into(),
-    }))
 }
 
 #[derive(Debug, Serialize)]
 struct RolloutListItem {
     id: String,
     path: PathBuf,
+    /// Present when the rollout is a sub-agent transcript.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub_agent: Option<SubAgentSource>,
 }
 
 #[derive(Debug, Serialize)]
 struct RolloutListResp {
     items: Vec<RolloutListItem>,
+    /// Pass as `cursor` to list older rollouts; absent once all are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RolloutListQuery {
+    /// `next_cursor` of the previous response.
+    cursor: Option<String>,
 }
 
+/// Rollouts listed per request.
+const ROLLOUT_LIST_PAGE_SIZE: usize = 20;
+
 async fn list_rollout_conversations(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
+    Query(query): Query<RolloutListQuery>,
 ) -> Result<Json<RolloutListResp>, (axum::http::StatusCode, String)> {
     let config =
         Config::load_with_cli_overrides(app.cli_kv_overrides.clone(), ConfigOverrides::default())
@@ -1157,25 +1215,144 @@ async fn list_rollout_conversations(
                 format!("config error: {e}"),
             )
         })?;
-    match codex_core::RolloutRecorder::list_conversations(&config.codex_home, 20, None).await {
-        Ok(page) => {
-            let items = page
-                .items
+    let cursor = match query.cursor {
+        Some(cursor) => Some(
+            serde_json::from_value::<Cursor>(serde_json::Value::String(cursor)).map_err(|e| {
+                (
+                    axum::http::StatusCode::BAD_REQUEST,
+                    format!("invalid cursor: {e}"),
+                )
+            })?,
+        ),
+        None => None,
+    };
+    list_rollouts(&config.codex_home, &grant, cursor)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("list error: {e}"),
+            )
+        })
+}
+
+/// Lists the rollouts `grant` may see, newest first, starting after `cursor`.
+/// Restricted tokens only see rollouts recorded in their directories, so
+/// pages are read until enough of those are found or none are left.
+async fn list_rollouts(
+    codex_home: &std::path::Path,
+    grant: &Grant,
+    mut cursor: Option<Cursor>,
+) -> std::io::Result<RolloutListResp> {
+    let mut items = Vec::new();
+    loop {
+        let page = RolloutRecorder::list_conversations(
+            codex_home,
+            ROLLOUT_LIST_PAGE_SIZE,
+            cursor.as_ref(),
+        )
+        .await?;
+        items.extend(
+            page.items
                 .into_iter()
+                .filter(|it| {
+                    grant.is_unrestricted()
+                        || it
+                            .head
+                            .first()
+                            .and_then(|meta| meta.get("cwd"))
+                            .and_then(|cwd| cwd.as_str())
+                            .is_some_and(|cwd| grant.check_cwd(std::path::Path::new(cwd)).is_ok())
+                })
                 .map(|it| {
                     let id = it
                         .path
                         .file_name()
                         .map(|s| s.to_string_lossy().to_string())
                         .unwrap_or_else(|| it.path.display().to_string());
-                    RolloutListItem { id, path: it.path }
-                })
-                .collect();
-            Ok(Json(RolloutListResp { items }))
+                    let sub_agent = it.sub_agent();
+                    RolloutListItem {
+                        id,
+                        path: it.path,
+                        sub_agent,
+                    }
+                }),
+        );
+        cursor = page.next_cursor;
+        if items.len() >= ROLLOUT_LIST_PAGE_SIZE || cursor.is_none() {
+            break;
         }
-        Err(e) => Err((
-            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-            format!("list error: {e}"),
-        )),
+    }
+    let next_cursor = match cursor.map(serde_json::to_value) {
+        Some(Ok(serde_json::Value::String(cursor))) => Some(cursor),
+        _ => None,
+    };
+    Ok(RolloutListResp { items, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_rollout(codex_home: &std::path::Path, minute: u32, cwd: &std::path::Path) {
+        let ts = format!("2025-01-01T00-{minute:02}-00");
+        let id = uuid::Uuid::new_v4();
+        let dir = codex_home.join("sessions/2025/01/01");
+        std::fs::create_dir_all(&dir).unwrap();
+        let meta = serde_json::json!({
+            "timestamp": ts,
+            "type": "session_meta",
+            "payload": {
+                "id": id,
+                "timestamp": ts,
+                "instructions": null,
+                "cwd": cwd,
+                "originator": "test",
+                "cli_version": "0.0.0"
+            }
+        });
+        let user_message = serde_json::json!({
+            "timestamp": ts,
+            "type": "event_msg",
+            "payload": {"type": "user_message", "message": "hi", "kind": "plain"}
+        });
+        std::fs::write(
+            dir.join(format!("rollout-{ts}-{id}.jsonl")),
+            format!("{meta}\n{user_message}\n"),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn restricted_listing_reads_past_rollouts_of_other_directories() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        for minute in 0..3 {
+            write_rollout(codex_home.path(), minute, root.path());
+        }
+        for minute in 10..35 {
+            write_rollout(codex_home.path(), minute, elsewhere.path());
+        }
+
+        let grant = Grant::restricted("alice", root.path());
+        let listed = list_rollouts(codex_home.path(), &grant, None)
+            .await
+            .unwrap();
+        assert_eq!(listed.items.len(), 3);
+        assert_eq!(listed.next_cursor, None);
+
+        let admin = Grant::unrestricted("admin");
+        let first = list_rollouts(codex_home.path(), &admin, None)
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), ROLLOUT_LIST_PAGE_SIZE);
+        let cursor =
+            serde_json::from_value(serde_json::Value::String(first.next_cursor.unwrap())).unwrap();
+        let rest = list_rollouts(codex_home.path(), &admin, Some(cursor))
+            .await
+            .unwrap();
+        assert_eq!(rest.items.len(), 8);
     }
 }
@@ -0,0 +1,466 @@
+//! Terminals running the Codex TUI behind `/api/pty`.
+//!
+//! Client to server: binary frames are terminal input; text frames are JSON
+//! control messages (`resize`, `detach`), and text that is not a control
+//! message is written to the terminal as-is.
+//!
+//! Server to client: binary frames are terminal output; text frames are JSON
+//! status messages (`attached`, `exit`, `error`).
+//!
+//! A terminal started with `detach=true` keeps running when its socket goes
+//! away and can be reattached with `id=<uuid>`; otherwise it is killed when
+//! the socket closes. Detached terminals are killed once nobody has attached
+//! for [`DETACHED_IDLE_TIMEOUT`], and each token may keep at most
+//! [`MAX_DETACHABLE_PER_OWNER`] of them.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use axum::extract::ws::WebSocket;
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use futures_util::stream::SplitSink;
+use portable_pty::ChildKiller;
+use portable_pty::CommandBuilder;
+use portable_pty::MasterPty;
+use portable_pty::NativePtySystem;
+use portable_pty::PtySize;
+use portable_pty::PtySystem;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Detachable terminals by id.
+pub type PtyRegistry = Arc<RwLock<HashMap<uuid::Uuid, Arc<PtySession>>>>;
+
+/// Output kept per terminal so a reattaching client can redraw the screen.
+const SCROLLBACK_BYTES: usize = 256 * 1024;
+
+const DEFAULT_ROWS: u16 = 30;
+const DEFAULT_COLS: u16 = 100;
+
+/// How long a detached terminal may go without a client before it is killed.
+pub const DETACHED_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Detachable terminals a single token may have running at once.
+pub const MAX_DETACHABLE_PER_OWNER: usize = 4;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PtyQuery {
+    /// Reattach to a detached terminal instead of starting a new one.
+    pub id: Option<uuid::Uuid>,
+    pub cwd: Option<PathBuf>,
+    /// Extra arguments for `codex`, as a JSON array of strings.
+    pub args: Option<String>,
+    /// Keep the terminal running when the socket disconnects.
+    #[serde(default)]
+    pub detach: bool,
+    pub rows: Option<u16>,
+    pub cols: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PtyControl {
+    Resize {
+        rows: u16,
+        cols: u16,
+        #[serde(default)]
+        pixel_width: u16,
+        #[serde(default)]
+        pixel_height: u16,
+    },
+    /// Close this connection, leaving a detachable terminal running.
+    Detach,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PtyStatus<'a> {
+    Attached { id: uuid::Uuid, detachable: bool },
+    Exit { code: Option<u32> },
+    Error { message: &'a str },
+}
+
+impl PtyStatus<'_> {
+    pub fn to_message(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap_or_default())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum PtyOutput {
+    Data(Vec<u8>),
+    Exited(Option<u32>),
+}
+
+/// How an attachment ended.
+#[derive(Debug, PartialEq, Eq)]
+enum Detached {
+    /// The client asked to detach or went away.
+    ByClient,
+    /// Another connection attached to the same terminal.
+    Replaced,
+    /// The process exited.
+    Exited,
+}
+
+#[derive(Default)]
+struct PtyState {
+    scrollback: VecDeque<u8>,
+    /// Set once the process has exited.
+    exit: Option<Option<u32>>,
+}
+
+pub struct PtySession {
+    pub owner: String,
+    id: uuid::Uuid,
+    detachable: bool,
+    master: StdMutex<Box<dyn MasterPty + Send>>,
+    writer: StdMutex<Box<dyn Write + Send>>,
+    killer: StdMutex<Box<dyn ChildKiller + Send + Sync>>,
+    /// Updated together with publishing to `output_tx` so an attaching client
+    /// sees each chunk exactly once, either in the scrollback or live.
+    state: StdMutex<PtyState>,
+    output_tx: broadcast::Sender<PtyOutput>,
+    /// Tells the currently attached socket to let go when another attaches.
+    attached: StdMutex<Option<oneshot::Sender<()>>>,
+    /// Number of attachments so far; an idle kill is called off when this
+    /// changes before it fires.
+    attachments: AtomicU64,
+}
+
+impl PtySession {
+    /// Starts `codex` in a new terminal. Detachable terminals are added to
+    /// `registry` until the process exits.
+    pub async fn spawn(
+        owner: String,
+        query: &PtyQuery,
+        cwd: Option<PathBuf>,
+        args: Vec<String>,
+        registry: &PtyRegistry,
+    ) -> anyhow::Result<Arc<Self>> {
+        let mut cmd = CommandBuilder::new("codex");
+        cmd.args(args);
+        if let Some(cwd) = cwd {
+            cmd.cwd(cwd);
+        }
+        Self::spawn_command(owner, query, cmd, registry).await
+    }
+
+    async fn spawn_command(
+        owner: String,
+        query: &PtyQuery,
+        cmd: CommandBuilder,
+        registry: &PtyRegistry,
+    ) -> anyhow::Result<Arc<Self>> {
+        // Held until the new terminal is registered so concurrent requests
+        // cannot exceed the limit together.
+        let mut detachable = if query.detach {
+            let ptys = registry.write().await;
+            let running = ptys
+                .values()
+                .filter(|session| session.owner == owner)
+                .count();
+            if running >= MAX_DETACHABLE_PER_OWNER {
+                anyhow::bail!(
+                    "too many detachable terminals ({running}); close one before starting another"
+                );
+            }
+            Some(ptys)
+        } else {
+            None
+        };
+
+        let pair = NativePtySystem::default().openpty(PtySize {
+            rows: query.rows.unwrap_or(DEFAULT_ROWS),
+            cols: query.cols.unwrap_or(DEFAULT_COLS),
+            pixel_width: 0,
+            pixel_height: 0,
+        })?;
+
+        let mut child = pair.slave.spawn_command(cmd)?;
+        let mut killer = child.clone_killer();
+        let (mut reader, writer) = match (pair.master.try_clone_reader(), pair.master.take_writer())
+        {
+            (Ok(reader), Ok(writer)) => (reader, writer),
+            (Err(e), _) | (_, Err(e)) => {
+                let _ = killer.kill();
+                return Err(e);
+            }
+        };
+
+        let (output_tx, _) = broadcast::channel(256);
+        let session = Arc::new(Self {
+            owner,
+            id: uuid::Uuid::new_v4(),
+            detachable: query.detach,
+            master: StdMutex::new(pair.master),
+            writer: StdMutex::new(writer),
+            killer: StdMutex::new(killer),
+            state: StdMutex::new(PtyState::default()),
+            output_tx,
+            attached: StdMutex::new(None),
+            attachments: AtomicU64::new(0),
+        });
+        if let Some(ptys) = &mut detachable {
+            ptys.insert(session.id, session.clone());
+        }
+        drop(detachable);
+
+        let reader_session = session.clone();
+        let registry = registry.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut buf = [0u8; 8192];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => reader_session.publish_output(&buf[..n]),
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+            let code = child.wait().ok().map(|status| status.exit_code());
+            reader_session.publish_exit(code);
+            if reader_session.detachable {
+                registry.blocking_write().remove(&reader_session.id);
+            }
+        });
+        Ok(session)
+    }
+
+    fn publish_output(&self, bytes: &[u8]) {
+        if let Ok(mut state) = self.state.lock() {
+            state.scrollback.extend(bytes);
+            let excess = state.scrollback.len().saturating_sub(SCROLLBACK_BYTES);
+            state.scrollback.drain(..excess);
+            let _ = self.output_tx.send(PtyOutput::Data(bytes.to_vec()));
+        }
+    }
+
+    fn publish_exit(&self, code: Option<u32>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.exit = Some(code);
+            let _ = self.output_tx.send(PtyOutput::Exited(code));
+        }
+    }
+
+    fn write_input(&self, bytes: &[u8]) {
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.write_all(bytes);
+            let _ = writer.flush();
+        }
+    }
+
+    fn resize(&self, size: PtySize) {
+        if let Ok(master) = self.master.lock()
+            && let Err(e) = master.resize(size)
+        {
+            debug!("pty resize failed: {e}");
+        }
+    }
+
+    fn kill(&self) {
+        if let Ok(mut killer) = self.killer.lock() {
+            let _ = killer.kill();
+        }
+    }
+
+    /// Streams the terminal over `socket` until the attachment ends, then
+    /// kills the process unless it is detachable, in which case it is killed
+    /// after [`DETACHED_IDLE_TIMEOUT`] without a client.
+    pub async fn serve(self: Arc<Self>, socket: WebSocket) {
+        let detached = self.attach(socket).await;
+        debug!("pty {} detached: {detached:?}", self.id);
+        if !self.detachable {
+            self.kill();
+        } else if detached == Detached::ByClient {
+            self.kill_when_idle(DETACHED_IDLE_TIMEOUT);
+        }
+    }
+
+    /// Kills the process after `timeout` unless a client attaches meanwhile.
+    fn kill_when_idle(self: &Arc<Self>, timeout: Duration) {
+        let attachments = self.attachments.load(Ordering::SeqCst);
+        let session = Arc::downgrade(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            if let Some(session) = session.upgrade()
+                && session.attachments.load(Ordering::SeqCst) == attachments
+            {
+                debug!("pty {} idle for {timeout:?}; killing it", session.id);
+                session.kill();
+            }
+        });
+    }
+
+    async fn attach(&self, socket: WebSocket) -> Detached {
+        self.attachments.fetch_add(1, Ordering::SeqCst);
+        let (kick_tx, mut kick_rx) = oneshot::channel();
+        if let Ok(mut attached) = self.attached.lock()
+            && let Some(previous) = attached.replace(kick_tx)
+        {
+            let _ = previous.send(());
+        }
+
+        let (scrollback, exit, mut output_rx) = match self.state.lock() {
+            Ok(state) => (
+                state.scrollback.iter().copied().collect::<Vec<u8>>(),
+                state.exit,
+                self.output_tx.subscribe(),
+            ),
+            Err(_) => return Detached::Exited,
+        };
+
+        let (mut sender, mut receiver) = socket.split();
+        let attached = PtyStatus::Attached {
+            id: self.id,
+            detachable: self.detachable,
+        };
+        if sender.send(attached.to_message()).await.is_err() {
+            return Detached::ByClient;
+        }
+        if !scrollback.is_empty() && sender.send(Message::Binary(scrollback)).await.is_err() {
+            return Detached::ByClient;
+        }
+        if let Some(code) = exit {
+            return send_exit(&mut sender, code).await;
+        }
+
+        loop {
+            tokio::select! {
+                _ = &mut kick_rx => {
+                    let status = PtyStatus::Error { message: "attached from another connection" };
+                    let _ = sender.send(status.to_message()).await;
+                    return Detached::Replaced;
+                }
+                output = output_rx.recv() => match output {
+                    Ok(PtyOutput::Data(bytes)) => {
+                        if sender.send(Message::Binary(bytes)).await.is_err() {
+                            return Detached::ByClient;
+                        }
+                    }
+                    Ok(PtyOutput::Exited(code)) => return send_exit(&mut sender, code).await,
+                    // Output was dropped; the TUI redraws on the next frame.
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return Detached::Exited,
+                },
+                msg = receiver.next() => match msg {
+                    Some(Ok(Message::Binary(bytes))) => self.write_input(&bytes),
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<PtyControl>(&text) {
+                        Ok(PtyControl::Resize { rows, cols, pixel_width, pixel_height }) => {
+                            self.resize(PtySize { rows, cols, pixel_width, pixel_height });
+                        }
+                        Ok(PtyControl::Detach) => return Detached::ByClient,
+                        Err(_) => self.write_input(text.as_bytes()),
+                    },
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Detached::ByClient,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
+
+async fn send_exit(sender: &mut SplitSink<WebSocket, Message>, code: Option<u32>) -> Detached {
+    let _ = sender.send(PtyStatus::Exit { code }.to_message()).await;
+    Detached::Exited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_control_messages() {
+        assert_eq!(
+            serde_json::from_str::<PtyControl>(r#"{"type":"resize","rows":40,"cols":120}"#)
+                .expect("resize"),
+            PtyControl::Resize {
+                rows: 40,
+                cols: 120,
+                pixel_width: 0,
+                pixel_height: 0,
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<PtyControl>(r#"{"type":"detach"}"#).expect("detach"),
+            PtyControl::Detach
+        );
+        // Anything else is terminal input.
+        assert!(serde_json::from_str::<PtyControl>("ls -la\r").is_err());
+        assert!(serde_json::from_str::<PtyControl>(r#"{"type":"resize"}"#).is_err());
+    }
+
+    fn registry() -> PtyRegistry {
+        Arc::new(RwLock::new(HashMap::new()))
+    }
+
+    fn detachable() -> PtyQuery {
+        PtyQuery {
+            detach: true,
+            ..PtyQuery::default()
+        }
+    }
+
+    async fn spawn_sleep(owner: &str, registry: &PtyRegistry) -> anyhow::Result<Arc<PtySession>> {
+        let mut cmd = CommandBuilder::new("sleep");
+        cmd.arg("60");
+        PtySession::spawn_command(owner.to_string(), &detachable(), cmd, registry).await
+    }
+
+    #[tokio::test]
+    async fn limits_detachable_terminals_per_owner() {
+        let registry = registry();
+        let mut sessions = Vec::new();
+        for _ in 0..MAX_DETACHABLE_PER_OWNER {
+            sessions.push(spawn_sleep("alice", &registry).await.expect("spawn"));
+        }
+        let err = spawn_sleep("alice", &registry)
+            .await
+            .err()
+            .expect("over the limit");
+        assert!(err.to_string().contains("too many"), "{err}");
+        sessions.push(spawn_sleep("bob", &registry).await.expect("spawn"));
+        for session in sessions {
+            session.kill();
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_detached_terminals_are_killed() {
+        let registry = registry();
+        let session = spawn_sleep("alice", &registry).await.expect("spawn");
+        session.kill_when_idle(Duration::from_millis(50));
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while registry.read().await.contains_key(&session.id) {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "terminal still running"
+            );
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    #[test]
+    fn status_messages_are_tagged_json() {
+        let Message::Text(text) = PtyStatus::Exit { code: Some(1) }.to_message() else {
+            panic!("expected a text frame");
+        };
+        assert_eq!(text, r#"{"type":"exit","code":1}"#);
+    }
+}
@@ -1,36 +1,59 @@
 use std::sync::Arc;
+use std::sync::Weak;
 
+use axum::Extension;
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::extract::State;
 use axum::extract::WebSocketUpgrade;
 use axum::extract::ws::Message;
 use axum::extract::ws::WebSocket;
+#[cfg(unix)]
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
+#[cfg(unix)]
+use axum::response::Response;
 use futures_util::SinkExt;
 use futures_util::StreamExt;
+use futures_util::stream::SplitSink;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::task::JoinHandle;
 use tracing::debug;
 
 use crate::AppState;
-use crate::session::ClientMsg;
-#[cfg(unix)]
-use portable_pty::CommandBuilder;
+use crate::auth::Grant;
+use crate::event_log::EventLog;
+use crate::event_log::Replay;
 #[cfg(unix)]
-use portable_pty::NativePtySystem;
+use crate::pty::PtyQuery;
 #[cfg(unix)]
-use portable_pty::PtySize;
+use crate::pty::PtySession;
 #[cfg(unix)]
-use portable_pty::PtySystem;
+use crate::pty::PtyStatus;
+use crate::session::ClientMsg;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Sequence number of the last event the client has seen; events after it
+    /// are replayed before the live stream. Defaults to 0, which replays
+    /// everything still buffered.
+    #[serde(default)]
+    since: u64,
+}
 
 pub async fn ws_events(
     State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
     ws: WebSocketUpgrade,
     Path(id): Path<String>,
+    Query(query): Query<EventsQuery>,
 ) -> impl IntoResponse {
     let session_id = uuid::Uuid::parse_str(&id).ok();
     ws.on_upgrade(move |socket| async move {
         if let Some(uuid) = session_id {
-            handle_socket(app, uuid, socket).await;
+            handle_socket(app, grant, uuid, query.since, socket).await;
         } else {
             let mut sock = socket;
             let _ = sock
@@ -42,22 +65,27 @@ pub async fn ws_events(
     })
 }
 
-async fn handle_socket(app: Arc<AppState>, session_id: uuid::Uuid, mut socket: WebSocket) {
-    // Lookup session and subscribe to its broadcaster.
-    let (mut rx, ops_tx_opt, initial_event_json) = {
+async fn handle_socket(
+    app: Arc<AppState>,
+    grant: Grant,
+    session_id: uuid::Uuid,
+    since: u64,
+    mut socket: WebSocket,
+) {
+    // Lookup session and grab its event log. Sessions owned by another token
+    // are reported as not found.
+    let found = {
         let guard = app.sessions.read().await;
         match guard.get(&session_id) {
-            Some(entry) => (
-                entry.broadcaster.subscribe(),
-                Some(entry.ops_tx.clone()),
-                entry.initial_event_json.clone(),
-            ),
-            None => (tokio::sync::broadcast::channel::<String>(1).1, None, None),
+            Some(entry) if entry.owner == grant.name => {
+                Some((entry.events.clone(), entry.ops_tx.clone()))
+            }
+            _ => None,
         }
     };
 
-    let ops_tx = match ops_tx_opt {
-        Some(tx) => tx,
+    let (events, ops_tx) = match found {
+        Some(found) => found,
         None => {
             let _ = socket
                 .send(Message::Text(
@@ -68,16 +96,36 @@ async fn handle_socket(app: Arc<AppState>, session_id: uuid::Uuid, mut socket: W
         }
     };
 
-    // Fan-out task: forward broadcasted events to the websocket.
+    // Replay what the client missed, then forward live events. The forwarder
+    // only holds a weak reference to the log so that the socket closes once
+    // the session is dropped.
+    let (replay, mut rx) = events.lock().await.subscribe(since);
+    let events = Arc::downgrade(&events);
     let (mut sender, mut receiver) = socket.split();
-    // Send the initial SessionConfigured to the new subscriber so UIs can render history.
-    if let Some(json) = initial_event_json {
-        let _ = sender.send(Message::Text(json)).await;
-    }
     let fwd_task: JoinHandle<()> = tokio::spawn(async move {
-        while let Ok(s) = rx.recv().await {
-            if sender.send(Message::Text(s)).await.is_err() {
-                break;
+        let Some(mut last_seq) = send_replay(&mut sender, replay).await else {
+            return;
+        };
+        loop {
+            match rx.recv().await {
+                Ok(ev) if ev.seq <= last_seq => {}
+                Ok(ev) => {
+                    if sender.send(Message::Text(ev.json)).await.is_err() {
+                        break;
+                    }
+                    last_seq = ev.seq;
+                }
+                // Fell behind the live stream; catch up from the log.
+                Err(RecvError::Lagged(_)) => {
+                    let Some(replay) = replay_from(&events, last_seq).await else {
+                        break;
+                    };
+                    match send_replay(&mut sender, replay).await {
+                        Some(seq) => last_seq = seq,
+                        None => break,
+                    }
+                }
+                Err(RecvError::Closed) => break,
             }
         }
     });
@@ -87,6 +135,23 @@ async fn handle_socket(app: Arc<AppState>, session_id: uuid::Uuid, mut socket: W
         let Ok(msg) = msg else { break };
         match msg {
             Message::Text(text) => match serde_json::from_str::<ClientMsg>(&text) {
+                Ok(ClientMsg::OverrideTurnContext { cwd: Some(cwd), .. })
+                    if grant.check_cwd(&cwd).is_err() =>
+                {
+                    debug!("rejected cwd override outside the token's roots: {cwd:?}");
+                }
+                Ok(ClientMsg::OverrideTurnContext {
+                    approval_policy,
+                    sandbox_mode,
+                    ..
+                }) if grant
+                    .check_permissions(approval_policy, sandbox_mode)
+                    .is_err() =>
+                {
+                    debug!(
+                        "rejected override beyond the token's permissions: {approval_policy:?} {sandbox_mode:?}"
+                    );
+                }
                 Ok(msg) => {
                     let _ = ops_tx.send(msg);
                 }
@@ -104,97 +169,89 @@ async fn handle_socket(app: Arc<AppState>, session_id: uuid::Uuid, mut socket: W
     fwd_task.abort();
 }
 
-// WebSocket that proxies a PTY running the TUI. Unix-only; on non-Unix returns an error.
-pub async fn ws_pty(State(_app): State<Arc<AppState>>, ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(handle_pty_socket)
+async fn replay_from(events: &Weak<Mutex<EventLog>>, since: u64) -> Option<Replay> {
+    let events = events.upgrade()?;
+    let replay = events.lock().await.replay(since);
+    Some(replay)
 }
 
+/// Sends a replay to the client, preceded by a `replay_gap` notice when some
+/// of the requested events are no longer available. Returns the sequence
+/// number the client is now caught up to, or `None` if the socket closed.
+async fn send_replay(sender: &mut SplitSink<WebSocket, Message>, replay: Replay) -> Option<u64> {
+    if let Some(gap) = replay.gap
+        && let Ok(json) = serde_json::to_string(&gap)
+    {
+        sender.send(Message::Text(json)).await.ok()?;
+    }
+    for ev in replay.events {
+        sender.send(Message::Text(ev.json)).await.ok()?;
+    }
+    Some(replay.latest)
+}
+
+// WebSocket that proxies a PTY running the TUI. Unix-only; on non-Unix returns an error.
 #[cfg(unix)]
-async fn handle_pty_socket(mut socket: WebSocket) {
-    use std::io::Read;
-    use std::io::Write;
+pub async fn ws_pty(
+    State(app): State<Arc<AppState>>,
+    Extension(grant): Extension<Grant>,
+    Query(query): Query<PtyQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, (StatusCode, String)> {
+    // The terminal runs the full TUI with the server's config, where the
+    // approval policy, sandbox and `!` shell commands are not held to what a
+    // restricted token allows.
+    if !grant.is_unrestricted() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("token `{}` may not open terminals", grant.name),
+        ));
+    }
 
-    let pty_system = NativePtySystem::default();
-    let pair = match pty_system.openpty(PtySize {
-        rows: 30,
-        cols: 100,
-        pixel_width: 0,
-        pixel_height: 0,
-    }) {
-        Ok(p) => p,
-        Err(e) => {
-            let _ = socket
-                .send(Message::Text(format!(
-                    "{{\"type\":\"error\",\"message\":\"pty open failed: {e}\"}}"
-                )))
-                .await;
-            return;
-        }
-    };
+    // Reattach to a detached terminal started with the same token.
+    if let Some(id) = query.id {
+        let session = app
+            .ptys
+            .read()
+            .await
+            .get(&id)
+            .filter(|session| session.owner == grant.name)
+            .cloned()
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no such terminal: {id}")))?;
+        return Ok(ws.on_upgrade(move |socket| session.serve(socket)));
+    }
 
-    let cmd = CommandBuilder::new("codex");
-    let mut child = match pair.slave.spawn_command(cmd) {
-        Ok(c) => c,
-        Err(e) => {
-            let _ = socket
-                .send(Message::Text(format!(
-                    "{{\"type\":\"error\",\"message\":\"spawn failed: {e}\"}}"
-                )))
-                .await;
-            return;
-        }
+    let cwd = query.cwd.clone();
+    let args = match &query.args {
+        None => Vec::new(),
+        Some(args) => serde_json::from_str::<Vec<String>>(args).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("args must be a JSON array of strings: {e}"),
+            )
+        })?,
     };
-
-    let mut reader = pair.master.try_clone_reader().expect("clone reader");
-    let mut writer = pair.master.take_writer().expect("take writer");
-
-    // PTY -> WS via channel
-    let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
-    let read_task = tokio::task::spawn_blocking(move || {
-        let mut buf = [0u8; 8192];
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    let s = String::from_utf8_lossy(&buf[..n]).to_string();
-                    let _ = tx.send(s);
-                }
-                Err(_) => break,
+    Ok(ws.on_upgrade(move |mut socket| async move {
+        match PtySession::spawn(grant.name, &query, cwd, args, &app.ptys).await {
+            Ok(session) => session.serve(socket).await,
+            Err(e) => {
+                let message = format!("pty spawn failed: {e}");
+                let _ = socket
+                    .send(PtyStatus::Error { message: &message }.to_message())
+                    .await;
             }
         }
-    });
-    let ws_send_task = tokio::spawn(async move {
-        while let Some(s) = rx.recv().await {
-            if sender.send(Message::Text(s)).await.is_err() {
-                break;
-            }
-        }
-    });
-
-    // WS -> PTY
-    while let Some(msg) = receiver.next().await {
-        let Ok(msg) = msg else { break };
-        match msg {
-            Message::Text(s) => {
-                let _ = writer.write_all(s.as_bytes());
-                let _ = writer.flush();
-            }
-            Message::Close(_) => break,
-            _ => {}
-        }
-    }
-
-    let _ = read_task.await;
-    let _ = ws_send_task.await;
-    let _ = child.kill();
+    }))
 }
 
 #[cfg(not(unix))]
-async fn handle_pty_socket(mut socket: WebSocket) {
-    let _ = socket
-        .send(Message::Text(
-            "{\"type\":\"error\",\"message\":\"pty not supported on this platform\"}".to_string(),
-        ))
-        .await;
+pub async fn ws_pty(ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(|mut socket| async move {
+        let _ = socket
+            .send(Message::Text(
+                "{\"type\":\"error\",\"message\":\"pty not supported on this platform\"}"
+                    .to_string(),
+            ))
+            .await;
+    })
 }
@@ -1,8 +1,23 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use codex_core::CodexConversation;
+use codex_core::NewConversation;
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::TokenUsage;
 use serde::Deserialize;
-use tokio::sync::broadcast;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
+use crate::event_log::EVENT_LOG_CAPACITY;
+use crate::event_log::EventLog;
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMsg {
@@ -34,13 +49,245 @@ pub enum ClientMsg {
     },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    /// Waiting for user input.
+    Idle,
+    /// A task is in progress.
+    Running,
+}
+
+/// Live state of a session, as reported by `GET /api/sessions`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub session_id: uuid::Uuid,
+    pub cwd: PathBuf,
+    pub model: String,
+    pub status: SessionStatus,
+    pub rollout_path: PathBuf,
+    /// Unix timestamps, in seconds.
+    pub created_at: u64,
+    pub last_activity: u64,
+    /// Cumulative usage for the session, once the model has reported any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+}
+
+impl SessionInfo {
+    /// Folds an event from the conversation into the tracked state.
+    fn observe(&mut self, msg: &EventMsg) {
+        self.last_activity = unix_now();
+        match msg {
+            EventMsg::SessionConfigured(ev) => self.model = ev.model.clone(),
+            EventMsg::TaskStarted(_) => self.status = SessionStatus::Running,
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => {
+                self.status = SessionStatus::Idle
+            }
+            EventMsg::TokenCount(ev) => {
+                if let Some(info) = &ev.info {
+                    self.token_usage = Some(info.total_token_usage.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 pub struct SessionEntry {
-    pub broadcaster: broadcast::Sender<String>,
+    /// Name of the access token that created the session; only that token
+    /// may attach to it.
+    pub owner: String,
+    pub conversation: Arc<CodexConversation>,
+    pub info: Arc<RwLock<SessionInfo>>,
+    pub events: Arc<Mutex<EventLog>>,
     pub ops_tx: mpsc::UnboundedSender<ClientMsg>,
-    pub _event_task: JoinHandle<()>,
-    pub _ops_task: JoinHandle<()>,
-    pub initial_event_json: Option<String>,
+    pub event_task: JoinHandle<()>,
+    pub ops_task: JoinHandle<()>,
 }
 
-// Tasks are spawned inline where the conversation handle is available to avoid
-// referring to private types from codex-core.
+impl SessionEntry {
+    /// Spawns the tasks that pump the conversation's events to WebSocket
+    /// subscribers and forward client messages to it.
+    pub fn spawn(owner: String, conv: NewConversation, cwd: PathBuf) -> Self {
+        let NewConversation {
+            conversation_id,
+            conversation,
+            session_configured,
+        } = conv;
+        let now = unix_now();
+        let info = Arc::new(RwLock::new(SessionInfo {
+            session_id: conversation_id.into(),
+            cwd,
+            model: session_configured.model.clone(),
+            status: SessionStatus::Idle,
+            rollout_path: session_configured.rollout_path.clone(),
+            created_at: now,
+            last_activity: now,
+            token_usage: None,
+        }));
+
+        // Pin the initial SessionConfigured (consumed by ConversationManager)
+        // so every subscriber can render history.
+        let initial = Event {
+            id: "".to_string(),
+            msg: EventMsg::SessionConfigured(session_configured),
+        };
+        let events = Arc::new(Mutex::new(EventLog::new(EVENT_LOG_CAPACITY, &initial)));
+
+        let conversation_for_events = conversation.clone();
+        let info_for_events = info.clone();
+        let events_for_pump = events.clone();
+        let event_task = tokio::spawn(async move {
+            loop {
+                let ev = match conversation_for_events.next_event().await {
+                    Ok(ev) => ev,
+                    Err(_) => break,
+                };
+                info_for_events.write().await.observe(&ev.msg);
+                events_for_pump.lock().await.publish(&ev);
+            }
+        });
+
+        let (ops_tx, ops_rx) = mpsc::unbounded_channel::<ClientMsg>();
+        let ops_task = tokio::spawn(forward_ops(conversation.clone(), info.clone(), ops_rx));
+
+        Self {
+            owner,
+            conversation,
+            info,
+            events,
+            ops_tx,
+            event_task,
+            ops_task,
+        }
+    }
+}
+
+async fn forward_ops(
+    conversation: Arc<CodexConversation>,
+    info: Arc<RwLock<SessionInfo>>,
+    mut ops_rx: mpsc::UnboundedReceiver<ClientMsg>,
+) {
+    use codex_core::protocol::InputItem;
+    use codex_core::protocol::Op;
+    while let Some(msg) = ops_rx.recv().await {
+        match msg {
+            ClientMsg::UserMessage { text, images } => {
+                let mut items = vec![InputItem::Text { text }];
+                if let Some(imgs) = images {
+                    for image_url in imgs {
+                        items.push(InputItem::Image { image_url });
+                    }
+                }
+                let _ = conversation.submit(Op::UserInput { items }).await;
+            }
+            ClientMsg::Interrupt => {
+                let _ = conversation.submit(Op::Interrupt).await;
+            }
+            ClientMsg::ExecApproval { id, decision } => {
+                let _ = conversation.submit(Op::ExecApproval { id, decision }).await;
+            }
+            ClientMsg::PatchApproval { id, decision } => {
+                let _ = conversation
+                    .submit(Op::PatchApproval { id, decision })
+                    .await;
+            }
+            ClientMsg::Compact => {
+                let _ = conversation.submit(Op::Compact).await;
+            }
+            ClientMsg::OverrideTurnContext {
+                cwd,
+                model,
+                approval_policy,
+                effort,
+                sandbox_mode,
+            } => {
+                use codex_core::protocol::SandboxPolicy;
+                use codex_protocol::config_types::SandboxMode;
+                let sandbox_policy = match sandbox_mode {
+                    Some(SandboxMode::ReadOnly) => Some(SandboxPolicy::new_read_only_policy()),
+                    Some(SandboxMode::WorkspaceWrite) => {
+                        Some(SandboxPolicy::new_workspace_write_policy())
+                    }
+                    Some(SandboxMode::DangerFullAccess) => Some(SandboxPolicy::DangerFullAccess),
+                    None => None,
+                };
+                {
+                    let mut info = info.write().await;
+                    if let Some(cwd) = &cwd {
+                        info.cwd = cwd.clone();
+                    }
+                    if let Some(model) = &model {
+                        info.model = model.clone();
+                    }
+                }
+                let _ = conversation
+                    .submit(Op::OverrideTurnContext {
+                        cwd,
+                        approval_policy,
+                        sandbox_policy,
+                        model,
+                        effort,
+                        summary: None,
+                        default_exec_timeout_ms: None,
+                    })
+                    .await;
+            }
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::TaskCompleteEvent;
+    use codex_core::protocol::TaskStartedEvent;
+    use codex_core::protocol::TokenCountEvent;
+    use codex_core::protocol::TokenUsageInfo;
+
+    #[test]
+    fn observe_tracks_status_and_token_usage() {
+        let mut info = SessionInfo {
+            session_id: uuid::Uuid::nil(),
+            cwd: PathBuf::from("/tmp"),
+            model: "gpt-5".to_string(),
+            status: SessionStatus::Idle,
+            rollout_path: PathBuf::from("/tmp/rollout.jsonl"),
+            created_at: 0,
+            last_activity: 0,
+            token_usage: None,
+        };
+
+        info.observe(&EventMsg::TaskStarted(TaskStartedEvent {
+            model_context_window: None,
+        }));
+        assert_eq!(info.status, SessionStatus::Running);
+        assert!(info.last_activity > 0);
+
+        let usage = TokenUsage {
+            total_tokens: 42,
+            ..Default::default()
+        };
+        info.observe(&EventMsg::TokenCount(TokenCountEvent {
+            info: Some(TokenUsageInfo {
+                total_token_usage: usage.clone(),
+                last_token_usage: usage,
+                model_context_window: None,
+            }),
+        }));
+        assert_eq!(info.token_usage.as_ref().map(|u| u.total_tokens), Some(42));
+
+        info.observe(&EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+        }));
+        assert_eq!(info.status, SessionStatus::Idle);
+    }
+}
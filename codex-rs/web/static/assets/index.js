@@ -1,8 +1,23 @@
 // Helpers
 const qs = (s, el = document) => el.querySelector(s);
 const qsa = (s, el = document) => [...el.querySelectorAll(s)];
-const getJSON = (u) => fetch(u).then((r) => { if(!r.ok) throw new Error(`GET ${u} ${r.status}`); return r.json(); });
-const postJSON = (u, b) => fetch(u, { method: 'POST', headers: { 'content-type': 'application/json' }, body: JSON.stringify(b||{}) }).then((r)=>{ if(!r.ok) throw new Error(`POST ${u} ${r.status}`); return r.json(); });
+// Access token: picked up from ?token= once, then kept in localStorage.
+const TOKEN_KEY = 'codex-web-token';
+(() => {
+  try{
+    const sp = new URLSearchParams(location.search);
+    const t = sp.get('token');
+    if(!t) return;
+    localStorage.setItem(TOKEN_KEY, t);
+    sp.delete('token');
+    history.replaceState(null, '', location.pathname + (sp.toString() ? `?${sp}` : '') + location.hash);
+  }catch{}
+})();
+const authHeaders = () => { const t = localStorage.getItem(TOKEN_KEY); return t ? { authorization: `Bearer ${t}` } : {}; };
+// WebSockets cannot carry headers, so the token goes in the query string.
+const withToken = (u) => { const t = localStorage.getItem(TOKEN_KEY); return t ? `${u}${u.includes('?') ? '&' : '?'}token=${encodeURIComponent(t)}` : u; };
+const getJSON = (u) => fetch(u, { headers: authHeaders() }).then((r) => { if(!r.ok) throw new Error(`GET ${u} ${r.status}`); return r.json(); });
+const postJSON = (u, b) => fetch(u, { method: 'POST', headers: { 'content-type': 'application/json', ...authHeaders() }, body: JSON.stringify(b||{}) }).then((r)=>{ if(!r.ok) throw new Error(`POST ${u} ${r.status}`); return r.json(); });
 
 // Theme
 const THEME_KEY = 'codex-theme';
@@ -214,7 +229,7 @@ function renderRollouts(filter){
 
     const row = document.createElement('div'); row.className = 'rowi'; row.setAttribute('role','listitem');
     const left = document.createElement('div');
-    const idDiv = document.createElement('div'); idDiv.textContent = sid; idDiv.className='mono';
+    const idDiv = document.createElement('div'); idDiv.textContent = it.sub_agent ? `${sid} [sub-agent: ${it.sub_agent.label}]` : sid; idDiv.className='mono';
     const pathDiv = document.createElement('div'); pathDiv.textContent = p; pathDiv.className='muted mono';
     left.append(idDiv, pathDiv);
     const btn = document.createElement('button'); btn.textContent = 'Resume'; btn.className='btn ghost';
@@ -4,6 +4,21 @@
 // ---------- Utils ----------
 const qs = (s, el = document) => el.querySelector(s);
 const qsa = (s, el = document) => [...el.querySelectorAll(s)];
+// Access token: picked up from ?token= once, then kept in localStorage.
+const TOKEN_KEY = 'codex-web-token';
+(() => {
+  try{
+    const sp = new URLSearchParams(location.search);
+    const t = sp.get('token');
+    if(!t) return;
+    localStorage.setItem(TOKEN_KEY, t);
+    sp.delete('token');
+    history.replaceState(null, '', location.pathname + (sp.toString() ? `?${sp}` : '') + location.hash);
+  }catch{}
+})();
+const authHeaders = () => { const t = localStorage.getItem(TOKEN_KEY); return t ? { authorization: `Bearer ${t}` } : {}; };
+// WebSockets cannot carry headers, so the token goes in the query string.
+const withToken = (u) => { const t = localStorage.getItem(TOKEN_KEY); return t ? `${u}${u.includes('?') ? '&' : '?'}token=${encodeURIComponent(t)}` : u; };
 // Debug logger (enable with ?debug=1 or localStorage codex-debug=1)
 const DEBUG = (() => {
   try{
@@ -506,9 +521,12 @@ function initSession(){
   }
 
   // WS
-  const wsUrl = `${location.protocol==='https:'?'wss':'ws'}://${location.host}/api/sessions/${encodeURIComponent(id)}/events`;
-  dlog('ws url', wsUrl);
+  const wsUrl = withToken(`${location.protocol==='https:'?'wss':'ws'}://${location.host}/api/sessions/${encodeURIComponent(id)}/events`);
+  dlog('ws url', wsUrl.replace(/token=[^&]+/, 'token=***'));
   let eventsLog=[];
+  // Sequence number of the last event rendered; reconnects resume after it.
+  let lastSeq=null;
+  function eventsUrl(){ return lastSeq==null ? wsUrl : `${wsUrl}${wsUrl.includes('?')?'&':'?'}since=${lastSeq}`; }
   let reasonHeaderBuffer='';
   function extractFirstBold(md){
     const open = md.indexOf('**'); if(open<0) return null; const after = md.indexOf('**', open+2); if(after<0) return null; return md.slice(open+2, after).trim();
@@ -595,7 +613,7 @@ function initSession(){
   }
 
   function connect(){
-    const ws=new WebSocket(wsUrl);
+    const ws=new WebSocket(eventsUrl());
     let gotFirstEvent = false;
     setTimeout(()=>{ if(!gotFirstEvent) dlog('no events received within 2s after ws open'); }, 2000);
     ws.onopen=()=>{
@@ -620,6 +638,8 @@ function initSession(){
         dlog('event raw', rawPreview);
         const e=JSON.parse(ev.data); const t=e.msg?.type; dlog('event type', t);
         gotFirstEvent = true;
+        if(e.type==='replay_gap'){ addSystem(`Some events were missed while disconnected (resumed at #${e.oldest}).`); return; }
+        if(typeof e.seq==='number'){ if(lastSeq!=null && e.seq<=lastSeq) return; lastSeq=e.seq; }
         if(t==='user_message'){
           const m = e.msg.message||'';
           const kind = (()=>{ try{ return String(e.msg.kind||'').toLowerCase(); }catch{ return ''; } })();
@@ -928,7 +948,7 @@ function initSession(){
       const approval_policy = qs('#ctx-approval')?.value || undefined;
       const sandbox_mode = qs('#ctx-sandbox')?.value || undefined;
       const body = { cwd: cwd || null, model, approval_policy, sandbox_mode };
-      const r = await fetch('/api/sessions', { method:'POST', headers:{'content-type':'application/json'}, body: JSON.stringify(body) });
+      const r = await fetch('/api/sessions', { method:'POST', headers:{'content-type':'application/json', ...authHeaders()}, body: JSON.stringify(body) });
       if(!r.ok){ throw new Error(await r.text()); }
       const j = await r.json();
       location.href = `/session/${j.session_id}`;